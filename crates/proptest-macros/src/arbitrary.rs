@@ -0,0 +1,690 @@
+//! `#[derive(Arbitrary)]` for structs and enums.
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{ToTokens, quote};
+use syn::{
+    Data,
+    DeriveInput,
+    Expr,
+    Field,
+    Fields,
+    GenericParam,
+    LitInt,
+    Token,
+    Variant,
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+};
+
+/// How a field of a derived type gets its value.
+enum FieldSource {
+    Arbitrary,
+    Default,
+    Value(Expr),
+    Strategy(Expr),
+}
+
+/// Draws `generate` makes by default before rejecting a value that fails
+/// its `validate` check.
+const DEFAULT_RETRIES: usize = 16;
+
+/// The `#[arbitrary(validate = path, retries = n)]` option of a type.
+struct Validate {
+    check: Expr,
+    retries: usize,
+}
+
+/// What the generated code builds a value from.
+#[derive(Clone, Copy)]
+enum Build<'a> {
+    /// Values of the fields, from `Arbitrary::generate`.
+    Value,
+    /// Trees of the fields, from `Arbitrary::generate_tree`, which go to the
+    /// `DerivedFields` of the value's tree. Fields of enums belong to the
+    /// named variant.
+    Tree(Option<&'a syn::Ident>),
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let validate = parse_validate(&input)?;
+    let methods = match &input.data {
+        Data::Struct(data) => {
+            let strategies = uses_strategy(&data.fields)?;
+            let value = construct(quote! { Self }, &data.fields, Build::Value)?;
+            let tree =
+                construct(quote! { Self }, &data.fields, Build::Tree(None))?;
+            let tree = quote! {{
+                #[allow(unused_mut)]
+                let mut __fields = __derived_fields();
+                let value = #tree;
+                ::estoa_proptest::DerivedTree::new(value, __fields)
+            }};
+            generator_methods(value, tree, strategies, validate.as_ref())
+        }
+        Data::Enum(data) => expand_enum(name, data, validate.as_ref())?,
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span(),
+                "#[derive(Arbitrary)] does not support unions",
+            ));
+        }
+    };
+
+    // Trees of the fields are boxed, which takes them to be `'static`.
+    let mut generics = input.generics.clone();
+    for param in &mut generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(::estoa_proptest::Arbitrary));
+            param.bounds.push(parse_quote!('static));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::estoa_proptest::Arbitrary for #name #ty_generics #where_clause {
+            #methods
+        }
+    })
+}
+
+fn arbitrary_method(body: TokenStream) -> TokenStream {
+    quote! {
+        fn arbitrary<R>(rng: &mut R) -> Self
+        where
+            R: ::estoa_proptest::__private::rand::RngCore
+                + ::estoa_proptest::__private::rand::CryptoRng
+                + ?Sized,
+        {
+            #body
+        }
+    }
+}
+
+/// Implement `Arbitrary::generate` with `value`, `Arbitrary::arbitrary`
+/// through it and `Arbitrary::generate_tree` with `tree`.
+///
+/// `tree` builds the `DerivedTree` of a value, taking the fields from their
+/// trees, which it hands to a `__fields` from `__derived_fields()`. It draws
+/// the same value as `value` from the same randomness, unless it also draws
+/// alternatives for the earlier variants of an enum. With
+/// `strategies`, both set `__rejected` when a field strategy rejects its
+/// value, which rejects the whole value. With `validate`, accepted values
+/// failing the check are drawn again, up to its retries, and the last one is
+/// rejected; trees also skip simplifications that fail it.
+fn generator_methods(
+    value: TokenStream,
+    tree: TokenStream,
+    strategies: bool,
+    validate: Option<&Validate>,
+) -> TokenStream {
+    let arbitrary = arbitrary_method(quote! {
+        let mut generator = ::estoa_proptest::Generator::build(rng);
+        <Self as ::estoa_proptest::Arbitrary>::generate(&mut generator).take()
+    });
+
+    let finish = |body: TokenStream| {
+        if strategies {
+            quote! {
+                let mut __rejected = false;
+                let value = #body;
+                if __rejected {
+                    generator.reject(value)
+                } else {
+                    generator.accept(value)
+                }
+            }
+        } else {
+            quote! {
+                let value = #body;
+                generator.accept(value)
+            }
+        }
+    };
+    let validate_fields = validate.map(|Validate { check, .. }| {
+        quote! { __fields.validate(#check); }
+    });
+    let generate = finish(value);
+    let generate_tree = finish(quote! {{
+        let __derived_fields = || {
+            #[allow(unused_mut)]
+            let mut __fields = ::estoa_proptest::DerivedFields::<Self>::new();
+            #validate_fields
+            __fields
+        };
+        ::estoa_proptest::ArbitraryTree::from(#tree)
+    }});
+
+    let retry = |generate: TokenStream, current: TokenStream| match validate {
+        Some(Validate { check, retries }) => quote! {
+            let __draw = |generator: &mut ::estoa_proptest::Generator<R>| {
+                #generate
+            };
+            let mut __draws = 0usize;
+            loop {
+                __draws += 1;
+                match __draw(generator) {
+                    ::estoa_proptest::Generation::Accepted { value, .. }
+                        if !(#check)(#current) =>
+                    {
+                        if __draws >= #retries {
+                            break generator.reject_with(
+                                ::estoa_proptest::RejectReason::Filter,
+                                value,
+                            );
+                        }
+                    }
+                    generation => break generation,
+                }
+            }
+        },
+        None => generate,
+    };
+    let generate = retry(generate, quote! { &value });
+    let generate_tree = retry(
+        generate_tree,
+        quote! { ::estoa_proptest::strategy::ValueTree::current(&value) },
+    );
+
+    quote! {
+        #arbitrary
+
+        fn generate<R>(
+            generator: &mut ::estoa_proptest::Generator<R>,
+        ) -> ::estoa_proptest::Generation<Self>
+        where
+            R: ::estoa_proptest::__private::rand::RngCore
+                + ::estoa_proptest::__private::rand::CryptoRng,
+        {
+            #generate
+        }
+
+        fn generate_tree<R>(
+            generator: &mut ::estoa_proptest::Generator<R>,
+        ) -> ::estoa_proptest::Generation<::estoa_proptest::ArbitraryTree<Self>>
+        where
+            R: ::estoa_proptest::__private::rand::RngCore
+                + ::estoa_proptest::__private::rand::CryptoRng,
+        {
+            #generate_tree
+        }
+    }
+}
+
+/// A variant of a derived enum that can be picked.
+#[derive(Clone, Copy)]
+struct Choice<'a> {
+    variant: &'a Variant,
+    weight: usize,
+    recursive: bool,
+}
+
+/// Expand the methods of an enum, picking variants by weight.
+///
+/// Enums whose variants contain the enum itself generate through
+/// `Arbitrary::generate`, entering [`Generator::recurse`] for every
+/// recursive variant and falling back to the other variants once the
+/// generator's recursion budget is spent.
+fn expand_enum(
+    name: &syn::Ident,
+    data: &syn::DataEnum,
+    validate: Option<&Validate>,
+) -> syn::Result<TokenStream> {
+    if data.variants.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "#[derive(Arbitrary)] needs at least one variant",
+        ));
+    }
+
+    let mut choices = Vec::new();
+    for variant in &data.variants {
+        let weight = variant_weight(variant)?;
+        if weight > 0 {
+            choices.push(Choice {
+                variant,
+                weight,
+                recursive: mentions(variant, name),
+            });
+        }
+    }
+    if choices.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "#[derive(Arbitrary)] needs a variant with a nonzero weight",
+        ));
+    }
+
+    let mut strategies = false;
+    for choice in &choices {
+        strategies |= uses_strategy(&choice.variant.fields)?;
+    }
+    if !choices.iter().any(|choice| choice.recursive) {
+        return Ok(generator_methods(
+            pick(&choices)?,
+            pick_tree(&choices)?,
+            strategies,
+            validate,
+        ));
+    }
+
+    let leaves = choices
+        .iter()
+        .filter(|choice| !choice.recursive)
+        .copied()
+        .collect::<Vec<_>>();
+    if leaves.is_empty() {
+        return Err(syn::Error::new(
+            name.span(),
+            "recursive #[derive(Arbitrary)] enums need a variant with a nonzero weight that does not contain the enum",
+        ));
+    }
+
+    let recursive = |pick: fn(&[Choice<'_>]) -> syn::Result<TokenStream>| {
+        let any = pick(&choices)?;
+        let leaf = pick(&leaves)?;
+        syn::Result::Ok(quote! {
+            if generator.recursion_exhausted() {
+                #leaf
+            } else {
+                #any
+            }
+        })
+    };
+    Ok(generator_methods(
+        recursive(pick)?,
+        recursive(pick_tree)?,
+        strategies,
+        validate,
+    ))
+}
+
+/// Pick one of `choices` in proportion to its weight.
+fn pick(choices: &[Choice<'_>]) -> syn::Result<TokenStream> {
+    let draw = draw_choice(choices)?;
+    let mut arms = Vec::new();
+    for (index, choice) in choices.iter().enumerate() {
+        let ident = &choice.variant.ident;
+        let mut value = construct(
+            quote! { Self::#ident },
+            &choice.variant.fields,
+            Build::Value,
+        )?;
+        if choice.recursive {
+            value = quote! { generator.recurse(|generator| #value) };
+        }
+        arms.push(quote! { #index => #value, });
+    }
+
+    Ok(quote! {
+        match #draw {
+            #(#arms)*
+            _ => unreachable!(),
+        }
+    })
+}
+
+/// Pick one of `choices` in proportion to its weight, building the
+/// `DerivedTree` of the value.
+///
+/// The tree can switch to the variants before the one picked, which are
+/// drawn first, so it shrinks toward the first variant. Recursive variants
+/// are left out of the alternatives, as are those a field strategy rejects.
+fn pick_tree(choices: &[Choice<'_>]) -> syn::Result<TokenStream> {
+    let draw = draw_choice(choices)?;
+    let mut alternatives = Vec::new();
+    let mut arms = Vec::new();
+    for (index, choice) in choices.iter().enumerate() {
+        let ident = &choice.variant.ident;
+        let fields = &choice.variant.fields;
+        let value = construct(
+            quote! { Self::#ident },
+            fields,
+            Build::Tree(Some(ident)),
+        )?;
+
+        if !choice.recursive {
+            let push = if uses_strategy(fields)? {
+                quote! {
+                    let mut __rejected = false;
+                    let value = #value;
+                    if !__rejected {
+                        __alternatives.push(value, __fields);
+                    }
+                }
+            } else {
+                quote! { __alternatives.push(#value, __fields); }
+            };
+            alternatives.push(quote! {
+                if #index < __variant {
+                    #[allow(unused_mut)]
+                    let mut __fields = __derived_fields();
+                    #push
+                }
+            });
+        }
+
+        let value = if choice.recursive {
+            quote! { generator.recurse(|generator| #value) }
+        } else {
+            value
+        };
+        arms.push(quote! { #index => #value, });
+    }
+
+    Ok(quote! {{
+        let __variant = #draw;
+        #[allow(unused_mut)]
+        let mut __alternatives = ::estoa_proptest::DerivedAlternatives::<Self>::new();
+        #(#alternatives)*
+        #[allow(unused_mut)]
+        let mut __fields = __derived_fields();
+        let value = match __variant {
+            #(#arms)*
+            _ => unreachable!(),
+        };
+        ::estoa_proptest::DerivedTree::with_alternatives(__alternatives, value, __fields)
+    }})
+}
+
+/// Draw the index of one of `choices` in proportion to its weight.
+fn draw_choice(choices: &[Choice<'_>]) -> syn::Result<TokenStream> {
+    let mut total = 0usize;
+    let mut arms = Vec::new();
+    for (index, choice) in choices.iter().enumerate() {
+        total = total.checked_add(choice.weight).ok_or_else(|| {
+            syn::Error::new(
+                choice.variant.span(),
+                "variant weights overflow usize",
+            )
+        })?;
+        arms.push(quote! { __draw if __draw < #total => #index, });
+    }
+
+    Ok(quote! {
+        match ::estoa_proptest::__private::rand::Rng::random_range(&mut generator.rng, 0..#total) {
+            #(#arms)*
+            _ => unreachable!(),
+        }
+    })
+}
+
+/// Whether the field types of `variant` mention the enum `name`.
+fn mentions(variant: &Variant, name: &syn::Ident) -> bool {
+    fn walk(tokens: TokenStream, name: &syn::Ident) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == *name || ident == "Self",
+            TokenTree::Group(group) => walk(group.stream(), name),
+            _ => false,
+        })
+    }
+
+    variant
+        .fields
+        .iter()
+        .any(|field| walk(field.ty.to_token_stream(), name))
+}
+
+/// Whether any of `fields` is generated by a `#[strategy]`.
+fn uses_strategy(fields: &Fields) -> syn::Result<bool> {
+    for field in fields {
+        if let FieldSource::Strategy(_) = field_source(field)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Build `path` from values for each of `fields`, in declaration order.
+fn construct(
+    path: TokenStream,
+    fields: &Fields,
+    build: Build<'_>,
+) -> syn::Result<TokenStream> {
+    let values = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let value = match (field_source(field)?, build) {
+                (FieldSource::Arbitrary, Build::Value) => quote! {
+                    ::estoa_proptest::Arbitrary::generate(generator).take()
+                },
+                (FieldSource::Arbitrary, Build::Tree(variant)) => {
+                    let project = project(field, index, variant);
+                    quote! {
+                        __fields.field(
+                            ::estoa_proptest::Arbitrary::generate_tree(generator)
+                                .take(),
+                            #project,
+                        )
+                    }
+                }
+                (FieldSource::Strategy(strategy), build) => {
+                    let take = match build {
+                        Build::Value => quote! {
+                            ::core::clone::Clone::clone(
+                                ::estoa_proptest::strategy::ValueTree::current(
+                                    &generation.take(),
+                                ),
+                            )
+                        },
+                        Build::Tree(variant) => {
+                            let project = project(field, index, variant);
+                            quote! {
+                                __fields.field(
+                                    ::estoa_proptest::ArbitraryTree::new(
+                                        generation.take(),
+                                    ),
+                                    #project,
+                                )
+                            }
+                        }
+                    };
+                    quote! {{
+                        let generation = ::estoa_proptest::strategy::Strategy::new_tree(
+                            &mut (#strategy),
+                            generator,
+                        );
+                        __rejected |= matches!(
+                            generation,
+                            ::estoa_proptest::Generation::Rejected { .. },
+                        );
+                        #take
+                    }}
+                }
+                (FieldSource::Default, _) => quote! {
+                    ::core::default::Default::default()
+                },
+                (FieldSource::Value(expr), _) => quote! { #expr },
+            };
+            Ok(match &field.ident {
+                Some(ident) => quote! { #ident: #value },
+                None => value,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(match fields {
+        Fields::Named(_) => quote! { #path { #(#values),* } },
+        Fields::Unnamed(_) => quote! { #path ( #(#values),* ) },
+        Fields::Unit => path,
+    })
+}
+
+/// A function reaching `field`, the `index`th field of the struct or of its
+/// `variant`, in a value of the derived type.
+fn project(
+    field: &Field,
+    index: usize,
+    variant: Option<&syn::Ident>,
+) -> TokenStream {
+    let some = quote! { ::core::option::Option::Some };
+    let member = match &field.ident {
+        Some(ident) => quote! { #ident },
+        None => {
+            let index = syn::Index::from(index);
+            quote! { #index }
+        }
+    };
+    let Some(variant) = variant else {
+        return quote! { |value: &mut Self| #some(&mut value.#member) };
+    };
+
+    let pattern = match &field.ident {
+        Some(ident) => quote! { Self::#variant { #ident: field, .. } },
+        None => {
+            let skipped = (0..index).map(|_| quote! { _, });
+            quote! { Self::#variant( #(#skipped)* field, .. ) }
+        }
+    };
+    quote! {
+        |value: &mut Self| match value {
+            #pattern => #some(field),
+            #[allow(unreachable_patterns)]
+            _ => ::core::option::Option::None,
+        }
+    }
+}
+
+/// Parse the `#[arbitrary(validate = path, retries = n)]` option of the
+/// derived type.
+fn parse_validate(input: &DeriveInput) -> syn::Result<Option<Validate>> {
+    let mut check = None;
+    let mut retries = None;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("arbitrary") {
+            continue;
+        }
+
+        let metas = attr.parse_args_with(
+            Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated,
+        )?;
+        for meta in metas {
+            let duplicate = if meta.path.is_ident("validate") {
+                check.replace(meta.value).is_some()
+            } else if meta.path.is_ident("retries") {
+                let value = match &meta.value {
+                    Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(int),
+                        ..
+                    }) => int.base10_parse::<usize>()?,
+                    other => {
+                        return Err(syn::Error::new(
+                            other.span(),
+                            "`retries` must be an integer literal",
+                        ));
+                    }
+                };
+                if value == 0 {
+                    return Err(syn::Error::new(
+                        meta.value.span(),
+                        "`retries` must be at least 1",
+                    ));
+                }
+                retries.replace((value, meta.path.span())).is_some()
+            } else {
+                return Err(syn::Error::new(
+                    meta.path.span(),
+                    "unknown #[arbitrary] option, expected `validate = path` or `retries = n`",
+                ));
+            };
+
+            if duplicate {
+                return Err(syn::Error::new(
+                    meta.path.span(),
+                    "#[arbitrary] option specified more than once",
+                ));
+            }
+        }
+    }
+
+    match (check, retries) {
+        (Some(check), retries) => Ok(Some(Validate {
+            check,
+            retries: retries.map_or(DEFAULT_RETRIES, |(value, _)| value),
+        })),
+        (None, Some((_, span))) => {
+            Err(syn::Error::new(span, "`retries` requires `validate`"))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Parse the `#[weight(n)]` attribute of an enum variant, defaulting to 1.
+fn variant_weight(variant: &Variant) -> syn::Result<usize> {
+    let mut weight = None;
+
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("weight") {
+            continue;
+        }
+
+        let value = attr.parse_args::<LitInt>()?.base10_parse::<usize>()?;
+        if weight.replace(value).is_some() {
+            return Err(syn::Error::new(
+                attr.span(),
+                "`weight` specified more than once",
+            ));
+        }
+    }
+
+    Ok(weight.unwrap_or(1))
+}
+
+/// Parse the `#[arbitrary(...)]` and `#[strategy(...)]` attributes of a
+/// field.
+///
+/// `default` and `skip` fill the field with `Default::default()`, `value =
+/// expr` evaluates `expr` for every generated value and `#[strategy(expr)]`
+/// draws the field from the strategy `expr`.
+fn field_source(field: &Field) -> syn::Result<FieldSource> {
+    let mut source = None;
+
+    for attr in &field.attrs {
+        if attr.path().is_ident("strategy") {
+            if source
+                .replace(FieldSource::Strategy(attr.parse_args()?))
+                .is_some()
+            {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "a field takes at most one #[arbitrary] option or #[strategy]",
+                ));
+            }
+            continue;
+        }
+        if !attr.path().is_ident("arbitrary") {
+            continue;
+        }
+
+        let metas = attr.parse_args_with(
+            Punctuated::<syn::Meta, Token![,]>::parse_terminated,
+        )?;
+        for meta in metas {
+            let parsed = if meta.path().is_ident("default")
+                || meta.path().is_ident("skip")
+            {
+                meta.require_path_only()?;
+                FieldSource::Default
+            } else if meta.path().is_ident("value") {
+                FieldSource::Value(meta.require_name_value()?.value.clone())
+            } else {
+                return Err(syn::Error::new(
+                    meta.path().span(),
+                    "unknown #[arbitrary] option, expected `default`, `skip` or `value = expr`",
+                ));
+            };
+
+            if source.replace(parsed).is_some() {
+                return Err(syn::Error::new(
+                    meta.span(),
+                    "a field takes at most one #[arbitrary] option or #[strategy]",
+                ));
+            }
+        }
+    }
+
+    Ok(source.unwrap_or(FieldSource::Arbitrary))
+}
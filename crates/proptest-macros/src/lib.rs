@@ -1,18 +1,66 @@
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 use syn::{
     Expr,
+    ExprLit,
     FnArg,
+    ImplItem,
+    Item,
     ItemFn,
+    ItemImpl,
     Lit,
     MetaNameValue,
     Token,
     Type,
+    parse::Parser,
     parse_macro_input,
     punctuated::Punctuated,
     spanned::Spanned,
 };
 
+mod arbitrary;
+
+/// Derive `Arbitrary` for a struct or enum.
+///
+/// Every field is generated with its own `Arbitrary` impl. Enum variants are
+/// picked in proportion to their `#[weight(n)]` (1 by default, 0 never picks
+/// the variant), so rare but important variants can be sampled more often.
+///
+/// Enums whose variants contain the enum itself, e.g. through `Box<Self>` or
+/// `Option<Box<Self>>`, generate through `Generator::recurse` and only pick
+/// the variants that do not once the generator's recursion budget is spent,
+/// so their depth follows the generator's size and never exceeds its
+/// recursion limit.
+/// Fields that cannot or should not be generated take
+/// one `#[arbitrary(...)]` option:
+///
+/// - `default` or `skip` fills the field with `Default::default()`;
+/// - `value = expr` evaluates `expr` for every generated value.
+///
+/// A field marked `#[strategy(expr)]` is drawn from the strategy `expr`
+/// instead, like a `#[strategy]` argument of `#[proptest]`; a rejection by
+/// the strategy rejects the whole value.
+///
+/// Types with an invariant take `#[arbitrary(validate = path)]`, where
+/// `path` is a `fn(&Self) -> bool` such as `Self::is_valid`. `generate`
+/// draws again while values fail the check, up to `retries = n` draws (16
+/// by default), and rejects the last one if none passed.
+///
+/// `generate_tree` builds the same values from the trees of their fields, so
+/// failing values shrink field by field, in declaration order, through the
+/// fields' `#[strategy]` or their own `Arbitrary::generate_tree`. Enums also
+/// draw a value for each variant declared before the one picked, except
+/// recursive ones, and shrink by switching to the first of them that still
+/// fails before shrinking the fields. Shrinking skips simplifications that
+/// fail the `validate` check. Type parameters of the type must be `'static`.
+#[proc_macro_derive(Arbitrary, attributes(arbitrary, strategy, weight))]
+pub fn derive_arbitrary(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::DeriveInput);
+    arbitrary::expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
 #[proc_macro_attribute]
 /// Duplicate `#[strategy]` annotations on the same argument trigger a compile error.
 ///
@@ -27,7 +75,112 @@ use syn::{
 /// ) {}
 /// ```
 pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let attr_args = parse_macro_input!(attr with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+    let item = parse_macro_input!(item as Item);
+    let expanded = match item {
+        Item::Fn(function) => {
+            expand_fn(attr.into(), function, None).map(|(test, function)| {
+                quote! {
+                    #test
+                    #function
+                }
+            })
+        }
+        Item::Impl(item_impl) => expand_impl(attr.into(), item_impl),
+        other => Err(syn::Error::new(
+            other.span(),
+            "#[proptest] can only be applied to functions and impl blocks",
+        )),
+    };
+
+    expanded.unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+/// Expand `#[proptest]` on an impl block.
+///
+/// Associated functions marked `#[proptest(...)]` stay in the impl, and a
+/// free `#[test]` function with the same name is generated next to it.
+fn expand_impl(
+    attr: proc_macro2::TokenStream,
+    mut item_impl: ItemImpl,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "options go on the associated functions, not on the impl block",
+        ));
+    }
+
+    if let Some((_, path, _)) = &item_impl.trait_ {
+        return Err(syn::Error::new(
+            path.span(),
+            "#[proptest] only supports inherent impl blocks",
+        ));
+    }
+
+    if !item_impl.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item_impl.generics.span(),
+            "#[proptest] does not support generic impl blocks",
+        ));
+    }
+
+    let self_ty = (*item_impl.self_ty).clone();
+    let mut tests = Vec::new();
+
+    for item in item_impl.items.iter_mut() {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let Some(position) = method
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("proptest"))
+        else {
+            continue;
+        };
+
+        let attr = method.attrs.remove(position);
+        let args = match attr.meta {
+            syn::Meta::Path(_) => proc_macro2::TokenStream::new(),
+            syn::Meta::List(list) => list.tokens,
+            syn::Meta::NameValue(name_value) => {
+                return Err(syn::Error::new(
+                    name_value.span(),
+                    "expected #[proptest] or #[proptest(...)]",
+                ));
+            }
+        };
+
+        let function = ItemFn {
+            attrs: method.attrs.clone(),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        };
+        let (test, function) = expand_fn(args, function, Some(&self_ty))?;
+        method.attrs = function.attrs;
+        method.sig = function.sig;
+        tests.push(test);
+    }
+
+    Ok(quote! {
+        #item_impl
+        #( #tests )*
+    })
+}
+
+/// Build the `#[test]` wrapper for `function`, returning it together with
+/// the function the wrapper calls.
+///
+/// Free functions are renamed out of the way; associated functions of
+/// `self_ty` keep their name and are called through the type.
+fn expand_fn(
+    attr: proc_macro2::TokenStream,
+    mut function: ItemFn,
+    self_ty: Option<&Type>,
+) -> syn::Result<(proc_macro2::TokenStream, ItemFn)> {
+    let attr_args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated
+        .parse2(attr)?;
     let mut config = MacroConfig::default();
     let mut errors: Option<syn::Error> = None;
 
@@ -40,94 +193,132 @@ pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
         }
     }
 
-    if let Some(err) = errors {
-        return err.to_compile_error().into();
+    if let Err(err) = config.validate() {
+        match &mut errors {
+            Some(existing) => existing.combine(err),
+            None => errors = Some(err),
+        }
     }
 
-    let mut function = parse_macro_input!(item as ItemFn);
+    if let Some(err) = errors {
+        return Err(err);
+    }
 
     if let Some(async_token) = &function.sig.asyncness {
-        return syn::Error::new(
+        return Err(syn::Error::new(
             async_token.span(),
-            "#[proptest] does not support async functions",
-        )
-        .to_compile_error()
-        .into();
+            "#[proptest] does not support async functions; use `async_test = true` for harnesses that need an async test",
+        ));
     }
 
     if let Some(const_token) = &function.sig.constness {
-        return syn::Error::new(
+        return Err(syn::Error::new(
             const_token.span(),
             "#[proptest] does not support const functions",
-        )
-        .to_compile_error()
-        .into();
+        ));
     }
 
     if let Some(abi) = &function.sig.abi {
-        return syn::Error::new(
+        return Err(syn::Error::new(
             abi.extern_token.span(),
             "#[proptest] does not support extern functions",
-        )
-        .to_compile_error()
-        .into();
+        ));
     }
 
     if !function.sig.generics.params.is_empty() {
-        return syn::Error::new(
+        return Err(syn::Error::new(
             function.sig.generics.span(),
             "#[proptest] does not support generic functions",
-        )
-        .to_compile_error()
-        .into();
+        ));
     }
 
     struct Argument {
+        name: String,
+        /// The parameter's type as written, which examples are bound to.
+        param_ty: Type,
         ty: Type,
-        strategy: Option<Expr>,
+        borrow: Borrow,
+        source: Source,
     }
 
     let mut arguments = Vec::<Argument>::new();
+    let mut context_seen = false;
 
     for input in function.sig.inputs.iter_mut() {
         match input {
             FnArg::Receiver(receiver) => {
-                return syn::Error::new(
+                return Err(syn::Error::new(
                     receiver.span(),
                     "#[proptest] cannot be applied to methods",
-                )
-                .to_compile_error()
-                .into();
+                ));
             }
             FnArg::Typed(pat_type) => {
-                let mut strategy_expr: Option<Expr> = None;
+                let mut source: Option<(&'static str, Source)> = None;
                 let mut retained_attrs = Vec::new();
 
                 for attr in pat_type.attrs.drain(..) {
-                    if attr.path().is_ident("strategy") {
-                        if strategy_expr.is_some() {
-                            return syn::Error::new(
-                                attr.span(),
-                                "#[strategy] cannot be specified more than once per argument",
-                            )
-                            .to_compile_error()
-                            .into();
+                    let (name, parsed) = match parse_source(&attr) {
+                        Ok(Some(parsed)) => parsed,
+                        Ok(None) => {
+                            retained_attrs.push(attr);
+                            continue;
                         }
+                        Err(err) => return Err(err),
+                    };
+
+                    if let Some((existing, _)) = &source {
+                        let message = if *existing == name {
+                            format!(
+                                "#[{name}] cannot be specified more than once per argument"
+                            )
+                        } else {
+                            format!(
+                                "#[{name}] cannot be combined with #[{existing}]"
+                            )
+                        };
+                        return Err(syn::Error::new(attr.span(), message));
+                    }
 
-                        match attr.parse_args::<Expr>() {
-                            Ok(expr) => strategy_expr = Some(expr),
-                            Err(err) => return err.to_compile_error().into(),
+                    if let Source::Context = parsed {
+                        if !config.has_context() {
+                            return Err(syn::Error::new(
+                                attr.span(),
+                                "#[context] requires `setup` or `setup_once`",
+                            ));
                         }
-                    } else {
-                        retained_attrs.push(attr);
+                        if context_seen {
+                            return Err(syn::Error::new(
+                                attr.span(),
+                                "#[context] can only be used on one argument",
+                            ));
+                        }
+                        context_seen = true;
                     }
+
+                    source = Some((name, parsed));
                 }
 
                 pat_type.attrs = retained_attrs;
 
+                let (ty, borrow) = owned_type(&pat_type.ty);
+                let source =
+                    source.map_or(Source::Arbitrary, |(_, source)| source);
+                if matches!(source, Source::Context)
+                    && matches!(borrow, Borrow::Owned)
+                {
+                    return Err(syn::Error::new(
+                        pat_type.ty.span(),
+                        "#[context] arguments must be `&T` or `&mut T`",
+                    ));
+                }
+
+                let name = pat_type.pat.to_token_stream().to_string();
                 arguments.push(Argument {
-                    ty: (*pat_type.ty).clone(),
-                    strategy: strategy_expr,
+                    name,
+                    param_ty: (*pat_type.ty).clone(),
+                    ty,
+                    borrow,
+                    source,
                 });
             }
         }
@@ -135,10 +326,13 @@ pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let mut doc_attrs = Vec::new();
     let mut other_attrs = Vec::new();
+    let mut examples = config.examples.take().unwrap_or_default();
 
     for attr in function.attrs.drain(..) {
         if attr.path().is_ident("doc") {
             doc_attrs.push(attr);
+        } else if attr.path().is_ident("example") {
+            examples.push(parse_example(&attr)?);
         } else {
             other_attrs.push(attr);
         }
@@ -149,46 +343,110 @@ pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let vis = function.vis.clone();
     let original_ident = function.sig.ident.clone();
-    let inner_ident = format_ident!("__{}_proptest_impl", original_ident);
-    function.sig.ident = inner_ident.clone();
-    function.vis = syn::Visibility::Inherited;
+    let inner_path = match self_ty {
+        Some(self_ty) => quote! { <#self_ty>::#original_ident },
+        None => {
+            let inner_ident =
+                format_ident!("__{}_proptest_impl", original_ident);
+            function.sig.ident = inner_ident.clone();
+            function.vis = syn::Visibility::Inherited;
+            quote! { #inner_ident }
+        }
+    };
 
     let mut bindings = Vec::new();
-    let mut binding_idents = Vec::new();
+    // The trees of the generated arguments, which the runner moves to shrink
+    // a failing case, and the bindings taking their values once it did.
+    let mut trees = Vec::new();
+    let mut tree_bindings = Vec::new();
+    let mut call_args = Vec::new();
+    // Bindings of the arguments examples do not list, which they share with
+    // generated cases.
+    let mut fixed_bindings = Vec::new();
+    let (registry_tokens, generate_arbitrary) = config.registry_tokens();
 
     for (index, argument) in arguments.iter().enumerate() {
+        if let Source::Context = argument.source {
+            call_args.push(match argument.borrow {
+                Borrow::Shared => quote! { &*__context },
+                Borrow::Mutable | Borrow::Owned => quote! { __context },
+            });
+            continue;
+        }
+
         let binding_ident = format_ident!("__proptest_binding_{index}");
-        binding_idents.push(binding_ident.clone());
         let ty = &argument.ty;
+        // Under a model the body gets clones of the bindings, which are
+        // only ever mutated there.
+        let mutability = match argument.borrow {
+            Borrow::Mutable if config.model.is_none() => quote! { mut },
+            Borrow::Mutable | Borrow::Owned | Borrow::Shared => quote! {},
+        };
+        // Shared arguments drawn from a strategy borrow straight from the
+        // value tree instead of cloning its value, unless the body runs
+        // under a model and needs values it owns.
+        let borrows_tree = matches!(argument.source, Source::Strategy(_))
+            && matches!(argument.borrow, Borrow::Shared)
+            && config.model.is_none();
+        call_args.push(match argument.borrow {
+            Borrow::Owned => quote! { #binding_ident },
+            Borrow::Shared if borrows_tree => quote! { &*#binding_ident },
+            Borrow::Shared => quote! { &#binding_ident },
+            Borrow::Mutable => quote! { &mut #binding_ident },
+        });
 
-        let binding_stmt = match &argument.strategy {
-            Some(expr) => {
+        let argument_name = &argument.name;
+        let source_name = match &argument.source {
+            Source::Strategy(expr) => expr.to_token_stream().to_string(),
+            _ => format!("Arbitrary for {}", ty.to_token_stream()),
+        };
+        let binding_stmt = match &argument.source {
+            Source::Strategy(expr) => {
                 let strategy_ident = format_ident!("__strategy_{index}");
+                let tree_ident = format_ident!("__proptest_tree_{index}");
+                let provenance_ident =
+                    format_ident!("__proptest_provenance_{index}");
+                let binding = if borrows_tree {
+                    quote! {
+                        let #binding_ident: &#ty =
+                            ::estoa_proptest::strategy::ValueTree::current(&#tree_ident);
+                    }
+                } else {
+                    quote! {
+                        let #mutability #binding_ident: #ty =
+                            ::estoa_proptest::strategy::ValueTree::into_current(#tree_ident);
+                    }
+                };
+                trees.push(tree_ident.clone());
+                tree_bindings.push(quote! {
+                    // Taken before the binding, which may consume the tree.
+                    let #provenance_ident = if ::estoa_proptest::runner::observing() {
+                        ::estoa_proptest::strategy::ValueTree::provenance(&#tree_ident)
+                    } else {
+                        ::core::option::Option::None
+                    };
+                    #binding
+                });
                 quote! {
                     let mut #strategy_ident = ::estoa_proptest::strategy::runtime::adapt(#expr);
-                    let #binding_ident: #ty = {
-                        let mut __attempts = 0usize;
+                    let mut #tree_ident = {
+                        let mut __rejections = ::estoa_proptest::runner::Rejections::new(
+                            #argument_name,
+                            #source_name,
+                            __REJECTION_LIMIT,
+                        );
                         loop {
-                            match ::estoa_proptest::strategy::runtime::execute(
+                            match ::estoa_proptest::strategy::runtime::execute_tree(
                                 &mut #strategy_ident,
-                                &mut generator,
+                                generator,
                             ) {
                                 ::estoa_proptest::strategy::runtime::Generation::Accepted { value, .. } => {
                                     generator.advance_iteration();
                                     break value;
                                 }
-                                ::estoa_proptest::strategy::runtime::Generation::Rejected { iteration, depth, .. } => {
+                                ::estoa_proptest::strategy::runtime::Generation::Rejected { iteration, depth, reason, .. } => {
                                     generator.advance_iteration();
-                                    __attempts += 1;
-                                    if __attempts >= __REJECTION_LIMIT {
-                                        panic!(
-                                            "#[proptest] strategy rejected value after {} attempts (iteration {}, depth {}; limit {})",
-                                            __attempts,
-                                            iteration,
-                                            depth,
-                                            __REJECTION_LIMIT,
-                                        );
-                                    }
+                                    __rejections.record(iteration, depth, reason);
                                     continue;
                                 }
                             }
@@ -196,28 +454,51 @@ pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
                     };
                 }
             }
-            None => {
+            Source::Context => {
+                unreachable!("context arguments are not generated")
+            }
+            Source::Fixture(expr) => {
+                quote! {
+                    let #mutability #binding_ident: #ty = #expr;
+                }
+            }
+            Source::Values(_) => {
+                let values_ident = format_ident!("__proptest_values_{index}");
+                quote! {
+                    let #mutability #binding_ident: #ty =
+                        ::core::clone::Clone::clone(&#values_ident);
+                }
+            }
+            Source::Arbitrary => {
+                let tree_ident = format_ident!("__proptest_tree_{index}");
+                let provenance_ident =
+                    format_ident!("__proptest_provenance_{index}");
+                trees.push(tree_ident.clone());
+                tree_bindings.push(quote! {
+                    let #provenance_ident = if ::estoa_proptest::runner::observing() {
+                        ::estoa_proptest::strategy::ValueTree::provenance(&#tree_ident)
+                    } else {
+                        ::core::option::Option::None
+                    };
+                    let #mutability #binding_ident: #ty =
+                        ::estoa_proptest::ArbitraryTree::into_current(#tree_ident);
+                });
                 quote! {
-                    let #binding_ident: #ty = {
-                        let mut __attempts = 0usize;
+                    let mut #tree_ident: ::estoa_proptest::ArbitraryTree<#ty> = {
+                        let mut __rejections = ::estoa_proptest::runner::Rejections::new(
+                            #argument_name,
+                            #source_name,
+                            __REJECTION_LIMIT,
+                        );
                         loop {
-                            match ::estoa_proptest::strategy::runtime::from_arbitrary(&mut generator) {
+                            match #generate_arbitrary {
                                 ::estoa_proptest::strategy::runtime::Generation::Accepted { value, .. } => {
                                     generator.advance_iteration();
                                     break value;
                                 }
-                                ::estoa_proptest::strategy::runtime::Generation::Rejected { iteration, depth, .. } => {
+                                ::estoa_proptest::strategy::runtime::Generation::Rejected { iteration, depth, reason, .. } => {
                                     generator.advance_iteration();
-                                    __attempts += 1;
-                                    if __attempts >= __REJECTION_LIMIT {
-                                        panic!(
-                                            "#[proptest] strategy rejected value after {} attempts (iteration {}, depth {}; limit {})",
-                                            __attempts,
-                                            iteration,
-                                            depth,
-                                            __REJECTION_LIMIT,
-                                        );
-                                    }
+                                    __rejections.record(iteration, depth, reason);
                                     continue;
                                 }
                             }
@@ -227,45 +508,470 @@ pub fn proptest(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         };
 
+        if matches!(argument.source, Source::Fixture(_) | Source::Values(_)) {
+            fixed_bindings.push(binding_stmt.clone());
+        }
         bindings.push(binding_stmt);
     }
 
-    let outer_rng_setup = if bindings.is_empty() {
-        quote! {}
+    let uses_generator = arguments.iter().any(|argument| {
+        matches!(argument.source, Source::Arbitrary | Source::Strategy(_))
+    });
+    let generator_ident = if uses_generator {
+        quote! { generator }
     } else {
-        quote! {
-            let mut generator = ::estoa_proptest::strategy::runtime::Generator::build(
-                ::estoa_proptest::rng(),
-            ).with_limit(
-                __RECURSION_LIMIT,
-            );
-        }
+        quote! { _ }
     };
 
     let cases_tokens = config.cases_tokens();
     let recursion_limit_tokens = config.recursion_limit_tokens();
     let rejection_limit_tokens = config.rejection_limit_tokens();
+    let entropy_limit_tokens = config.entropy_limit_tokens();
+    let threads_tokens = config.threads_tokens();
+    let max_failures_tokens = config.max_failures_tokens();
+    let retries_tokens = config.retries_tokens();
+    let time_budget_tokens = config.time_budget_tokens();
+    let swarm_tokens = config.swarm_tokens();
+    let shrink_tokens = config.shrink_tokens();
+    let dedup_tokens = config.dedup_tokens();
+    let capture_panics_tokens = config.capture_panics_tokens();
+    let chaos_order_tokens = config.chaos_order_tokens();
+    let observer_tokens = config.observer_tokens();
+
+    // Only generated arguments take part in deduplication: fixtures are
+    // constructed rather than generated, and `#[values]` are fixed per run.
+    let generated: Vec<_> = arguments
+        .iter()
+        .enumerate()
+        .filter(|(_, argument)| {
+            matches!(argument.source, Source::Arbitrary | Source::Strategy(_))
+        })
+        .map(|(index, _)| format_ident!("__proptest_binding_{index}"))
+        .collect();
+    let dedup_check = if config.dedup == Some(true) && !generated.is_empty() {
+        quote! {
+            if ::estoa_proptest::runner::is_duplicate(&( #( &#generated, )* )) {
+                return;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Observers see every argument but the context, formatted only when a
+    // run has observers and falling back to `None` for non-`Debug` types.
+    let observed: Vec<_> = arguments
+        .iter()
+        .enumerate()
+        .filter(|(_, argument)| !matches!(argument.source, Source::Context))
+        .map(|(index, argument)| {
+            let name = &argument.name;
+            let binding = format_ident!("__proptest_binding_{index}");
+            let provenance = if matches!(
+                argument.source,
+                Source::Arbitrary | Source::Strategy(_)
+            ) {
+                let provenance = format_ident!("__proptest_provenance_{index}");
+                quote! { ::core::clone::Clone::clone(&#provenance) }
+            } else {
+                quote! { ::core::option::Option::None }
+            };
+            quote! {
+                ::estoa_proptest::observer::CaseInput {
+                    name: #name,
+                    value: (&::estoa_proptest::observer::Describe(&#binding))
+                        .describe(),
+                    provenance: #provenance,
+                }
+            }
+        })
+        .collect();
+    // The bindings a model hands the body, and whether the body mutates
+    // them.
+    let arguments_bound: Vec<_> = arguments
+        .iter()
+        .enumerate()
+        .filter(|(_, argument)| !matches!(argument.source, Source::Context))
+        .map(|(index, argument)| {
+            (
+                format_ident!("__proptest_binding_{index}"),
+                matches!(argument.borrow, Borrow::Mutable),
+            )
+        })
+        .collect();
+    let record_inputs = quote! {
+        if ::estoa_proptest::runner::observing() {
+            #[allow(unused_imports)]
+            use ::estoa_proptest::observer::{
+                DescribeDebug as _,
+                DescribeOpaque as _,
+            };
+            ::estoa_proptest::runner::record_inputs(
+                ::std::vec![ #( #observed ),* ],
+            );
+        }
+    };
+
+    let call = quote! {
+        ::estoa_proptest::runner::CaseOutcome::check(
+            #inner_path( #( #call_args ),* ),
+        );
+    };
+    let call = config.model_tokens(&arguments_bound, call, observed.clone());
+    let shrink_trees = (!trees.is_empty()).then(|| {
+        quote! {
+            if !::estoa_proptest::runner::shrink_trees(&mut [
+                #( &mut #trees as &mut dyn ::estoa_proptest::shrink::ErasedValueTree ),*
+            ]) {
+                return;
+            }
+        }
+    });
+    let case_body = quote! {
+        #( #bindings )*
+        #shrink_trees
+        #( #tree_bindings )*
+        #record_inputs
+        #dedup_check
+        #call
+    };
+    let (test_context, case_body) = config.hook_tokens(case_body);
+
+    let mut run = quote! {
+        ::estoa_proptest::runner::run(&__config, |#generator_ident| {
+            #case_body
+        });
+    };
+
+    // Examples run before the generated cases, with the arguments they list
+    // in place of generated ones.
+    let generated_count = generated.len();
+    let mut example_runs = Vec::new();
+    for (example_index, example) in examples.iter().enumerate() {
+        let mut values = example_values(example, generated_count)?.into_iter();
+        let mut example_bindings = fixed_bindings.clone();
+        let mut example_args = Vec::new();
+        for (index, argument) in arguments.iter().enumerate() {
+            if !matches!(
+                argument.source,
+                Source::Arbitrary | Source::Strategy(_)
+            ) {
+                example_args.push(call_args[index].clone());
+                continue;
+            }
+
+            let binding_ident = format_ident!("__proptest_binding_{index}");
+            let param_ty = &argument.param_ty;
+            let value =
+                values.next().expect("one value per generated argument");
+            example_bindings.push(quote! {
+                let #binding_ident: #param_ty = #value;
+            });
+            example_args.push(quote! { #binding_ident });
+        }
+
+        let call = quote! {
+            ::estoa_proptest::runner::CaseOutcome::check(
+                #inner_path( #( #example_args ),* ),
+            );
+        };
+        let call = config.model_tokens(&arguments_bound, call, Vec::new());
+        let example_body = quote! {
+            #( #example_bindings )*
+            #call
+        };
+        let (_, example_body) = config.hook_tokens(example_body);
+        example_runs.push(quote! {
+            ::estoa_proptest::runner::run_example(&__config, #example_index, || {
+                #example_body
+            });
+        });
+    }
+    run = quote! {
+        #( #example_runs )*
+        #run
+    };
+
+    // Every `#[values]` argument wraps the run in a loop over its values,
+    // innermost argument first, so the run covers their cartesian product.
+    let matrix: Vec<_> = arguments
+        .iter()
+        .enumerate()
+        .filter_map(|(index, argument)| match &argument.source {
+            Source::Values(values) => Some((index, argument, values)),
+            _ => None,
+        })
+        .collect();
+
+    if !matrix.is_empty() {
+        let label_format = matrix
+            .iter()
+            .map(|(_, argument, _)| format!("{} = {{:?}}", argument.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let label_args = matrix
+            .iter()
+            .map(|(index, _, _)| format_ident!("__proptest_values_{index}"));
+        run = quote! {
+            let __config = __config
+                .clone()
+                .with_label(::std::format!(#label_format, #( &#label_args ),*));
+            #run
+        };
+    }
+
+    if let Some(strength) = config.covering {
+        if matrix.is_empty() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`covering` requires #[values] arguments",
+            ));
+        }
+
+        // Run only the rows of a covering array instead of the full product,
+        // so every `strength`-wise combination of listed values still runs.
+        let levels = matrix.iter().map(|(_, _, values)| values.len());
+        let arrays = matrix.iter().map(|(index, argument, values)| {
+            let levels_ident = format_ident!("__proptest_levels_{index}");
+            let ty = &argument.ty;
+            let len = values.len();
+            quote! {
+                let #levels_ident: [#ty; #len] = [ #( #values ),* ];
+            }
+        });
+        let picks = matrix.iter().enumerate().map(|(column, (index, ..))| {
+            let levels_ident = format_ident!("__proptest_levels_{index}");
+            let values_ident = format_ident!("__proptest_values_{index}");
+            quote! {
+                let #values_ident =
+                    ::core::clone::Clone::clone(&#levels_ident[__row[#column]]);
+            }
+        });
+        run = quote! {
+            #( #arrays )*
+            for __row in ::estoa_proptest::covering::covering_array(
+                &[ #( #levels ),* ],
+                #strength,
+            ) {
+                #( #picks )*
+                #run
+            }
+        };
+    } else {
+        for (index, argument, values) in matrix.iter().rev() {
+            let values_ident = format_ident!("__proptest_values_{index}");
+            let ty = &argument.ty;
+            let len = values.len();
+            run = quote! {
+                let __values: [#ty; #len] = [ #( #values ),* ];
+                for #values_ident in __values {
+                    #run
+                }
+            };
+        }
+    }
+
+    let (test_attr, asyncness) = config.test_attr_tokens();
+    let test_name = original_ident.to_string();
+    // `line!()` with the span of the name reports the line of the `fn`.
+    let test_line = quote_spanned! { original_ident.span()=> ::core::line!() };
 
     let output = quote! {
         #( #doc_attrs )*
         #( #outer_attrs )*
-        #[test]
-        #vis fn #original_ident() {
+        #test_attr
+        #vis #asyncness fn #original_ident() {
             const __CASES: usize = #cases_tokens;
             const __RECURSION_LIMIT: usize = #recursion_limit_tokens;
             const __REJECTION_LIMIT: usize = #rejection_limit_tokens;
-            for __case in 0..__CASES {
-                let _ = __case;
-                #outer_rng_setup
-                #( #bindings )*
-                #inner_ident( #( #binding_idents ),* );
-            }
+            let __config = ::estoa_proptest::runner::Config::new(__CASES)
+                .with_recursion_limit(__RECURSION_LIMIT)
+                .with_test(
+                    ::core::concat!(::core::module_path!(), "::", #test_name),
+                    ::core::file!(),
+                    #test_line,
+                )
+                #entropy_limit_tokens
+                #threads_tokens
+                #retries_tokens
+                #max_failures_tokens
+                #time_budget_tokens
+                #swarm_tokens
+                #dedup_tokens
+                #capture_panics_tokens
+                #chaos_order_tokens
+                #shrink_tokens
+                #observer_tokens;
+            #test_context
+            #registry_tokens
+            #run
         }
+    };
+
+    Ok((output, function))
+}
 
-        #function
+/// Where the value of an argument comes from.
+enum Source {
+    Arbitrary,
+    Strategy(Expr),
+    /// Explicit values; the property runs once per combination of them.
+    Values(Vec<Expr>),
+    /// An expression evaluated for every case instead of generating a value.
+    Fixture(Expr),
+    /// The context created by the `setup`/`setup_once` hook.
+    Context,
+}
+
+/// Parse an argument attribute that decides where the argument comes from.
+///
+/// Returns `None` for attributes that are not ours, so they stay on the
+/// argument.
+fn parse_source(
+    attr: &syn::Attribute,
+) -> syn::Result<Option<(&'static str, Source)>> {
+    let path = attr.path();
+    if path.is_ident("strategy") {
+        Ok(Some(("strategy", Source::Strategy(attr.parse_args()?))))
+    } else if path.is_ident("fixture") {
+        Ok(Some(("fixture", Source::Fixture(attr.parse_args()?))))
+    } else if path.is_ident("values") {
+        let values = attr
+            .parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+        if values.is_empty() {
+            return Err(syn::Error::new(
+                attr.span(),
+                "#[values] needs at least one value",
+            ));
+        }
+        Ok(Some((
+            "values",
+            Source::Values(values.into_iter().collect()),
+        )))
+    } else if path.is_ident("context") {
+        attr.meta.require_path_only()?;
+        Ok(Some(("context", Source::Context)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parse `#[example(a, b)]` into the example `(a, b)`, or `#[example(a)]`
+/// into `a`.
+fn parse_example(attr: &syn::Attribute) -> syn::Result<Expr> {
+    let values =
+        attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+    match values.len() {
+        0 => Err(syn::Error::new(
+            attr.span(),
+            "#[example] needs a value for every generated argument",
+        )),
+        1 => Ok(values.into_iter().next().expect("one value")),
+        _ => {
+            // The parentheses of the attribute become the tuple's, so errors
+            // about the example point at it.
+            let syn::MacroDelimiter::Paren(paren_token) =
+                attr.meta.require_list()?.delimiter
+            else {
+                return Err(syn::Error::new(
+                    attr.span(),
+                    "expected #[example(...)]",
+                ));
+            };
+            Ok(Expr::Tuple(syn::ExprTuple {
+                attrs: Vec::new(),
+                paren_token,
+                elems: values,
+            }))
+        }
+    }
+}
+
+/// Split an example into one value per generated argument.
+///
+/// With several generated arguments the example is a tuple of their values.
+/// A single argument takes the whole example, unless it is written as the
+/// one-element tuple `(value,)`.
+fn example_values(example: &Expr, generated: usize) -> syn::Result<Vec<Expr>> {
+    let tuple = match example {
+        Expr::Tuple(tuple) => Some(&tuple.elems),
+        _ => None,
+    };
+    match (generated, tuple) {
+        (0, _) => Err(syn::Error::new(
+            example.span(),
+            "examples need at least one generated argument",
+        )),
+        (1, Some(elems)) if elems.len() == 1 => {
+            Ok(elems.iter().cloned().collect())
+        }
+        (1, _) => Ok(vec![example.clone()]),
+        (_, Some(elems)) if elems.len() == generated => {
+            Ok(elems.iter().cloned().collect())
+        }
+        (_, Some(elems)) => Err(syn::Error::new(
+            example.span(),
+            format!(
+                "example has {} values but the property generates {} arguments",
+                elems.len(),
+                generated,
+            ),
+        )),
+        (_, None) => Err(syn::Error::new(
+            example.span(),
+            format!(
+                "example must be a tuple of values for the {} generated arguments",
+                generated,
+            ),
+        )),
+    }
+}
+
+/// How a generated value is handed to the test body.
+enum Borrow {
+    Owned,
+    Shared,
+    Mutable,
+}
+
+/// Map a parameter type to the owned type that is generated for it.
+///
+/// References are backed by an owned value living for the whole case: `&str`
+/// is generated as `String`, `&[T]` as `Vec<T>`, and `&T` as `T`. A
+/// `&'static T` cannot borrow from the case, so it is a value of its own,
+/// such as a fixture shared by the whole process.
+fn owned_type(ty: &Type) -> (Type, Borrow) {
+    let Type::Reference(reference) = ty else {
+        return (ty.clone(), Borrow::Owned);
+    };
+    if reference
+        .lifetime
+        .as_ref()
+        .is_some_and(|lifetime| lifetime.ident == "static")
+    {
+        return (ty.clone(), Borrow::Owned);
+    }
+
+    let borrow = if reference.mutability.is_some() {
+        Borrow::Mutable
+    } else {
+        Borrow::Shared
     };
 
-    output.into()
+    let owned = match &*reference.elem {
+        Type::Path(path)
+            if path.qself.is_none() && path.path.is_ident("str") =>
+        {
+            syn::parse_quote! { ::std::string::String }
+        }
+        Type::Slice(slice) => {
+            let elem = &slice.elem;
+            syn::parse_quote! { ::std::vec::Vec<#elem> }
+        }
+        other => other.clone(),
+    };
+
+    (owned, borrow)
 }
 
 #[derive(Default)]
@@ -273,6 +979,27 @@ struct MacroConfig {
     cases: Option<usize>,
     recursion_limit: Option<usize>,
     rejection_limit: Option<usize>,
+    entropy_limit: Option<usize>,
+    max_failures: Option<usize>,
+    threads: Option<usize>,
+    retries: Option<usize>,
+    time_budget_ms: Option<usize>,
+    covering: Option<usize>,
+    swarm: Option<bool>,
+    dedup: Option<bool>,
+    capture_panics: Option<bool>,
+    chaos_order: Option<bool>,
+    async_test: Option<bool>,
+    shrink: Option<syn::Ident>,
+    setup: Option<syn::Path>,
+    teardown: Option<syn::Path>,
+    setup_once: Option<syn::Path>,
+    teardown_once: Option<syn::Path>,
+    registry: Option<syn::Path>,
+    observer: Option<syn::Path>,
+    model: Option<syn::Path>,
+    examples: Option<Vec<Expr>>,
+    test_attr: Option<Expr>,
 }
 
 impl MacroConfig {
@@ -281,8 +1008,29 @@ impl MacroConfig {
             syn::Error::new(name_value.path.span(), "expected identifier")
         })?;
         let key = ident.to_string();
+        if let "setup" | "teardown" | "setup_once" | "teardown_once"
+        | "registry" | "observer" | "model" = key.as_str()
+        {
+            return self.apply_hook(&ident, &name_value.value);
+        }
+        if key == "test_attr" {
+            return self.apply_test_attr(&ident, name_value.value);
+        }
+        if let "swarm" | "dedup" | "capture_panics" | "chaos_order"
+        | "async_test" = key.as_str()
+        {
+            return self.apply_flag(&ident, &name_value.value);
+        }
+        if key == "shrink" {
+            return self.apply_shrink(&ident, &name_value.value);
+        }
+        if key == "examples" {
+            return self.apply_examples(&ident, name_value.value);
+        }
+
         let value = parse_usize(&name_value.value, &key)?;
-        if value == 0 {
+        // `retries = 0` turns off retries `ESTOA_PROPTEST_RETRIES` asks for.
+        if value == 0 && key != "retries" {
             return Err(syn::Error::new(
                 name_value.value.span(),
                 format!("`{}` must be at least 1", key),
@@ -320,6 +1068,66 @@ impl MacroConfig {
                     Ok(())
                 }
             }
+            "threads" => {
+                if self.threads.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`threads` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            "retries" => {
+                if self.retries.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`retries` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            "time_budget_ms" => {
+                if self.time_budget_ms.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`time_budget_ms` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            "covering" => {
+                if self.covering.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`covering` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            "max_failures" => {
+                if self.max_failures.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`max_failures` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            "entropy_limit" => {
+                if self.entropy_limit.replace(value).is_some() {
+                    Err(syn::Error::new(
+                        ident.span(),
+                        "`entropy_limit` specified more than once",
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
             _ => Err(syn::Error::new(
                 ident.span(),
                 format!("unknown #[proptest] option `{}`", key),
@@ -327,15 +1135,325 @@ impl MacroConfig {
         }
     }
 
+    fn apply_flag(
+        &mut self,
+        ident: &syn::Ident,
+        value: &Expr,
+    ) -> syn::Result<()> {
+        let key = ident.to_string();
+        let flag = match value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Bool(flag),
+                ..
+            }) => flag.value,
+            _ => {
+                return Err(syn::Error::new(
+                    value.span(),
+                    format!("`{}` must be `true` or `false`", key),
+                ));
+            }
+        };
+
+        let slot = match key.as_str() {
+            "swarm" => &mut self.swarm,
+            "dedup" => &mut self.dedup,
+            "chaos_order" => &mut self.chaos_order,
+            "async_test" => &mut self.async_test,
+            _ => &mut self.capture_panics,
+        };
+        if slot.replace(flag).is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!("`{}` specified more than once", key),
+            ));
+        }
+        Ok(())
+    }
+
+    fn apply_shrink(
+        &mut self,
+        ident: &syn::Ident,
+        value: &Expr,
+    ) -> syn::Result<()> {
+        let scheduler = match value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(name),
+                ..
+            }) => match name.value().as_str() {
+                "depth_first" => "DepthFirst",
+                "breadth_first" => "BreadthFirst",
+                "ddmin" => "Ddmin",
+                _ => "",
+            },
+            _ => "",
+        };
+        if scheduler.is_empty() {
+            return Err(syn::Error::new(
+                value.span(),
+                "`shrink` must be \"depth_first\", \"breadth_first\" or \
+                 \"ddmin\"",
+            ));
+        }
+
+        let scheduler = syn::Ident::new(scheduler, value.span());
+        if self.shrink.replace(scheduler).is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                "`shrink` specified more than once",
+            ));
+        }
+        Ok(())
+    }
+
+    fn apply_examples(
+        &mut self,
+        ident: &syn::Ident,
+        value: Expr,
+    ) -> syn::Result<()> {
+        let Expr::Array(array) = value else {
+            return Err(syn::Error::new(
+                value.span(),
+                "`examples` must be an array such as `[(0, \"x\"), (1, \"\")]`",
+            ));
+        };
+
+        let examples = array.elems.into_iter().collect();
+        if self.examples.replace(examples).is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                "`examples` specified more than once",
+            ));
+        }
+        Ok(())
+    }
+
+    fn apply_test_attr(
+        &mut self,
+        ident: &syn::Ident,
+        value: Expr,
+    ) -> syn::Result<()> {
+        // `tokio::test` parses as a path and `tokio::test(flavor = "...")`
+        // as a call, and both print back as the attribute tokens.
+        if !matches!(value, Expr::Path(_) | Expr::Call(_)) {
+            return Err(syn::Error::new(
+                value.span(),
+                "`test_attr` must be an attribute path such as `tokio::test`",
+            ));
+        }
+
+        if self.test_attr.replace(value).is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                "`test_attr` specified more than once",
+            ));
+        }
+        Ok(())
+    }
+
+    /// The harness attribute of the generated test and whether that harness
+    /// needs an `async fn`.
+    fn test_attr_tokens(
+        &self,
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let attr = match &self.test_attr {
+            Some(attr) => quote! { #[#attr] },
+            None => quote! { #[test] },
+        };
+        let asyncness = match self.async_test {
+            Some(true) => quote! { async },
+            _ => quote! {},
+        };
+        (attr, asyncness)
+    }
+
+    fn apply_hook(
+        &mut self,
+        ident: &syn::Ident,
+        value: &Expr,
+    ) -> syn::Result<()> {
+        let key = ident.to_string();
+        let Expr::Path(path) = value else {
+            return Err(syn::Error::new(
+                value.span(),
+                format!("`{}` must be a path to a function", key),
+            ));
+        };
+
+        let slot = match key.as_str() {
+            "setup" => &mut self.setup,
+            "teardown" => &mut self.teardown,
+            "setup_once" => &mut self.setup_once,
+            "teardown_once" => &mut self.teardown_once,
+            "registry" => &mut self.registry,
+            "observer" => &mut self.observer,
+            _ => &mut self.model,
+        };
+        if slot.replace(path.path.clone()).is_some() {
+            return Err(syn::Error::new(
+                ident.span(),
+                format!("`{}` specified more than once", key),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check combinations of options once every option has been applied.
+    fn validate(&self) -> syn::Result<()> {
+        if let (Some(_), Some(setup_once)) = (&self.setup, &self.setup_once) {
+            return Err(syn::Error::new(
+                setup_once.span(),
+                "`setup` and `setup_once` cannot be combined",
+            ));
+        }
+        if let (Some(teardown), None) = (&self.teardown, &self.setup) {
+            return Err(syn::Error::new(
+                teardown.span(),
+                "`teardown` requires `setup`",
+            ));
+        }
+        if let (Some(teardown), None) = (&self.teardown_once, &self.setup_once)
+        {
+            return Err(syn::Error::new(
+                teardown.span(),
+                "`teardown_once` requires `setup_once`",
+            ));
+        }
+        if self.async_test == Some(true) && self.test_attr.is_none() {
+            return Err(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`async_test` requires a `test_attr` harness that runs async tests, such as `tokio::test`",
+            ));
+        }
+        // The model runs the body on its own threads, where a context
+        // borrowed from the test cannot follow.
+        if let (Some(model), true) = (&self.model, self.has_context()) {
+            return Err(syn::Error::new(
+                model.span(),
+                "`model` cannot be combined with `setup` or `setup_once`",
+            ));
+        }
+        Ok(())
+    }
+
+    fn has_context(&self) -> bool {
+        self.setup.is_some() || self.setup_once.is_some()
+    }
+
+    /// Wrap `case_body` in the setup/teardown hooks.
+    ///
+    /// Returns the statements to emit before the run (for `setup_once`) and
+    /// the new case body.
+    fn hook_tokens(
+        &self,
+        case_body: proc_macro2::TokenStream,
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let drop = quote! { ::core::mem::drop };
+        let with_context = quote! {
+            __proptest_context.with(|__context| {
+                #case_body
+            });
+        };
+
+        if let Some(setup) = &self.setup {
+            let teardown =
+                self.teardown.as_ref().map_or(drop, |path| quote! { #path });
+            let body = quote! {
+                let __proptest_context =
+                    ::estoa_proptest::runner::Context::new(#setup(), #teardown);
+                #with_context
+            };
+            (quote! {}, body)
+        } else if let Some(setup) = &self.setup_once {
+            let teardown = self
+                .teardown_once
+                .as_ref()
+                .map_or(drop, |path| quote! { #path });
+            let context = quote! {
+                let __proptest_context =
+                    ::estoa_proptest::runner::Context::new(#setup(), #teardown);
+            };
+            (context, with_context)
+        } else {
+            (quote! {}, case_body)
+        }
+    }
+
+    /// Run `call` on clones of the `bound` arguments for every execution the
+    /// `model` explores, describing the arguments with `inputs` when one of
+    /// them fails. Without a `model`, `call` runs once as it is.
+    fn model_tokens(
+        &self,
+        bound: &[(syn::Ident, bool)],
+        call: proc_macro2::TokenStream,
+        inputs: Vec<proc_macro2::TokenStream>,
+    ) -> proc_macro2::TokenStream {
+        let Some(model) = &self.model else {
+            return call;
+        };
+
+        let idents = bound.iter().map(|(ident, _)| ident);
+        let patterns = bound.iter().map(|(ident, mutable)| {
+            if *mutable {
+                quote! { mut #ident }
+            } else {
+                quote! { #ident }
+            }
+        });
+        quote! {
+            let __proptest_arguments =
+                ( #( ::core::clone::Clone::clone(&#idents), )* );
+            ::estoa_proptest::runner::run_model(
+                #model,
+                || {
+                    #[allow(unused_imports)]
+                    use ::estoa_proptest::observer::{
+                        DescribeDebug as _,
+                        DescribeOpaque as _,
+                    };
+                    ::std::vec![ #( #inputs ),* ]
+                },
+                move || {
+                    let ( #( #patterns, )* ) =
+                        ::core::clone::Clone::clone(&__proptest_arguments);
+                    #call
+                },
+            );
+        }
+    }
+
+    /// Build the `registry` once per test, and return it together with the
+    /// expression generating arguments without a `#[strategy]`.
+    fn registry_tokens(
+        &self,
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        match &self.registry {
+            Some(path) => (
+                quote! {
+                    let __registry: ::estoa_proptest::registry::Registry =
+                        #path();
+                },
+                quote! { __registry.generate_tree(generator) },
+            ),
+            None => (
+                quote! {},
+                quote! { ::estoa_proptest::registry::generate_tree(generator) },
+            ),
+        }
+    }
+
     fn cases_tokens(&self) -> proc_macro2::TokenStream {
-        let value = self.cases.unwrap_or(10_000);
-        quote! { #value }
+        match (self.cases, self.time_budget_ms) {
+            (Some(value), _) => quote! { #value },
+            // A budget without an explicit count runs as many cases as fit.
+            (None, Some(_)) => quote! { usize::MAX },
+            (None, None) => quote! { ::estoa_proptest::runner::DEFAULT_CASES },
+        }
     }
 
     fn recursion_limit_tokens(&self) -> proc_macro2::TokenStream {
         match self.recursion_limit {
             Some(value) => quote! { #value },
-            None => quote! { ::core::usize::MAX },
+            None => quote! { usize::MAX },
         }
     }
 
@@ -347,6 +1465,97 @@ impl MacroConfig {
             }
         }
     }
+
+    fn entropy_limit_tokens(&self) -> proc_macro2::TokenStream {
+        match self.entropy_limit {
+            Some(value) => {
+                let value = value as u64;
+                quote! { .with_entropy_limit(#value) }
+            }
+            None => quote! {},
+        }
+    }
+
+    fn max_failures_tokens(&self) -> proc_macro2::TokenStream {
+        match self.max_failures {
+            Some(value) => quote! { .with_max_failures(#value) },
+            None => quote! {},
+        }
+    }
+
+    fn threads_tokens(&self) -> proc_macro2::TokenStream {
+        match self.threads {
+            Some(value) => quote! { .with_threads(#value) },
+            None => quote! {},
+        }
+    }
+
+    fn retries_tokens(&self) -> proc_macro2::TokenStream {
+        match self.retries {
+            Some(value) => quote! { .with_retries(#value) },
+            None => quote! {},
+        }
+    }
+
+    fn swarm_tokens(&self) -> proc_macro2::TokenStream {
+        match self.swarm {
+            Some(true) => quote! {
+                .with_swarm(::estoa_proptest::SwarmConfig::default())
+            },
+            _ => quote! {},
+        }
+    }
+
+    fn shrink_tokens(&self) -> proc_macro2::TokenStream {
+        match &self.shrink {
+            Some(scheduler) => quote! {
+                .with_shrink(
+                    ::estoa_proptest::shrink::ShrinkScheduler::#scheduler
+                )
+            },
+            None => quote! {},
+        }
+    }
+
+    fn observer_tokens(&self) -> proc_macro2::TokenStream {
+        match &self.observer {
+            Some(path) => quote! { .with_observer(#path()) },
+            None => quote! {},
+        }
+    }
+
+    fn dedup_tokens(&self) -> proc_macro2::TokenStream {
+        match self.dedup {
+            Some(true) => quote! { .with_dedup(true) },
+            _ => quote! {},
+        }
+    }
+
+    fn chaos_order_tokens(&self) -> proc_macro2::TokenStream {
+        match self.chaos_order {
+            Some(true) => quote! { .with_chaos_order(true) },
+            _ => quote! {},
+        }
+    }
+
+    fn capture_panics_tokens(&self) -> proc_macro2::TokenStream {
+        match self.capture_panics {
+            Some(false) => quote! { .with_capture_panics(false) },
+            _ => quote! {},
+        }
+    }
+
+    fn time_budget_tokens(&self) -> proc_macro2::TokenStream {
+        match self.time_budget_ms {
+            Some(value) => {
+                let millis = value as u64;
+                quote! {
+                    .with_time_budget(::core::time::Duration::from_millis(#millis))
+                }
+            }
+            None => quote! {},
+        }
+    }
 }
 
 fn parse_usize(expr: &Expr, key: &str) -> syn::Result<usize> {
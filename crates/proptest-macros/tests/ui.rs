@@ -8,4 +8,22 @@ fn ui() {
     tests.compile_fail("tests/ui/unknown_key.rs");
     tests.compile_fail("tests/ui/recursion_limit_zero.rs");
     tests.compile_fail("tests/ui/rejection_limit_zero.rs");
+    tests.compile_fail("tests/ui/threads_zero.rs");
+    tests.compile_fail("tests/ui/teardown_without_setup.rs");
+    tests.compile_fail("tests/ui/context_without_setup.rs");
+    tests.compile_fail("tests/ui/fixture_with_strategy.rs");
+    tests.compile_fail("tests/ui/impl_with_options.rs");
+    tests.compile_fail("tests/ui/impl_method_receiver.rs");
+    tests.compile_fail("tests/ui/test_attr_literal.rs");
+    tests.compile_fail("tests/ui/async_fn.rs");
+    tests.compile_fail("tests/ui/async_test_without_test_attr.rs");
+    tests.compile_fail("tests/ui/swarm_not_bool.rs");
+    tests.compile_fail("tests/ui/shrink_unknown_scheduler.rs");
+    tests.compile_fail("tests/ui/covering_without_values.rs");
+    tests.compile_fail("tests/ui/model_with_setup.rs");
+    tests.compile_fail("tests/ui/example_arity.rs");
+    tests.compile_fail("tests/ui/derive_unknown_option.rs");
+    tests.compile_fail("tests/ui/derive_zero_weights.rs");
+    tests.compile_fail("tests/ui/derive_retries_without_validate.rs");
+    tests.compile_fail("tests/ui/derive_recursive_without_leaf.rs");
 }
@@ -0,0 +1,8 @@
+use estoa_proptest_macros::proptest;
+
+#[proptest]
+fn property(#[context] context: &mut Vec<u8>, value: u8) {
+    context.push(value);
+}
+
+fn main() {}
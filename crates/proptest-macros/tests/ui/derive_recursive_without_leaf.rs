@@ -0,0 +1,9 @@
+use estoa_proptest_macros::Arbitrary;
+
+#[derive(Arbitrary)]
+enum Endless {
+    Next(Box<Endless>),
+    Both(Box<Endless>, Box<Self>),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use estoa_proptest_macros::Arbitrary;
+
+#[derive(Arbitrary)]
+#[arbitrary(retries = 4)]
+struct Span {
+    start: u8,
+    end: u8,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use estoa_proptest_macros::Arbitrary;
+
+#[derive(Arbitrary)]
+struct Account {
+    #[arbitrary(fixed)]
+    balance: i64,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use estoa_proptest_macros::Arbitrary;
+
+#[derive(Arbitrary)]
+enum Never {
+    #[weight(0)]
+    First,
+    #[weight(0)]
+    Second,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use estoa_proptest_macros::proptest;
+
+#[proptest]
+#[example(1, 2, 3)]
+fn property(a: u8, b: u8) {
+    let _ = (a, b);
+}
+
+fn main() {}
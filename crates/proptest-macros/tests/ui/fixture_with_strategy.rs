@@ -0,0 +1,8 @@
+use estoa_proptest_macros::proptest;
+
+#[proptest]
+fn property(#[fixture(0u8)] #[strategy(|_gen| todo!())] value: u8) {
+    let _ = value;
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use estoa_proptest_macros::proptest;
+
+struct Helpers;
+
+#[proptest]
+impl Helpers {
+    #[proptest]
+    fn property(&self, value: u8) {
+        let _ = value;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use estoa_proptest_macros::proptest;
+
+struct Helpers;
+
+#[proptest(cases = 4)]
+impl Helpers {
+    #[proptest]
+    fn property(value: u8) {
+        let _ = value;
+    }
+}
+
+fn main() {}
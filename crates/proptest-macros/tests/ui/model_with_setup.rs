@@ -0,0 +1,16 @@
+use estoa_proptest_macros::proptest;
+
+fn open() -> u8 {
+    0
+}
+
+fn model<F: Fn() + Send + Sync + 'static>(body: F) {
+    body();
+}
+
+#[proptest(setup = open, model = model)]
+fn property(#[context] context: &u8, value: u8) {
+    let _ = (context, value);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use estoa_proptest_macros::proptest;
+
+fn cleanup(_: ()) {}
+
+#[proptest(teardown = cleanup)]
+fn property(value: u8) {
+    let _ = value;
+}
+
+fn main() {}
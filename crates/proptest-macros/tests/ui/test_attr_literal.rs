@@ -0,0 +1,8 @@
+use estoa_proptest_macros::proptest;
+
+#[proptest(test_attr = "tokio::test")]
+fn property(value: u8) {
+    let _ = value;
+}
+
+fn main() {}
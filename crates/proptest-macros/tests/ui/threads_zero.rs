@@ -0,0 +1,6 @@
+use estoa_proptest_macros::proptest;
+
+#[proptest(threads = 0)]
+fn threads_zero() {}
+
+fn main() {}
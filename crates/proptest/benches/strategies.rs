@@ -0,0 +1,85 @@
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use estoa_proptest::{
+    bench::{generate_n, simplify_fully},
+    strategy::{
+        AnyI64,
+        AnyString,
+        AnyU8,
+        AnyU32,
+        BTreeMapStrategy,
+        HashMapStrategy,
+        Strategy,
+        VecStrategy,
+        runtime::Generator,
+    },
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+const VALUES: usize = 256;
+
+fn generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    group.bench_function("i64", |b| {
+        let mut strategy = AnyI64::default();
+        b.iter(|| generate_n(&mut strategy, VALUES));
+    });
+    group.bench_function("vec_u8", |b| {
+        let mut strategy = VecStrategy::new(AnyU8::default(), 0usize..=32);
+        b.iter(|| generate_n(&mut strategy, VALUES));
+    });
+    group.bench_function("string", |b| {
+        let mut strategy = AnyString::new(0usize..=32);
+        b.iter(|| generate_n(&mut strategy, VALUES));
+    });
+    group.bench_function("btree_map_u32", |b| {
+        let mut strategy = BTreeMapStrategy::new(
+            AnyU32::default(),
+            AnyU32::default(),
+            0usize..=32,
+        );
+        b.iter(|| generate_n(&mut strategy, VALUES));
+    });
+    group.finish();
+}
+
+/// Build a tree from a fixed seed for every iteration, so each measurement
+/// shrinks the same value.
+fn tree<S: Strategy>(strategy: &mut S) -> S::Tree {
+    let rng = StdRng::seed_from_u64(7);
+    strategy.new_tree(&mut Generator::build(rng)).take()
+}
+
+fn shrinking(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simplify_fully");
+    group.bench_function("vec_u32", |b| {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 32usize..=32);
+        b.iter_with_setup(
+            || tree(&mut strategy),
+            |mut tree| black_box(simplify_fully(&mut tree)),
+        );
+    });
+    group.bench_function("string", |b| {
+        let mut strategy = AnyString::new(32usize..=32);
+        b.iter_with_setup(
+            || tree(&mut strategy),
+            |mut tree| black_box(simplify_fully(&mut tree)),
+        );
+    });
+    group.bench_function("hash_map_u32", |b| {
+        let mut strategy = HashMapStrategy::new(
+            AnyU32::default(),
+            AnyU32::default(),
+            32usize..=32,
+        );
+        b.iter_with_setup(
+            || tree(&mut strategy),
+            |mut tree| black_box(simplify_fully(&mut tree)),
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, generation, shrinking);
+criterion_main!(benches);
@@ -1,23 +1,91 @@
-use std::{
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+    rc::{self, Rc},
+    string::String,
+    sync::{self, Arc},
+    vec::Vec,
+};
+use core::{
     array,
-    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
-    hash::Hash,
-    rc::Rc,
-    sync::Arc,
+    cell::OnceCell,
+    convert::Infallible,
+    marker::{PhantomData, PhantomPinned},
+    mem,
+    ops::{Range, RangeInclusive},
 };
-
-use rand::{
-    CryptoRng,
-    Rng,
-    RngCore,
-    distr::{SampleString, StandardUniform},
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hash},
+    sync::OnceLock,
 };
 
-use crate::strategy::runtime::{Generation, Generator};
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::strategy::{
+    AnyI8,
+    AnyI16,
+    AnyI32,
+    AnyI64,
+    AnyI128,
+    AnyIsize,
+    AnyU8,
+    AnyU16,
+    AnyU32,
+    AnyU64,
+    AnyU128,
+    AnyUsize,
+    BoolValueTree,
+    Provenance,
+    ValueTree,
+    runtime::{Generation, Generator, MAX_SIZE, RECURSION_SIZE_STEP},
+};
 
 pub(crate) const STRING_MAX_LEN: usize = 128;
 pub(crate) const COLLECTION_MAX_LEN: usize = 32;
 
+/// Code point tiers `Arbitrary for char` draws from, with their weights at
+/// [`MAX_SIZE`]: mostly ASCII, then Latin-1, the rest of the Basic
+/// Multilingual Plane and the astral planes. Every tier but ASCII is weighted
+/// in proportion to the generator size, so small sizes produce plain ASCII.
+const CHAR_TIERS: [(u32, u32, u32); 4] = [
+    (0x00, 0x7F, 60),
+    (0x80, 0xFF, 15),
+    (0x100, 0xFFFF, 15),
+    (0x1_0000, 0x10_FFFF, 10),
+];
+
+/// Draw a char from [`CHAR_TIERS`], weighting the tiers for `size`.
+fn tiered_char<R: RngCore + ?Sized>(rng: &mut R, size: usize) -> char {
+    let size = size.min(MAX_SIZE) as u32;
+    let weight = |tier: usize| {
+        let weight = CHAR_TIERS[tier].2;
+        if tier == 0 {
+            weight
+        } else {
+            weight * size / MAX_SIZE as u32
+        }
+    };
+
+    let total: u32 = (0..CHAR_TIERS.len()).map(weight).sum();
+    let mut pick = rng.random_range(0..total);
+    let mut tier = 0;
+    while pick >= weight(tier) {
+        pick -= weight(tier);
+        tier += 1;
+    }
+
+    let (start, end, _) = CHAR_TIERS[tier];
+    // Redraw the rare surrogate code points, which are not chars.
+    loop {
+        if let Some(ch) = char::from_u32(rng.random_range(start..=end)) {
+            return ch;
+        }
+    }
+}
+
 pub trait Arbitrary
 where
     Self: Sized,
@@ -31,108 +99,934 @@ where
         generator.accept(value)
     }
 
+    /// Generate a tree for `Self`, through which `#[proptest]` shrinks
+    /// failing arguments.
+    ///
+    /// The tree may draw more than [`generate`](Self::generate) does, such
+    /// as the alternatives a derived enum can shrink to. The default holds
+    /// the value `generate` draws in a tree that does not shrink.
+    fn generate_tree<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<ArbitraryTree<Self>> {
+        Self::generate(generator).map(ArbitraryTree::constant)
+    }
+
+    #[cfg(feature = "getrandom")]
     fn random() -> Generation<Self> {
         let mut generator = Generator::build(rand::rng());
         Self::generate(&mut generator)
     }
 }
 
+/// The value tree of an [`Arbitrary`] value, see
+/// [`Arbitrary::generate_tree`].
+///
+/// Unlike most trees, it hands out its value without cloning it, so types
+/// that are not `Clone` can still be generated through it.
+pub struct ArbitraryTree<T>(TreeKind<T>);
+
+enum TreeKind<T> {
+    Constant(T),
+    Shrinking(Box<dyn ShrinkingTree<T>>),
+}
+
+/// The trees an [`ArbitraryTree`] can shrink through.
+trait ShrinkingTree<T>: ValueTree<Value = T> {
+    fn take(self: Box<Self>) -> T;
+
+    /// Hand out the current value, keeping a tree that steps a value it
+    /// does not own, such as the field of a derived value, from then on.
+    fn detach(self: Box<Self>) -> (T, Box<dyn StepIn<T>>);
+}
+
+/// The steps of a tree, applied to a value held elsewhere.
+///
+/// The value starts out as the one the tree handed out when it was
+/// detached, and the tree keeps it in step with its own current value.
+trait StepIn<T> {
+    fn simplify(&mut self, target: &mut T) -> bool;
+
+    fn complicate(&mut self, target: &mut T) -> bool;
+}
+
+/// A tree whose value is cloned out of it.
+struct Cloning<V>(V);
+
+impl<V> ValueTree for Cloning<V>
+where
+    V: ValueTree,
+{
+    type Value = V::Value;
+
+    fn current(&self) -> &V::Value {
+        self.0.current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.0.simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.0.complicate()
+    }
+
+    fn provenance(&self) -> Option<Provenance> {
+        self.0.provenance()
+    }
+}
+
+impl<V> ShrinkingTree<V::Value> for Cloning<V>
+where
+    V: ValueTree + 'static,
+    V::Value: Clone,
+{
+    fn take(self: Box<Self>) -> V::Value {
+        self.0.into_current()
+    }
+
+    fn detach(self: Box<Self>) -> (V::Value, Box<dyn StepIn<V::Value>>) {
+        (self.0.current().clone(), self)
+    }
+}
+
+impl<V> StepIn<V::Value> for Cloning<V>
+where
+    V: ValueTree,
+    V::Value: Clone,
+{
+    fn simplify(&mut self, target: &mut V::Value) -> bool {
+        let simplified = self.0.simplify();
+        if simplified {
+            target.clone_from(self.0.current());
+        }
+        simplified
+    }
+
+    fn complicate(&mut self, target: &mut V::Value) -> bool {
+        // Trees may move back even when they have no alternatives left.
+        let complicated = self.0.complicate();
+        target.clone_from(self.0.current());
+        complicated
+    }
+}
+
+impl<T> ArbitraryTree<T> {
+    /// A tree holding `value`, which does not shrink.
+    pub fn constant(value: T) -> Self {
+        Self(TreeKind::Constant(value))
+    }
+
+    /// A tree shrinking like `tree`.
+    pub fn new<V>(tree: V) -> Self
+    where
+        V: ValueTree<Value = T> + 'static,
+        T: Clone,
+    {
+        Self(TreeKind::Shrinking(Box::new(Cloning(tree))))
+    }
+
+    /// Consume the tree and return its current value, which needs no
+    /// `Clone`.
+    pub fn into_current(self) -> T {
+        match self.0 {
+            TreeKind::Constant(value) => value,
+            TreeKind::Shrinking(tree) => tree.take(),
+        }
+    }
+
+    /// Hand out the current value, with the steps that keep it in line with
+    /// the tree unless it does not shrink.
+    fn detach(self) -> (T, Option<Box<dyn StepIn<T>>>) {
+        match self.0 {
+            TreeKind::Constant(value) => (value, None),
+            TreeKind::Shrinking(tree) => {
+                let (value, steps) = tree.detach();
+                (value, Some(steps))
+            }
+        }
+    }
+}
+
+impl<T: 'static> From<DerivedTree<T>> for ArbitraryTree<T> {
+    fn from(tree: DerivedTree<T>) -> Self {
+        Self(TreeKind::Shrinking(Box::new(tree)))
+    }
+}
+
+impl<T> ValueTree for ArbitraryTree<T> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        match &self.0 {
+            TreeKind::Constant(value) => value,
+            TreeKind::Shrinking(tree) => tree.current(),
+        }
+    }
+
+    fn into_current(self) -> T {
+        ArbitraryTree::into_current(self)
+    }
+
+    fn simplify(&mut self) -> bool {
+        match &mut self.0 {
+            TreeKind::Constant(_) => false,
+            TreeKind::Shrinking(tree) => tree.simplify(),
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        match &mut self.0 {
+            TreeKind::Constant(_) => false,
+            TreeKind::Shrinking(tree) => tree.complicate(),
+        }
+    }
+
+    fn provenance(&self) -> Option<Provenance> {
+        match &self.0 {
+            TreeKind::Constant(_) => None,
+            TreeKind::Shrinking(tree) => tree.provenance(),
+        }
+    }
+}
+
+/// The tree `#[derive(Arbitrary)]` builds values through, which shrinks a
+/// value by shrinking its fields.
+///
+/// The fields simplify in declaration order, as the tree of a tuple of their
+/// trees would: the first field that can simplify does. Fields that do not
+/// shrink, such as those filled with `#[arbitrary(default)]`, keep their
+/// value.
+///
+/// Enums first try to switch to the [`DerivedAlternatives`] drawn for their
+/// earlier variants, from the first one on, as a union of strategies does.
+pub struct DerivedTree<T> {
+    value: T,
+    steps: DerivedSteps<T>,
+}
+
+/// The values drawn for the variants before the one an enum picked, which
+/// its [`DerivedTree`] can switch to while shrinking.
+pub struct DerivedAlternatives<T>(Vec<Variant<T>>);
+
+/// A variant of a [`DerivedTree`], whose value is parked here while another
+/// variant is the current one.
+struct Variant<T> {
+    parked: Option<T>,
+    fields: DerivedFields<T>,
+}
+
+/// The steps of a [`DerivedTree`], applied to a value held elsewhere once
+/// the tree is detached.
+struct DerivedSteps<T> {
+    /// The alternatives, followed by the variant picked.
+    variants: Vec<Variant<T>>,
+    /// Variant of the current value.
+    current: usize,
+    /// Next alternative `simplify` tries to switch to.
+    next_switch: usize,
+    /// Variant the last `simplify` switched away from, which `complicate`
+    /// restores.
+    switched_from: Option<usize>,
+}
+
+impl<T> DerivedTree<T> {
+    /// A tree for `value`, whose fields were taken from `fields`.
+    pub fn new(value: T, fields: DerivedFields<T>) -> Self {
+        Self::with_alternatives(DerivedAlternatives::new(), value, fields)
+    }
+
+    /// A tree for `value`, the variant an enum picked with its fields taken
+    /// from `fields`, which can switch to the earlier variants in
+    /// `alternatives`.
+    pub fn with_alternatives(
+        alternatives: DerivedAlternatives<T>,
+        value: T,
+        fields: DerivedFields<T>,
+    ) -> Self {
+        let mut variants = alternatives.0;
+        let current = variants.len();
+        variants.push(Variant {
+            parked: None,
+            fields,
+        });
+        Self {
+            value,
+            steps: DerivedSteps {
+                variants,
+                current,
+                next_switch: 0,
+                switched_from: None,
+            },
+        }
+    }
+}
+
+impl<T> DerivedAlternatives<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `value`, whose fields were taken from `fields`, after the
+    /// alternatives drawn so far.
+    pub fn push(&mut self, value: T, fields: DerivedFields<T>) {
+        self.0.push(Variant {
+            parked: Some(value),
+            fields,
+        });
+    }
+}
+
+impl<T> Default for DerivedAlternatives<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T> DerivedSteps<T> {
+    /// Park `target` and move the value of `variant` into it.
+    fn switch(&mut self, variant: usize, target: &mut T) {
+        let value = self.variants[variant]
+            .parked
+            .take()
+            .expect("only the current variant has no parked value");
+        self.variants[self.current].parked = Some(mem::replace(target, value));
+        self.current = variant;
+    }
+}
+
+impl<T> ValueTree for DerivedTree<T> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        &self.value
+    }
+
+    fn into_current(self) -> T {
+        self.value
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.steps.simplify(&mut self.value)
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.steps.complicate(&mut self.value)
+    }
+}
+
+impl<T: 'static> ShrinkingTree<T> for DerivedTree<T> {
+    fn take(self: Box<Self>) -> T {
+        self.value
+    }
+
+    fn detach(self: Box<Self>) -> (T, Box<dyn StepIn<T>>) {
+        (self.value, Box::new(self.steps))
+    }
+}
+
+impl<T> StepIn<T> for DerivedSteps<T> {
+    fn simplify(&mut self, target: &mut T) -> bool {
+        while self.next_switch < self.current {
+            let previous = self.current;
+            let variant = self.next_switch;
+            self.next_switch += 1;
+
+            self.switch(variant, target);
+            if self.variants[variant].fields.valid(target) {
+                self.switched_from = Some(previous);
+                return true;
+            }
+            self.switch(previous, target);
+        }
+
+        self.switched_from = None;
+        self.variants[self.current].fields.simplify(target)
+    }
+
+    fn complicate(&mut self, target: &mut T) -> bool {
+        match self.switched_from.take() {
+            Some(previous) => {
+                self.switch(previous, target);
+                true
+            }
+            None => self.variants[self.current].fields.complicate(target),
+        }
+    }
+}
+
+/// The trees of the fields of a [`DerivedTree`].
+pub struct DerivedFields<T> {
+    fields: Vec<Box<dyn StepIn<T>>>,
+    /// Field simplified last, which `complicate` moves back.
+    last: Option<usize>,
+    validate: Option<fn(&T) -> bool>,
+}
+
+/// The tree of one field of a [`DerivedTree`], stepping the field `project`
+/// reaches in the derived value.
+struct Field<F, T> {
+    steps: Box<dyn StepIn<F>>,
+    project: fn(&mut T) -> Option<&mut F>,
+}
+
+impl<F, T> StepIn<T> for Field<F, T> {
+    fn simplify(&mut self, target: &mut T) -> bool {
+        (self.project)(target).is_some_and(|field| self.steps.simplify(field))
+    }
+
+    fn complicate(&mut self, target: &mut T) -> bool {
+        (self.project)(target).is_some_and(|field| self.steps.complicate(field))
+    }
+}
+
+impl<T> DerivedFields<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the value of a field from `tree`, keeping the tree to shrink
+    /// the field `project` reaches in the derived value.
+    pub fn field<F>(
+        &mut self,
+        tree: ArbitraryTree<F>,
+        project: fn(&mut T) -> Option<&mut F>,
+    ) -> F
+    where
+        F: 'static,
+        T: 'static,
+    {
+        let (value, steps) = tree.detach();
+        if let Some(steps) = steps {
+            self.fields.push(Box::new(Field { steps, project }));
+        }
+        value
+    }
+
+    /// Skip simplifications that make the derived value fail `check`, the
+    /// `#[arbitrary(validate = ...)]` of its type.
+    pub fn validate(&mut self, check: fn(&T) -> bool) {
+        self.validate = Some(check);
+    }
+
+    fn valid(&self, value: &T) -> bool {
+        self.validate.is_none_or(|check| check(value))
+    }
+}
+
+impl<T> Default for DerivedFields<T> {
+    fn default() -> Self {
+        Self {
+            fields: Vec::new(),
+            last: None,
+            validate: None,
+        }
+    }
+}
+
+impl<T> StepIn<T> for DerivedFields<T> {
+    fn simplify(&mut self, target: &mut T) -> bool {
+        for index in 0..self.fields.len() {
+            while self.fields[index].simplify(target) {
+                if self.valid(target) {
+                    self.last = Some(index);
+                    return true;
+                }
+                if !self.fields[index].complicate(target) {
+                    break;
+                }
+            }
+        }
+        self.last = None;
+        false
+    }
+
+    fn complicate(&mut self, target: &mut T) -> bool {
+        let Some(index) = self.last else {
+            return false;
+        };
+        let complicated = self.fields[index].complicate(target);
+        if !complicated {
+            self.last = None;
+        }
+        complicated
+    }
+}
+
+/// Implement [`Arbitrary`] for primitives with `rng.random()`, shrinking
+/// through the tree `tree` builds for a value.
 macro_rules! delegate_arbitrary {
-    ($($ty:ty),+ $(,)?) => {
+    ($($ty:ty => $tree:expr),+ $(,)?) => {
         $(
             impl Arbitrary for $ty {
                 fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
                     rng.random::<$ty>()
                 }
+
+                fn generate_tree<R: RngCore + CryptoRng>(
+                    generator: &mut Generator<R>,
+                ) -> Generation<ArbitraryTree<Self>> {
+                    Self::generate(generator)
+                        .map(|value| ArbitraryTree::new($tree(value)))
+                }
+            }
+        )+
+    };
+}
+
+delegate_arbitrary!(bool => BoolValueTree::new);
+delegate_arbitrary!(
+    u8 => |value| AnyU8::default().tree_for(value),
+    u16 => |value| AnyU16::default().tree_for(value),
+    u32 => |value| AnyU32::default().tree_for(value),
+    u64 => |value| AnyU64::default().tree_for(value),
+    u128 => |value| AnyU128::default().tree_for(value),
+);
+delegate_arbitrary!(
+    i8 => |value| AnyI8::default().tree_for(value),
+    i16 => |value| AnyI16::default().tree_for(value),
+    i32 => |value| AnyI32::default().tree_for(value),
+    i64 => |value| AnyI64::default().tree_for(value),
+    i128 => |value| AnyI128::default().tree_for(value),
+);
+
+impl Arbitrary for f32 {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        rng.random()
+    }
+}
+
+impl Arbitrary for f64 {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        rng.random()
+    }
+}
+
+impl Arbitrary for () {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(_: &mut R) -> Self {}
+}
+
+impl<T: ?Sized> Arbitrary for PhantomData<T> {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(_: &mut R) -> Self {
+        PhantomData
+    }
+}
+
+impl Arbitrary for PhantomPinned {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(_: &mut R) -> Self {
+        PhantomPinned
+    }
+}
+
+impl Arbitrary for char {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        tiered_char(rng, MAX_SIZE)
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        let size = generator.size();
+        let value = tiered_char(&mut generator.rng, size);
+        generator.accept(value)
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let len = rng.random_range(0..=STRING_MAX_LEN);
+        (0..len).map(|_| tiered_char(rng, MAX_SIZE)).collect()
+    }
+}
+
+macro_rules! string_like_arbitrary {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl Arbitrary for $ty {
+                fn arbitrary<R: RngCore + CryptoRng + ?Sized>(
+                    rng: &mut R,
+                ) -> Self {
+                    String::arbitrary(rng).into()
+                }
+
+                fn generate<R: RngCore + CryptoRng>(
+                    generator: &mut Generator<R>,
+                ) -> Generation<Self> {
+                    String::generate(generator).map(Into::into)
+                }
             }
         )+
     };
 }
 
-delegate_arbitrary!(bool);
-delegate_arbitrary!(char);
-delegate_arbitrary!(u8, u16, u32, u64, u128);
-delegate_arbitrary!(i8, i16, i32, i64, i128);
-delegate_arbitrary!(f32, f64);
+string_like_arbitrary!(Box<str>, Rc<str>, Arc<str>, Cow<'static, str>);
+
+impl Arbitrary for usize {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut bytes = [0u8; core::mem::size_of::<usize>()];
+        rng.fill_bytes(&mut bytes);
+        usize::from_ne_bytes(bytes)
+    }
+
+    fn generate_tree<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<ArbitraryTree<Self>> {
+        Self::generate(generator).map(|value| {
+            ArbitraryTree::new(AnyUsize::default().tree_for(value))
+        })
+    }
+}
+
+impl Arbitrary for isize {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut bytes = [0u8; core::mem::size_of::<isize>()];
+        rng.fill_bytes(&mut bytes);
+        isize::from_ne_bytes(bytes)
+    }
+
+    fn generate_tree<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<ArbitraryTree<Self>> {
+        Self::generate(generator).map(|value| {
+            ArbitraryTree::new(AnyIsize::default().tree_for(value))
+        })
+    }
+}
+
+/// Probability with which a generated `Option` is `Some`: one half at the
+/// top level, falling linearly to zero at the depth where
+/// [`Generator::recursion_exhausted`] stops recursive values, so nested
+/// options such as `Option<Option<Option<T>>>` stay as shallow as the size
+/// asks for.
+fn some_probability<R: RngCore + CryptoRng>(generator: &Generator<R>) -> f64 {
+    let levels = generator
+        .recursion_limit()
+        .min(generator.size().div_ceil(RECURSION_SIZE_STEP));
+    if levels == 0 {
+        return 0.0;
+    }
+    let remaining = levels.saturating_sub(generator.depth());
+    remaining as f64 / (2 * levels) as f64
+}
+
+/// Length of a collection drawn by `generate`: up to
+/// [`COLLECTION_MAX_LEN`] scaled by the generator size, halved for every
+/// level of recursion so a recursive type holding a collection of itself
+/// stays small.
+fn collection_len<R: RngCore + CryptoRng>(
+    generator: &mut Generator<R>,
+) -> usize {
+    let max = COLLECTION_MAX_LEN
+        .checked_shr(generator.depth() as u32)
+        .unwrap_or(0);
+    let range = generator.sized_len(0..=max);
+    generator.rng.random_range(range)
+}
+
+/// Generate a `T` through `generate`, setting `rejected` when it is rejected.
+fn draw<T: Arbitrary, R: RngCore + CryptoRng>(
+    generator: &mut Generator<R>,
+    rejected: &mut bool,
+) -> T {
+    let generation = T::generate(generator);
+    *rejected |= matches!(generation, Generation::Rejected { .. });
+    generation.take()
+}
+
+/// Generate a collection of [`collection_len`] items, rejected when any of
+/// its items is.
+fn generate_collection<R, T, C, F>(
+    generator: &mut Generator<R>,
+    max: usize,
+    mut item: F,
+) -> Generation<C>
+where
+    R: RngCore + CryptoRng,
+    C: FromIterator<T>,
+    F: FnMut(&mut Generator<R>, &mut bool) -> T,
+{
+    let len = collection_len(generator).min(max);
+    let mut rejected = false;
+    let values = (0..len)
+        .map(|_| item(generator, &mut rejected))
+        .collect::<C>();
+    settle(generator, rejected, values)
+}
+
+/// Accept `value`, or reject it when one of the parts it was built from was.
+fn settle<T, R: RngCore + CryptoRng>(
+    generator: &Generator<R>,
+    rejected: bool,
+    value: T,
+) -> Generation<T> {
+    if rejected {
+        generator.reject(value)
+    } else {
+        generator.accept(value)
+    }
+}
+
+/// `generate` draws the value one level deeper, and is `None` more often the
+/// deeper it is, see [`Generator::recursion_exhausted`].
+impl<T> Arbitrary for Option<T>
+where
+    T: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        if rng.random::<bool>() {
+            Some(T::arbitrary(rng))
+        } else {
+            None
+        }
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        if generator.rng.random_bool(some_probability(generator)) {
+            generator.recurse(|generator| T::generate(generator).map(Some))
+        } else {
+            generator.accept(None)
+        }
+    }
+}
+
+/// `generate` draws either side one level deeper until recursion is
+/// exhausted, so options nested inside the result count their depth from
+/// it.
+impl<T, E> Arbitrary for Result<T, E>
+where
+    T: Arbitrary,
+    E: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        if bool::arbitrary(rng) {
+            Ok(T::arbitrary(rng))
+        } else {
+            Err(E::arbitrary(rng))
+        }
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        let draw = |generator: &mut Generator<R>| {
+            if generator.rng.random::<bool>() {
+                T::generate(generator).map(Ok)
+            } else {
+                E::generate(generator).map(Err)
+            }
+        };
+        if generator.recursion_exhausted() {
+            draw(generator)
+        } else {
+            generator.recurse(draw)
+        }
+    }
+}
+
+/// `Infallible` has no values, so results that cannot fail are always `Ok`.
+impl<T> Arbitrary for Result<T, Infallible>
+where
+    T: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        Ok(T::arbitrary(rng))
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        T::generate(generator).map(Ok)
+    }
+}
+
+/// Bounds are drawn independently and swapped when the end comes first, so
+/// ranges are never reversed; bounds that do not compare, such as NaN, are
+/// kept as drawn.
+impl<T> Arbitrary for Range<T>
+where
+    T: Arbitrary + PartialOrd,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let (start, end) = (T::arbitrary(rng), T::arbitrary(rng));
+        if end < start { end..start } else { start..end }
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        let mut rejected = false;
+        let start = draw::<T, R>(generator, &mut rejected);
+        let end = draw::<T, R>(generator, &mut rejected);
+        let range = if end < start { end..start } else { start..end };
+        settle(generator, rejected, range)
+    }
+}
+
+impl<T> Arbitrary for RangeInclusive<T>
+where
+    T: Arbitrary + PartialOrd,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let (start, end) = (T::arbitrary(rng), T::arbitrary(rng));
+        if end < start {
+            end..=start
+        } else {
+            start..=end
+        }
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        Range::<T>::generate(generator).map(|range| range.start..=range.end)
+    }
+}
+
+impl<T> Arbitrary for Box<T>
+where
+    T: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        Box::new(T::arbitrary(rng))
+    }
 
-impl Arbitrary for () {
-    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(_: &mut R) -> Self {}
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        T::generate(generator).map(Box::new)
+    }
 }
 
-impl Arbitrary for String {
+impl<T> Arbitrary for Rc<T>
+where
+    T: Arbitrary,
+{
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        let len = rng.random_range(0..=STRING_MAX_LEN);
-        StandardUniform.sample_string(rng, len)
+        Rc::new(T::arbitrary(rng))
     }
-}
 
-impl Arbitrary for usize {
-    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        let mut bytes = [0u8; core::mem::size_of::<usize>()];
-        rng.fill_bytes(&mut bytes);
-        usize::from_ne_bytes(bytes)
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        T::generate(generator).map(Rc::new)
     }
 }
 
-impl Arbitrary for isize {
+impl<T> Arbitrary for Arc<T>
+where
+    T: Arbitrary,
+{
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        let mut bytes = [0u8; core::mem::size_of::<isize>()];
-        rng.fill_bytes(&mut bytes);
-        isize::from_ne_bytes(bytes)
+        Arc::new(T::arbitrary(rng))
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        T::generate(generator).map(Arc::new)
     }
 }
 
-impl<T> Arbitrary for Option<T>
+/// Weak references are dangling half of the time. The other half point at a
+/// fresh `Rc` the runner keeps until the current case ends, so the reference
+/// upgrades during the case; outside a run of the test runner, the `Rc` is
+/// dropped right away and the reference dangles too.
+impl<T> Arbitrary for rc::Weak<T>
 where
-    T: Arbitrary,
+    T: Arbitrary + 'static,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
         if rng.random::<bool>() {
-            Some(T::arbitrary(rng))
+            case_rc(T::arbitrary(rng))
         } else {
-            None
+            rc::Weak::new()
         }
     }
-}
 
-impl<T, E> Arbitrary for Result<T, E>
-where
-    T: Arbitrary,
-    E: Arbitrary,
-{
-    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        if bool::arbitrary(rng) {
-            Ok(T::arbitrary(rng))
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        if generator.rng.random::<bool>() {
+            T::generate(generator).map(case_rc)
         } else {
-            Err(E::arbitrary(rng))
+            generator.accept(rc::Weak::new())
         }
     }
 }
 
-impl<T> Arbitrary for Box<T>
+fn case_rc<T: 'static>(value: T) -> rc::Weak<T> {
+    let strong = Rc::new(value);
+    let weak = Rc::downgrade(&strong);
+    #[cfg(feature = "std")]
+    crate::runner::keep_for_case(strong);
+    weak
+}
+
+/// Like `rc::Weak`, dangling half of the time and upgrading from an `Arc`
+/// kept until the current case ends otherwise.
+impl<T> Arbitrary for sync::Weak<T>
 where
-    T: Arbitrary,
+    T: Arbitrary + 'static,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        Box::new(T::arbitrary(rng))
+        if rng.random::<bool>() {
+            case_arc(T::arbitrary(rng))
+        } else {
+            sync::Weak::new()
+        }
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        if generator.rng.random::<bool>() {
+            T::generate(generator).map(case_arc)
+        } else {
+            generator.accept(sync::Weak::new())
+        }
     }
 }
 
-impl<T> Arbitrary for Rc<T>
+fn case_arc<T: 'static>(value: T) -> sync::Weak<T> {
+    let strong = Arc::new(value);
+    let weak = Arc::downgrade(&strong);
+    #[cfg(feature = "std")]
+    crate::runner::keep_for_case(strong);
+    weak
+}
+
+/// Cells are empty half of the time and initialised otherwise.
+impl<T> Arbitrary for OnceCell<T>
 where
     T: Arbitrary,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        Rc::new(T::arbitrary(rng))
+        Option::<T>::arbitrary(rng).map_or_else(OnceCell::new, OnceCell::from)
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        Option::<T>::generate(generator)
+            .map(|value| value.map_or_else(OnceCell::new, OnceCell::from))
     }
 }
 
-impl<T> Arbitrary for Arc<T>
+#[cfg(feature = "std")]
+impl<T> Arbitrary for OnceLock<T>
 where
     T: Arbitrary,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
-        Arc::new(T::arbitrary(rng))
+        Option::<T>::arbitrary(rng).map_or_else(OnceLock::new, OnceLock::from)
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        Option::<T>::generate(generator)
+            .map(|value| value.map_or_else(OnceLock::new, OnceLock::from))
     }
 }
 
@@ -148,6 +1042,27 @@ where
         }
         values
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
+}
+
+impl<T> Arbitrary for Box<[T]>
+where
+    T: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        Vec::arbitrary(rng).into_boxed_slice()
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        Vec::generate(generator).map(Vec::into_boxed_slice)
+    }
 }
 
 impl<T> Arbitrary for VecDeque<T>
@@ -162,6 +1077,12 @@ where
         }
         values
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
 }
 
 impl<T> Arbitrary for BinaryHeap<T>
@@ -176,30 +1097,46 @@ where
         }
         heap
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
 }
 
-impl<T> Arbitrary for HashSet<T>
+#[cfg(feature = "std")]
+impl<T, S> Arbitrary for HashSet<T, S>
 where
     T: Arbitrary + Eq + Hash,
+    S: BuildHasher + Default,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
         let len = rng.random_range(0..=COLLECTION_MAX_LEN);
-        let mut set = HashSet::with_capacity(len);
+        let mut set = HashSet::with_capacity_and_hasher(len, S::default());
         for _ in 0..len {
             set.insert(T::arbitrary(rng));
         }
         set
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
 }
 
-impl<K, V> Arbitrary for HashMap<K, V>
+#[cfg(feature = "std")]
+impl<K, V, S> Arbitrary for HashMap<K, V, S>
 where
     K: Arbitrary + Eq + Hash,
     V: Arbitrary,
+    S: BuildHasher + Default,
 {
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
         let len = rng.random_range(0..=COLLECTION_MAX_LEN);
-        let mut map = HashMap::with_capacity(len);
+        let mut map = HashMap::with_capacity_and_hasher(len, S::default());
 
         for _ in 0..len {
             map.insert(K::arbitrary(rng), V::arbitrary(rng));
@@ -207,6 +1144,21 @@ where
 
         map
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(
+            generator,
+            COLLECTION_MAX_LEN,
+            |generator, rejected| {
+                (
+                    draw::<K, R>(generator, rejected),
+                    draw::<V, R>(generator, rejected),
+                )
+            },
+        )
+    }
 }
 
 impl<T> Arbitrary for BTreeSet<T>
@@ -223,6 +1175,12 @@ where
 
         set
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
 }
 
 impl<K, V> Arbitrary for BTreeMap<K, V>
@@ -240,6 +1198,196 @@ where
 
         map
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(
+            generator,
+            COLLECTION_MAX_LEN,
+            |generator, rejected| {
+                (
+                    draw::<K, R>(generator, rejected),
+                    draw::<V, R>(generator, rejected),
+                )
+            },
+        )
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl<A> Arbitrary for smallvec::SmallVec<A>
+where
+    A: smallvec::Array,
+    A::Item: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let len = rng.random_range(0..=COLLECTION_MAX_LEN);
+        (0..len).map(|_| A::Item::arbitrary(rng)).collect()
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<A::Item, R>)
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T, const CAP: usize> Arbitrary for arrayvec::ArrayVec<T, CAP>
+where
+    T: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let len = rng.random_range(0..=COLLECTION_MAX_LEN.min(CAP));
+        (0..len).map(|_| T::arbitrary(rng)).collect()
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, CAP, draw::<T, R>)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Arbitrary for chrono::NaiveDate {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::NaiveDateStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Arbitrary for chrono::NaiveDateTime {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::NaiveDateTimeStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Arbitrary for chrono::DateTime<chrono::Utc> {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::UtcDateTimeStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "time")]
+impl Arbitrary for time::OffsetDateTime {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::OffsetDateTimeStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl Arbitrary for uuid::Uuid {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::UuidStrategy::sample(rng)
+    }
+}
+
+#[cfg(feature = "url")]
+impl Arbitrary for url::Url {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::UrlStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl Arbitrary for serde_json::Value {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::JsonValueStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl Arbitrary for num_bigint::BigUint {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::BigUintStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl Arbitrary for num_bigint::BigInt {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::BigIntStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl Arbitrary for rust_decimal::Decimal {
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        crate::strategy::DecimalStrategy::default().sample(rng)
+    }
+}
+
+#[cfg(feature = "ordered-float")]
+impl<T> Arbitrary for ordered_float::NotNan<T>
+where
+    T: Arbitrary + ordered_float::FloatCore,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        loop {
+            if let Ok(value) = Self::new(T::arbitrary(rng)) {
+                return value;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ordered-float")]
+impl<T> Arbitrary for ordered_float::OrderedFloat<T>
+where
+    T: Arbitrary + ordered_float::FloatCore,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        Self(T::arbitrary(rng))
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<T> Arbitrary for indexmap::IndexSet<T>
+where
+    T: Arbitrary + Eq + Hash,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let len = rng.random_range(0..=COLLECTION_MAX_LEN);
+        (0..len).map(|_| T::arbitrary(rng)).collect()
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(generator, COLLECTION_MAX_LEN, draw::<T, R>)
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K, V> Arbitrary for indexmap::IndexMap<K, V>
+where
+    K: Arbitrary + Eq + Hash,
+    V: Arbitrary,
+{
+    fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let len = rng.random_range(0..=COLLECTION_MAX_LEN);
+        (0..len)
+            .map(|_| (K::arbitrary(rng), V::arbitrary(rng)))
+            .collect()
+    }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        generate_collection(
+            generator,
+            COLLECTION_MAX_LEN,
+            |generator, rejected| {
+                (
+                    draw::<K, R>(generator, rejected),
+                    draw::<V, R>(generator, rejected),
+                )
+            },
+        )
+    }
 }
 
 impl<T, const N: usize> Arbitrary for [T; N]
@@ -249,6 +1397,14 @@ where
     fn arbitrary<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
         array::from_fn(|_| T::arbitrary(rng))
     }
+
+    fn generate<R: RngCore + CryptoRng>(
+        generator: &mut Generator<R>,
+    ) -> Generation<Self> {
+        let mut rejected = false;
+        let values = array::from_fn(|_| draw::<T, R>(generator, &mut rejected));
+        settle(generator, rejected, values)
+    }
 }
 
 macro_rules! impl_arbitrary_tuple {
@@ -264,6 +1420,17 @@ macro_rules! impl_arbitrary_tuple {
                     $( $rest::arbitrary(rng), )+
                 )
             }
+
+            fn generate<R: RngCore + CryptoRng>(
+                generator: &mut Generator<R>,
+            ) -> Generation<Self> {
+                let mut rejected = false;
+                let values = (
+                    draw::<$first, R>(generator, &mut rejected),
+                    $( draw::<$rest, R>(generator, &mut rejected), )+
+                );
+                settle(generator, rejected, values)
+            }
         }
     };
 }
@@ -0,0 +1,68 @@
+//! Helpers for measuring strategy throughput.
+//!
+//! These drive strategies and value trees the way the runner and the shrink
+//! driver do, from a fixed seed so repeated measurements do the same work,
+//! and pass every value through [`black_box`] so the optimiser cannot skip
+//! building it. The crate's own criterion benches under `benches/` use them
+//! to guard hot paths such as integer shrink candidates and collection
+//! shrinking.
+
+use core::hint::black_box;
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{CountingRng, DefaultGenerator, Generation, Generator},
+};
+
+/// Seed of the generator [`generate_n`] draws from.
+pub const BENCH_SEED: u64 = 0x5eed;
+
+/// Generate `n` value trees from `strategy`, returning how many were
+/// accepted.
+pub fn generate_n<S: Strategy>(strategy: &mut S, n: usize) -> usize {
+    let mut generator: DefaultGenerator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(BENCH_SEED)));
+    let mut accepted = 0;
+    for _ in 0..n {
+        let generation = strategy.new_tree(&mut generator);
+        if let Generation::Accepted { .. } = generation {
+            accepted += 1;
+        }
+        black_box(generation.take().current());
+        generator.advance_iteration();
+    }
+    accepted
+}
+
+/// Simplify `tree` until it has nothing left to try, as when every
+/// simplification keeps failing, returning the number of steps taken.
+pub fn simplify_fully<T: ValueTree>(tree: &mut T) -> usize {
+    let mut steps = 0;
+    while tree.simplify() {
+        black_box(tree.current());
+        steps += 1;
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{AnyU32, IntValueTree, VecStrategy};
+
+    #[test]
+    fn generate_n_counts_accepted_trees() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 0usize..=8);
+        assert_eq!(generate_n(&mut strategy, 16), 16);
+    }
+
+    #[test]
+    fn simplify_fully_walks_every_candidate() {
+        let mut tree = IntValueTree::new(9u32, vec![0, 4, 8]);
+        assert_eq!(simplify_fully(&mut tree), 3);
+        assert!(!tree.simplify());
+    }
+}
@@ -0,0 +1,431 @@
+//! Regression corpora kept in a directory.
+//!
+//! A [`Corpus`] stores interesting cases of a property, one [`Entry`] per
+//! file, as the seed, size and [`ShrinkTrace`] that regenerate the value from
+//! its strategy. Properties replay the stored values with [`Corpus::values`]
+//! before, or instead of, generating new ones, and add the cases worth
+//! keeping with [`Corpus::add`].
+//!
+//! Corpora only grow that way. [`minimize`] keeps them small: it sorts the
+//! entries by the outcome a classifier gives their values, such as the
+//! labels they reach or the error they fail with, keeps one entry per
+//! distinct outcome and shrinks it as far as it goes without changing that
+//! outcome:
+//!
+//! ```no_run
+//! use estoa_proptest::{
+//!     corpus,
+//!     strategy::{AnyU32, VecStrategy},
+//! };
+//!
+//! fn outcome(values: &Vec<u32>) -> Option<&'static str> {
+//!     match values.iter().sum::<u32>() {
+//!         0 => Some("zero"),
+//!         sum if sum % 2 == 0 => Some("even"),
+//!         _ => None,
+//!     }
+//! }
+//!
+//! let strategy = VecStrategy::new(AnyU32::default(), 0usize..=64usize);
+//! corpus::minimize("tests/corpus/sums", strategy, outcome).unwrap();
+//! ```
+//!
+//! Entries that no longer regenerate, because the strategy changed, are
+//! dropped.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::{
+    shrink::{ShrinkTrace, shrink},
+    strategy::{
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator},
+    },
+};
+
+/// Extension of the files holding corpus entries.
+const EXTENSION: &str = "case";
+
+/// Regenerate the tree generated from `seed` at `size` and move it to the
+/// end of `trace`. `None` when the strategy rejects it.
+pub(crate) fn regenerate<S: Strategy>(
+    strategy: &mut S,
+    seed: u64,
+    size: usize,
+    trace: &ShrinkTrace,
+) -> Option<S::Tree> {
+    let rng = StdRng::seed_from_u64(seed);
+    let mut generator = Generator::build(rng).with_size(size);
+    let Generation::Accepted {
+        value: mut tree, ..
+    } = strategy.new_tree(&mut generator)
+    else {
+        return None;
+    };
+    trace.replay(&mut tree);
+    Some(tree)
+}
+
+/// A case of a [`Corpus`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entry {
+    /// Seed of the case's generator.
+    pub seed: u64,
+    /// Size of the case's generator.
+    pub size: usize,
+    /// Steps from the generated tree to the stored value.
+    pub trace: ShrinkTrace,
+}
+
+impl Entry {
+    pub fn new(seed: u64, size: usize, trace: ShrinkTrace) -> Self {
+        Self { seed, size, trace }
+    }
+
+    /// The value of the entry, or `None` when `strategy` rejects it.
+    pub fn value<S>(&self, strategy: &mut S) -> Option<S::Value>
+    where
+        S: Strategy,
+        S::Value: Clone,
+    {
+        self.tree(strategy).map(ValueTree::into_current)
+    }
+
+    fn tree<S: Strategy>(&self, strategy: &mut S) -> Option<S::Tree> {
+        regenerate(strategy, self.seed, self.size, &self.trace)
+    }
+
+    /// The name of the entry's file: a hash of its contents, so adding the
+    /// same entry twice writes the same file.
+    fn file_name(&self) -> String {
+        // FNV-1a, which unlike `DefaultHasher` is the same everywhere.
+        let hash = self
+            .to_string()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325, |h, b| {
+                (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{hash:016x}.{EXTENSION}")
+    }
+}
+
+/// The seed in hex, the size and the trace, `-` when empty.
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x} {} ", self.seed, self.size)?;
+        if self.trace.is_empty() {
+            f.write_str("-")
+        } else {
+            write!(f, "{}", self.trace)
+        }
+    }
+}
+
+/// Error returned when parsing an [`Entry`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseEntryError {
+    /// The text that failed to parse.
+    pub text: String,
+}
+
+impl fmt::Display for ParseEntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid corpus entry `{}`, expected a hex seed, a size and a \
+             trace",
+            self.text,
+        )
+    }
+}
+
+impl core::error::Error for ParseEntryError {}
+
+impl FromStr for Entry {
+    type Err = ParseEntryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseEntryError { text: s.to_owned() };
+        let fields: Vec<_> = s.split_whitespace().collect();
+        let [seed, size, trace] = fields[..] else {
+            return Err(error());
+        };
+        let seed = seed
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .ok_or_else(error)?;
+        let size = size.parse().map_err(|_| error())?;
+        let trace = match trace {
+            "-" => ShrinkTrace::default(),
+            trace => trace.parse().map_err(|_| error())?,
+        };
+        Ok(Self::new(seed, size, trace))
+    }
+}
+
+/// A directory of [`Entry`] files, see the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct Corpus {
+    dir: PathBuf,
+    /// The entries, sorted and without duplicates.
+    entries: Vec<Entry>,
+}
+
+impl Corpus {
+    /// Read the entries stored in `dir`. A missing directory has none, and
+    /// files of other extensions are ignored.
+    ///
+    /// # Errors
+    ///
+    /// When the directory or an entry cannot be read, or an entry does not
+    /// parse.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_owned();
+        let mut entries = Vec::new();
+        let files = match fs::read_dir(&dir) {
+            Ok(files) => files,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self { dir, entries });
+            }
+            Err(err) => return Err(err),
+        };
+        for file in files {
+            let path = file?.path();
+            if path.extension().is_none_or(|ext| ext != EXTENSION) {
+                continue;
+            }
+            let entry =
+                fs::read_to_string(&path)?.trim().parse().map_err(|err| {
+                    io::Error::new(io::ErrorKind::InvalidData, err)
+                })?;
+            entries.push(entry);
+        }
+        entries.sort();
+        entries.dedup();
+        Ok(Self { dir, entries })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The values of the entries `strategy` still generates.
+    pub fn values<S>(&self, strategy: &mut S) -> Vec<S::Value>
+    where
+        S: Strategy,
+        S::Value: Clone,
+    {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.value(strategy))
+            .collect()
+    }
+
+    /// Store `entry`, creating the directory. Returns whether it is new.
+    ///
+    /// # Errors
+    ///
+    /// When the directory or the entry cannot be written.
+    pub fn add(&mut self, entry: Entry) -> io::Result<bool> {
+        let Err(index) = self.entries.binary_search(&entry) else {
+            return Ok(false);
+        };
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(entry.file_name()), format!("{entry}\n"))?;
+        self.entries.insert(index, entry);
+        Ok(true)
+    }
+
+    /// Delete `entry`. Returns whether it was stored.
+    ///
+    /// # Errors
+    ///
+    /// When its file cannot be removed.
+    pub fn remove(&mut self, entry: &Entry) -> io::Result<bool> {
+        let Ok(index) = self.entries.binary_search(entry) else {
+            return Ok(false);
+        };
+        match fs::remove_file(self.dir.join(entry.file_name())) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(err);
+            }
+            _ => {}
+        }
+        self.entries.remove(index);
+        Ok(true)
+    }
+}
+
+/// What [`minimize`] did to a corpus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Minimized {
+    /// The number of entries before.
+    pub before: usize,
+    /// The number of entries left, one per distinct outcome.
+    pub after: usize,
+}
+
+/// Deduplicate and shrink the corpus in `dir`, keeping one entry for each
+/// distinct outcome `classify` gives the values of `strategy`, see the
+/// [module documentation](self).
+///
+/// The entry kept for an outcome is the first one in the corpus, shrunk
+/// while `classify` still gives its value the same outcome.
+///
+/// # Errors
+///
+/// When the corpus cannot be read or written.
+pub fn minimize<S, C, O>(
+    dir: impl AsRef<Path>,
+    mut strategy: S,
+    classify: C,
+) -> io::Result<Minimized>
+where
+    S: Strategy,
+    S::Value: Clone,
+    C: Fn(&S::Value) -> O,
+    O: Ord,
+{
+    let mut corpus = Corpus::open(dir)?;
+    let before = corpus.entries.len();
+
+    let mut kept = BTreeMap::new();
+    for entry in &corpus.entries {
+        let Some(tree) = entry.tree(&mut strategy) else {
+            continue;
+        };
+        kept.entry(classify(tree.current()))
+            .or_insert((entry.clone(), tree));
+    }
+
+    let mut minimized = Vec::with_capacity(kept.len());
+    for (outcome, (mut entry, mut tree)) in kept {
+        let shrunk = shrink(&mut tree, |value| classify(value) == outcome);
+        entry.trace.extend(shrunk.trace.steps().iter().copied());
+        minimized.push(entry);
+    }
+
+    for entry in corpus.entries.clone() {
+        if !minimized.contains(&entry) {
+            corpus.remove(&entry)?;
+        }
+    }
+    for entry in minimized {
+        corpus.add(entry)?;
+    }
+    Ok(Minimized {
+        before,
+        after: corpus.entries.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, env};
+
+    use super::*;
+    use crate::strategy::{AnyU32, VecStrategy};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("estoa-corpus-{}-{name}", std::process::id()))
+    }
+
+    fn strategy() -> VecStrategy<AnyU32> {
+        VecStrategy::new(AnyU32::new(0..=1_000), 0usize..=16usize)
+    }
+
+    /// Whether the sum is large, and whether it is even.
+    fn outcome(values: &[u32]) -> (bool, bool) {
+        let sum = values.iter().sum::<u32>();
+        (sum >= 2_000, sum % 2 == 0)
+    }
+
+    #[test]
+    fn entries_round_trip_through_text() {
+        let entry = Entry::new(0xbeef, 42, "ssc".parse().unwrap());
+        assert_eq!(entry.to_string(), "0xbeef 42 ssc");
+        assert_eq!("0xbeef 42 ssc".parse(), Ok(entry));
+        assert_eq!(
+            "0x1 0 -".parse(),
+            Ok(Entry::new(1, 0, ShrinkTrace::default()))
+        );
+        assert!("0x1 0".parse::<Entry>().is_err());
+        assert!("1 0 -".parse::<Entry>().is_err());
+    }
+
+    #[test]
+    fn entries_are_stored_once() {
+        let dir = temp_dir("stored");
+        let mut corpus = Corpus::open(&dir).unwrap();
+        let entry = Entry::new(7, 3, ShrinkTrace::default());
+        assert!(corpus.add(entry.clone()).unwrap());
+        assert!(!corpus.add(entry.clone()).unwrap());
+
+        let reopened = Corpus::open(&dir).unwrap();
+        assert_eq!(reopened.entries(), core::slice::from_ref(&entry));
+        assert_eq!(
+            reopened.values(&mut strategy()),
+            [entry.value(&mut strategy()).unwrap()]
+        );
+
+        assert!(corpus.remove(&entry).unwrap());
+        assert!(Corpus::open(&dir).unwrap().entries().is_empty());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn minimizing_keeps_one_small_entry_per_outcome() {
+        let dir = temp_dir("minimized");
+        let mut corpus = Corpus::open(&dir).unwrap();
+        for seed in 0..64 {
+            corpus
+                .add(Entry::new(seed, 100, ShrinkTrace::default()))
+                .unwrap();
+        }
+        let outcomes = |corpus: &Corpus| {
+            corpus
+                .values(&mut strategy())
+                .iter()
+                .map(|values| outcome(values))
+                .collect::<BTreeSet<_>>()
+        };
+        let before = outcomes(&corpus);
+
+        let minimized = minimize(&dir, strategy(), |v| outcome(v)).unwrap();
+        assert_eq!(minimized.before, 64);
+        assert_eq!(minimized.after, before.len());
+
+        let corpus = Corpus::open(&dir).unwrap();
+        assert_eq!(outcomes(&corpus), before);
+        let values = corpus.values(&mut strategy());
+        // The small sums shrink all the way.
+        assert!(values.contains(&Vec::new()));
+        assert!(values.contains(&vec![1]));
+
+        // Minimizing again keeps the same values.
+        let again = minimize(&dir, strategy(), |v| outcome(v)).unwrap();
+        assert_eq!(again.before, again.after);
+        let corpus = Corpus::open(&dir).unwrap();
+        let mut again = corpus.values(&mut strategy());
+        again.sort();
+        let mut values = values;
+        values.sort();
+        assert_eq!(again, values);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
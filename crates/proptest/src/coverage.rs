@@ -0,0 +1,68 @@
+//! Coverage requirements for the inputs of a property.
+//!
+//! Bodies label the interesting branches their inputs reach with [`hit`] and
+//! declare how often a label must be reached with [`require`]. Once every
+//! case has passed, the run fails if a required label was hit by a smaller
+//! share of the cases than requested, which catches strategies that quietly
+//! stopped producing the inputs a property was written for.
+//!
+//! ```
+//! use estoa_proptest::{coverage, proptest};
+//!
+//! #[proptest]
+//! fn parses_numbers(input: String) {
+//!     coverage::require("digits only", 1.0);
+//!     if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) {
+//!         coverage::hit("digits only");
+//!         assert!(input.parse::<u128>().is_ok() || input.len() > 39);
+//!     }
+//! }
+//! ```
+//!
+//! Both functions do nothing outside a run of the test runner.
+
+use crate::runner::with_active_case;
+
+/// Record that the current case reached `label`.
+///
+/// Hitting a label several times in one case counts once.
+pub fn hit(label: &str) {
+    with_active_case(|case| case.hit(label));
+}
+
+/// Require that at least `min_percent` percent of the cases of the run hit
+/// `label`.
+///
+/// Call it from every case, or at least from one that is sure to run; the
+/// strictest requirement declared for a label wins.
+pub fn require(label: &str, min_percent: f64) {
+    with_active_case(|case| case.require(label, min_percent));
+}
+
+/// How often a label was hit during a run.
+#[derive(Clone, Debug)]
+pub struct LabelCoverage {
+    pub label: String,
+    /// Number of cases that hit the label.
+    pub hits: usize,
+    /// Share of cases required to hit the label, if any.
+    pub min_percent: Option<f64>,
+}
+
+impl LabelCoverage {
+    pub(crate) fn new(label: &str) -> Self {
+        Self {
+            label: label.to_owned(),
+            hits: 0,
+            min_percent: None,
+        }
+    }
+
+    /// Percentage of `cases` that hit the label.
+    pub fn percent(&self, cases: usize) -> f64 {
+        if cases == 0 {
+            return 0.0;
+        }
+        self.hits as f64 * 100.0 / cases as f64
+    }
+}
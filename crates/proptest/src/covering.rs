@@ -0,0 +1,177 @@
+//! Covering arrays for combinatorial coverage.
+//!
+//! A covering array of strength `t` over parameters with the given numbers of
+//! levels is a list of rows, one level per parameter, in which every
+//! combination of levels of every `t` parameters appears at least once. For
+//! `t = 2` (pairwise coverage) this needs far fewer rows than the full
+//! cartesian product while still exercising every interaction between two
+//! parameters.
+
+use alloc::{vec, vec::Vec};
+
+/// Plan rows covering every `strength`-wise combination of `levels`.
+///
+/// Rows are built greedily: each starts from a combination that is not
+/// covered yet and fills the remaining parameters with the level covering the
+/// most uncovered combinations. The plan is deterministic. A `strength` of at
+/// least `levels.len()` yields the full cartesian product, and a parameter
+/// with no levels yields no rows.
+pub fn covering_array(levels: &[usize], strength: usize) -> Vec<Vec<usize>> {
+    if levels.contains(&0) {
+        return Vec::new();
+    }
+    if levels.is_empty() {
+        return vec![Vec::new()];
+    }
+
+    let strength = strength.clamp(1, levels.len());
+    let combos = combinations(levels.len(), strength);
+    let mut uncovered: Vec<Vec<bool>> = combos
+        .iter()
+        .map(|combo| {
+            vec![true; combo.iter().map(|&param| levels[param]).product()]
+        })
+        .collect();
+    let mut remaining: usize = uncovered.iter().map(Vec::len).sum();
+    let mut rows = Vec::new();
+
+    while remaining > 0 {
+        let mut row = vec![None; levels.len()];
+
+        // Seed the row with the first combination still missing.
+        let (combo_index, tuple_index) = uncovered
+            .iter()
+            .enumerate()
+            .find_map(|(combo_index, tuples)| {
+                let tuple = tuples.iter().position(|&missing| missing)?;
+                Some((combo_index, tuple))
+            })
+            .expect("remaining combinations are uncovered");
+        let mut rest = tuple_index;
+        for &param in combos[combo_index].iter().rev() {
+            row[param] = Some(rest % levels[param]);
+            rest /= levels[param];
+        }
+
+        for param in 0..levels.len() {
+            if row[param].is_some() {
+                continue;
+            }
+
+            let mut best = (0, 0);
+            for level in 0..levels[param] {
+                row[param] = Some(level);
+                let gain = combos
+                    .iter()
+                    .zip(&uncovered)
+                    .filter(|(combo, _)| combo.contains(&param))
+                    .filter(|(combo, tuples)| {
+                        tuple_of(combo, &row, levels)
+                            .is_some_and(|tuple| tuples[tuple])
+                    })
+                    .count();
+                if gain > best.1 {
+                    best = (level, gain);
+                }
+            }
+            row[param] = Some(best.0);
+        }
+
+        let row: Vec<usize> =
+            row.into_iter().map(|level| level.unwrap_or(0)).collect();
+        let assigned: Vec<Option<usize>> =
+            row.iter().copied().map(Some).collect();
+        for (combo, tuples) in combos.iter().zip(&mut uncovered) {
+            if let Some(tuple) = tuple_of(combo, &assigned, levels)
+                && tuples[tuple]
+            {
+                tuples[tuple] = false;
+                remaining -= 1;
+            }
+        }
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Index of the levels `row` assigns to the parameters of `combo`, or `None`
+/// while one of them is unassigned.
+fn tuple_of(
+    combo: &[usize],
+    row: &[Option<usize>],
+    levels: &[usize],
+) -> Option<usize> {
+    combo
+        .iter()
+        .try_fold(0, |index, &param| Some(index * levels[param] + row[param]?))
+}
+
+/// Every `k`-element subset of `0..n`, in lexicographic order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut combos = Vec::new();
+    let mut combo: Vec<usize> = (0..k).collect();
+
+    loop {
+        combos.push(combo.clone());
+
+        let Some(position) = (0..k).rev().find(|&i| combo[i] < n - k + i)
+        else {
+            return combos;
+        };
+        combo[position] += 1;
+        for i in position + 1..k {
+            combo[i] = combo[i - 1] + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covers(rows: &[Vec<usize>], levels: &[usize], strength: usize) -> bool {
+        combinations(levels.len(), strength).iter().all(|combo| {
+            let total: usize =
+                combo.iter().map(|&param| levels[param]).product();
+            let seen: Vec<usize> = rows
+                .iter()
+                .map(|row| {
+                    let row: Vec<Option<usize>> =
+                        row.iter().copied().map(Some).collect();
+                    tuple_of(combo, &row, levels).unwrap()
+                })
+                .collect();
+            (0..total).all(|tuple| seen.contains(&tuple))
+        })
+    }
+
+    #[test]
+    fn pairwise_covers_every_pair_with_few_rows() {
+        let levels = [3, 3, 3, 3];
+        let rows = covering_array(&levels, 2);
+        assert!(covers(&rows, &levels, 2));
+        assert!(rows.len() <= 12, "{} rows", rows.len());
+    }
+
+    #[test]
+    fn mixed_levels_and_higher_strength_are_covered() {
+        let levels = [2, 4, 3, 2, 3];
+        let rows = covering_array(&levels, 3);
+        assert!(covers(&rows, &levels, 3));
+        assert!(rows.len() < levels.iter().product());
+    }
+
+    #[test]
+    fn full_strength_is_the_cartesian_product() {
+        let rows = covering_array(&[2, 3], 5);
+        assert_eq!(rows.len(), 6);
+        assert!(covers(&rows, &[2, 3], 2));
+    }
+
+    #[test]
+    fn empty_levels_produce_no_rows() {
+        assert!(covering_array(&[2, 0, 3], 2).is_empty());
+        assert_eq!(covering_array(&[], 2), vec![Vec::<usize>::new()]);
+    }
+}
@@ -0,0 +1,119 @@
+//! Fixtures shared by every case and test of the process.
+//!
+//! Some resources are too expensive to build for every case, or even for
+//! every test: a compiled set of regexes, a database loaded from disk, a
+//! container started for the whole test binary. [`once`] builds such a
+//! resource the first time any test asks for its type and hands every later
+//! caller, on any thread, the same instance:
+//!
+//! ```
+//! use estoa_proptest::{once, proptest};
+//!
+//! struct Keywords(Vec<&'static str>);
+//!
+//! fn keywords() -> &'static Keywords {
+//!     once(|| Keywords(vec!["fn", "let", "match"]))
+//! }
+//!
+//! #[proptest(cases = 16)]
+//! fn identifiers_are_not_keywords(
+//!     #[fixture(keywords())] keywords: &'static Keywords,
+//!     #[strategy(estoa_proptest::strategy::AnyString::new(1usize..=4usize))]
+//!     name: String,
+//! ) {
+//!     let _ = keywords.0.contains(&name.as_str());
+//! }
+//! ```
+//!
+//! Fixtures are keyed by their type, so two fixtures of the same type, say
+//! two `Vec<String>`s, need a newtype each to be kept apart. They live until
+//! the process exits and are never dropped; `setup_once` and
+//! `teardown_once` suit resources that must be released when the test ends.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock, PoisonError},
+};
+
+/// The cell of every fixture type asked for so far, leaked so fixtures can
+/// be borrowed for `'static`.
+static CELLS: LazyLock<
+    Mutex<HashMap<TypeId, &'static (dyn Any + Send + Sync)>>,
+> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The process-wide instance of `T`, built by `init` on the first call for
+/// `T`, see the [module documentation](self).
+///
+/// Callers racing for a fixture that is still being built wait for it, and
+/// `init` runs outside any lock shared with other fixture types, so it can
+/// ask for other fixtures itself. An `init` that panics leaves the fixture
+/// unbuilt for the next caller to try again.
+///
+/// # Panics
+///
+/// When `init` panics, or, as it would deadlock, asks for `T` itself.
+pub fn once<T, F>(init: F) -> &'static T
+where
+    T: Send + Sync + 'static,
+    F: FnOnce() -> T,
+{
+    let cell = *CELLS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(TypeId::of::<T>())
+        .or_insert_with(|| Box::leak(Box::new(OnceLock::<T>::new())));
+    cell.downcast_ref::<OnceLock<T>>()
+        .expect("fixture cells are keyed by their type")
+        .get_or_init(init)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        panic::catch_unwind,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn fixtures_are_built_once_across_threads() {
+        struct Expensive(usize);
+        static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+        let build =
+            || once(|| Expensive(BUILT.fetch_add(1, Ordering::Relaxed) + 41));
+        let fixtures: Vec<&'static Expensive> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..8).map(|_| scope.spawn(build)).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(BUILT.load(Ordering::Relaxed), 1);
+        assert!(fixtures.iter().all(|fixture| fixture.0 == 41));
+        assert!(
+            fixtures
+                .iter()
+                .all(|fixture| std::ptr::eq(*fixture, build()))
+        );
+    }
+
+    #[test]
+    fn fixtures_of_other_types_build_on_their_own() {
+        struct Outer(&'static Inner);
+        struct Inner(u8);
+
+        let outer = once(|| Outer(once(|| Inner(7))));
+        assert_eq!(outer.0.0, 7);
+        assert!(std::ptr::eq(outer.0, once(|| Inner(8))));
+    }
+
+    #[test]
+    fn panicking_fixtures_are_built_again() {
+        struct Flaky(u8);
+
+        assert!(catch_unwind(|| once::<Flaky, _>(|| panic!("down"))).is_err());
+        assert_eq!(once(|| Flaky(3)).0, 3);
+    }
+}
@@ -1,12 +1,60 @@
-use rand::{CryptoRng, RngCore, rngs::ThreadRng};
+//! Property testing with integrated shrinking.
+//!
+//! The strategy engine only needs `alloc`; disable the default `std` feature
+//! to use it from `#![no_std]` targets. The test runner, the `#[proptest]`
+//! macro output and the hash-based collections require `std`, while
+//! OS-seeded randomness (`rng()`, `random()` and unseeded runs) additionally
+//! requires the default `getrandom` feature.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use rand::{CryptoRng, RngCore};
 
 mod arbitrary;
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod corpus;
+#[cfg(feature = "std")]
+pub mod coverage;
+pub mod covering;
+#[cfg(feature = "std")]
+pub mod fixture;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "std")]
+pub mod output;
+#[cfg(feature = "std")]
+pub mod reduce;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod runner;
+pub mod shrink;
 pub mod strategy;
+pub mod strategy_test;
 
-pub use arbitrary::Arbitrary;
-pub use estoa_proptest_macros::proptest;
+pub use arbitrary::{
+    Arbitrary,
+    ArbitraryTree,
+    DerivedAlternatives,
+    DerivedFields,
+    DerivedTree,
+};
+pub use estoa_proptest_macros::{Arbitrary, proptest};
+#[cfg(feature = "std")]
+pub use fixture::once;
 pub use strategy::{SizeHint, runtime::*};
 
+#[doc(hidden)]
+pub mod __private {
+    pub use rand;
+}
+
+#[cfg(feature = "getrandom")]
 pub fn random<T: Arbitrary>() -> strategy::runtime::Generation<T> {
     T::random()
 }
@@ -17,6 +65,7 @@ pub fn arbitrary<T: Arbitrary, R: RngCore + CryptoRng>(
     T::generate(generator)
 }
 
-pub fn rng() -> ThreadRng {
+#[cfg(feature = "getrandom")]
+pub fn rng() -> rand::rngs::ThreadRng {
     rand::rng()
 }
@@ -0,0 +1,334 @@
+//! Hooks that see every case of a run.
+//!
+//! A [`CaseObserver`] is told about each case once it finishes: its index
+//! and seed, the inputs `#[proptest]` generated for it and whether it
+//! passed, failed or was skipped as a duplicate. Shrinking a failing value
+//! with the [`shrink`](crate::shrink) functions inside a case also reports
+//! every candidate it tries. Observers let tooling record runs, for example
+//! by logging every input to a database, without touching the properties.
+//!
+//! Once the run is over, observers also get a [`RunEvent`] summing it up,
+//! which the emitters of [`report`](crate::report) turn into CI reports.
+//!
+//! Observers are added to one test with `#[proptest(observer = path)]`,
+//! where `path` is a function returning the observer, or to a
+//! [`Config`](crate::runner::Config) with
+//! [`with_observer`](crate::runner::Config::with_observer). Observers added
+//! with [`add_global`] see every run of the process that starts afterwards.
+
+use std::{
+    fmt::{self, Debug},
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use crate::{runner::Report, strategy::Provenance};
+
+/// Receives the cases of the runs it observes.
+///
+/// Runs with several worker threads call observers from all of them, in
+/// whatever order the cases finish.
+pub trait CaseObserver: Send + Sync {
+    fn on_case(&self, event: &CaseEvent<'_>);
+
+    /// Called for every candidate tried while shrinking inside a case.
+    fn on_shrink_attempt(&self, attempt: &ShrinkAttempt) {
+        let _ = attempt;
+    }
+
+    /// Called once the cases of a run are over, before it panics if it
+    /// failed.
+    fn on_run(&self, event: &RunEvent<'_>) {
+        let _ = event;
+    }
+}
+
+/// Where a `#[proptest]` test is defined, see
+/// [`Config::with_test`](crate::runner::Config::with_test).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestLocation {
+    /// The test's path, starting with its module path.
+    pub name: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+}
+
+/// A finished run.
+#[derive(Clone, Copy)]
+pub struct RunEvent<'a> {
+    /// The test the run belongs to, when it is known.
+    pub test: Option<&'a TestLocation>,
+    /// The run's label, such as the `#[values]` it used.
+    pub label: Option<&'a str>,
+    pub report: &'a Report,
+    /// How long the cases took, zero on targets without a clock.
+    pub elapsed: Duration,
+    /// The message the run panics with, when a case failed or a coverage
+    /// requirement was missed.
+    pub failure: Option<&'a str>,
+}
+
+/// A finished case.
+#[derive(Clone, Copy, Debug)]
+pub struct CaseEvent<'a> {
+    pub case: usize,
+    /// The seed the case's generator was built from.
+    pub seed: u64,
+    /// The arguments the case was called with, in order.
+    pub inputs: &'a [CaseInput],
+    pub status: CaseStatus<'a>,
+}
+
+/// An argument of a case.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseInput {
+    pub name: &'static str,
+    /// The argument's `Debug` output, or `None` when its type does not
+    /// implement `Debug`.
+    pub value: Option<String>,
+    /// Which strategies produced the argument and its parts, when it was
+    /// generated by a strategy whose tree tracks it.
+    pub provenance: Option<Provenance>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseStatus<'a> {
+    Passed,
+    /// The case panicked, with its panic message.
+    Failed {
+        message: &'a str,
+    },
+    /// The case repeated the inputs of an earlier one and was skipped.
+    Skipped,
+}
+
+/// A candidate tried while shrinking a failing value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShrinkAttempt {
+    /// The case shrinking ran in.
+    pub case: usize,
+    /// How many candidates the case tried before this one.
+    pub attempt: usize,
+    /// Whether the candidate still fails, so shrinking continues from it.
+    pub fails: bool,
+}
+
+/// Observers shared by a run, with a `Debug` impl for
+/// [`Config`](crate::runner::Config).
+#[derive(Clone, Default)]
+pub(crate) struct Observers(Vec<Arc<dyn CaseObserver>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Arc<dyn CaseObserver>) {
+        self.0.push(observer);
+    }
+
+    /// These observers followed by the global ones and the reporters
+    /// [`REPORT_ENV`](crate::report::REPORT_ENV) asks for.
+    pub(crate) fn with_global(&self) -> Self {
+        let global = GLOBAL.read().unwrap_or_else(PoisonError::into_inner);
+        Self(
+            self.0
+                .iter()
+                .chain(global.iter())
+                .chain(crate::report::from_env())
+                .cloned()
+                .collect(),
+        )
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn on_case(&self, event: &CaseEvent<'_>) {
+        for observer in &self.0 {
+            observer.on_case(event);
+        }
+    }
+
+    pub(crate) fn on_shrink_attempt(&self, attempt: &ShrinkAttempt) {
+        for observer in &self.0 {
+            observer.on_shrink_attempt(attempt);
+        }
+    }
+
+    pub(crate) fn on_run(&self, event: &RunEvent<'_>) {
+        for observer in &self.0 {
+            observer.on_run(event);
+        }
+    }
+}
+
+impl Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observer(s)", self.0.len())
+    }
+}
+
+static GLOBAL: RwLock<Vec<Arc<dyn CaseObserver>>> = RwLock::new(Vec::new());
+
+/// Observe every run of the process that starts from now on.
+pub fn add_global<O: CaseObserver + 'static>(observer: O) {
+    GLOBAL
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Arc::new(observer));
+}
+
+/// Remove every observer added with [`add_global`].
+pub fn clear_global() {
+    GLOBAL
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Wraps an argument so `#[proptest]` can ask for its `Debug` output
+/// whether or not its type implements `Debug`: [`DescribeDebug`] applies
+/// when it does, and [`DescribeOpaque`] through one more reference when it
+/// does not.
+#[doc(hidden)]
+pub struct Describe<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait DescribeDebug {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T: Debug + ?Sized> DescribeDebug for Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(format!("{:?}", self.0))
+    }
+}
+
+#[doc(hidden)]
+pub trait DescribeOpaque {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T: ?Sized> DescribeOpaque for &Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::{
+        runner::{Config, is_duplicate, record_inputs, run_cases},
+        shrink::shrink,
+        strategy::{IntValueTree, runtime::DefaultGenerator},
+    };
+
+    /// A case's index, inputs and status.
+    type Recorded = (usize, Vec<CaseInput>, String);
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        cases: Arc<Mutex<Vec<Recorded>>>,
+        attempts: Arc<Mutex<Vec<ShrinkAttempt>>>,
+    }
+
+    impl CaseObserver for Recorder {
+        fn on_case(&self, event: &CaseEvent<'_>) {
+            let status = match event.status {
+                CaseStatus::Passed => "passed".to_owned(),
+                CaseStatus::Failed { message } => message.to_owned(),
+                CaseStatus::Skipped => "skipped".to_owned(),
+            };
+            self.cases.lock().unwrap().push((
+                event.case,
+                event.inputs.to_vec(),
+                status,
+            ));
+        }
+
+        fn on_shrink_attempt(&self, attempt: &ShrinkAttempt) {
+            self.attempts.lock().unwrap().push(*attempt);
+        }
+    }
+
+    fn input(value: u8) -> Vec<CaseInput> {
+        vec![CaseInput {
+            name: "value",
+            value: Some(value.to_string()),
+            provenance: None,
+        }]
+    }
+
+    #[test]
+    fn observers_see_inputs_and_outcomes() {
+        let recorder = Recorder::default();
+        let config = Config::new(8).with_observer(recorder.clone());
+        let report =
+            run_cases(&config, 3, &|generator: &mut DefaultGenerator| {
+                let value = generator.rng.random_range(0..4u8);
+                record_inputs(input(value));
+                assert!(value < 3, "too large");
+            });
+
+        let cases = recorder.cases.lock().unwrap();
+        assert_eq!(cases.len(), report.cases_run);
+        for (_, inputs, status) in cases.iter() {
+            let value = inputs[0].value.as_deref().unwrap();
+            let expected = if value == "3" { "too large" } else { "passed" };
+            assert_eq!(status, expected);
+        }
+    }
+
+    #[test]
+    fn skipped_duplicates_are_reported() {
+        let recorder = Recorder::default();
+        let config = Config::new(16)
+            .with_dedup(true)
+            .with_observer(recorder.clone());
+        run_cases(&config, 5, &|_: &mut DefaultGenerator| {
+            let _ = is_duplicate(&0u8);
+        });
+
+        let cases = recorder.cases.lock().unwrap();
+        let skipped = cases
+            .iter()
+            .filter(|(.., status)| status == "skipped")
+            .count();
+        assert_eq!(skipped, 15);
+    }
+
+    #[test]
+    fn shrinking_inside_a_case_reports_every_attempt() {
+        let recorder = Recorder::default();
+        let config = Config::new(1).with_observer(recorder.clone());
+        let results = Mutex::new(Vec::new());
+        run_cases(&config, 9, &|_: &mut DefaultGenerator| {
+            let mut tree = IntValueTree::new(30u8, vec![0, 10, 20]);
+            shrink(&mut tree, |value| {
+                results.lock().unwrap().push(*value >= 10);
+                *value >= 10
+            });
+        });
+
+        let attempts = recorder.attempts.lock().unwrap();
+        let fails: Vec<bool> =
+            attempts.iter().map(|attempt| attempt.fails).collect();
+        assert_eq!(fails, *results.lock().unwrap());
+        assert!(!fails.is_empty());
+        for (index, attempt) in attempts.iter().enumerate() {
+            assert_eq!(attempt.case, 0);
+            assert_eq!(attempt.attempt, index);
+        }
+    }
+
+    #[test]
+    fn describe_falls_back_for_types_without_debug() {
+        struct Opaque;
+
+        assert_eq!(Describe(&7u8).describe(), Some("7".to_owned()));
+        assert_eq!((&Describe(&Opaque)).describe(), None);
+    }
+}
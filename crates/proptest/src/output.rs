@@ -0,0 +1,105 @@
+//! Terminal output of failures.
+//!
+//! The messages the runner prints to stderr highlight what a reader looks
+//! for first: the seed to rerun with, the names of the failing case's
+//! arguments, where it panicked and shrink statistics. Colors are only used
+//! when stderr is a terminal and the `NO_COLOR` environment variable (see
+//! <https://no-color.org>) is unset or empty, so logs and CI output stay
+//! plain text. The messages the runner panics with, and those observers and
+//! [`report`](crate::report) emitters receive, never contain colors.
+
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    io::{self, IsTerminal},
+};
+
+/// Environment variable that turns colors off when set to anything but the
+/// empty string.
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// The kinds of text [`paint`] highlights.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Style {
+    /// A seed, or the setting that reruns it.
+    Seed,
+    /// The name of an argument of the failing case.
+    Argument,
+    /// A source location.
+    Location,
+    /// Counts of cases, evaluations and shrink steps.
+    Stats,
+    /// A section heading, such as `Inputs:`.
+    Heading,
+}
+
+impl Style {
+    fn escape(self) -> &'static str {
+        match self {
+            Self::Seed => "\x1b[1;33m",
+            Self::Argument => "\x1b[36m",
+            Self::Location => "\x1b[2m",
+            Self::Stats => "\x1b[35m",
+            Self::Heading => "\x1b[1m",
+        }
+    }
+}
+
+/// Whether output to stderr should be colored.
+pub fn color() -> bool {
+    use_color(env::var_os(NO_COLOR_ENV), io::stderr().is_terminal())
+}
+
+fn use_color(no_color: Option<OsString>, terminal: bool) -> bool {
+    terminal && no_color.is_none_or(|value| value.is_empty())
+}
+
+/// `text`, displayed in `style` when `color` is set and as is otherwise.
+pub fn paint<T: fmt::Display>(
+    text: T,
+    style: Style,
+    color: bool,
+) -> Painted<T> {
+    Painted { text, style, color }
+}
+
+/// Text that may be displayed in a [`Style`], see [`paint`].
+#[derive(Clone, Copy, Debug)]
+pub struct Painted<T> {
+    text: T,
+    style: Style,
+    color: bool,
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.color {
+            write!(f, "{}{}\x1b[0m", self.style.escape(), self.text)
+        } else {
+            self.text.fmt(f)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_need_a_terminal_and_no_no_color() {
+        assert!(use_color(None, true));
+        assert!(use_color(Some(OsString::new()), true));
+        assert!(!use_color(Some("1".into()), true));
+        assert!(!use_color(None, false));
+    }
+
+    #[test]
+    fn painting_wraps_text_in_escapes_only_with_color() {
+        assert_eq!(paint("0x7", Style::Seed, false).to_string(), "0x7");
+        assert_eq!(
+            paint(42, Style::Stats, true).to_string(),
+            "\x1b[35m42\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+//! Saved failures, and shrinking them further outside the test run.
+//!
+//! [`check`] runs a property like `#[proptest]` does, and also shrinks its
+//! first failure and saves the seed, size and [`ShrinkTrace`] that reach
+//! the shrunk value to a [`FailureFile`]. Later runs replay the saved
+//! failure before generating new cases, so a regression stays covered until
+//! it is fixed. Once it passes, it is removed from the file.
+//!
+//! Shrinking inside `cargo test` stops after 1,000 evaluations of the
+//! property, keeping the best value found so far. Both modes run the
+//! property through a [`ShrinkCache`], so values shrinking revisits are not
+//! checked twice. The `estoa-reduce` binary
+//! reruns a test in reduction mode instead, by setting [`REDUCE_ENV`]. In
+//! that mode, [`check`] only shrinks the saved failure further with a
+//! [`Reducer`], which runs every [`ShrinkScheduler`] in turn under a larger
+//! budget. It then writes the smaller case back to the file:
+//!
+//! ```text
+//! estoa-reduce --budget 1000000 --scheduler ddmin parser_round_trips
+//! ```
+//!
+//! Reduction replays the saved trace first, so its result never ends up
+//! larger than the saved case.
+
+use std::{
+    cell::Cell,
+    env,
+    fmt::{self, Debug},
+    fs,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    corpus::regenerate,
+    output::{self, Style, paint},
+    runner::{Config, case_seed, case_size},
+    shrink::{
+        ShrinkBudget,
+        ShrinkCache,
+        ShrinkScheduler,
+        ShrinkTrace,
+        shrink_with_budget,
+        shrink_within,
+    },
+    strategy::{Strategy, ValueTree},
+};
+
+/// Environment variable that puts [`check`] in reduction mode.
+pub const REDUCE_ENV: &str = "ESTOA_REDUCE";
+
+/// Environment variable with the number of property evaluations a
+/// [`Reducer`] may spend.
+pub const BUDGET_ENV: &str = "ESTOA_REDUCE_BUDGET";
+
+/// Environment variable listing the schedulers a [`Reducer`] runs, by
+/// [`name`](ShrinkScheduler::name) and separated by commas.
+pub const SCHEDULERS_ENV: &str = "ESTOA_REDUCE_SCHEDULERS";
+
+/// Property evaluations [`check`] spends shrinking a new failure.
+const CHECK_BUDGET: usize = 1_000;
+
+/// A failing case, saved as what regenerates and shrinks it again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedFailure {
+    name: String,
+    /// Seed of the case's generator.
+    pub seed: u64,
+    /// Size of the case's generator.
+    pub size: usize,
+    /// Steps from the generated tree to the shrunk value.
+    pub trace: ShrinkTrace,
+}
+
+impl SavedFailure {
+    /// # Panics
+    ///
+    /// When `name` is empty or contains whitespace, which the file format
+    /// separates fields with.
+    pub fn new(
+        name: impl Into<String>,
+        seed: u64,
+        size: usize,
+        trace: ShrinkTrace,
+    ) -> Self {
+        let name = name.into();
+        assert!(
+            !name.is_empty() && !name.contains(char::is_whitespace),
+            "failure names cannot be empty or contain whitespace",
+        );
+        Self {
+            name,
+            seed,
+            size,
+            trace,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Regenerate the case from `strategy` and move it to the end of the
+    /// trace. `None` when the strategy rejects it, which a changed strategy
+    /// may do.
+    fn tree<S: Strategy>(&self, strategy: &mut S) -> Option<S::Tree> {
+        regenerate(strategy, self.seed, self.size, &self.trace)
+    }
+}
+
+/// One line of a [`FailureFile`]: the name, the seed in hex, the size and
+/// the trace, `-` when empty.
+impl fmt::Display for SavedFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:#x} {} ", self.name, self.seed, self.size)?;
+        if self.trace.is_empty() {
+            f.write_str("-")
+        } else {
+            write!(f, "{}", self.trace)
+        }
+    }
+}
+
+/// Error returned when parsing a [`SavedFailure`] from a line fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseSavedFailureError {
+    /// The line that failed to parse.
+    pub line: String,
+}
+
+impl fmt::Display for ParseSavedFailureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid saved failure `{}`, expected a name, a hex seed, a size \
+             and a trace",
+            self.line,
+        )
+    }
+}
+
+impl core::error::Error for ParseSavedFailureError {}
+
+impl FromStr for SavedFailure {
+    type Err = ParseSavedFailureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSavedFailureError { line: s.to_owned() };
+        let fields: Vec<_> = s.split_whitespace().collect();
+        let [name, seed, size, trace] = fields[..] else {
+            return Err(error());
+        };
+        let seed = seed
+            .strip_prefix("0x")
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+            .ok_or_else(error)?;
+        let size = size.parse().map_err(|_| error())?;
+        let trace = match trace {
+            "-" => ShrinkTrace::default(),
+            trace => trace.parse().map_err(|_| error())?,
+        };
+        Ok(Self::new(name, seed, size, trace))
+    }
+}
+
+/// A file of [`SavedFailure`]s, one per line, with `#` starting a comment.
+#[derive(Clone, Debug)]
+pub struct FailureFile {
+    path: PathBuf,
+    failures: Vec<SavedFailure>,
+}
+
+impl FailureFile {
+    /// Read the failures saved at `path`. A missing file has none.
+    ///
+    /// # Errors
+    ///
+    /// When the file cannot be read, or a line is not a saved failure.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let failures = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.parse().map_err(|err| {
+                    io::Error::new(io::ErrorKind::InvalidData, err)
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { path, failures })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn failures(&self) -> &[SavedFailure] {
+        &self.failures
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SavedFailure> {
+        self.failures.iter().find(|failure| failure.name == name)
+    }
+
+    /// Save `failure`, replacing the one saved under the same name.
+    pub fn insert(&mut self, failure: SavedFailure) {
+        match self
+            .failures
+            .iter_mut()
+            .find(|saved| saved.name == failure.name)
+        {
+            Some(saved) => *saved = failure,
+            None => self.failures.push(failure),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<SavedFailure> {
+        let index = self
+            .failures
+            .iter()
+            .position(|failure| failure.name == name)?;
+        Some(self.failures.remove(index))
+    }
+
+    /// Write the failures back to the file, creating its directory.
+    ///
+    /// # Errors
+    ///
+    /// When the file or its directory cannot be written.
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents =
+            String::from("# Failures saved by estoa: name seed size trace\n");
+        for failure in &self.failures {
+            contents.push_str(&failure.to_string());
+            contents.push('\n');
+        }
+        fs::write(&self.path, contents)
+    }
+}
+
+/// A saved failure shrunk further by a [`Reducer`].
+#[derive(Clone, Debug)]
+pub struct Reduced<V> {
+    pub value: V,
+    /// The failure reaching `value`, to save in place of the old one.
+    pub failure: SavedFailure,
+    /// Whether every scheduler ran to the end within the budget.
+    pub complete: bool,
+    /// How many times the property ran.
+    pub evaluations: usize,
+}
+
+/// Shrinks saved failures with several schedulers and a larger budget than
+/// a test run affords.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reducer {
+    schedulers: Vec<ShrinkScheduler>,
+    budget: usize,
+}
+
+impl Default for Reducer {
+    /// Every scheduler, depth-first first, within 100,000 evaluations.
+    fn default() -> Self {
+        Self {
+            schedulers: vec![
+                ShrinkScheduler::DepthFirst,
+                ShrinkScheduler::Ddmin,
+                ShrinkScheduler::BreadthFirst,
+            ],
+            budget: 100_000,
+        }
+    }
+}
+
+impl Reducer {
+    /// The reducer `estoa-reduce` asked for, or `None` outside reduction
+    /// mode. [`BUDGET_ENV`] and [`SCHEDULERS_ENV`] override the defaults.
+    ///
+    /// # Panics
+    ///
+    /// When either variable does not parse.
+    pub fn from_env() -> Option<Self> {
+        env::var_os(REDUCE_ENV)?;
+        let mut reducer = Self::default();
+        if let Ok(budget) = env::var(BUDGET_ENV) {
+            let budget = budget.trim().parse().unwrap_or_else(|_| {
+                panic!("{BUDGET_ENV} must be a number, not `{budget}`")
+            });
+            reducer = reducer.with_budget(budget);
+        }
+        if let Ok(schedulers) = env::var(SCHEDULERS_ENV) {
+            let schedulers = schedulers
+                .split(',')
+                .map(|name| name.trim().parse())
+                .collect::<Result<_, _>>()
+                .unwrap_or_else(|err| panic!("{SCHEDULERS_ENV}: {err}"));
+            reducer = reducer.with_schedulers(schedulers);
+        }
+        Some(reducer)
+    }
+
+    /// Run `schedulers` in order, each continuing from where the previous
+    /// one stopped.
+    ///
+    /// # Panics
+    ///
+    /// When `schedulers` is empty.
+    pub fn with_schedulers(mut self, schedulers: Vec<ShrinkScheduler>) -> Self {
+        assert!(!schedulers.is_empty(), "a reducer needs a scheduler");
+        self.schedulers = schedulers;
+        self
+    }
+
+    /// Evaluate the property at most `budget` times over all schedulers.
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Shrink `failure` of a property of `strategy` further. `property`
+    /// returns whether it holds. `None` when the saved case no longer fails
+    /// or cannot be regenerated.
+    pub fn reduce<S, P>(
+        &self,
+        failure: &SavedFailure,
+        strategy: &mut S,
+        property: P,
+    ) -> Option<Reduced<S::Value>>
+    where
+        S: Strategy,
+        S::Value: Clone + Debug,
+        P: Fn(&S::Value) -> bool,
+    {
+        let start = failure.tree(strategy)?;
+        if property(start.current()) {
+            return None;
+        }
+
+        let spent = Cell::new(0);
+        let mut cache = ShrinkCache::new(|value: &S::Value| {
+            spent.set(spent.get() + 1);
+            !property(value)
+        });
+
+        let mut reduced = Reduced {
+            value: start.into_current(),
+            failure: failure.clone(),
+            complete: true,
+            evaluations: 0,
+        };
+        for &scheduler in &self.schedulers {
+            let base = reduced.failure.clone();
+            let budget = ShrinkBudget::default()
+                .with_attempts(self.budget.saturating_sub(spent.get()));
+            let shrunk = shrink_with_budget(
+                || base.tree(strategy).expect("the saved case regenerates"),
+                scheduler,
+                budget,
+                |value| cache.fails(value),
+            );
+            reduced.value = shrunk.value;
+            reduced.complete &= shrunk.complete;
+            reduced
+                .failure
+                .trace
+                .extend(shrunk.trace.steps().iter().copied());
+        }
+        reduced.evaluations = spent.get();
+        Some(reduced)
+    }
+}
+
+/// Check that `property` holds for `cases` values of `strategy`, replaying
+/// the failure saved under `name` at `path` first, see the
+/// [module documentation](self). `property` returns whether it holds.
+///
+/// Cases are seeded like those of `#[proptest]`, from
+/// [`SEED_ENV`](crate::runner::SEED_ENV) when it is set. In reduction mode,
+/// only the saved failure is shrunk further and saved again.
+///
+/// # Panics
+///
+/// When the saved failure or a generated case fails, with the shrunk
+/// value, or when the file cannot be read or written.
+pub fn check<S, P>(
+    path: impl AsRef<Path>,
+    name: &str,
+    cases: usize,
+    mut strategy: S,
+    property: P,
+) where
+    S: Strategy,
+    S::Value: Clone + Debug,
+    P: Fn(&S::Value) -> bool,
+{
+    check_with(
+        path.as_ref(),
+        name,
+        cases,
+        &mut strategy,
+        property,
+        Reducer::from_env().as_ref(),
+    );
+}
+
+fn check_with<S, P>(
+    path: &Path,
+    name: &str,
+    cases: usize,
+    strategy: &mut S,
+    property: P,
+    reducer: Option<&Reducer>,
+) where
+    S: Strategy,
+    S::Value: Clone + Debug,
+    P: Fn(&S::Value) -> bool,
+{
+    let mut file = or_panic(FailureFile::open(path), path);
+
+    if let Some(reducer) = reducer {
+        let Some(saved) = file.get(name) else {
+            eprintln!("{name}: no failure saved in {}", path.display());
+            return;
+        };
+        match reducer.reduce(saved, strategy, &property) {
+            Some(reduced) => {
+                let note = if reduced.complete {
+                    ""
+                } else {
+                    " (the budget ran out, so it may not be minimal)"
+                };
+                eprintln!(
+                    "{name}: reduced to {:?} in {} evaluations{note}",
+                    reduced.value,
+                    paint(reduced.evaluations, Style::Stats, output::color()),
+                );
+                file.insert(reduced.failure);
+            }
+            None => {
+                eprintln!("{name}: the saved failure no longer fails");
+                file.remove(name);
+            }
+        }
+        or_panic(file.save(), path);
+        return;
+    }
+
+    if let Some(saved) = file.get(name) {
+        if let Some(tree) = saved.tree(strategy)
+            && !property(tree.current())
+        {
+            panic!(
+                "{name}: the failure saved in {} still fails: {:?}",
+                path.display(),
+                tree.current(),
+            );
+        }
+        file.remove(name);
+        or_panic(file.save(), path);
+    }
+
+    let root_seed = Config::new(cases).resolve_seed();
+    for index in 0..cases {
+        let seed = case_seed(root_seed, index);
+        let size = case_size(index, cases);
+        let failure =
+            SavedFailure::new(name, seed, size, ShrinkTrace::default());
+        let Some(mut tree) = failure.tree(strategy) else {
+            continue;
+        };
+        if property(tree.current()) {
+            continue;
+        }
+
+        let budget = ShrinkBudget::default().with_attempts(CHECK_BUDGET);
+        let mut cache = ShrinkCache::new(|value: &S::Value| !property(value));
+        let shrunk =
+            shrink_within(&mut tree, budget, |value| cache.fails(value));
+        let color = output::color();
+        let stats = format!(
+            "shrunk in {} steps with {} evaluations ({} cached)",
+            paint(shrunk.trace.len(), Style::Stats, color),
+            paint(cache.len(), Style::Stats, color),
+            paint(cache.hits(), Style::Stats, color),
+        );
+        file.insert(SavedFailure {
+            trace: shrunk.trace,
+            ..failure
+        });
+        or_panic(file.save(), path);
+        let minimal = if shrunk.complete {
+            ""
+        } else {
+            ", which may not be minimal"
+        };
+        panic!(
+            "{name}: case {index} failed, saved to {} (run `estoa-reduce \
+             {name}` to shrink it further){minimal}: {:?}\n  {stats}",
+            path.display(),
+            shrunk.value,
+        );
+    }
+}
+
+fn or_panic<T>(result: io::Result<T>, path: &Path) -> T {
+    result.unwrap_or_else(|err| panic!("cannot use {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    use super::*;
+    use crate::strategy::{AnyU32, VecStrategy};
+
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("estoa-reduce-{}-{name}.txt", std::process::id()))
+    }
+
+    fn holds(values: &[u32]) -> bool {
+        values.iter().sum::<u32>() < 1_000
+    }
+
+    fn strategy() -> VecStrategy<AnyU32> {
+        VecStrategy::new(AnyU32::new(0..=10_000), 0usize..=16usize)
+    }
+
+    #[test]
+    fn saved_failures_round_trip_through_lines() {
+        let failure =
+            SavedFailure::new("sorts", 0xbeef, 42, "ssc".parse().unwrap());
+        assert_eq!(failure.to_string(), "sorts 0xbeef 42 ssc");
+        assert_eq!("sorts 0xbeef 42 ssc".parse(), Ok(failure));
+
+        let empty = SavedFailure::new("sorts", 1, 0, ShrinkTrace::default());
+        assert_eq!("sorts 0x1 0 -".parse(), Ok(empty));
+        assert!("sorts 0x1 0".parse::<SavedFailure>().is_err());
+        assert!("sorts 1 0 -".parse::<SavedFailure>().is_err());
+    }
+
+    #[test]
+    fn failures_are_saved_replayed_and_reduced() {
+        let path = temp_file("reduced");
+        let run = |reducer: Option<&Reducer>| {
+            catch_unwind(AssertUnwindSafe(|| {
+                check_with(
+                    &path,
+                    "sum",
+                    256,
+                    &mut strategy(),
+                    |v| holds(v),
+                    reducer,
+                )
+            }))
+        };
+
+        assert!(run(None).is_err());
+        let saved = FailureFile::open(&path).unwrap().get("sum").cloned();
+        let saved = saved.expect("the failure was saved");
+        // The saved failure fails again before any new case runs.
+        assert!(run(None).is_err());
+
+        run(Some(&Reducer::default())).unwrap();
+        let file = FailureFile::open(&path).unwrap();
+        let reduced = file.get("sum").unwrap();
+        assert!(reduced.trace.steps().starts_with(saved.trace.steps()));
+        let before = saved.tree(&mut strategy()).unwrap().into_current();
+        let after = reduced.tree(&mut strategy()).unwrap().into_current();
+        assert!(!holds(&after));
+        assert!(after.len() <= before.len());
+        assert!(after.iter().sum::<u32>() <= before.iter().sum::<u32>());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fixed_failures_are_removed() {
+        let path = temp_file("fixed");
+        let mut file = FailureFile::open(&path).unwrap();
+        file.insert(SavedFailure::new("sum", 7, 0, ShrinkTrace::default()));
+        file.save().unwrap();
+
+        check_with(&path, "sum", 16, &mut strategy(), |_| true, None);
+        assert!(FailureFile::open(&path).unwrap().failures().is_empty());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reducers_respect_their_budget() {
+        let (failure, unshrunk) = (0..)
+            .find_map(|seed| {
+                let failure =
+                    SavedFailure::new("sum", seed, 100, ShrinkTrace::default());
+                let value = failure.tree(&mut strategy())?.into_current();
+                (!holds(&value)).then_some((failure, value))
+            })
+            .unwrap();
+        let reduced = Reducer::default()
+            .with_budget(0)
+            .reduce(&failure, &mut strategy(), |v| holds(v))
+            .unwrap();
+        assert_eq!(reduced.value, unshrunk);
+        assert!(!reduced.complete);
+    }
+}
@@ -0,0 +1,223 @@
+//! Default strategies for types, looked up before [`Arbitrary`].
+//!
+//! `#[proptest]` arguments without a `#[strategy]` are generated through
+//! [`generate_tree`], which draws from the strategy registered for the
+//! argument's type and only falls back to its [`Arbitrary`] impl when there
+//! is none, keeping the tree to shrink the argument with. A
+//! project can register its defaults once, say strings of at most 16
+//! characters, instead of repeating the same `#[strategy]` on every
+//! argument:
+//!
+//! ```
+//! use estoa_proptest::{registry, strategy::AnyString};
+//!
+//! registry::register(|| AnyString::new(0usize..=16usize));
+//! ```
+//!
+//! The global registry is shared by every test of the process, so it suits
+//! defaults that hold for the whole project. A single test can use its own
+//! [`Registry`] with `#[proptest(registry = path::to::fn)]`, where the
+//! function builds the registry; types it leaves out go to the global
+//! registry as usual.
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, LazyLock, PoisonError, RwLock},
+};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::{
+    Arbitrary,
+    ArbitraryTree,
+    strategy::{
+        Strategy,
+        runtime::{DynGenerator, Generation, Generator},
+    },
+};
+
+type Draw<T> = Arc<
+    dyn Fn(&mut DynGenerator<'_>) -> Generation<ArbitraryTree<T>> + Send + Sync,
+>;
+
+static GLOBAL: LazyLock<RwLock<Registry>> =
+    LazyLock::new(|| RwLock::new(Registry::new()));
+
+/// Strategies for types, keyed by their [`TypeId`].
+#[derive(Default)]
+pub struct Registry {
+    draws: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate `S::Value`s from the strategies `strategy` returns, replacing
+    /// any strategy registered for the type before.
+    ///
+    /// `strategy` runs once per generated value, like the expression of a
+    /// `#[strategy]` attribute.
+    pub fn register<S, F>(&mut self, strategy: F)
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: Strategy,
+        S::Tree: 'static,
+        S::Value: Clone + 'static,
+    {
+        let draw: Draw<S::Value> = Arc::new(move |generator| {
+            strategy().new_tree(generator).map(ArbitraryTree::new)
+        });
+        self.draws.insert(TypeId::of::<S::Value>(), Box::new(draw));
+    }
+
+    /// Remove the strategy registered for `T`, returning whether there was
+    /// one.
+    pub fn unregister<T: 'static>(&mut self) -> bool {
+        self.draws.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.draws.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Generate a `T` from the strategy registered here, or like [`generate`]
+    /// when there is none.
+    pub fn generate<T, R>(&self, generator: &mut Generator<R>) -> Generation<T>
+    where
+        T: Arbitrary + 'static,
+        R: RngCore + CryptoRng,
+    {
+        match self.draw::<T>() {
+            Some(draw) => generator
+                .erased(|erased| draw(erased).map(ArbitraryTree::into_current)),
+            None => generate(generator),
+        }
+    }
+
+    /// Generate a tree for a `T` from the strategy registered here, or like
+    /// [`generate_tree`] when there is none.
+    pub fn generate_tree<T, R>(
+        &self,
+        generator: &mut Generator<R>,
+    ) -> Generation<ArbitraryTree<T>>
+    where
+        T: Arbitrary + 'static,
+        R: RngCore + CryptoRng,
+    {
+        match self.draw::<T>() {
+            Some(draw) => generator.erased(|erased| draw(erased)),
+            None => generate_tree(generator),
+        }
+    }
+
+    fn draw<T: 'static>(&self) -> Option<Draw<T>> {
+        self.draws
+            .get(&TypeId::of::<T>())
+            .and_then(|draw| draw.downcast_ref::<Draw<T>>())
+            .cloned()
+    }
+}
+
+/// Register a strategy for `S::Value` in the global registry, see
+/// [`Registry::register`].
+pub fn register<S, F>(strategy: F)
+where
+    F: Fn() -> S + Send + Sync + 'static,
+    S: Strategy,
+    S::Tree: 'static,
+    S::Value: Clone + 'static,
+{
+    GLOBAL
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .register(strategy);
+}
+
+/// Remove the strategy for `T` from the global registry, returning whether
+/// there was one.
+pub fn unregister<T: 'static>() -> bool {
+    GLOBAL
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .unregister::<T>()
+}
+
+/// Generate a `T` from the strategy in the global registry, or from its
+/// [`Arbitrary`] impl when none is registered.
+pub fn generate<T, R>(generator: &mut Generator<R>) -> Generation<T>
+where
+    T: Arbitrary + 'static,
+    R: RngCore + CryptoRng,
+{
+    // Release the lock before drawing, so strategies may use the registry.
+    let draw = GLOBAL
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .draw::<T>();
+    match draw {
+        Some(draw) => generator
+            .erased(|erased| draw(erased).map(ArbitraryTree::into_current)),
+        None => T::generate(generator),
+    }
+}
+
+/// Generate a tree for a `T` from the strategy in the global registry, or
+/// through [`Arbitrary::generate_tree`] when none is registered.
+pub fn generate_tree<T, R>(
+    generator: &mut Generator<R>,
+) -> Generation<ArbitraryTree<T>>
+where
+    T: Arbitrary + 'static,
+    R: RngCore + CryptoRng,
+{
+    let draw = GLOBAL
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .draw::<T>();
+    match draw {
+        Some(draw) => generator.erased(|erased| draw(erased)),
+        None => T::generate_tree(generator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::AnyU16;
+
+    /// Only this test registers `u16`s globally, so it can run alongside
+    /// the others.
+    #[test]
+    fn the_global_registry_overrides_arbitrary() {
+        let mut generator = Generator::build(StdRng::seed_from_u64(41));
+        register(|| AnyU16::new(7..=9));
+        for _ in 0..32 {
+            let value: u16 = generate(&mut generator).take();
+            assert!((7..=9).contains(&value));
+        }
+
+        assert!(unregister::<u16>());
+        assert!(!unregister::<u16>());
+    }
+
+    #[test]
+    fn a_local_registry_only_covers_its_types() {
+        let mut registry = Registry::new();
+        registry.register(|| AnyU16::new(100..=100));
+        assert!(registry.contains::<u16>());
+        assert!(!registry.contains::<u32>());
+
+        let mut generator = Generator::build(StdRng::seed_from_u64(43));
+        let value: u16 = registry.generate(&mut generator).take();
+        assert_eq!(value, 100);
+        let _: u32 = registry.generate(&mut generator).take();
+
+        assert!(registry.unregister::<u16>());
+        assert!(!registry.contains::<u16>());
+    }
+}
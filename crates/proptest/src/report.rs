@@ -0,0 +1,390 @@
+//! Reports for CI systems.
+//!
+//! [`JUnit`] writes a JUnit XML file per property run, which most CI
+//! systems collect into a test summary, and [`GithubAnnotations`] prints a
+//! GitHub Actions `::error` command for every failing run, so the failure
+//! shows up on the line of the test in the pull request. Both report the
+//! seed to rerun with and the inputs of the failing case.
+//!
+//! They are [observers](crate::observer) of the runs' ends, added like any
+//! other observer, or to every run of the process by setting [`REPORT_ENV`]
+//! to a comma-separated list of `junit=DIR` and `github`:
+//!
+//! ```text
+//! ESTOA_PROPTEST_REPORT=junit=target/junit,github cargo test
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    observer::{CaseEvent, CaseObserver, RunEvent},
+    runner::SEED_ENV,
+};
+
+/// Environment variable listing the reporters every run uses.
+pub const REPORT_ENV: &str = "ESTOA_PROPTEST_REPORT";
+
+static FROM_ENV: LazyLock<Vec<Arc<dyn CaseObserver>>> = LazyLock::new(|| {
+    let Ok(reporters) = std::env::var(REPORT_ENV) else {
+        return Vec::new();
+    };
+    reporters
+        .split(',')
+        .map(str::trim)
+        .filter(|reporter| !reporter.is_empty())
+        .map(|reporter| -> Arc<dyn CaseObserver> {
+            match reporter.split_once('=') {
+                None if reporter == "github" => Arc::new(GithubAnnotations),
+                Some(("junit", dir)) if !dir.is_empty() => {
+                    Arc::new(JUnit::new(dir))
+                }
+                _ => panic!(
+                    "{REPORT_ENV}: unknown reporter `{reporter}`, expected \
+                     `junit=DIR` or `github`"
+                ),
+            }
+        })
+        .collect()
+});
+
+/// The reporters [`REPORT_ENV`] asks for.
+///
+/// # Panics
+///
+/// When it lists an unknown reporter.
+pub(crate) fn from_env() -> &'static [Arc<dyn CaseObserver>] {
+    &FROM_ENV
+}
+
+/// Writes a JUnit XML file for each run to a directory, named after the
+/// test and the run's label.
+///
+/// Files of later runs of the same test and label replace earlier ones.
+#[derive(Clone, Debug)]
+pub struct JUnit {
+    dir: PathBuf,
+}
+
+impl JUnit {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn write(&self, event: &RunEvent<'_>) -> io::Result<PathBuf> {
+        let name = event.test.map_or("proptest", |test| test.name);
+        let (class, test) = name.rsplit_once("::").unwrap_or(("", name));
+        let test = match event.label {
+            Some(label) => format!("{test} with {label}"),
+            None => test.to_owned(),
+        };
+        let time = event.elapsed.as_secs_f64();
+        let failures = usize::from(event.failure.is_some());
+
+        let mut xml =
+            String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuite name=\"{}\" tests=\"1\" failures=\"{failures}\" \
+             time=\"{time:.3}\">",
+            escape(name),
+        );
+        let _ = writeln!(xml, "  <properties>");
+        let _ = writeln!(
+            xml,
+            "    <property name=\"seed\" value=\"{:#x}\"/>",
+            event.report.seed,
+        );
+        let _ = writeln!(
+            xml,
+            "    <property name=\"cases\" value=\"{}\"/>",
+            event.report.cases_run,
+        );
+        let _ = writeln!(xml, "  </properties>");
+        let _ = write!(
+            xml,
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{time:.3}\"",
+            escape(class),
+            escape(&test),
+        );
+        if let Some(location) = event.test {
+            let _ = write!(
+                xml,
+                " file=\"{}\" line=\"{}\"",
+                escape(location.file),
+                location.line,
+            );
+        }
+        // Flaky cases are reported the way Maven Surefire reports reruns.
+        let mut children = String::new();
+        for flaky in &event.report.flaky {
+            let _ = writeln!(
+                children,
+                "    <flakyFailure message=\"case {} passed on retry {}\">{}</flakyFailure>",
+                flaky.case,
+                flaky.passed_on,
+                escape(&flaky.message),
+            );
+        }
+        if let Some(message) = event.failure {
+            let summary = message.lines().next().unwrap_or_default();
+            let _ = writeln!(
+                children,
+                "    <failure message=\"{}\">{}</failure>",
+                escape(summary),
+                escape(&details(event, message)),
+            );
+        }
+        if children.is_empty() {
+            xml.push_str("/>\n");
+        } else {
+            let _ = write!(xml, ">\n{children}  </testcase>\n");
+        }
+        xml.push_str("</testsuite>\n");
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self
+            .dir
+            .join(format!("{}.xml", file_stem(&name_with_label(event))));
+        fs::write(&path, xml)?;
+        Ok(path)
+    }
+}
+
+impl CaseObserver for JUnit {
+    fn on_case(&self, _event: &CaseEvent<'_>) {}
+
+    fn on_run(&self, event: &RunEvent<'_>) {
+        if let Err(err) = self.write(event) {
+            eprintln!(
+                "#[proptest] cannot write a JUnit report to {}: {err}",
+                self.dir.display(),
+            );
+        }
+    }
+}
+
+/// Prints a GitHub Actions `::error` annotation for each failing run,
+/// pointing at the test when its location is known.
+///
+/// Annotations go straight to the standard output, past the test harness's
+/// capture, which is where the Actions runner reads commands from.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GithubAnnotations;
+
+impl CaseObserver for GithubAnnotations {
+    fn on_case(&self, _event: &CaseEvent<'_>) {}
+
+    fn on_run(&self, event: &RunEvent<'_>) {
+        if let Some(annotation) = annotation(event) {
+            let _ = writeln!(io::stdout().lock(), "{annotation}");
+        }
+    }
+}
+
+/// The `::error` command for `event`, or `None` when the run passed.
+fn annotation(event: &RunEvent<'_>) -> Option<String> {
+    let message = event.failure?;
+    let mut annotation = String::from("::error ");
+    if let Some(location) = event.test {
+        let _ = write!(
+            annotation,
+            "file={},line={},",
+            escape_property(location.file),
+            location.line,
+        );
+    }
+    let _ = write!(
+        annotation,
+        "title={}::{}",
+        escape_property(&format!("{} failed", name_with_label(event))),
+        escape_data(&details(event, message)),
+    );
+    Some(annotation)
+}
+
+/// The test's name, followed by the run's label.
+fn name_with_label(event: &RunEvent<'_>) -> String {
+    let name = event.test.map_or("proptest", |test| test.name);
+    match event.label {
+        Some(label) => format!("{name} with {label}"),
+        None => name.to_owned(),
+    }
+}
+
+/// The failure message, which lists the inputs of the failing case, followed
+/// by the seed to rerun it with.
+fn details(event: &RunEvent<'_>, message: &str) -> String {
+    let mut details = message.to_owned();
+    let _ = write!(
+        details,
+        "\n\nRerun with {SEED_ENV}={:#x}",
+        event.report.seed,
+    );
+    details
+}
+
+fn file_stem(name: &str) -> String {
+    name.replace("::", ".")
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command, such as its `title`.
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        panic::{AssertUnwindSafe, catch_unwind},
+        sync::Mutex,
+    };
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::{
+        observer::CaseInput,
+        runner::{Config, record_inputs, run},
+        strategy::runtime::DefaultGenerator,
+    };
+
+    /// Keeps the annotations of the runs it sees.
+    #[derive(Clone, Default)]
+    struct Annotations(Arc<Mutex<Vec<Option<String>>>>);
+
+    impl CaseObserver for Annotations {
+        fn on_case(&self, _event: &CaseEvent<'_>) {}
+
+        fn on_run(&self, event: &RunEvent<'_>) {
+            self.0.lock().unwrap().push(annotation(event));
+        }
+    }
+
+    fn failing(generator: &mut DefaultGenerator) {
+        let value = generator.rng.random_range(0..8u8);
+        record_inputs(vec![CaseInput {
+            name: "value",
+            value: Some(value.to_string()),
+            provenance: None,
+        }]);
+        assert!(value < 4, "too large, 50% of the time");
+    }
+
+    fn config() -> Config {
+        Config::new(64).with_seed(7).with_test(
+            "estoa::tests::small",
+            "src/lib.rs",
+            12,
+        )
+    }
+
+    #[test]
+    fn junit_reports_are_written_per_run() {
+        let dir =
+            env::temp_dir().join(format!("estoa-junit-{}", std::process::id()));
+        let failed = config().with_observer(JUnit::new(&dir));
+        assert!(
+            catch_unwind(AssertUnwindSafe(|| run(&failed, failing))).is_err()
+        );
+
+        let xml =
+            fs::read_to_string(dir.join("estoa.tests.small.xml")).unwrap();
+        assert!(xml.contains(
+            "<testsuite name=\"estoa::tests::small\" tests=\"1\" failures=\"1\""
+        ));
+        assert!(
+            xml.contains("<testcase classname=\"estoa::tests\" name=\"small\"")
+        );
+        assert!(xml.contains("file=\"src/lib.rs\" line=\"12\""));
+        assert!(xml.contains("<property name=\"seed\" value=\"0x7\"/>"));
+        assert!(xml.contains("too large, 50% of the time"));
+        assert!(xml.contains("Inputs:\n  value = "));
+
+        let passing =
+            config().with_label("n = 1").with_observer(JUnit::new(&dir));
+        run(&passing, |_| {});
+        let xml =
+            fs::read_to_string(dir.join("estoa.tests.small_with_n___1.xml"))
+                .unwrap();
+        assert!(xml.contains("failures=\"0\""));
+        assert!(xml.contains("name=\"small with n = 1\" time="));
+        assert!(!xml.contains("<failure"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn github_annotations_point_at_failing_tests() {
+        let annotations = Annotations::default();
+        let config = config().with_observer(annotations.clone());
+        assert!(
+            catch_unwind(AssertUnwindSafe(|| run(&config, failing))).is_err()
+        );
+        run(&config, |_| {});
+
+        let annotations = annotations.0.lock().unwrap();
+        let [Some(failed), None] = &annotations[..] else {
+            panic!("expected one annotation, got {annotations:?}");
+        };
+        assert!(failed.starts_with(
+            "::error file=src/lib.rs,line=12,title=estoa%3A%3Atests%3A%3Asmall failed::#[proptest] case "
+        ));
+        assert!(failed.contains(&format!(
+            "too large, 50%25 of the time%0A  at {}:",
+            file!(),
+        )));
+        assert!(failed.contains("%0A%0AInputs:%0A  value = "));
+        assert!(failed.ends_with(&format!("%0A%0ARerun with {SEED_ENV}=0x7")));
+        assert!(!failed.contains('\n'));
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert_eq!(
+            escape("<a href=\"x\">&'"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&apos;"
+        );
+        assert_eq!(escape_data("50%\r\n"), "50%25%0D%0A");
+        assert_eq!(escape_property("a::b, c"), "a%3A%3Ab%2C c");
+        assert_eq!(file_stem("a::b with x = [1]"), "a.b_with_x____1_");
+    }
+}
@@ -0,0 +1,2400 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, hash_map::Entry},
+    env,
+    fmt::{self, Debug, Write},
+    hash::{DefaultHasher, Hasher},
+    mem,
+    panic::{self, AssertUnwindSafe, catch_unwind},
+    sync::{
+        Arc,
+        Mutex,
+        Once,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::{
+    coverage::LabelCoverage,
+    observer::{
+        CaseEvent,
+        CaseInput,
+        CaseObserver,
+        CaseStatus,
+        Observers,
+        RunEvent,
+        ShrinkAttempt,
+        TestLocation,
+    },
+    output::{self, Painted, Style, paint},
+    shrink::{
+        ErasedValueTree,
+        ShrinkBudget,
+        ShrinkScheduler,
+        ShrinkStep,
+        ShrinkTrace,
+        shrink_with_budget,
+    },
+    strategy::{
+        ValueTree,
+        runtime::{
+            CountingRng,
+            DefaultGenerator,
+            Generator,
+            GeneratorStats,
+            MAX_SIZE,
+            RejectReason,
+            SwarmConfig,
+        },
+        set_order_seed,
+    },
+};
+
+/// Environment variable overriding the root seed used to derive case seeds.
+pub const SEED_ENV: &str = "ESTOA_PROPTEST_SEED";
+
+/// Environment variable selecting the worker count when `threads` is unset.
+pub const THREADS_ENV: &str = "ESTOA_PROPTEST_THREADS";
+
+/// Environment variable selecting how often a failing case is retried when
+/// `retries` is unset.
+pub const RETRIES_ENV: &str = "ESTOA_PROPTEST_RETRIES";
+
+/// `std::time::Instant::now` panics on `wasm32-unknown-unknown`.
+const HAS_CLOCK: bool =
+    !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+/// Number of cases run when a property does not set `cases`.
+///
+/// Interpreting a property under Miri is orders of magnitude slower than
+/// running it natively, so the default shrinks to a handful of cases there.
+pub const DEFAULT_CASES: usize = if cfg!(miri) { 16 } else { 10_000 };
+
+/// Runs of a failing case the runner spends shrinking it.
+const SHRINK_BUDGET: usize = 1_000;
+
+/// Settings for a single `#[proptest]` run.
+#[derive(Clone, Debug)]
+pub struct Config {
+    cases: usize,
+    recursion_limit: usize,
+    entropy_limit: Option<u64>,
+    threads: Option<usize>,
+    retries: Option<usize>,
+    seed: Option<u64>,
+    time_budget: Option<Duration>,
+    label: Option<String>,
+    test: Option<TestLocation>,
+    swarm: Option<SwarmConfig>,
+    dedup: bool,
+    capture_panics: bool,
+    chaos_order: bool,
+    max_failures: usize,
+    shrink: ShrinkScheduler,
+    observers: Observers,
+}
+
+impl Config {
+    pub fn new(cases: usize) -> Self {
+        Self {
+            cases,
+            recursion_limit: usize::MAX,
+            entropy_limit: None,
+            threads: None,
+            retries: None,
+            seed: None,
+            time_budget: None,
+            label: None,
+            test: None,
+            swarm: None,
+            dedup: false,
+            capture_panics: true,
+            chaos_order: false,
+            max_failures: 1,
+            shrink: ShrinkScheduler::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    pub fn with_recursion_limit(mut self, recursion_limit: usize) -> Self {
+        self.recursion_limit = recursion_limit;
+        self
+    }
+
+    /// Fail a case once it drew more than `bytes` bytes of randomness,
+    /// naming the strategy drawing, instead of letting a strategy that
+    /// loops without end hang the run. Cases are unbounded by default.
+    pub fn with_entropy_limit(mut self, bytes: u64) -> Self {
+        self.entropy_limit = Some(bytes);
+        self
+    }
+
+    /// Run cases on `threads` workers instead of consulting [`THREADS_ENV`].
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    /// Retry a failing case up to `retries` times with the same input
+    /// instead of consulting [`RETRIES_ENV`].
+    ///
+    /// A case that passes on a retry is flaky: like a flaky test under
+    /// `cargo nextest`, it is listed in [`Report::flaky`] and reported on
+    /// stderr, but does not fail the run. Only a case failing every retry
+    /// does.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// Fix the root seed instead of consulting [`SEED_ENV`].
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Stop starting new cases once `budget` has elapsed.
+    ///
+    /// `cases` still bounds the run, so pair this with `usize::MAX` cases to
+    /// run as many as fit in the budget.
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Enable swarm testing: every case disables a different random subset
+    /// of the alternatives at each choice point.
+    pub fn with_swarm(mut self, swarm: SwarmConfig) -> Self {
+        self.swarm = Some(swarm);
+        self
+    }
+
+    /// Skip cases whose generated inputs repeat an earlier case; see
+    /// [`is_duplicate`].
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Whether a failure reports where the case panicked and which
+    /// [`prop_assert!`](crate::prop_assert) failed, on by default.
+    ///
+    /// Capturing the location installs a panic hook for the process, once,
+    /// which runs the hook it replaces after recording the location. Turn
+    /// it off for properties that install their own hook.
+    pub fn with_capture_panics(mut self, capture: bool) -> Self {
+        self.capture_panics = capture;
+        self
+    }
+
+    /// Give every case an ordering seed of its own, derived from the case
+    /// seed, so the `HashMap`s and `HashSet`s hashing with
+    /// [`ChaosState`](crate::strategy::ChaosState) iterate in a different
+    /// order in every case. A failure reports the seed of its case.
+    pub fn with_chaos_order(mut self, chaos_order: bool) -> Self {
+        self.chaos_order = chaos_order;
+        self
+    }
+
+    /// Keep running cases after a failure until `max_failures` cases
+    /// failed, instead of stopping at the first one.
+    ///
+    /// The failure message then lists one example for every distinct way
+    /// the cases failed, see [`Report::groups`], so a single run can reveal
+    /// several bugs. Failures past the first do not change which cases
+    /// run: the run still ends with the `max_failures` lowest failing case
+    /// indices, whatever the number of threads. Each of them is shrunk,
+    /// which takes a shrink phase per failure.
+    pub fn with_max_failures(mut self, max_failures: usize) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Pick the order in which failing cases are shrunk.
+    ///
+    /// The runner shrinks a failing case by running it again with the value
+    /// trees of the arguments `#[proptest]` draws from strategies moved
+    /// along the traces this scheduler explores.
+    pub fn with_shrink(mut self, scheduler: ShrinkScheduler) -> Self {
+        self.shrink = scheduler;
+        self
+    }
+
+    /// Tell `observer` about every case of the run, see
+    /// [`observer`](crate::observer).
+    pub fn with_observer<O: CaseObserver + 'static>(
+        mut self,
+        observer: O,
+    ) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    /// Describe the run in failure messages, e.g. the `#[values]` in use.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Name the test the run belongs to, for observers and the reports of
+    /// [`report`](crate::report). `#[proptest]` passes the test's path and
+    /// where it is defined.
+    pub fn with_test(
+        mut self,
+        name: &'static str,
+        file: &'static str,
+        line: u32,
+    ) -> Self {
+        self.test = Some(TestLocation { name, file, line });
+        self
+    }
+
+    pub fn cases(&self) -> usize {
+        self.cases
+    }
+
+    pub fn shrink(&self) -> ShrinkScheduler {
+        self.shrink
+    }
+
+    /// ` with <label>` for messages about a labelled run, empty otherwise.
+    fn label_suffix(&self) -> String {
+        self.label
+            .as_ref()
+            .map(|label| format!(" with {label}"))
+            .unwrap_or_default()
+    }
+
+    fn resolve_threads(&self) -> usize {
+        // Spawning threads panics on wasm targets built without atomics.
+        if cfg!(all(target_family = "wasm", not(target_feature = "atomics"))) {
+            return 1;
+        }
+
+        self.threads
+            .or_else(|| env_usize(THREADS_ENV))
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    fn resolve_retries(&self) -> usize {
+        self.retries.or_else(|| env_usize(RETRIES_ENV)).unwrap_or(0)
+    }
+
+    pub(crate) fn resolve_seed(&self) -> u64 {
+        self.seed
+            .or_else(|| env::var(SEED_ENV).ok().and_then(|v| parse_u64(&v)))
+            .unwrap_or_else(fresh_seed)
+    }
+
+    /// A generator for the cases of the run, to [`start_case`] on.
+    ///
+    /// [`start_case`]: Self::start_case
+    fn generator(&self) -> DefaultGenerator {
+        let generator =
+            Generator::build(CountingRng::new(StdRng::seed_from_u64(0)))
+                .with_limit(self.recursion_limit);
+        match self.swarm {
+            Some(swarm) => generator.with_swarm(swarm, 0),
+            None => generator,
+        }
+    }
+
+    /// Reset `generator` for the case of `seed`, drawing values of `size`,
+    /// and give the thread the case's ordering seed.
+    fn start_case(
+        &self,
+        generator: &mut DefaultGenerator,
+        seed: u64,
+        size: usize,
+    ) {
+        set_order_seed(self.chaos_order.then(|| case_seed(seed, 1)));
+        generator.reset(
+            CountingRng::new(StdRng::seed_from_u64(seed))
+                .with_limit(self.entropy_limit),
+            size,
+            case_seed(seed, 0),
+        );
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new(DEFAULT_CASES)
+    }
+}
+
+/// Outcome of a run.
+pub struct Report {
+    pub seed: u64,
+    /// Number of cases that were actually executed.
+    pub cases_run: usize,
+    /// Whether the time budget ended the run before `cases` were executed.
+    pub budget_exhausted: bool,
+    /// Number of cases, included in `cases_run`, that were skipped because
+    /// their inputs repeated an earlier case.
+    pub duplicates: usize,
+    /// Labels recorded with [`coverage::hit`](crate::coverage::hit) and
+    /// [`coverage::require`](crate::coverage::require), sorted by label.
+    pub coverage: Vec<LabelCoverage>,
+    /// Generator counters summed over every case that ran.
+    pub stats: GeneratorStats,
+    /// Cases that failed and then passed on a retry, by case index.
+    pub flaky: Vec<Flaky>,
+    pub failure: Option<Failure>,
+    /// Failing cases after [`failure`](Self::failure), by case index, when
+    /// the run went on after it, see [`Config::with_max_failures`].
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    /// The failing cases, grouped by how they failed.
+    ///
+    /// Cases that panicked at the same location, or with the same message
+    /// once the numbers in it are left out, are likely the same bug, and so
+    /// are the cases either of them is grouped with. The run shrinks every
+    /// failure before grouping them, so a group's earliest case serves as
+    /// its example, and groups are ordered by it, so the group of
+    /// [`failure`](Self::failure) comes first.
+    pub fn groups(&self) -> Vec<FailureGroup<'_>> {
+        let failures: Vec<&Failure> =
+            self.failure.iter().chain(&self.failures).collect();
+        let mut sets = DisjointSets::new(failures.len());
+        let mut seen = HashMap::new();
+        for (index, failure) in failures.iter().enumerate() {
+            let signature = failure.signature();
+            let keys = signature
+                .location
+                .map(FailureKey::Location)
+                .into_iter()
+                .chain([FailureKey::Message(signature.message)]);
+            for key in keys {
+                match seen.entry(key) {
+                    Entry::Occupied(entry) => sets.union(*entry.get(), index),
+                    Entry::Vacant(entry) => {
+                        entry.insert(index);
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<FailureGroup<'_>> = Vec::new();
+        let mut by_root = HashMap::new();
+        for (index, &failure) in failures.iter().enumerate() {
+            let root = sets.find(index);
+            let group = *by_root.entry(root).or_insert_with(|| {
+                groups.push(FailureGroup {
+                    example: failure,
+                    cases: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[group].cases.push(failure.case);
+        }
+        groups
+    }
+}
+
+/// Failing cases of a run that failed the same way, see [`Report::groups`].
+pub struct FailureGroup<'a> {
+    /// The earliest failure of the group, already shrunk.
+    pub example: &'a Failure,
+    /// The index of every case in the group, in order.
+    pub cases: Vec<usize>,
+}
+
+/// What [`Report::groups`] compares failures by.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FailureSignature {
+    /// Where the case panicked, when the run captured panics.
+    pub location: Option<PanicLocation>,
+    /// The panic message, with every run of digits replaced by `N`.
+    pub message: String,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum FailureKey {
+    Location(PanicLocation),
+    Message(String),
+}
+
+/// Union-find over failure indices.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let parent = self.parents[index];
+        if parent == index {
+            return index;
+        }
+        let root = self.find(parent);
+        self.parents[index] = root;
+        root
+    }
+
+    /// Merge the sets of `a` and `b`, keeping the lower root so groups are
+    /// named after their earliest failure.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+/// A case that failed, then passed when retried with the same input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Flaky {
+    pub case: usize,
+    pub seed: u64,
+    /// The retry the case passed on, starting at 1.
+    pub passed_on: usize,
+    /// The panic message of the first attempt.
+    pub message: String,
+}
+
+/// The earliest failing case of a run.
+pub struct Failure {
+    pub case: usize,
+    pub seed: u64,
+    /// The seed `HashMap`s and `HashSet`s hashing with
+    /// [`ChaosState`](crate::strategy::ChaosState) ordered their entries
+    /// with, in a run with [`Config::with_chaos_order`].
+    pub order_seed: Option<u64>,
+    /// The arguments the case was called with, when the run was observed.
+    pub inputs: Vec<CaseInput>,
+    /// Where the case first panicked, when the run captured panics.
+    pub location: Option<PanicLocation>,
+    /// The first [`prop_assert!`](crate::prop_assert) of the case that
+    /// failed, when the run captured panics.
+    pub assertion: Option<Assertion>,
+    /// How many retries failed too, all of the configured ones.
+    pub retries: usize,
+    pub payload: Box<dyn Any + Send>,
+}
+
+impl Failure {
+    pub fn message(&self) -> String {
+        panic_message(&*self.payload)
+    }
+
+    /// How the case failed, for [`Report::groups`].
+    pub fn signature(&self) -> FailureSignature {
+        let mut message = String::new();
+        let mut digits = false;
+        for c in self.message().chars() {
+            if c.is_ascii_digit() {
+                if !digits {
+                    message.push('N');
+                }
+            } else {
+                message.push(c);
+            }
+            digits = c.is_ascii_digit();
+        }
+        FailureSignature {
+            location: self.location.clone(),
+            message,
+        }
+    }
+}
+
+/// A source location a case panicked at.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PanicLocation {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for PanicLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A failed [`prop_assert!`](crate::prop_assert) or
+/// [`prop_assert_eq!`](crate::prop_assert_eq).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assertion {
+    /// The asserted expression, as written.
+    pub expression: &'static str,
+    /// The message given after the expression, if any.
+    pub context: Option<String>,
+    /// The `Debug` output of both sides of a
+    /// [`prop_assert_eq!`](crate::prop_assert_eq).
+    pub values: Option<(String, String)>,
+}
+
+/// The panic message of the assertion, like the one of `assert!` or
+/// `assert_eq!` but keeping the expression when there is a message.
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "assertion `{}` failed", self.expression)?;
+        if let Some(context) = &self.context {
+            write!(f, ": {context}")?;
+        }
+        if let Some((left, right)) = &self.values {
+            write!(f, "\n  left: {left}\n right: {right}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Fail the current case with `assertion`, recording it for the report.
+/// Called by [`prop_assert!`](crate::prop_assert) and
+/// [`prop_assert_eq!`](crate::prop_assert_eq).
+#[doc(hidden)]
+#[track_caller]
+pub fn fail_assertion(assertion: Assertion) -> ! {
+    let message = assertion.to_string();
+    with_active_case(|case| {
+        if case.run.capture_panics && case.assertion.is_none() {
+            case.assertion = Some(assertion);
+        }
+    });
+    panic!("{message}");
+}
+
+/// Assert that a condition holds in a property, like `assert!`.
+///
+/// The failure names the condition even when a message follows it, and
+/// the run's report includes both, see [`Failure::assertion`]:
+///
+/// ```should_panic
+/// use estoa_proptest::prop_assert;
+///
+/// let len = 12;
+/// prop_assert!(len < 10, "{len} is too long");
+/// ```
+#[macro_export]
+macro_rules! prop_assert {
+    ($condition:expr $(,)?) => {
+        if !$condition {
+            $crate::runner::fail_assertion($crate::runner::Assertion {
+                expression: ::core::stringify!($condition),
+                context: ::core::option::Option::None,
+                values: ::core::option::Option::None,
+            });
+        }
+    };
+    ($condition:expr, $($context:tt)+) => {
+        if !$condition {
+            $crate::runner::fail_assertion($crate::runner::Assertion {
+                expression: ::core::stringify!($condition),
+                context: ::core::option::Option::Some(
+                    ::std::format!($($context)+),
+                ),
+                values: ::core::option::Option::None,
+            });
+        }
+    };
+}
+
+/// Assert that two expressions are equal in a property, like
+/// `assert_eq!`, see [`prop_assert!`].
+#[macro_export]
+macro_rules! prop_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::prop_assert_eq!(@values $left, $right, ::core::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($context:tt)+) => {
+        $crate::prop_assert_eq!(
+            @values $left,
+            $right,
+            ::core::option::Option::Some(::std::format!($($context)+))
+        )
+    };
+    (@values $left:expr, $right:expr, $context:expr) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::runner::fail_assertion($crate::runner::Assertion {
+                        expression: ::core::concat!(
+                            ::core::stringify!($left),
+                            " == ",
+                            ::core::stringify!($right),
+                        ),
+                        context: $context,
+                        values: ::core::option::Option::Some((
+                            ::std::format!("{left:?}"),
+                            ::std::format!("{right:?}"),
+                        )),
+                    });
+                }
+            }
+        }
+    };
+}
+
+/// Run `config.cases()` cases of `case`, panicking with the seed of the first
+/// failing case.
+///
+/// Every case receives a generator seeded from the root seed and the case
+/// index, so the same root seed reproduces the same inputs regardless of how
+/// many threads were used.
+pub fn run<F>(config: &Config, case: F) -> Report
+where
+    F: Fn(&mut DefaultGenerator) + Sync,
+{
+    let root_seed = config.resolve_seed();
+    let started = HAS_CLOCK.then(Instant::now);
+    let report = run_cases(config, root_seed, &case);
+    let failure = failure_message(config, &report, false);
+    let color = output::color();
+    for flaky in &report.flaky {
+        eprintln!(
+            "#[proptest] case {}{} is flaky: it failed, then passed on retry {} (rerun with {}; case seed {:#x}): {}",
+            flaky.case,
+            config.label_suffix(),
+            flaky.passed_on,
+            rerun(report.seed, color),
+            flaky.seed,
+            flaky.message,
+        );
+    }
+
+    let observers = config.observers.with_global();
+    if !observers.is_empty() {
+        observers.on_run(&RunEvent {
+            test: config.test.as_ref(),
+            label: config.label.as_deref(),
+            report: &report,
+            elapsed: started
+                .map(|started| started.elapsed())
+                .unwrap_or_default(),
+            failure: failure.as_deref(),
+        });
+    }
+    if let Some(message) = failure {
+        // The payload stays plain so `#[should_panic(expected = ...)]` and
+        // anything else matching on it see the same text on a terminal.
+        if color {
+            eprintln!("{}", failure_message(config, &report, true).unwrap());
+        }
+        panic!("{message}");
+    }
+
+    if report.duplicates > 0 {
+        eprintln!(
+            "#[proptest] skipped {} of {} cases ({}) as duplicates",
+            paint(report.duplicates, Style::Stats, color),
+            report.cases_run,
+            paint(
+                format_args!(
+                    "{:.1}%",
+                    report.duplicates as f64 * 100.0 / report.cases_run as f64
+                ),
+                Style::Stats,
+                color,
+            ),
+        );
+    }
+
+    if report.budget_exhausted {
+        eprintln!(
+            "#[proptest] ran {} cases within the time budget",
+            paint(report.cases_run, Style::Stats, color),
+        );
+    }
+
+    report
+}
+
+/// `SEED_ENV=seed`, the setting that reruns a run.
+fn rerun(seed: u64, color: bool) -> Painted<String> {
+    paint(format!("{SEED_ENV}={seed:#x}"), Style::Seed, color)
+}
+
+/// Why the run of `report` fails: its earliest failing case, or a coverage
+/// requirement it missed. Highlighted for a terminal with `color`.
+fn failure_message(
+    config: &Config,
+    report: &Report,
+    color: bool,
+) -> Option<String> {
+    let label = config.label_suffix();
+    if let Some(failure) = &report.failure {
+        let retries = match failure.retries {
+            0 => String::new(),
+            retries => format!(", and all {retries} retries"),
+        };
+        let order_seed = failure
+            .order_seed
+            .map(|seed| format!("; ordering seed {seed:#x}"))
+            .unwrap_or_default();
+        let mut message = format!(
+            "#[proptest] case {}{} failed{} (rerun with {}; case seed {:#x}{}): {}",
+            failure.case,
+            label,
+            retries,
+            rerun(report.seed, color),
+            failure.seed,
+            order_seed,
+            failure.message(),
+        );
+        if let Some(location) = &failure.location {
+            let location = paint(location, Style::Location, color);
+            let _ = write!(message, "\n  at {location}");
+        }
+        if !failure.inputs.is_empty() {
+            let _ = write!(
+                message,
+                "\n\n{}",
+                paint("Inputs:", Style::Heading, color)
+            );
+            for input in &failure.inputs {
+                let value = input.value.as_deref().unwrap_or("<no Debug>");
+                let name = paint(input.name, Style::Argument, color);
+                let _ = write!(message, "\n  {name} = {value}");
+                if let Some(provenance) = &input.provenance {
+                    for line in provenance.breakdown(input.name) {
+                        let _ = write!(message, "\n    {line}");
+                    }
+                }
+            }
+        }
+        let groups = report.groups();
+        if groups.len() > 1 {
+            let _ = write!(
+                message,
+                "\n\n{}",
+                paint("Other failures:", Style::Heading, color)
+            );
+            for group in &groups[1..] {
+                write_group(&mut message, group, color);
+            }
+        }
+        return Some(message);
+    }
+
+    let executed = report.cases_run - report.duplicates;
+    report.coverage.iter().find_map(|coverage| {
+        let min_percent = coverage.min_percent?;
+        (coverage.percent(executed) < min_percent).then(|| {
+            format!(
+                "#[proptest] `{}` was hit in {:.1}% of {} cases{}, below the required {}% (rerun with {})",
+                coverage.label,
+                coverage.percent(executed),
+                executed,
+                label,
+                min_percent,
+                rerun(report.seed, color),
+            )
+        })
+    })
+}
+
+/// One line naming the example of `group` and how many cases it stands
+/// for, followed by where it panicked.
+fn write_group(message: &mut String, group: &FailureGroup<'_>, color: bool) {
+    let example = group.example;
+    let others = match group.cases.len() - 1 {
+        0 => String::new(),
+        1 => format!(" and {} more case", paint(1, Style::Stats, color)),
+        others => {
+            format!(" and {} more cases", paint(others, Style::Stats, color))
+        }
+    };
+    let _ = write!(
+        message,
+        "\n  case {}{} (case seed {:#x}): {}",
+        example.case,
+        others,
+        example.seed,
+        example.message().lines().next().unwrap_or_default(),
+    );
+    if let Some(location) = &example.location {
+        let location = paint(location, Style::Location, color);
+        let _ = write!(message, "\n    at {location}");
+    }
+}
+
+/// Run example `index` of a test, panicking with its index when it fails.
+///
+/// `#[proptest]` runs the examples listed with `examples = [...]` or
+/// `#[example(...)]` this way, in order, before any generated case.
+pub fn run_example<F: FnOnce()>(config: &Config, index: usize, example: F) {
+    if let Err(payload) = catch_unwind(AssertUnwindSafe(example)) {
+        panic!(
+            "#[proptest] example {}{} failed: {}",
+            index,
+            config.label_suffix(),
+            panic_message(&*payload),
+        );
+    }
+}
+
+/// Execute every case and report the lowest-indexed failure, if any.
+pub fn run_cases<F>(config: &Config, root_seed: u64, case: &F) -> Report
+where
+    F: Fn(&mut DefaultGenerator) + Sync,
+{
+    // Under Miri the clock is virtual, so budgets would not bound anything;
+    // budgeted runs fall back to the default number of cases instead.
+    let (cases, time_budget) = match config.time_budget {
+        Some(_) if cfg!(miri) => (config.cases.min(DEFAULT_CASES), None),
+        budget => (config.cases, budget),
+    };
+    let threads = config.resolve_threads().min(cases.max(1));
+    let retries = config.resolve_retries();
+    assert!(
+        time_budget.is_none() || HAS_CLOCK,
+        "#[proptest] time budgets need std::time::Instant, which this \
+         target does not provide; bound the run with `cases` instead",
+    );
+    let deadline = time_budget.map(|budget| Instant::now() + budget);
+    let next = AtomicUsize::new(0);
+    let cases_run = AtomicUsize::new(0);
+    let budget_exhausted = AtomicBool::new(false);
+    // Index of the `max_failures`-th lowest failing case seen so far;
+    // workers keep draining lower indices so the reported failures do not
+    // depend on scheduling.
+    let last_failure = AtomicUsize::new(usize::MAX);
+    // Failures, with whether the case has trees to shrink.
+    let failures = Mutex::new(Vec::<(Failure, bool)>::new());
+    let flaky = Mutex::new(Vec::new());
+    if config.capture_panics {
+        install_panic_hook();
+    }
+    let state = Arc::new(RunState {
+        dedup: config.dedup,
+        capture_panics: config.capture_panics,
+        observers: config.observers.with_global(),
+        ..RunState::default()
+    });
+
+    // Each worker builds its generator once and resets it for every case.
+    let worker = || {
+        let previous_order_seed = set_order_seed(None);
+        let mut generator = config.generator();
+
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            if index >= cases || index > last_failure.load(Ordering::Acquire) {
+                break;
+            }
+
+            // The first case always runs so a tiny budget still tests something.
+            if index > 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                budget_exhausted.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            cases_run.fetch_add(1, Ordering::Relaxed);
+            let seed = case_seed(root_seed, index);
+            let order_seed = config.chaos_order.then(|| case_seed(seed, 1));
+            let reset = |generator: &mut DefaultGenerator| {
+                config.start_case(generator, seed, case_size(index, cases));
+            };
+            reset(&mut generator);
+
+            let active = ActiveCase::enter(&state, index);
+            let outcome =
+                catch_unwind(AssertUnwindSafe(|| case(&mut generator)));
+            let (mut location, mut assertion, trees) =
+                with_active_case(|case| {
+                    (case.location.take(), case.assertion.take(), case.trees)
+                })
+                .unwrap_or_default();
+            let mut inputs = Vec::new();
+            if !state.observers.is_empty() {
+                let skipped;
+                (inputs, skipped) = with_active_case(|case| {
+                    (std::mem::take(&mut case.inputs), case.skipped)
+                })
+                .unwrap_or_default();
+                let message = outcome
+                    .as_ref()
+                    .err()
+                    .map(|payload| panic_message(&**payload));
+                let status = match (&message, skipped) {
+                    (Some(message), _) => CaseStatus::Failed { message },
+                    (None, true) => CaseStatus::Skipped,
+                    (None, false) => CaseStatus::Passed,
+                };
+                state.observers.on_case(&CaseEvent {
+                    case: index,
+                    seed,
+                    inputs: &inputs,
+                    status,
+                });
+            }
+            drop(active);
+            state
+                .stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .merge(&generator.stats());
+            if let Err(mut payload) = outcome {
+                // A case that fails every retry reports its last attempt.
+                let mut passed_on = None;
+                for retry in 1..=retries {
+                    reset(&mut generator);
+                    let active = ActiveCase::retry(&state, index);
+                    let outcome =
+                        catch_unwind(AssertUnwindSafe(|| case(&mut generator)));
+                    let (retry_location, retry_assertion) =
+                        with_active_case(|case| {
+                            (case.location.take(), case.assertion.take())
+                        })
+                        .unwrap_or_default();
+                    drop(active);
+                    match outcome {
+                        Ok(()) => {
+                            passed_on = Some(retry);
+                            break;
+                        }
+                        Err(retry_payload) => {
+                            payload = retry_payload;
+                            location = retry_location;
+                            assertion = retry_assertion;
+                        }
+                    }
+                }
+                if let Some(passed_on) = passed_on {
+                    flaky.lock().unwrap_or_else(|e| e.into_inner()).push(
+                        Flaky {
+                            case: index,
+                            seed,
+                            passed_on,
+                            message: panic_message(&*payload),
+                        },
+                    );
+                    continue;
+                }
+
+                let mut failures =
+                    failures.lock().unwrap_or_else(|e| e.into_inner());
+                failures.push((
+                    Failure {
+                        case: index,
+                        seed,
+                        order_seed,
+                        inputs,
+                        location,
+                        assertion,
+                        retries,
+                        payload,
+                    },
+                    trees,
+                ));
+                if failures.len() >= config.max_failures {
+                    let mut indices: Vec<usize> = failures
+                        .iter()
+                        .map(|(failure, _)| failure.case)
+                        .collect();
+                    let (_, last, _) =
+                        indices.select_nth_unstable(config.max_failures - 1);
+                    last_failure.fetch_min(*last, Ordering::AcqRel);
+                }
+            }
+        }
+        set_order_seed(previous_order_seed);
+    };
+
+    if threads == 1 {
+        worker();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(worker);
+            }
+        });
+    }
+
+    let mut failures = failures.into_inner().unwrap_or_else(|e| e.into_inner());
+    failures.sort_by_key(|(failure, _)| failure.case);
+    failures.truncate(config.max_failures);
+    // Every failure is shrunk before the failures are grouped, so cases
+    // failing the same way on different inputs end up alike.
+    let previous_order_seed = set_order_seed(None);
+    for (failure, trees) in &mut failures {
+        if *trees {
+            shrink_failure(config, &state, case, failure, cases);
+        }
+    }
+    set_order_seed(previous_order_seed);
+    let mut failures: Vec<Failure> =
+        failures.into_iter().map(|(failure, _)| failure).collect();
+    let failure = (!failures.is_empty()).then(|| failures.remove(0));
+    Report {
+        seed: root_seed,
+        cases_run: cases_run.into_inner(),
+        budget_exhausted: budget_exhausted.into_inner(),
+        duplicates: state.duplicates.load(Ordering::Relaxed),
+        coverage: state
+            .coverage
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect(),
+        stats: *state.stats.lock().unwrap_or_else(|e| e.into_inner()),
+        flaky: {
+            let mut flaky =
+                flaky.into_inner().unwrap_or_else(|e| e.into_inner());
+            flaky.sort_by_key(|flaky| flaky.case);
+            flaky
+        },
+        failure,
+        failures,
+    }
+}
+
+/// Shrink the case of `failure`, which has trees, and report it with the
+/// panic and inputs of the simplest run that still fails.
+///
+/// Shrinking runs the case again and again, with the trees of its arguments
+/// moved along the [`ShrinkTrace`] the run's
+/// [`ShrinkScheduler`](Config::with_shrink) is exploring, until it found
+/// the simplest failing trace or spent [`SHRINK_BUDGET`] runs.
+fn shrink_failure<F>(
+    config: &Config,
+    state: &Arc<RunState>,
+    case: &F,
+    failure: &mut Failure,
+    cases: usize,
+) where
+    F: Fn(&mut DefaultGenerator),
+{
+    let replayer = RefCell::new(Replayer {
+        config,
+        state,
+        case,
+        generator: config.generator(),
+        index: failure.case,
+        seed: failure.seed,
+        size: case_size(failure.case, cases),
+        moves: vec![[None; 2]],
+    });
+    let shrinking = ActiveCase::shrinking(state, failure.case);
+    let shrunk = shrink_with_budget(
+        || TraceTree {
+            replayer: &replayer,
+            node: 0,
+            trace: ShrinkTrace::default(),
+        },
+        config.shrink,
+        ShrinkBudget::default().with_attempts(SHRINK_BUDGET),
+        |trace| replayer.borrow_mut().run(trace, false).outcome.is_err(),
+    );
+    drop(shrinking);
+    if shrunk.value.is_empty() {
+        return;
+    }
+
+    // The shrunk inputs may fail elsewhere, or another assertion.
+    let rerun = replayer.borrow_mut().run(&shrunk.value, false);
+    if let Err(payload) = rerun.outcome {
+        failure.payload = payload;
+        failure.location = rerun.location;
+        failure.assertion = rerun.assertion;
+        if !rerun.inputs.is_empty() {
+            failure.inputs = rerun.inputs;
+        }
+    }
+}
+
+/// Runs a failing case again for [`shrink_failure`], with the trees of its
+/// arguments moved along a [`ShrinkTrace`].
+struct Replayer<'a, F> {
+    config: &'a Config,
+    state: &'a Arc<RunState>,
+    case: &'a F,
+    generator: DefaultGenerator,
+    index: usize,
+    seed: u64,
+    size: usize,
+    /// Whether each step of the traces run so far moved the trees, as a
+    /// trie with the empty trace at its root: the child a step leads to,
+    /// and what the step returned.
+    moves: Vec<[Option<(usize, bool)>; 2]>,
+}
+
+/// A run of a case by a [`Replayer`].
+struct Rerun {
+    outcome: Result<(), Box<dyn Any + Send>>,
+    location: Option<PanicLocation>,
+    assertion: Option<Assertion>,
+    inputs: Vec<CaseInput>,
+}
+
+impl<F> Replayer<'_, F>
+where
+    F: Fn(&mut DefaultGenerator),
+{
+    /// Run the case with its trees moved along `trace`, stopping before the
+    /// body with `probe`.
+    fn run(&mut self, trace: &ShrinkTrace, probe: bool) -> Rerun {
+        self.config
+            .start_case(&mut self.generator, self.seed, self.size);
+        let active = ActiveCase::replay(
+            self.state,
+            self.index,
+            Replay {
+                trace: trace.clone(),
+                probe,
+                moved: Vec::new(),
+            },
+        );
+        let outcome =
+            catch_unwind(AssertUnwindSafe(|| (self.case)(&mut self.generator)));
+        let (moved, location, assertion, inputs) = with_active_case(|case| {
+            let moved = case
+                .replay
+                .as_mut()
+                .map(|replay| mem::take(&mut replay.moved))
+                .unwrap_or_default();
+            (
+                moved,
+                case.location.take(),
+                case.assertion.take(),
+                mem::take(&mut case.inputs),
+            )
+        })
+        .unwrap_or_default();
+        drop(active);
+
+        self.remember(trace, &moved);
+        Rerun {
+            outcome,
+            location,
+            assertion,
+            inputs,
+        }
+    }
+
+    /// Record which steps of `trace` moved the trees, returning the node of
+    /// `trace`. Steps past those the case took, when it did not reach
+    /// [`shrink_trees`], did not move anything.
+    fn remember(&mut self, trace: &ShrinkTrace, moved: &[bool]) -> usize {
+        let mut node = 0;
+        for (index, &step) in trace.steps().iter().enumerate() {
+            node = match self.moves[node][step as usize] {
+                Some((child, _)) => child,
+                None => {
+                    let child = self.moves.len();
+                    self.moves.push([None; 2]);
+                    let moved = moved.get(index).copied().unwrap_or(false);
+                    self.moves[node][step as usize] = Some((child, moved));
+                    child
+                }
+            };
+        }
+        node
+    }
+
+    /// Take the last step of `trace` from `node`, the node of the steps
+    /// before it, running the case up to its body to learn whether the
+    /// step moves the trees unless an earlier run did.
+    fn step(&mut self, node: usize, trace: &ShrinkTrace) -> (usize, bool) {
+        let step = *trace.steps().last().expect("a step was taken");
+        if let Some(next) = self.moves[node][step as usize] {
+            return next;
+        }
+        self.run(trace, true);
+        self.moves[node][step as usize].expect("the run remembered the step")
+    }
+}
+
+/// The trees of a failing case as the shrink phase sees them: the trace
+/// moving them from where the case generated them, stepped through a
+/// [`Replayer`].
+struct TraceTree<'r, 'a, F> {
+    replayer: &'r RefCell<Replayer<'a, F>>,
+    node: usize,
+    trace: ShrinkTrace,
+}
+
+impl<F> TraceTree<'_, '_, F>
+where
+    F: Fn(&mut DefaultGenerator),
+{
+    fn step(&mut self, step: ShrinkStep) -> bool {
+        self.trace.extend([step]);
+        let (node, moved) =
+            self.replayer.borrow_mut().step(self.node, &self.trace);
+        self.node = node;
+        moved
+    }
+}
+
+impl<F> ValueTree for TraceTree<'_, '_, F>
+where
+    F: Fn(&mut DefaultGenerator),
+{
+    type Value = ShrinkTrace;
+
+    fn current(&self) -> &ShrinkTrace {
+        &self.trace
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.step(ShrinkStep::Simplify)
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.step(ShrinkStep::Complicate)
+    }
+}
+
+/// Rejections met while generating one argument of a case.
+///
+/// Used by `#[proptest]`, which records every rejection of an argument's
+/// strategy and panics with a breakdown once the rejection limit is reached.
+pub struct Rejections {
+    argument: &'static str,
+    source: &'static str,
+    limit: usize,
+    attempts: usize,
+    /// Rejections per depth and reason, telling apart a strategy that
+    /// rejects its own values from one whose nested strategies do.
+    causes: BTreeMap<(usize, Option<RejectReason>), usize>,
+}
+
+impl Rejections {
+    pub fn new(
+        argument: &'static str,
+        source: &'static str,
+        limit: usize,
+    ) -> Self {
+        Self {
+            argument,
+            source,
+            limit,
+            attempts: 0,
+            causes: BTreeMap::new(),
+        }
+    }
+
+    /// Record a rejection, panicking once `limit` rejections were recorded.
+    pub fn record(
+        &mut self,
+        iteration: usize,
+        depth: usize,
+        reason: Option<RejectReason>,
+    ) {
+        self.attempts += 1;
+        *self.causes.entry((depth, reason)).or_default() += 1;
+        if self.attempts < self.limit {
+            return;
+        }
+
+        let breakdown = self
+            .causes
+            .iter()
+            .map(|((depth, reason), count)| match reason {
+                Some(reason) => format!("{count} at depth {depth} ({reason})"),
+                None => format!("{count} at depth {depth}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        panic!(
+            "#[proptest] strategy for `{}` ({}) rejected value after {} attempts (iteration {}, depth {}; limit {}); rejections: {}",
+            self.argument,
+            self.source,
+            self.attempts,
+            iteration,
+            depth,
+            self.limit,
+            breakdown,
+        );
+    }
+}
+
+/// State shared by the cases of a run, reached from case bodies through
+/// [`is_duplicate`] and the [`coverage`](crate::coverage) functions.
+#[derive(Default)]
+struct RunState {
+    dedup: bool,
+    capture_panics: bool,
+    /// Fingerprints of the inputs seen so far, mapped to the lowest case
+    /// index that produced them.
+    seen: Mutex<HashMap<u64, usize>>,
+    duplicates: AtomicUsize,
+    coverage: Mutex<BTreeMap<String, LabelCoverage>>,
+    stats: Mutex<GeneratorStats>,
+    observers: Observers,
+}
+
+/// The case executing on the current thread.
+pub(crate) struct CaseState {
+    run: Arc<RunState>,
+    index: usize,
+    labels: BTreeSet<String>,
+    /// Inputs recorded with [`record_inputs`], for the run's observers.
+    inputs: Vec<CaseInput>,
+    /// Whether [`is_duplicate`] skipped the case.
+    skipped: bool,
+    shrink_attempts: usize,
+    /// Where the case first panicked, when the run captures panics.
+    location: Option<PanicLocation>,
+    /// The first failed [`prop_assert!`](crate::prop_assert).
+    assertion: Option<Assertion>,
+    /// Whether the case handed the trees of its arguments to
+    /// [`shrink_trees`].
+    trees: bool,
+    /// Where to move those trees, when the shrink phase runs the case.
+    replay: Option<Replay>,
+    /// Values kept alive until the case ends, see [`keep_for_case`].
+    kept: Vec<Box<dyn Any>>,
+}
+
+/// How the shrink phase of a run runs a case again, see [`shrink_trees`].
+#[derive(Default)]
+struct Replay {
+    trace: ShrinkTrace,
+    /// Stop before the body, to learn whether the steps move the trees.
+    probe: bool,
+    /// What each step of `trace` returned, once the case took them.
+    moved: Vec<bool>,
+}
+
+impl CaseState {
+    pub(crate) fn hit(&mut self, label: &str) {
+        if !self.labels.contains(label) {
+            self.labels.insert(label.to_owned());
+        }
+    }
+
+    pub(crate) fn require(&self, label: &str, min_percent: f64) {
+        let mut coverage =
+            self.run.coverage.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = coverage
+            .entry(label.to_owned())
+            .or_insert_with(|| LabelCoverage::new(label));
+        entry.min_percent = Some(
+            entry
+                .min_percent
+                .map_or(min_percent, |current| current.max(min_percent)),
+        );
+    }
+}
+
+thread_local! {
+    static ACTIVE_CASE: RefCell<Option<CaseState>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the case executing on this thread, if any.
+pub(crate) fn with_active_case<T>(
+    f: impl FnOnce(&mut CaseState) -> T,
+) -> Option<T> {
+    ACTIVE_CASE.with(|active| active.borrow_mut().as_mut().map(f))
+}
+
+/// Keep `value` alive until the current case ends, dropping it right away
+/// outside a run.
+///
+/// Arbitrary weak references point at values kept this way, so they upgrade
+/// for the whole case without leaking.
+pub(crate) fn keep_for_case(value: impl Any) {
+    with_active_case(|case| case.kept.push(Box::new(value)));
+}
+
+/// Chain a hook recording where cases of runs that capture panics first
+/// panic in front of the current panic hook, once per process.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // The panic may come from code borrowing the case, or from a
+            // thread that is shutting down.
+            let replaying = ACTIVE_CASE.try_with(|active| {
+                let Ok(mut active) = active.try_borrow_mut() else {
+                    return false;
+                };
+                let Some(case) = active.as_mut() else {
+                    return false;
+                };
+                if let Some(location) = info.location()
+                    && case.run.capture_panics
+                    && case.location.is_none()
+                {
+                    case.location = Some(PanicLocation {
+                        file: location.file().to_owned(),
+                        line: location.line(),
+                        column: location.column(),
+                    });
+                }
+                case.replay.is_some()
+            });
+            // Retries and shrinking fail the case over and over; its first
+            // run already reported the panic.
+            if !replaying.unwrap_or(false) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Marks a case as executing on the current thread until dropped, then
+/// merges the labels it hit into the run.
+struct ActiveCase {
+    previous: Option<CaseState>,
+}
+
+impl ActiveCase {
+    fn enter(run: &Arc<RunState>, index: usize) -> Self {
+        Self::start(run, index, None)
+    }
+
+    /// Mark case `index` as run again by the shrink phase, which neither
+    /// counts towards coverage nor looks like a duplicate.
+    fn replay(run: &Arc<RunState>, index: usize, replay: Replay) -> Self {
+        Self::start(run, index, Some(replay))
+    }
+
+    /// Mark case `index` as retried after a failure, which like a replay
+    /// neither counts towards coverage nor looks like a duplicate.
+    fn retry(run: &Arc<RunState>, index: usize) -> Self {
+        Self::start(run, index, Some(Replay::default()))
+    }
+
+    /// Mark case `index` as being shrunk, so that its observers see every
+    /// run the shrink phase tries as a shrink attempt of the case.
+    fn shrinking(run: &Arc<RunState>, index: usize) -> Self {
+        Self::start(run, index, Some(Replay::default()))
+    }
+
+    fn start(
+        run: &Arc<RunState>,
+        index: usize,
+        replay: Option<Replay>,
+    ) -> Self {
+        let case = CaseState {
+            run: Arc::clone(run),
+            index,
+            labels: BTreeSet::new(),
+            inputs: Vec::new(),
+            skipped: false,
+            shrink_attempts: 0,
+            location: None,
+            assertion: None,
+            trees: false,
+            replay,
+            kept: Vec::new(),
+        };
+        let previous = ACTIVE_CASE.with(|active| active.replace(Some(case)));
+        Self { previous }
+    }
+}
+
+impl Drop for ActiveCase {
+    fn drop(&mut self) {
+        let case =
+            ACTIVE_CASE.with(|active| active.replace(self.previous.take()));
+        let Some(case) = case else {
+            return;
+        };
+        if case.replay.is_some() {
+            return;
+        }
+
+        let mut coverage =
+            case.run.coverage.lock().unwrap_or_else(|e| e.into_inner());
+        for label in case.labels {
+            coverage
+                .entry(label)
+                .or_insert_with_key(|label| LabelCoverage::new(label))
+                .hits += 1;
+        }
+    }
+}
+
+/// Whether `inputs` repeat the inputs of an earlier case of the current run.
+///
+/// `#[proptest(dedup = true)]` calls this with the generated arguments and
+/// skips the case when it returns `true`. Inputs are compared by hashing
+/// their `Debug` output. A case is only a duplicate of a lower-numbered one,
+/// so the reported failure stays the same with any number of threads. Always
+/// `false` outside a run with [`Config::with_dedup`].
+pub fn is_duplicate<T: Debug + ?Sized>(inputs: &T) -> bool {
+    with_active_case(|case| {
+        if !case.run.dedup || case.replay.is_some() {
+            return false;
+        }
+
+        let mut seen = case.run.seen.lock().unwrap_or_else(|e| e.into_inner());
+        match seen.entry(fingerprint(inputs)) {
+            Entry::Occupied(entry) if *entry.get() < case.index => {
+                case.run.duplicates.fetch_add(1, Ordering::Relaxed);
+                case.skipped = true;
+                true
+            }
+            Entry::Occupied(mut entry) => {
+                entry.insert(case.index);
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(case.index);
+                false
+            }
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Whether the current case has observers that want its inputs.
+///
+/// `#[proptest]` only formats the arguments for [`record_inputs`] when it
+/// returns `true`.
+pub fn observing() -> bool {
+    with_active_case(|case| !case.run.observers.is_empty()).unwrap_or(false)
+}
+
+/// Hand the inputs of the current case to the run's observers, which see
+/// them once the case finishes.
+pub fn record_inputs(inputs: Vec<CaseInput>) {
+    with_active_case(|case| case.inputs = inputs);
+}
+
+/// Move the trees of the current case's arguments to where the shrink
+/// phase of the run wants them, returning whether the case goes on to run
+/// its body.
+///
+/// `#[proptest]` calls this once its generated arguments have their trees,
+/// before it takes their values. When a case fails, the runner
+/// shrinks it by running it again with the trees moved along a
+/// [`ShrinkTrace`], which simplifies them as the tree of a tuple of their
+/// strategies would: the first tree that can simplify does. Outside the
+/// shrink phase, and outside a run, the trees stay where they are.
+pub fn shrink_trees(trees: &mut [&mut dyn ErasedValueTree]) -> bool {
+    let replay = with_active_case(|case| {
+        case.trees = true;
+        case.replay
+            .as_ref()
+            .map(|replay| (replay.trace.clone(), replay.probe))
+    })
+    .flatten();
+    let Some((trace, probe)) = replay else {
+        return true;
+    };
+
+    let mut last_changed = None;
+    let moved = trace
+        .steps()
+        .iter()
+        .map(|&step| step_trees(trees, &mut last_changed, step))
+        .collect();
+    with_active_case(|case| {
+        if let Some(replay) = &mut case.replay {
+            replay.moved = moved;
+        }
+    });
+    !probe
+}
+
+/// Take `step` on `trees` as the tree of a tuple of them would: simplify the
+/// first tree that can, and complicate the one simplified last.
+fn step_trees(
+    trees: &mut [&mut dyn ErasedValueTree],
+    last_changed: &mut Option<usize>,
+    step: ShrinkStep,
+) -> bool {
+    match step {
+        ShrinkStep::Simplify => {
+            let simplified = trees.iter_mut().position(|tree| tree.step(step));
+            if simplified.is_some() {
+                *last_changed = simplified;
+            }
+            simplified.is_some()
+        }
+        ShrinkStep::Complicate => {
+            let Some(index) = *last_changed else {
+                return false;
+            };
+            let complicated = trees[index].step(step);
+            if !complicated {
+                *last_changed = None;
+            }
+            complicated
+        }
+    }
+}
+
+/// Run one case of a `#[proptest(model = path)]` property under `model`,
+/// a model checker such as `loom::model` calling `body` once for every
+/// thread interleaving it explores.
+///
+/// A failure names the execution of the model it happened in together with
+/// the case's inputs, described by `inputs`. Models explore deterministically,
+/// so the same inputs fail in the same execution again, where the model's
+/// own logging shows the interleaving.
+pub fn run_model<M, B>(
+    model: M,
+    inputs: impl FnOnce() -> Vec<CaseInput>,
+    body: B,
+) where
+    M: FnOnce(Box<dyn Fn() + Send + Sync>),
+    B: Fn() + Send + Sync + 'static,
+{
+    let executions = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&executions);
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        model(Box::new(move || {
+            counter.fetch_add(1, Ordering::Relaxed);
+            body();
+        }));
+    }));
+
+    if let Err(payload) = outcome {
+        let mut context = format!(
+            "in execution {} of the model",
+            executions.load(Ordering::Relaxed),
+        );
+        for (index, input) in inputs().iter().enumerate() {
+            let value = input.value.as_deref().unwrap_or("<no Debug>");
+            let separator = if index == 0 { ", with inputs: " } else { ", " };
+            let _ = write!(context, "{separator}{} = {value}", input.name);
+        }
+        panic!("{} ({context})", panic_message(&*payload));
+    }
+}
+
+/// Tell the run's observers that shrinking inside the current case tried a
+/// candidate.
+pub(crate) fn observe_shrink_attempt(fails: bool) {
+    // Observers run outside the borrow of the case, so they may use it too.
+    let observed = with_active_case(|case| {
+        if case.run.observers.is_empty() {
+            return None;
+        }
+        let attempt = ShrinkAttempt {
+            case: case.index,
+            attempt: case.shrink_attempts,
+            fails,
+        };
+        case.shrink_attempts += 1;
+        Some((attempt, Arc::clone(&case.run)))
+    });
+    if let Some(Some((attempt, run))) = observed {
+        run.observers.on_shrink_attempt(&attempt);
+    }
+}
+
+/// Hash the `Debug` output of `value` without allocating it.
+pub(crate) fn fingerprint<T: Debug + ?Sized>(value: &T) -> u64 {
+    struct HashWriter(DefaultHasher);
+
+    impl Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(DefaultHasher::new());
+    let _ = write!(writer, "{value:?}");
+    writer.0.finish()
+}
+
+/// Return types accepted from a `#[proptest]` body.
+///
+/// `()` always passes, while `Result<(), E>` fails the case on `Err`, so
+/// bodies can use `?` (including with `anyhow::Result<()>`). A case failing
+/// with `Err` shrinks like one that panicked, and reports the `Err` of its
+/// shrunk inputs.
+pub trait CaseOutcome {
+    /// Panic if the outcome represents a failing case.
+    fn check(self);
+}
+
+impl CaseOutcome for () {
+    fn check(self) {}
+}
+
+impl<E: Debug> CaseOutcome for Result<(), E> {
+    fn check(self) {
+        if let Err(err) = self {
+            panic!("#[proptest] body returned Err: {err:?}");
+        }
+    }
+}
+
+/// Context produced by a `setup` hook and handed to the body by reference.
+///
+/// The teardown hook runs when the context is dropped, so it also runs when
+/// the body panics. The value sits behind a mutex so a context created once
+/// per test can be shared by every worker thread.
+pub struct Context<C, F>
+where
+    F: FnOnce(C),
+{
+    value: Mutex<Option<C>>,
+    teardown: Option<F>,
+}
+
+impl<C, F> Context<C, F>
+where
+    F: FnOnce(C),
+{
+    pub fn new(value: C, teardown: F) -> Self {
+        Self {
+            value: Mutex::new(Some(value)),
+            teardown: Some(teardown),
+        }
+    }
+
+    /// Run `body` with exclusive access to the context.
+    pub fn with<T>(&self, body: impl FnOnce(&mut C) -> T) -> T {
+        let mut guard = self.value.lock().unwrap_or_else(|e| e.into_inner());
+        body(guard.as_mut().expect("context already torn down"))
+    }
+}
+
+impl<C, F> Drop for Context<C, F>
+where
+    F: FnOnce(C),
+{
+    fn drop(&mut self) {
+        let value = self
+            .value
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        if let (Some(value), Some(teardown)) = (value, self.teardown.take()) {
+            teardown(value);
+        }
+    }
+}
+
+/// Derive the seed of case `index` from the root seed (SplitMix64).
+pub fn case_seed(root_seed: u64, index: usize) -> u64 {
+    let mut z = root_seed.wrapping_add(
+        (index as u64)
+            .wrapping_add(1)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15),
+    );
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Most cases a run spends growing the generator size to [`MAX_SIZE`].
+const SIZE_RAMP_CASES: usize = 1_000;
+
+/// Generator size of the first case of a run, so runs cut short by a time
+/// budget do not only draw the smallest values.
+const MIN_CASE_SIZE: usize = MAX_SIZE / 10;
+
+/// Generator size of case `index` in a run of `cases` cases.
+///
+/// The size grows linearly from [`MIN_CASE_SIZE`] over the first half of the
+/// run (at most [`SIZE_RAMP_CASES`] cases), so early cases are small and
+/// cheap while the rest draw from the full range. Runs of a single case are
+/// too short to grow and draw from the full range right away.
+pub fn case_size(index: usize, cases: usize) -> usize {
+    let ramp = (cases / 2).min(SIZE_RAMP_CASES);
+    if ramp == 0 {
+        return MAX_SIZE;
+    }
+    let growth = index.saturating_mul(MAX_SIZE - MIN_CASE_SIZE) / ramp;
+    (MIN_CASE_SIZE + growth).min(MAX_SIZE)
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_owned()
+    }
+}
+
+/// Root seed of runs that cannot, or should not, draw a random one.
+const FIXED_SEED: u64 = 0x5eed_e570_a000_0001;
+
+/// Root seed used when neither [`Config::with_seed`] nor [`SEED_ENV`] pick
+/// one.
+///
+/// Without the `getrandom` feature there is no entropy source, and under Miri
+/// runs should be reproducible, so both always start from [`FIXED_SEED`].
+#[cfg(feature = "getrandom")]
+fn fresh_seed() -> u64 {
+    if cfg!(miri) {
+        return FIXED_SEED;
+    }
+    rand::Rng::random(&mut rand::rng())
+}
+
+#[cfg(not(feature = "getrandom"))]
+fn fresh_seed() -> u64 {
+    FIXED_SEED
+}
+
+fn env_usize(key: &str) -> Option<usize> {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+fn parse_u64(value: &str) -> Option<u64> {
+    let value = value.trim();
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::strategy::{AnyU32, ChaosState, Strategy};
+
+    #[test]
+    fn err_outcome_fails_the_case() {
+        let config = Config::new(4);
+        let report = run_cases(&config, 0, &|_: &mut DefaultGenerator| {
+            Err::<(), _>("broken").check();
+        });
+        let failure = report.failure.expect("Err must fail the case");
+        assert_eq!(failure.case, 0);
+        assert!(failure.message().contains("\"broken\""));
+    }
+
+    #[test]
+    fn context_tears_down_after_panicking_body() {
+        let torn_down = AtomicUsize::new(0);
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let context = Context::new(41, |value: i32| {
+                torn_down.store(value as usize, Ordering::Relaxed);
+            });
+            context.with(|value| {
+                *value += 1;
+                panic!("body failed");
+            })
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(torn_down.load(Ordering::Relaxed), 42);
+    }
+
+    #[test]
+    fn case_seeds_are_distinct_per_index() {
+        let seeds: Vec<u64> =
+            (0..64).map(|index| case_seed(7, index)).collect();
+        let mut unique = seeds.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), seeds.len());
+    }
+
+    #[test]
+    fn case_sizes_grow_to_the_maximum() {
+        assert_eq!(case_size(0, 100), MIN_CASE_SIZE);
+        assert_eq!(case_size(25, 100), (MIN_CASE_SIZE + MAX_SIZE) / 2);
+        assert_eq!(case_size(50, 100), MAX_SIZE);
+        assert_eq!(case_size(99, 100), MAX_SIZE);
+        assert_eq!(case_size(0, 2), MIN_CASE_SIZE);
+        assert_eq!(case_size(1, 2), MAX_SIZE);
+        assert_eq!(case_size(0, usize::MAX), MIN_CASE_SIZE);
+        assert_eq!(case_size(1_000, usize::MAX), MAX_SIZE);
+    }
+
+    #[test]
+    fn single_case_runs_draw_from_the_full_range() {
+        assert_eq!(case_size(0, 1), MAX_SIZE);
+
+        let sizes = Mutex::new(Vec::new());
+        run_cases(&Config::new(1), 0, &|generator: &mut DefaultGenerator| {
+            sizes.lock().unwrap().push(generator.size());
+        });
+        assert_eq!(*sizes.lock().unwrap(), [MAX_SIZE]);
+    }
+
+    #[test]
+    fn dedup_skips_repeated_inputs() {
+        let executed = AtomicUsize::new(0);
+        let config = Config::new(64).with_seed(7).with_dedup(true);
+        let report =
+            run_cases(&config, 7, &|generator: &mut DefaultGenerator| {
+                let flag = generator.rng.random::<bool>();
+                if !is_duplicate(&flag) {
+                    executed.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+
+        assert_eq!(report.cases_run, 64);
+        assert_eq!(executed.into_inner() + report.duplicates, 64);
+        assert!(report.duplicates >= 62);
+    }
+
+    #[test]
+    fn is_duplicate_is_false_outside_deduplicating_runs() {
+        assert!(!is_duplicate(&1u8));
+        assert!(!is_duplicate(&1u8));
+    }
+
+    #[test]
+    fn coverage_counts_each_case_once() {
+        let config = Config::new(200).with_threads(4);
+        let report =
+            run_cases(&config, 11, &|generator: &mut DefaultGenerator| {
+                crate::coverage::require("even", 10.0);
+                if generator.rng.random::<u8>() % 2 == 0 {
+                    crate::coverage::hit("even");
+                    crate::coverage::hit("even");
+                }
+            });
+
+        assert_eq!(report.coverage.len(), 1);
+        let even = &report.coverage[0];
+        assert_eq!(even.label, "even");
+        assert_eq!(even.min_percent, Some(10.0));
+        assert!((60..=140).contains(&even.hits), "{} hits", even.hits);
+    }
+
+    #[test]
+    #[should_panic(expected = "`never` was hit in 0.0% of 32 cases")]
+    fn run_fails_unmet_coverage_requirements() {
+        run(&Config::new(32).with_seed(1), |_| {
+            crate::coverage::require("never", 1.0);
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "rejections: 1 at depth 0 (filter failed), 2 at depth 2"
+    )]
+    fn rejections_break_down_by_depth_and_reason() {
+        let mut rejections = Rejections::new("value", "Nested", 3);
+        rejections.record(0, 2, None);
+        rejections.record(1, 0, Some(RejectReason::Filter));
+        rejections.record(2, 2, None);
+    }
+
+    #[test]
+    fn parse_u64_accepts_hex_and_decimal() {
+        assert_eq!(parse_u64("0x2a"), Some(42));
+        assert_eq!(parse_u64(" 42 "), Some(42));
+        assert_eq!(parse_u64("nope"), None);
+    }
+
+    fn fails_on_large_bytes(generator: &mut DefaultGenerator) {
+        let value = generator.rng.random::<u8>();
+        assert!(value < 200, "value {value} too large");
+    }
+
+    #[test]
+    fn parallel_runs_report_lowest_failing_case() {
+        let config = Config::new(256).with_threads(8);
+        let failure = run_cases(&config, 3, &fails_on_large_bytes);
+        let sequential = run_cases(
+            &config.clone().with_threads(1),
+            3,
+            &fails_on_large_bytes,
+        );
+
+        let failure = failure.failure.expect("some case should fail");
+        let sequential = sequential.failure.expect("some case should fail");
+        assert_eq!(failure.case, sequential.case);
+        assert_eq!(failure.seed, sequential.seed);
+        assert_eq!(failure.message(), sequential.message());
+    }
+
+    #[test]
+    fn runs_go_on_until_max_failures_cases_failed() {
+        let fails = |generator: &mut DefaultGenerator| {
+            let value = generator.rng.random::<u8>();
+            assert!(value < 240, "value {value} too large");
+            assert!(!value.is_multiple_of(16), "value {value} divisible by 16");
+        };
+        let config = Config::new(512).with_max_failures(6);
+        let report = run_cases(&config.clone().with_threads(1), 3, &fails);
+        let parallel = run_cases(&config.with_threads(8), 3, &fails);
+
+        let cases = |report: &Report| -> Vec<usize> {
+            report
+                .groups()
+                .iter()
+                .flat_map(|g| g.cases.clone())
+                .collect()
+        };
+        assert_eq!(report.failures.len(), 5);
+        assert_eq!(cases(&report), cases(&parallel));
+        assert!(report.cases_run > report.failures[0].case);
+
+        let messages: BTreeSet<_> = report
+            .groups()
+            .iter()
+            .map(|group| group.example.signature().message)
+            .collect();
+        assert_eq!(
+            messages,
+            BTreeSet::from([
+                "value N divisible by N".to_owned(),
+                "value N too large".to_owned(),
+            ])
+        );
+    }
+
+    #[test]
+    fn chaos_order_gives_every_case_its_own_ordering_seed() {
+        let seeds = Mutex::new(BTreeMap::new());
+        let case = |generator: &mut DefaultGenerator| {
+            let seed = ChaosState::default().seed();
+            let index = generator.rng.random::<u64>();
+            seeds.lock().unwrap().insert(index, seed);
+            assert!(seeds.lock().unwrap().len() < 8, "out of cases");
+        };
+
+        let report =
+            run_cases(&Config::new(16).with_chaos_order(true), 3, &case);
+        let failure = report.failure.expect("the eighth case fails");
+        let seeds = std::mem::take(&mut *seeds.lock().unwrap());
+        let unique: BTreeSet<u64> = seeds.values().copied().collect();
+        assert_eq!(unique.len(), 8);
+        assert!(unique.contains(&failure.order_seed.unwrap()));
+        assert_eq!(ChaosState::default().seed(), 0, "the seed is cleared");
+
+        let report = run_cases(&Config::new(16), 3, &case);
+        assert_eq!(report.failure.unwrap().order_seed, None);
+    }
+
+    #[test]
+    fn retried_cases_see_the_same_input() {
+        let attempts = Mutex::new(Vec::new());
+        let config = Config::new(64).with_retries(2);
+        let report =
+            run_cases(&config, 5, &|generator: &mut DefaultGenerator| {
+                let value = generator.rng.random::<u8>();
+                // Fails the first time it sees a value, then passes.
+                let first = {
+                    let mut attempts = attempts.lock().unwrap();
+                    attempts.push(value);
+                    attempts.iter().filter(|seen| **seen == value).count() == 1
+                };
+                assert!(!first || value < 128, "large on the first attempt");
+            });
+
+        let failure = report.failure;
+        assert!(failure.is_none(), "flaky cases do not fail the run");
+        assert!(!report.flaky.is_empty());
+        assert!(report.flaky.is_sorted_by_key(|flaky| flaky.case));
+        for flaky in &report.flaky {
+            assert_eq!(flaky.passed_on, 1);
+            assert_eq!(flaky.message, "large on the first attempt");
+        }
+        assert_eq!(
+            attempts.lock().unwrap().len(),
+            report.cases_run + report.flaky.len(),
+        );
+    }
+
+    #[test]
+    fn cases_failing_every_retry_fail_the_run() {
+        let attempts = AtomicUsize::new(0);
+        let config = Config::new(8).with_retries(3);
+        let report = run_cases(&config, 5, &|_: &mut DefaultGenerator| {
+            attempts.fetch_add(1, Ordering::Relaxed);
+            panic!("always");
+        });
+
+        let failure = report.failure.as_ref().expect("the first case fails");
+        assert_eq!((failure.case, failure.retries), (0, 3));
+        assert!(report.flaky.is_empty());
+        assert_eq!(attempts.load(Ordering::Relaxed), 4);
+        let message = failure_message(&config, &report, false).unwrap();
+        assert!(message.contains("failed, and all 3 retries (rerun"));
+    }
+
+    #[test]
+    fn retried_failures_report_the_last_attempt() {
+        let attempts = AtomicUsize::new(0);
+        let lines = Mutex::new(Vec::new());
+        let config = Config::new(1).with_retries(3);
+        let report = run_cases(&config, 5, &|_: &mut DefaultGenerator| {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+            if attempt == 0 {
+                lines.lock().unwrap().push(line!() + 1);
+                panic!("first attempt");
+            }
+            lines.lock().unwrap().push(line!() + 1);
+            panic!("attempt {attempt}");
+        });
+
+        assert!(report.failures.is_empty());
+        let failure = report.failure.as_ref().expect("the case fails");
+        assert_eq!(failure.retries, 3);
+        assert_eq!(panic_message(&*failure.payload), "attempt 3");
+        let location = failure.location.as_ref().expect("panics are captured");
+        assert_eq!(location.line, *lines.lock().unwrap().last().unwrap());
+        assert_ne!(location.line, lines.lock().unwrap()[0]);
+    }
+
+    #[test]
+    fn failures_report_where_the_case_panicked() {
+        let config = Config::new(16);
+        let line = AtomicUsize::new(0);
+        let report =
+            run_cases(&config, 3, &|generator: &mut DefaultGenerator| {
+                let len = generator.rng.random_range(0..100u32);
+                line.store(line!() as usize + 1, Ordering::Relaxed);
+                crate::prop_assert!(len < 50, "{len} is too long");
+                // Only the first panic of a case is reported.
+                panic!("after the assertion");
+            });
+
+        let failure = report.failure.as_ref().expect("a case fails");
+        let location = failure.location.as_ref().expect("panics are captured");
+        assert_eq!(location.file, file!());
+        assert_eq!(location.line as usize, line.load(Ordering::Relaxed));
+
+        let assertion = failure.assertion.as_ref().expect("prop_assert failed");
+        assert_eq!(assertion.expression, "len < 50");
+        let len = assertion.context.as_ref().unwrap().split(' ').next();
+        assert!(len.unwrap().parse::<u32>().unwrap() >= 50);
+        assert_eq!(assertion.values, None);
+
+        let message = failure_message(&config, &report, false).unwrap();
+        assert!(message.contains(": assertion `len < 50` failed: "));
+        assert!(message.ends_with(&format!("\n  at {location}")));
+    }
+
+    #[test]
+    fn shrunk_failures_report_where_the_shrunk_case_panicked() {
+        let config = Config::new(64).with_seed(5);
+        let line = AtomicUsize::new(0);
+        let report =
+            run_cases(&config, 64, &|generator: &mut DefaultGenerator| {
+                let mut tree = AnyU32::new(0..=999).new_tree(generator).take();
+                if !shrink_trees(&mut [&mut tree]) {
+                    return;
+                }
+                let value = *tree.current();
+                crate::prop_assert!(value < 2, "{value}");
+                line.store(line!() as usize + 1, Ordering::Relaxed);
+                crate::prop_assert!(value == 0, "{value}");
+            });
+
+        let failure = report.failure.as_ref().expect("a case fails");
+        let location = failure.location.as_ref().expect("panics are captured");
+        assert_eq!(location.line as usize, line.load(Ordering::Relaxed));
+        let assertion = failure.assertion.as_ref().expect("prop_assert failed");
+        assert_eq!(assertion.expression, "value == 0");
+        assert_eq!(assertion.context.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn every_collected_failure_is_shrunk() {
+        let config = Config::new(64).with_seed(5).with_max_failures(4);
+        let report =
+            run_cases(&config, 64, &|generator: &mut DefaultGenerator| {
+                let mut tree = AnyU32::new(0..=999).new_tree(generator).take();
+                if !shrink_trees(&mut [&mut tree]) {
+                    return;
+                }
+                let value = *tree.current();
+                crate::prop_assert!(value == 0, "{value}");
+            });
+
+        let failures: Vec<&Failure> =
+            report.failure.iter().chain(&report.failures).collect();
+        assert_eq!(failures.len(), 4);
+        for failure in failures {
+            let assertion = failure.assertion.as_ref().expect("prop_assert");
+            assert_eq!(assertion.context.as_deref(), Some("1"));
+        }
+    }
+
+    #[test]
+    fn colored_messages_highlight_the_seed_and_location() {
+        let config = Config::new(4);
+        let report = run_cases(&config, 7, &|_: &mut DefaultGenerator| {
+            panic!("broken");
+        });
+
+        let plain = failure_message(&config, &report, false).unwrap();
+        assert!(!plain.contains('\x1b'));
+        let colored = failure_message(&config, &report, true).unwrap();
+        assert!(colored.contains(&format!("\x1b[1;33m{SEED_ENV}=0x7\x1b[0m")));
+        assert!(colored.contains(&format!("\n  at \x1b[2m{}:", file!())));
+        let strip = colored.replace("\x1b[1;33m", "").replace("\x1b[2m", "");
+        assert_eq!(strip.replace("\x1b[0m", ""), plain);
+    }
+
+    /// Draws bytes until one is above `u8::MAX`, which none ever is.
+    struct Runaway;
+
+    impl crate::strategy::Strategy for Runaway {
+        type Value = u8;
+        type Tree = crate::strategy::runtime::ConstantValueTree<u8>;
+
+        fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+            &mut self,
+            generator: &mut Generator<R>,
+        ) -> crate::strategy::runtime::Generation<Self::Tree> {
+            loop {
+                let value = generator.rng.random::<u8>();
+                if u16::from(value) > 256 {
+                    return generator.accept(
+                        crate::strategy::runtime::ConstantValueTree::new(value),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn entropy_limits_name_runaway_strategies() {
+        let config = Config::new(4).with_entropy_limit(1024);
+        let report =
+            run_cases(&config, 0, &|generator: &mut DefaultGenerator| {
+                let _ = generator.rng.random::<u64>();
+                let mut strategy = crate::strategy::runtime::adapt(Runaway);
+                crate::strategy::runtime::execute_tree(
+                    &mut strategy,
+                    generator,
+                );
+            });
+
+        let failure = report.failure.expect("the strategy never returns");
+        assert_eq!(failure.case, 0);
+        assert_eq!(
+            failure.message(),
+            format!(
+                "#[proptest] strategy `{}` drew past the entropy limit of \
+                 1024 bytes in one case; does its `new_tree` loop without \
+                 end?",
+                core::any::type_name::<Runaway>(),
+            )
+        );
+
+        let report = run_cases(
+            &Config::new(4).with_entropy_limit(8),
+            0,
+            &|generator: &mut DefaultGenerator| {
+                let _ = generator.rng.random::<u64>();
+            },
+        );
+        assert!(report.failure.is_none(), "exactly the limit is fine");
+    }
+
+    fn failure(case: usize, message: &str, file: &str, value: &str) -> Failure {
+        Failure {
+            case,
+            seed: case as u64,
+            order_seed: None,
+            inputs: vec![CaseInput {
+                name: "value",
+                value: Some(value.to_owned()),
+                provenance: None,
+            }],
+            location: Some(PanicLocation {
+                file: file.to_owned(),
+                line: 1,
+                column: 1,
+            }),
+            assertion: None,
+            retries: 0,
+            payload: Box::new(message.to_owned()),
+        }
+    }
+
+    #[test]
+    fn failures_are_grouped_by_location_and_message() {
+        let config = Config::new(0);
+        let mut report = run_cases(&config, 0, &|_: &mut DefaultGenerator| {});
+        report.failure =
+            Some(failure(0, "index 7 out of range", "a.rs", "[7]"));
+        report.failures = vec![
+            failure(3, "index 12 out of range", "b.rs", "[1, 12]"),
+            // Grouped with the first case through the second one.
+            failure(5, "overflow", "b.rs", "0"),
+            failure(6, "divide by zero", "c.rs", "[0, 0]"),
+        ];
+
+        let groups = report.groups();
+        let groups: Vec<_> = groups
+            .iter()
+            .map(|group| (group.example.case, group.cases.clone()))
+            .collect();
+        assert_eq!(groups, [(0, vec![0, 3, 5]), (6, vec![6])]);
+
+        let message = failure_message(&config, &report, false).unwrap();
+        assert!(message.ends_with(
+            "\n\nOther failures:\n  case 6 (case seed 0x6): divide by zero\n    at c.rs:1:1"
+        ));
+    }
+
+    #[test]
+    fn failure_signatures_leave_numbers_out() {
+        let signature = failure(0, "len 10 > 3", "a.rs", "").signature();
+        assert_eq!(signature.message, "len N > N");
+        assert_eq!(signature.location.unwrap().file, "a.rs");
+    }
+
+    #[test]
+    fn panic_capture_can_be_turned_off() {
+        let config = Config::new(4).with_capture_panics(false);
+        let report = run_cases(&config, 0, &|_: &mut DefaultGenerator| {
+            crate::prop_assert_eq!(1 + 1, 3, "arithmetic");
+        });
+
+        let failure = report.failure.as_ref().expect("the first case fails");
+        assert_eq!(failure.location, None);
+        assert_eq!(failure.assertion, None);
+        assert_eq!(
+            failure.message(),
+            "assertion `1 + 1 == 3` failed: arithmetic\n  left: 2\n right: 3",
+        );
+        let message = failure_message(&config, &report, false).unwrap();
+        assert!(!message.contains("\n  at "));
+    }
+
+    #[test]
+    fn passing_runs_execute_every_case() {
+        let counter = AtomicUsize::new(0);
+        let config = Config::new(100).with_threads(4);
+        let report = run_cases(&config, 0, &|_: &mut DefaultGenerator| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        assert!(report.failure.is_none());
+        assert_eq!(report.cases_run, 100);
+        assert_eq!(counter.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn reports_sum_generator_stats() {
+        let config = Config::new(10).with_threads(2);
+        let report =
+            run_cases(&config, 0, &|generator: &mut DefaultGenerator| {
+                let _ = generator.rng.random::<u64>();
+                let _ = generator.accept(());
+            });
+        assert_eq!(report.stats.entropy_bytes, 80);
+        assert_eq!(report.stats.accepted, 10);
+        assert_eq!(report.stats.rejected, 0);
+    }
+
+    #[test]
+    fn time_budget_stops_unbounded_runs() {
+        let config =
+            Config::new(usize::MAX).with_time_budget(Duration::from_millis(20));
+        let report = run_cases(&config, 0, &|_: &mut DefaultGenerator| {
+            thread::sleep(Duration::from_millis(1));
+        });
+        assert!(report.budget_exhausted);
+        assert!(report.cases_run >= 1);
+        assert!(report.cases_run < 1_000);
+    }
+}
@@ -0,0 +1,1378 @@
+//! Shrinking value trees, with a trace of the steps taken.
+//!
+//! [`shrink`] walks a [`ValueTree`] towards a minimal value for which a
+//! predicate still holds, recording every `simplify` and `complicate` call in
+//! a [`ShrinkTrace`]. Trees are deterministic, so replaying the trace on a
+//! tree regenerated from the same seed reaches the same minimal value without
+//! running the predicate again. Traces print as a compact string of `s` and
+//! `c` steps and parse back from it, which makes them easy to log and store.
+//!
+//! [`shrink_with`] explores the same steps in the order picked by a
+//! [`ShrinkScheduler`], regenerating the tree to backtrack, and
+//! [`shrink_parallel`] evaluates several of them at once on worker threads.
+//!
+//! Every driver keeps the simplest failing value it has accepted so far.
+//! [`shrink_within`] and [`shrink_with_budget`] stop once a [`ShrinkBudget`]
+//! of predicate evaluations or time is spent and return that value, marked
+//! as possibly not minimal, instead of wherever the tree was left.
+//!
+//! Predicates that are slow to run can be wrapped in a [`ShrinkCache`], which
+//! remembers their outcome for every candidate so the values shrinking
+//! revisits, as it simplifies and complicates back, do not run them again.
+//!
+//! None of this needs a test run: [`shrink`] takes any tree and any
+//! predicate, so a value captured elsewhere, such as an input that crashed
+//! a fuzzer or a service, can be minimized from a tree holding it. Integer
+//! strategies build those trees with `tree_for`, and collection trees
+//! assemble from them:
+//!
+//! ```
+//! use estoa_proptest::{
+//!     shrink::shrink,
+//!     strategy::{AnyU8, VecValueTree},
+//! };
+//!
+//! // The parser crashes on any input holding a byte above 0x7f.
+//! let crashes = |input: &Vec<u8>| input.iter().any(|&byte| byte > 0x7f);
+//!
+//! let captured = vec![0x12, 0x34, 0xfe, 0x56, 0x80];
+//! let bytes = AnyU8::default();
+//! let trees = captured.iter().map(|&byte| bytes.tree_for(byte)).collect();
+//! let mut tree = VecValueTree::from_trees(trees, 0);
+//!
+//! assert_eq!(shrink(&mut tree, crashes).value, [0x80]);
+//! ```
+//!
+//! [`LengthShrinker`] is the length pass of the built-in collection trees,
+//! for custom collection strategies to shrink their length the same way, and
+//! [`DedupShrinker`] keeps the keys of set and map trees distinct while they
+//! shrink.
+
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::{
+    fmt,
+    ops::{Deref, Range},
+    str::FromStr,
+    time::Duration,
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::{BuildHasher, Hash},
+    panic,
+    thread,
+    time::Instant,
+};
+
+use crate::strategy::ValueTree;
+
+/// One call made on a value tree while shrinking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShrinkStep {
+    Simplify,
+    Complicate,
+}
+
+/// The calls made on a value tree while shrinking, in order.
+///
+/// Calls that returned `false` are recorded too, as they may still move the
+/// tree's internal state.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShrinkTrace {
+    steps: Vec<ShrinkStep>,
+}
+
+impl ShrinkTrace {
+    pub fn steps(&self) -> &[ShrinkStep] {
+        &self.steps
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Apply the recorded steps to `tree`.
+    ///
+    /// On a tree regenerated from the same seed, `tree.current()` ends at the
+    /// value the trace was recorded for.
+    pub fn replay<T: ValueTree>(&self, tree: &mut T) {
+        for step in &self.steps {
+            match step {
+                ShrinkStep::Simplify => tree.simplify(),
+                ShrinkStep::Complicate => tree.complicate(),
+            };
+        }
+    }
+}
+
+/// A [`ValueTree`] with the type of its value erased, so that trees of
+/// different types can be stepped together.
+///
+/// `#[proptest]` hands the trees of a case's arguments to
+/// [`shrink_trees`](crate::runner::shrink_trees) this way, and the runner
+/// moves them along the trace it is shrinking the case with.
+pub trait ErasedValueTree {
+    /// Take `step`, returning what `simplify` or `complicate` returned.
+    fn step(&mut self, step: ShrinkStep) -> bool;
+}
+
+impl<T: ValueTree> ErasedValueTree for T {
+    fn step(&mut self, step: ShrinkStep) -> bool {
+        match step {
+            ShrinkStep::Simplify => self.simplify(),
+            ShrinkStep::Complicate => self.complicate(),
+        }
+    }
+}
+
+impl Extend<ShrinkStep> for ShrinkTrace {
+    fn extend<I: IntoIterator<Item = ShrinkStep>>(&mut self, steps: I) {
+        self.steps.extend(steps);
+    }
+}
+
+impl fmt::Display for ShrinkTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            f.write_str(match step {
+                ShrinkStep::Simplify => "s",
+                ShrinkStep::Complicate => "c",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`ShrinkTrace`] from a string fails.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseShrinkTraceError {
+    /// Byte offset of the first character that is not `s` or `c`.
+    pub position: usize,
+}
+
+impl fmt::Display for ParseShrinkTraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid shrink step at position {}, expected `s` or `c`",
+            self.position
+        )
+    }
+}
+
+impl core::error::Error for ParseShrinkTraceError {}
+
+impl FromStr for ShrinkTrace {
+    type Err = ParseShrinkTraceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = s
+            .char_indices()
+            .map(|(position, step)| match step {
+                's' => Ok(ShrinkStep::Simplify),
+                'c' => Ok(ShrinkStep::Complicate),
+                _ => Err(ParseShrinkTraceError { position }),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { steps })
+    }
+}
+
+/// The outcome of [`shrink`].
+#[derive(Clone, Debug)]
+pub struct Shrunk<V> {
+    /// The simplest value for which the predicate held.
+    pub value: V,
+    /// The steps leading a tree regenerated from the same seed to `value`.
+    pub trace: ShrinkTrace,
+    /// Whether shrinking ran until nothing simpler failed. `false` when it
+    /// stopped at its budget, so `value` may not be minimal.
+    pub complete: bool,
+}
+
+/// How long shrinking may run, in predicate evaluations and time. The
+/// default is unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShrinkBudget {
+    attempts: Option<usize>,
+    time: Option<Duration>,
+}
+
+impl ShrinkBudget {
+    /// Evaluate the predicate at most `attempts` times.
+    pub fn with_attempts(mut self, attempts: usize) -> Self {
+        self.attempts = Some(attempts);
+        self
+    }
+
+    /// Stop evaluating the predicate once `time` has passed.
+    ///
+    /// # Panics
+    ///
+    /// Shrinking panics without the `std` feature, which provides the
+    /// clock, or on targets where `std::time::Instant` is unavailable.
+    pub fn with_time(mut self, time: Duration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn attempts(&self) -> Option<usize> {
+        self.attempts
+    }
+
+    pub fn time(&self) -> Option<Duration> {
+        self.time
+    }
+}
+
+/// What is left of a [`ShrinkBudget`] while shrinking.
+struct Spending {
+    attempts: Option<usize>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+    exhausted: bool,
+}
+
+impl Spending {
+    fn new(budget: ShrinkBudget) -> Self {
+        #[cfg(not(feature = "std"))]
+        assert!(
+            budget.time.is_none(),
+            "shrinking within a time budget needs the `std` feature"
+        );
+        Self {
+            attempts: budget.attempts,
+            #[cfg(feature = "std")]
+            deadline: budget.time.map(|time| Instant::now() + time),
+            exhausted: false,
+        }
+    }
+
+    /// Take an evaluation out of the budget, or `false` once it is spent.
+    fn spend(&mut self) -> bool {
+        #[cfg(feature = "std")]
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.exhausted = true;
+        }
+        match &mut self.attempts {
+            Some(0) => self.exhausted = true,
+            Some(attempts) if !self.exhausted => *attempts -= 1,
+            _ => {}
+        }
+        !self.exhausted
+    }
+
+    /// Evaluate `fails` on a candidate, reporting it to the observers of the
+    /// case shrinking runs in, if any. `None` once the budget is spent.
+    fn attempt<V>(
+        &mut self,
+        fails: &mut impl FnMut(&V) -> bool,
+        value: &V,
+    ) -> Option<bool> {
+        if !self.spend() {
+            return None;
+        }
+        let fails = fails(value);
+        #[cfg(feature = "std")]
+        crate::runner::observe_shrink_attempt(fails);
+        Some(fails)
+    }
+}
+
+/// Shrink `tree` towards a minimal value for which `fails` holds.
+///
+/// `fails` must hold for the tree's current value. The tree simplifies while
+/// `fails` keeps holding and complicates after a simplification made it pass,
+/// stopping once the tree has nothing left to try, so it may end on a value
+/// for which `fails` does not hold; the returned value is the last one for
+/// which it did.
+pub fn shrink<T, F>(tree: &mut T, fails: F) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    F: FnMut(&T::Value) -> bool,
+{
+    shrink_within(tree, ShrinkBudget::default(), fails)
+}
+
+/// [`shrink`] until `budget` is spent.
+///
+/// Running out of budget returns the simplest failing value reached so far
+/// with its trace, and [`Shrunk::complete`] unset. The tree is left where
+/// shrinking stopped, which may be a value `fails` was not evaluated on.
+pub fn shrink_within<T, F>(
+    tree: &mut T,
+    budget: ShrinkBudget,
+    mut fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    F: FnMut(&T::Value) -> bool,
+{
+    let mut spending = Spending::new(budget);
+    let mut value = tree.current().clone();
+    let mut trace = ShrinkTrace::default();
+    // Length of the trace when the tree last held a failing value.
+    let mut failing = 0;
+    let mut passing = false;
+
+    loop {
+        let step = if passing {
+            ShrinkStep::Complicate
+        } else {
+            ShrinkStep::Simplify
+        };
+        let moved = match step {
+            ShrinkStep::Simplify => tree.simplify(),
+            ShrinkStep::Complicate => tree.complicate(),
+        };
+        trace.steps.push(step);
+        if !moved {
+            break;
+        }
+
+        let Some(fails) = spending.attempt(&mut fails, tree.current()) else {
+            break;
+        };
+        passing = !fails;
+        if !passing {
+            value = tree.current().clone();
+            failing = trace.len();
+        }
+    }
+
+    if passing || spending.exhausted {
+        trace.steps.truncate(failing);
+    }
+    Shrunk {
+        value,
+        trace,
+        complete: !spending.exhausted,
+    }
+}
+
+/// A shrink predicate that runs once per distinct candidate.
+///
+/// Shrinking revisits values: a simplification that makes the predicate
+/// pass is complicated back to where it was, and the other schedulers
+/// replay traces through values they already tried. The cache keys each
+/// outcome by a hash of the candidate's `Debug` output, the fingerprint
+/// [`is_duplicate`](crate::runner::is_duplicate) compares inputs by, and
+/// answers from it when the same candidate comes back:
+///
+/// ```
+/// use estoa_proptest::{
+///     shrink::{ShrinkCache, shrink},
+///     strategy::IntValueTree,
+/// };
+///
+/// let mut tree = IntValueTree::new(1_000u32, vec![0, 500, 50, 20]);
+/// let mut cache = ShrinkCache::new(|value: &u32| *value > 100);
+/// let shrunk = shrink(&mut tree, |value| cache.fails(value));
+/// assert_eq!(shrunk.value, 500);
+/// // Complicating back to 500 after 50 passed reused its outcome.
+/// assert_eq!(cache.hits(), 1);
+/// ```
+///
+/// Cache hits still count against a [`ShrinkBudget`] of attempts.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct ShrinkCache<F> {
+    fails: F,
+    outcomes: HashMap<u64, bool>,
+    hits: usize,
+}
+
+#[cfg(feature = "std")]
+impl<F> ShrinkCache<F> {
+    pub fn new(fails: F) -> Self {
+        Self {
+            fails,
+            outcomes: HashMap::new(),
+            hits: 0,
+        }
+    }
+
+    /// Whether the predicate fails on `value`, running it only the first
+    /// time `value` is seen.
+    pub fn fails<V>(&mut self, value: &V) -> bool
+    where
+        V: Debug + ?Sized,
+        F: FnMut(&V) -> bool,
+    {
+        let key = crate::runner::fingerprint(value);
+        if let Some(&fails) = self.outcomes.get(&key) {
+            self.hits += 1;
+            return fails;
+        }
+        let fails = (self.fails)(value);
+        self.outcomes.insert(key, fails);
+        fails
+    }
+
+    /// The number of candidates answered without running the predicate.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// The number of distinct candidates the predicate ran on.
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+}
+
+/// The order in which [`shrink_with`] explores simplifications.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ShrinkScheduler {
+    /// Commit to the first simplification that still fails, as [`shrink`]
+    /// does.
+    #[default]
+    DepthFirst,
+    /// Try every simplification of each failing value before moving on to
+    /// theirs, continuing from the simplest failing one of the deepest level
+    /// reached.
+    BreadthFirst,
+    /// Delta debugging: take several simplifications at once and test only
+    /// where they land, halving their number each time the predicate stops
+    /// holding until single steps are left.
+    Ddmin,
+}
+
+impl ShrinkScheduler {
+    /// The name `#[proptest(shrink = "...")]` takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DepthFirst => "depth_first",
+            Self::BreadthFirst => "breadth_first",
+            Self::Ddmin => "ddmin",
+        }
+    }
+}
+
+impl fmt::Display for ShrinkScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Error returned when parsing an unknown [`ShrinkScheduler`] name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseShrinkSchedulerError;
+
+impl fmt::Display for ParseShrinkSchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "unknown shrink scheduler, expected `depth_first`, \
+             `breadth_first` or `ddmin`",
+        )
+    }
+}
+
+impl core::error::Error for ParseShrinkSchedulerError {}
+
+impl FromStr for ShrinkScheduler {
+    type Err = ParseShrinkSchedulerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depth_first" => Ok(Self::DepthFirst),
+            "breadth_first" => Ok(Self::BreadthFirst),
+            "ddmin" => Ok(Self::Ddmin),
+            _ => Err(ParseShrinkSchedulerError),
+        }
+    }
+}
+
+/// Predicate evaluations a breadth-first shrink may spend; its frontier
+/// grows with every failing simplification.
+const BREADTH_FIRST_BUDGET: usize = 4096;
+
+/// Simplifications a delta-debugging shrink first takes at once.
+const DDMIN_STRIDE: usize = 16;
+
+/// Shrink a tree built by `new_tree` in the order `scheduler` picks.
+///
+/// Backtracking replays a trace on a fresh tree, so `new_tree` must build
+/// the same tree every time, e.g. from the same seed. The result is the
+/// simplest failing value reached, with its trace from a fresh tree.
+pub fn shrink_with<T, N, F>(
+    new_tree: N,
+    scheduler: ShrinkScheduler,
+    fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    N: FnMut() -> T,
+    F: FnMut(&T::Value) -> bool,
+{
+    shrink_with_budget(new_tree, scheduler, ShrinkBudget::default(), fails)
+}
+
+/// [`shrink_with`] until `budget` is spent, returning the simplest failing
+/// value reached so far, as [`shrink_within`] does.
+pub fn shrink_with_budget<T, N, F>(
+    mut new_tree: N,
+    scheduler: ShrinkScheduler,
+    budget: ShrinkBudget,
+    fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    N: FnMut() -> T,
+    F: FnMut(&T::Value) -> bool,
+{
+    match scheduler {
+        ShrinkScheduler::DepthFirst => {
+            shrink_within(&mut new_tree(), budget, fails)
+        }
+        ShrinkScheduler::BreadthFirst => {
+            breadth_first(new_tree, Spending::new(budget), fails)
+        }
+        ShrinkScheduler::Ddmin => ddmin(new_tree, Spending::new(budget), fails),
+    }
+}
+
+/// A fresh tree moved to the end of `trace`.
+fn replayed<T: ValueTree>(
+    new_tree: &mut impl FnMut() -> T,
+    trace: &ShrinkTrace,
+) -> T {
+    let mut tree = new_tree();
+    trace.replay(&mut tree);
+    tree
+}
+
+/// Visit the simplifications of `tree`, which sits at the end of `base`,
+/// simplest first, until `visit` returns `true` or none are left.
+///
+/// Each simplification is reached by complicating back from the previous
+/// one and simplifying again, the steps [`shrink`] takes while the predicate
+/// passes.
+fn simplifications<T: ValueTree>(
+    tree: &mut T,
+    base: &ShrinkTrace,
+    mut visit: impl FnMut(&ShrinkTrace, &T::Value) -> bool,
+) {
+    let mut trace = base.clone();
+    loop {
+        trace.steps.push(ShrinkStep::Simplify);
+        if !tree.simplify() || visit(&trace, tree.current()) {
+            return;
+        }
+        trace.steps.push(ShrinkStep::Complicate);
+        if !tree.complicate() {
+            return;
+        }
+    }
+}
+
+fn breadth_first<T, N, F>(
+    mut new_tree: N,
+    mut spending: Spending,
+    mut fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    N: FnMut() -> T,
+    F: FnMut(&T::Value) -> bool,
+{
+    let mut best = Shrunk {
+        value: new_tree().into_current(),
+        trace: ShrinkTrace::default(),
+        complete: true,
+    };
+    let mut frontier = vec![ShrinkTrace::default()];
+    let mut budget = BREADTH_FIRST_BUDGET;
+
+    loop {
+        let mut next = Vec::new();
+        for base in &frontier {
+            let mut tree = replayed(&mut new_tree, base);
+            simplifications(&mut tree, base, |trace, value| {
+                if budget == 0 {
+                    return true;
+                }
+                budget -= 1;
+                match spending.attempt(&mut fails, value) {
+                    None => {
+                        budget = 0;
+                        return true;
+                    }
+                    Some(true) => next.push(Shrunk {
+                        value: value.clone(),
+                        trace: trace.clone(),
+                        complete: true,
+                    }),
+                    Some(false) => {}
+                }
+                false
+            });
+        }
+
+        // A level cut short still found failing values simpler than the
+        // last level's.
+        if let Some(simplest) = next.first() {
+            best = simplest.clone();
+        }
+        if budget == 0 {
+            best.complete = false;
+            return best;
+        }
+        if next.is_empty() {
+            return best;
+        }
+        frontier = next.into_iter().map(|shrunk| shrunk.trace).collect();
+    }
+}
+
+fn ddmin<T, N, F>(
+    mut new_tree: N,
+    mut spending: Spending,
+    mut fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    N: FnMut() -> T,
+    F: FnMut(&T::Value) -> bool,
+{
+    let mut best = Shrunk {
+        value: new_tree().into_current(),
+        trace: ShrinkTrace::default(),
+        complete: true,
+    };
+    let mut stride = DDMIN_STRIDE;
+
+    while !spending.exhausted {
+        let mut tree = replayed(&mut new_tree, &best.trace);
+        if stride > 1 {
+            let mut trace = best.trace.clone();
+            let landed = (0..stride).all(|_| {
+                trace.steps.push(ShrinkStep::Simplify);
+                tree.simplify()
+            });
+            if landed
+                && spending.attempt(&mut fails, tree.current()) == Some(true)
+            {
+                best.value = tree.current().clone();
+                best.trace = trace;
+            } else {
+                stride /= 2;
+            }
+            continue;
+        }
+
+        let mut found = None;
+        simplifications(&mut tree, &best.trace, |trace, value| {
+            match spending.attempt(&mut fails, value) {
+                Some(false) => return false,
+                Some(true) => found = Some((value.clone(), trace.clone())),
+                None => {}
+            }
+            true
+        });
+        match found {
+            Some((value, trace)) => {
+                best.value = value;
+                best.trace = trace;
+                stride = DDMIN_STRIDE;
+            }
+            None if spending.exhausted => break,
+            None => return best,
+        }
+    }
+    best.complete = false;
+    best
+}
+
+/// Shrink a tree built by `new_tree` as [`ShrinkScheduler::DepthFirst`]
+/// does, evaluating up to `threads` simplifications of each failing value at
+/// once on scoped threads.
+///
+/// The simplifications of a composite value mostly touch different fields,
+/// and depth-first shrinking tries them one after the other until one still
+/// fails. Evaluating them in batches keeps the first failing one of each
+/// batch and discards the rest, so the result is the one
+/// [`shrink_with`] reaches with any number of threads, in less wall time
+/// when the predicate is slow and most simplifications pass. `fails` runs
+/// off the case's thread, so it cannot record coverage; the attempts are
+/// still reported to the case's observers. Every candidate evaluated spends
+/// `budget`, including those discarded.
+#[cfg(feature = "std")]
+pub fn shrink_parallel<T, N, F>(
+    mut new_tree: N,
+    threads: usize,
+    budget: ShrinkBudget,
+    fails: F,
+) -> Shrunk<T::Value>
+where
+    T: ValueTree,
+    T::Value: Clone + Sync,
+    N: FnMut() -> T,
+    F: Fn(&T::Value) -> bool + Sync,
+{
+    // Spawning threads panics on wasm targets built without atomics.
+    let threads =
+        if cfg!(all(target_family = "wasm", not(target_feature = "atomics"))) {
+            1
+        } else {
+            threads.max(1)
+        };
+    let mut spending = Spending::new(budget);
+    let mut best = Shrunk {
+        value: new_tree().into_current(),
+        trace: ShrinkTrace::default(),
+        complete: true,
+    };
+
+    while !spending.exhausted {
+        let mut tree = replayed(&mut new_tree, &best.trace);
+        let mut batch = Vec::with_capacity(threads);
+        let mut found = None;
+        simplifications(&mut tree, &best.trace, |trace, value| {
+            batch.push((trace.clone(), value.clone()));
+            if batch.len() < threads {
+                return false;
+            }
+            found = first_failing(&mut batch, &mut spending, &fails);
+            found.is_some() || spending.exhausted
+        });
+        if found.is_none() && !spending.exhausted {
+            found = first_failing(&mut batch, &mut spending, &fails);
+        }
+        match found {
+            Some((trace, value)) => {
+                best.trace = trace;
+                best.value = value;
+            }
+            None if spending.exhausted => break,
+            None => return best,
+        }
+    }
+    best.complete = false;
+    best
+}
+
+/// Evaluate the candidates of `batch` that `spending` affords, each on its
+/// own thread, and return the first that fails. Empties `batch`.
+#[cfg(feature = "std")]
+fn first_failing<V, F>(
+    batch: &mut Vec<(ShrinkTrace, V)>,
+    spending: &mut Spending,
+    fails: &F,
+) -> Option<(ShrinkTrace, V)>
+where
+    V: Sync,
+    F: Fn(&V) -> bool + Sync,
+{
+    let affordable = batch.iter().take_while(|_| spending.spend()).count();
+    batch.truncate(affordable);
+    let outcomes: Vec<bool> = match &batch[..] {
+        [] => Vec::new(),
+        [(_, value)] => vec![fails(value)],
+        candidates => thread::scope(|scope| {
+            let workers: Vec<_> = candidates
+                .iter()
+                .map(|(_, value)| scope.spawn(|| fails(value)))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| {
+                    worker.join().unwrap_or_else(|p| panic::resume_unwind(p))
+                })
+                .collect()
+        }),
+    };
+    for &fails in &outcomes {
+        crate::runner::observe_shrink_attempt(fails);
+    }
+    let found = outcomes
+        .iter()
+        .position(|&fails| fails)
+        .map(|index| batch.swap_remove(index));
+    batch.clear();
+    found
+}
+
+/// Chunk sizes a collection of `len` elements drops while shrinking its
+/// length: halves of `len` down to single elements.
+fn drop_plan(len: usize) -> Vec<usize> {
+    let mut plan = Vec::new();
+    let mut size = len / 2;
+
+    while size > 0 {
+        plan.push(size);
+        size /= 2;
+    }
+
+    if !plan.contains(&1) && len > 0 {
+        plan.push(1);
+    }
+
+    plan
+}
+
+/// Shrinks the length of a collection by removing contiguous chunks.
+///
+/// The plan halves the collection's initial length down to single elements,
+/// so 8 elements try chunks of 4, then 2, then 1. Each chunk size slides over
+/// the collection from the front, and sizes that would take the collection
+/// below its minimum length are skipped. A tree asks [`seek`](Self::seek)
+/// for the chunk to remove next and, when the removal is rejected, puts the
+/// chunk back and calls [`backtrack`](Self::backtrack) to move past it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LengthShrinker {
+    plan: Vec<usize>,
+    min_len: usize,
+    chunk_index: usize,
+    offset: usize,
+}
+
+/// A chunk of elements [`LengthShrinker::seek`] proposes removing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LengthChunk {
+    chunk_index: usize,
+    offset: usize,
+    size: usize,
+}
+
+impl LengthChunk {
+    /// The indices of the elements to remove.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.size
+    }
+}
+
+impl LengthShrinker {
+    /// Plan the length pass of a collection of `len` elements that must
+    /// keep at least `min_len` of them.
+    pub fn new(len: usize, min_len: usize) -> Self {
+        Self {
+            plan: drop_plan(len),
+            min_len,
+            chunk_index: 0,
+            offset: 0,
+        }
+    }
+
+    /// The chunk sizes the pass tries, in order.
+    pub fn plan(&self) -> &[usize] {
+        &self.plan
+    }
+
+    /// The next chunk to remove from a collection now holding `len`
+    /// elements, or `None` once the plan has run out.
+    ///
+    /// Seeking does not move past the chunk: after it is removed, the next
+    /// seek tries the elements that took its place.
+    pub fn seek(&mut self, len: usize) -> Option<LengthChunk> {
+        while let Some(&size) = self.plan.get(self.chunk_index) {
+            if size == 0
+                || len <= self.min_len
+                || size > len
+                || len - size < self.min_len
+                || self.offset + size > len
+            {
+                self.chunk_index += 1;
+                self.offset = 0;
+                continue;
+            }
+
+            return Some(LengthChunk {
+                chunk_index: self.chunk_index,
+                offset: self.offset,
+                size,
+            });
+        }
+        None
+    }
+
+    /// Move past `chunk`, which was removed and then put back.
+    pub fn backtrack(&mut self, chunk: LengthChunk) {
+        self.chunk_index = chunk.chunk_index;
+        self.offset = chunk.offset + 1;
+    }
+}
+
+/// A set of keys a [`DedupShrinker`] looks candidates up in.
+pub trait KeyIndex<K> {
+    fn contains_key(&self, key: &K) -> bool;
+
+    fn insert_key(&mut self, key: K);
+
+    fn remove_key(&mut self, key: &K);
+}
+
+impl<K: Ord> KeyIndex<K> for BTreeSet<K> {
+    fn contains_key(&self, key: &K) -> bool {
+        self.contains(key)
+    }
+
+    fn insert_key(&mut self, key: K) {
+        self.insert(key);
+    }
+
+    fn remove_key(&mut self, key: &K) {
+        self.remove(key);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Hash + Eq, S: BuildHasher> KeyIndex<K> for HashSet<K, S> {
+    fn contains_key(&self, key: &K) -> bool {
+        self.contains(key)
+    }
+
+    fn insert_key(&mut self, key: K) {
+        self.insert(key);
+    }
+
+    fn remove_key(&mut self, key: &K) {
+        self.remove(key);
+    }
+}
+
+/// The distinct keys of a set or map tree, in generation order, with an
+/// index that answers whether a shrunk key would collide with another one.
+///
+/// Keyed collection trees shrink a key by asking its tree for a simpler
+/// value and must reject it when another entry already holds that value.
+/// Scanning every key makes that quadratic in the size of the collection;
+/// the index keeps it to one [`BTreeSet`] or `HashSet` lookup. The shrinker
+/// derefs to the slice of keys, and every change goes through it so the
+/// index stays in sync.
+#[derive(Clone, Debug)]
+pub struct DedupShrinker<K, I> {
+    keys: Vec<K>,
+    index: I,
+}
+
+impl<K, I> DedupShrinker<K, I>
+where
+    K: Clone,
+    I: KeyIndex<K> + Default,
+{
+    /// Index `keys`, which must be distinct.
+    pub fn new(keys: Vec<K>) -> Self {
+        let mut index = I::default();
+        for key in &keys {
+            index.insert_key(key.clone());
+        }
+        Self { keys, index }
+    }
+
+    /// The keys, as a set.
+    pub fn index(&self) -> &I {
+        &self.index
+    }
+
+    pub fn into_index(self) -> I {
+        self.index
+    }
+
+    /// Whether `candidate` is held by a key other than the one at `index`.
+    pub fn is_duplicate(&self, index: usize, candidate: &K) -> bool
+    where
+        K: PartialEq,
+    {
+        self.keys[index] != *candidate && self.index.contains_key(candidate)
+    }
+
+    /// Replace the key at `index`, returning the previous one.
+    pub fn replace(&mut self, index: usize, key: K) -> K {
+        let previous = core::mem::replace(&mut self.keys[index], key.clone());
+        self.index.remove_key(&previous);
+        self.index.insert_key(key);
+        previous
+    }
+
+    pub fn insert(&mut self, index: usize, key: K) {
+        self.index.insert_key(key.clone());
+        self.keys.insert(index, key);
+    }
+
+    pub fn remove(&mut self, index: usize) -> K {
+        let key = self.keys.remove(index);
+        self.index.remove_key(&key);
+        key
+    }
+
+    /// Insert `keys` before the key at `index`.
+    pub fn splice(&mut self, index: usize, keys: Vec<K>) {
+        for key in &keys {
+            self.index.insert_key(key.clone());
+        }
+        self.keys.splice(index..index, keys);
+    }
+
+    pub fn drain(&mut self, range: Range<usize>) -> Vec<K> {
+        let keys: Vec<K> = self.keys.drain(range).collect();
+        for key in &keys {
+            self.index.remove_key(key);
+        }
+        keys
+    }
+}
+
+impl<K, I> Deref for DedupShrinker<K, I> {
+    type Target = [K];
+
+    fn deref(&self) -> &[K] {
+        &self.keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::{
+        AnyU32,
+        IntValueTree,
+        Strategy,
+        VecStrategy,
+        runtime::Generator,
+    };
+
+    #[test]
+    fn shrink_finds_the_smallest_failing_integer() {
+        let tree = || IntValueTree::new(1_000u32, vec![0, 500, 750]);
+        let shrunk = shrink(&mut tree(), |value| *value >= 600);
+        assert_eq!(shrunk.value, 750);
+
+        let mut replayed = tree();
+        shrunk.trace.replay(&mut replayed);
+        assert_eq!(*replayed.current(), 750);
+    }
+
+    #[test]
+    fn replay_reaches_the_shrunk_value_of_a_regenerated_tree() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 4usize..=16);
+        let mut generate = || {
+            let rng = StdRng::seed_from_u64(9);
+            strategy.new_tree(&mut Generator::build(rng)).take()
+        };
+        let fails = |values: &Vec<u32>| {
+            values.iter().map(|&v| u64::from(v)).sum::<u64>() > 10
+        };
+
+        let mut tree = generate();
+        let shrunk = shrink(&mut tree, fails);
+        assert!(fails(&shrunk.value));
+
+        let mut replayed = generate();
+        shrunk.trace.replay(&mut replayed);
+        assert_eq!(*replayed.current(), shrunk.value);
+    }
+
+    #[test]
+    fn budgets_return_the_best_value_so_far() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 4usize..=16);
+        let mut generate = || {
+            let rng = StdRng::seed_from_u64(9);
+            strategy.new_tree(&mut Generator::build(rng)).take()
+        };
+        let fails = |values: &Vec<u32>| {
+            values.iter().map(|&v| u64::from(v)).sum::<u64>() > 10
+        };
+        let unlimited = shrink(&mut generate(), fails);
+        assert!(unlimited.complete);
+
+        for scheduler in [
+            ShrinkScheduler::DepthFirst,
+            ShrinkScheduler::BreadthFirst,
+            ShrinkScheduler::Ddmin,
+        ] {
+            let mut evaluated = Vec::new();
+            let budget = ShrinkBudget::default().with_attempts(5);
+            let shrunk =
+                shrink_with_budget(&mut generate, scheduler, budget, |v| {
+                    evaluated.push(v.clone());
+                    fails(v)
+                });
+            assert!(!shrunk.complete, "{scheduler}");
+            assert_eq!(evaluated.len(), 5, "{scheduler}");
+            assert!(fails(&shrunk.value), "{scheduler}");
+
+            // The value is the last failing one evaluated, not where the
+            // tree stopped.
+            let last_failing = evaluated.iter().rev().find(|v| fails(v));
+            let start = generate().into_current();
+            match scheduler {
+                ShrinkScheduler::DepthFirst => {
+                    assert_eq!(&shrunk.value, last_failing.unwrap_or(&start),)
+                }
+                _ => assert!(
+                    evaluated.contains(&shrunk.value) || shrunk.value == start
+                ),
+            }
+
+            let mut replayed = generate();
+            shrunk.trace.replay(&mut replayed);
+            assert_eq!(*replayed.current(), shrunk.value, "{scheduler}");
+        }
+    }
+
+    #[test]
+    fn budgets_spent_on_nothing_keep_the_original_value() {
+        let tree = || IntValueTree::new(1_000u32, vec![0, 500, 750]);
+        let budget = ShrinkBudget::default().with_attempts(0);
+        let shrunk = shrink_within(&mut tree(), budget, |_| true);
+        assert_eq!(shrunk.value, 1_000);
+        assert!(shrunk.trace.is_empty());
+        assert!(!shrunk.complete);
+
+        #[cfg(feature = "std")]
+        {
+            let budget = ShrinkBudget::default().with_time(Duration::ZERO);
+            let shrunk = shrink_within(&mut tree(), budget, |_| true);
+            assert_eq!(shrunk.value, 1_000);
+            assert!(!shrunk.complete);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn caches_run_the_predicate_once_per_candidate() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 4usize..=16);
+        let mut generate = || {
+            let rng = StdRng::seed_from_u64(3);
+            strategy.new_tree(&mut Generator::build(rng)).take()
+        };
+        let fails = |values: &Vec<u32>| values.iter().any(|&v| v > 1_000);
+
+        for scheduler in [
+            ShrinkScheduler::DepthFirst,
+            ShrinkScheduler::BreadthFirst,
+            ShrinkScheduler::Ddmin,
+        ] {
+            let uncached = shrink_with(&mut generate, scheduler, fails);
+
+            let mut runs = Vec::new();
+            let mut cache = ShrinkCache::new(|values: &Vec<u32>| {
+                runs.push(values.clone());
+                fails(values)
+            });
+            let cached = shrink_with(&mut generate, scheduler, |values| {
+                cache.fails(values)
+            });
+            assert_eq!(cached.value, uncached.value, "{scheduler}");
+            assert_eq!(cached.trace, uncached.trace, "{scheduler}");
+
+            let (hits, len) = (cache.hits(), cache.len());
+            assert_eq!(len, runs.len(), "{scheduler}");
+            let distinct: BTreeSet<_> = runs.iter().collect();
+            assert_eq!(distinct.len(), runs.len(), "{scheduler}");
+            if scheduler != ShrinkScheduler::Ddmin {
+                assert!(hits > 0, "{scheduler}");
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parallel_shrinking_matches_depth_first() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 4usize..=16);
+        let mut generate = || {
+            let rng = StdRng::seed_from_u64(5);
+            strategy.new_tree(&mut Generator::build(rng)).take()
+        };
+        let fails = |values: &Vec<u32>| {
+            values.iter().filter(|&&v| v % 3 == 0).count() >= 2
+        };
+        let sequential =
+            shrink_with(&mut generate, ShrinkScheduler::DepthFirst, fails);
+        assert!(sequential.complete);
+
+        for threads in [1, 2, 8] {
+            let parallel = shrink_parallel(
+                &mut generate,
+                threads,
+                ShrinkBudget::default(),
+                fails,
+            );
+            assert_eq!(parallel.value, sequential.value, "{threads} threads");
+            assert!(parallel.complete);
+            let mut replayed = generate();
+            parallel.trace.replay(&mut replayed);
+            assert_eq!(*replayed.current(), parallel.value);
+        }
+
+        let budget = ShrinkBudget::default().with_attempts(6);
+        let evaluated = std::sync::atomic::AtomicUsize::new(0);
+        let limited = shrink_parallel(&mut generate, 4, budget, |values| {
+            evaluated.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            fails(values)
+        });
+        assert!(!limited.complete);
+        assert_eq!(evaluated.into_inner(), 6);
+        assert!(fails(&limited.value));
+    }
+
+    #[test]
+    fn traces_round_trip_through_strings() {
+        let trace: ShrinkTrace = "sscsc".parse().unwrap();
+        assert_eq!(trace.len(), 5);
+        assert_eq!(trace.steps()[2], ShrinkStep::Complicate);
+        assert_eq!(trace.to_string(), "sscsc");
+        assert_eq!(
+            "ssx".parse::<ShrinkTrace>(),
+            Err(ParseShrinkTraceError { position: 2 })
+        );
+    }
+
+    #[test]
+    fn every_scheduler_reaches_a_replayable_failing_value() {
+        let mut strategy = VecStrategy::new(AnyU32::default(), 4usize..=16);
+        let mut generate = || {
+            let rng = StdRng::seed_from_u64(9);
+            strategy.new_tree(&mut Generator::build(rng)).take()
+        };
+        let fails = |values: &Vec<u32>| {
+            values.iter().map(|&v| u64::from(v)).sum::<u64>() > 10
+        };
+
+        for scheduler in [
+            ShrinkScheduler::DepthFirst,
+            ShrinkScheduler::BreadthFirst,
+            ShrinkScheduler::Ddmin,
+        ] {
+            let shrunk = shrink_with(&mut generate, scheduler, fails);
+            assert!(fails(&shrunk.value), "{scheduler}");
+
+            let mut replayed = generate();
+            shrunk.trace.replay(&mut replayed);
+            assert_eq!(*replayed.current(), shrunk.value, "{scheduler}");
+        }
+    }
+
+    #[test]
+    fn ddmin_leaps_over_failing_candidates() {
+        let tree = || IntValueTree::new(1_000u32, (0..1_000).rev().collect());
+        let mut evaluations = 0;
+        let shrunk = shrink_with(tree, ShrinkScheduler::Ddmin, |value| {
+            evaluations += 1;
+            *value >= 100
+        });
+        assert_eq!(shrunk.value, 100);
+        assert!(evaluations < 200, "{evaluations} evaluations");
+
+        let mut depth_first = 0;
+        let shrunk = shrink_with(tree, ShrinkScheduler::DepthFirst, |value| {
+            depth_first += 1;
+            *value >= 100
+        });
+        assert_eq!(shrunk.value, 100);
+        assert!(depth_first > evaluations);
+    }
+
+    #[test]
+    fn breadth_first_keeps_the_simplest_failing_value() {
+        let tree = || IntValueTree::new(1_000u32, vec![0, 500, 750]);
+        let shrunk =
+            shrink_with(tree, ShrinkScheduler::BreadthFirst, |value| {
+                *value >= 600
+            });
+        assert_eq!(shrunk.value, 750);
+        assert_eq!(shrunk.trace.to_string(), "scscs");
+    }
+
+    #[test]
+    fn schedulers_parse_from_their_names() {
+        for scheduler in [
+            ShrinkScheduler::DepthFirst,
+            ShrinkScheduler::BreadthFirst,
+            ShrinkScheduler::Ddmin,
+        ] {
+            assert_eq!(scheduler.name().parse(), Ok(scheduler));
+        }
+        assert_eq!(
+            "bisect".parse::<ShrinkScheduler>(),
+            Err(ParseShrinkSchedulerError)
+        );
+    }
+
+    #[test]
+    fn length_plan_halves_down_to_single_elements() {
+        assert_eq!(LengthShrinker::new(8, 0).plan(), &[4, 2, 1]);
+        assert_eq!(LengthShrinker::new(3, 0).plan(), &[1]);
+        assert!(LengthShrinker::new(0, 0).plan().is_empty());
+    }
+
+    #[test]
+    fn length_shrinker_slides_every_chunk_size_over_the_collection() {
+        let mut shrinker = LengthShrinker::new(4, 0);
+        let mut tried = Vec::new();
+        while let Some(chunk) = shrinker.seek(4) {
+            tried.push(chunk.range());
+            shrinker.backtrack(chunk);
+        }
+        assert_eq!(tried, vec![0..2, 1..3, 2..4, 0..1, 1..2, 2..3, 3..4]);
+    }
+
+    #[test]
+    fn length_shrinker_keeps_the_minimum_length() {
+        let mut shrinker = LengthShrinker::new(8, 6);
+        let chunk = shrinker.seek(8).unwrap();
+        assert_eq!(chunk.range(), 0..2);
+
+        // With the chunk removed the collection is at its minimum length.
+        assert_eq!(shrinker.seek(6), None);
+
+        let mut shrinker = LengthShrinker::new(8, 6);
+        assert_eq!(shrinker.seek(7).map(|chunk| chunk.range()), Some(0..1));
+    }
+
+    #[test]
+    fn length_shrinker_retries_the_same_offset_after_a_removal() {
+        let mut shrinker = LengthShrinker::new(8, 0);
+        assert_eq!(shrinker.seek(8).map(|chunk| chunk.range()), Some(0..4));
+        assert_eq!(shrinker.seek(4).map(|chunk| chunk.range()), Some(0..4));
+        assert_eq!(shrinker.seek(0), None);
+    }
+
+    #[test]
+    fn dedup_shrinker_detects_duplicates_of_other_keys() {
+        let keys: DedupShrinker<u32, BTreeSet<u32>> =
+            DedupShrinker::new(vec![3, 7, 9]);
+        assert!(keys.is_duplicate(0, &7));
+        assert!(!keys.is_duplicate(1, &7));
+        assert!(!keys.is_duplicate(0, &4));
+    }
+
+    #[test]
+    fn dedup_shrinker_keeps_its_index_in_sync() {
+        let mut keys: DedupShrinker<u32, BTreeSet<u32>> =
+            DedupShrinker::new(vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(keys.replace(0, 10), 1);
+        assert_eq!(keys.drain(1..3), vec![2, 3]);
+        assert_eq!(keys.remove(0), 10);
+        keys.splice(0, vec![6, 7]);
+        keys.insert(4, 8);
+
+        assert_eq!(&*keys, &[6, 7, 4, 5, 8]);
+        let sorted: Vec<u32> = keys.index().iter().copied().collect();
+        assert_eq!(sorted, vec![4, 5, 6, 7, 8]);
+        assert!(!keys.is_duplicate(0, &1));
+    }
+}
@@ -0,0 +1,182 @@
+//! Strategies and value trees behind a `Box`, with their types erased.
+
+use alloc::boxed::Box;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Provenance,
+    Strategy,
+    ValueTree,
+    runtime::{EntropySource, Generation, Generator},
+};
+
+/// A [`Strategy`] for `T` values whose type has been erased.
+///
+/// Every strategy producing `T` boxes into the same type through
+/// [`Strategy::boxed`], so differing strategies can be kept in a `Vec`,
+/// returned from the branches of one function or picked while a test runs.
+/// Generation goes through an [`ErasedStrategy`] and the trees through a
+/// [`BoxedValueTree`], which costs a virtual call per step.
+pub struct BoxedStrategy<T>(Box<dyn ErasedStrategy<T>>);
+
+impl<T> BoxedStrategy<T> {
+    pub fn new<S>(strategy: S) -> Self
+    where
+        S: Strategy<Value = T> + 'static,
+        S::Tree: 'static,
+        T: 'static,
+    {
+        Self(Box::new(strategy))
+    }
+}
+
+impl<T> Strategy for BoxedStrategy<T> {
+    type Value = T;
+    type Tree = BoxedValueTree<T>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.0.new_erased_tree(generator)
+    }
+}
+
+/// A [`ValueTree`] for `T` values whose type has been erased.
+pub struct BoxedValueTree<T>(Box<dyn ValueTree<Value = T>>);
+
+impl<T> BoxedValueTree<T> {
+    pub fn new<V>(tree: V) -> Self
+    where
+        V: ValueTree<Value = T> + 'static,
+    {
+        Self(Box::new(tree))
+    }
+}
+
+impl<T> ValueTree for BoxedValueTree<T> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        self.0.current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.0.simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.0.complicate()
+    }
+
+    fn provenance(&self) -> Option<Provenance> {
+        self.0.provenance()
+    }
+}
+
+impl<T> From<Box<dyn ErasedStrategy<T>>> for BoxedStrategy<T> {
+    fn from(strategy: Box<dyn ErasedStrategy<T>>) -> Self {
+        Self(strategy)
+    }
+}
+
+/// The object-safe part of [`Strategy`], implemented by every strategy.
+///
+/// [`Strategy::new_tree`] is generic over the generator's RNG, so a
+/// `dyn Strategy` cannot exist. An `ErasedStrategy` takes the generator as a
+/// `&mut dyn` [`EntropySource`] and boxes the tree instead, so strategies of
+/// different types can sit behind one trait object, e.g. in a registry keyed
+/// by name, and still be turned back into a [`Strategy`] with
+/// [`BoxedStrategy::from`].
+pub trait ErasedStrategy<T> {
+    fn new_erased_tree(
+        &mut self,
+        source: &mut dyn EntropySource,
+    ) -> Generation<BoxedValueTree<T>>;
+}
+
+impl<S> ErasedStrategy<S::Value> for S
+where
+    S: Strategy,
+    S::Tree: 'static,
+{
+    fn new_erased_tree(
+        &mut self,
+        source: &mut dyn EntropySource,
+    ) -> Generation<BoxedValueTree<S::Value>> {
+        let mut generation = None;
+        source.with_erased(&mut |generator| {
+            generation =
+                Some(self.new_tree(generator).map(BoxedValueTree::new));
+        });
+        generation.expect("the entropy source called back")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::{AnyU8, VecStrategy};
+
+    #[test]
+    fn boxed_strategies_generate_like_the_strategy_they_box() {
+        let mut plain = VecStrategy::new(AnyU8::default(), 0usize..=8usize);
+        let mut boxed =
+            VecStrategy::new(AnyU8::default(), 0usize..=8usize).boxed();
+        let mut plain_generator = Generator::build(StdRng::seed_from_u64(29));
+        let mut boxed_generator = Generator::build(StdRng::seed_from_u64(29));
+
+        for _ in 0..16 {
+            let mut expected = plain.new_tree(&mut plain_generator).take();
+            let mut tree = boxed.new_tree(&mut boxed_generator).take();
+            assert_eq!(tree.current(), expected.current());
+            while expected.simplify() {
+                assert!(tree.simplify());
+                assert_eq!(tree.current(), expected.current());
+            }
+            assert!(!tree.simplify());
+        }
+    }
+
+    #[test]
+    fn differing_strategies_share_a_collection() {
+        let mut strategies: Vec<BoxedStrategy<u8>> = vec![
+            AnyU8::new(0..=9).boxed(),
+            BoxedStrategy::new(AnyU8::new(200..=209)),
+        ];
+        let mut generator = Generator::build(StdRng::seed_from_u64(31));
+
+        let values: Vec<u8> = strategies
+            .iter_mut()
+            .map(|strategy| *strategy.new_tree(&mut generator).take().current())
+            .collect();
+        assert!(values[0] <= 9);
+        assert!((200..=209).contains(&values[1]));
+    }
+
+    #[test]
+    fn erased_strategies_generate_through_a_dyn_entropy_source() {
+        let mut registry: Vec<(&str, Box<dyn ErasedStrategy<u8>>)> = vec![
+            ("small", Box::new(AnyU8::new(0..=9))),
+            ("large", Box::new(AnyU8::new(200..=209))),
+        ];
+        let mut generator = Generator::build(StdRng::seed_from_u64(37));
+        let source: &mut dyn EntropySource = &mut generator;
+
+        for (name, strategy) in &mut registry {
+            let mut tree = strategy.new_erased_tree(source).take();
+            while tree.simplify() {}
+            let expected = if *name == "small" { 0 } else { 200 };
+            assert_eq!(*tree.current(), expected);
+        }
+
+        let (_, strategy) = registry.pop().expect("two strategies");
+        let mut boxed = BoxedStrategy::from(strategy);
+        assert!(*boxed.new_tree(&mut generator).take().current() >= 200);
+    }
+}
@@ -0,0 +1,391 @@
+//! Simulated monotonic clocks.
+//!
+//! [`SimClockStrategy`] generates a [`SimClock`]: a fixed sequence of
+//! monotonically increasing readings, for testing timeout, retry and
+//! rate-limiting logic without waiting on real time. Consecutive readings
+//! are a small jitter apart, sometimes equal, and now and then the clock
+//! jumps ahead the way a suspended machine or a long pause makes it.
+//!
+//! The property hands the clock to the code under test by reference and
+//! that code reads it with [`SimClock::now`] and waits with
+//! [`SimClock::sleep`]:
+//!
+//! ```
+//! use core::time::Duration;
+//!
+//! use estoa_proptest::strategy::clock::SimClock;
+//!
+//! fn retry(clock: &SimClock, deadline: Duration) -> usize {
+//!     let start = clock.now();
+//!     let mut attempts = 0;
+//!     while clock.now() - start < deadline {
+//!         attempts += 1;
+//!         clock.sleep(Duration::from_millis(100));
+//!     }
+//!     attempts
+//! }
+//!
+//! // A jump ahead while sleeping cuts the retries short.
+//! let clock = SimClock::new(vec![
+//!     Duration::ZERO,
+//!     Duration::from_millis(250),
+//!     Duration::from_secs(5),
+//! ]);
+//! assert_eq!(retry(&clock, Duration::from_secs(1)), 1);
+//! ```
+//!
+//! Shrinking turns jumps into plain steps, shortens steps towards the lower
+//! end of their range and drops readings.
+
+use alloc::{vec, vec::Vec};
+use core::{
+    fmt,
+    ops::RangeInclusive,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    AnyU64,
+    FrequencyValueTree,
+    IntValueTree,
+    SizeHint,
+    Strategy,
+    ValueTree,
+    VecStrategy,
+    VecValueTree,
+    frequency,
+    runtime::{Generation, Generator},
+};
+
+/// How far the clock moves per reading once the generated ones run out.
+const EXTRAPOLATION_STEP: Duration = Duration::from_millis(1);
+
+/// A clock that returns a fixed sequence of readings, see the
+/// [module documentation](self).
+///
+/// Readings are offsets from an arbitrary origin. Past the last generated
+/// one, every reading moves the clock ahead by a millisecond, so loops
+/// waiting for a deadline still finish. The clock is read through a shared
+/// reference; cloning it copies how far it has been read.
+pub struct SimClock {
+    readings: Vec<Duration>,
+    /// The index of the reading [`now`](Self::now) returns next.
+    cursor: AtomicUsize,
+}
+
+impl SimClock {
+    /// A clock returning `readings` in order.
+    ///
+    /// # Panics
+    ///
+    /// When `readings` is empty or decreases anywhere.
+    pub fn new(readings: Vec<Duration>) -> Self {
+        assert!(!readings.is_empty(), "a clock needs at least one reading");
+        assert!(
+            readings.is_sorted(),
+            "the readings of a monotonic clock cannot decrease"
+        );
+        Self {
+            readings,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    /// The generated readings, in order.
+    pub fn readings(&self) -> &[Duration] {
+        &self.readings
+    }
+
+    /// Read the clock, moving it on to its next reading.
+    pub fn now(&self) -> Duration {
+        self.reading(self.cursor.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// The reading [`now`](Self::now) returns next, without moving on.
+    pub fn peek(&self) -> Duration {
+        self.reading(self.cursor.load(Ordering::Relaxed))
+    }
+
+    /// Skip the readings earlier than `duration` after the last one taken,
+    /// or after the origin when the clock has not been read yet. The next
+    /// reading is at least `duration` later, and maybe more.
+    pub fn sleep(&self, duration: Duration) {
+        let cursor = self.cursor.load(Ordering::Relaxed);
+        let from = match cursor {
+            0 => Duration::ZERO,
+            cursor => self.reading(cursor - 1),
+        };
+        let target = from.saturating_add(duration);
+
+        let mut next = cursor;
+        while next < self.readings.len() && self.readings[next] < target {
+            next += 1;
+        }
+        if next >= self.readings.len() {
+            // Past the end, pick the first extrapolated reading that is late
+            // enough.
+            let last = self.readings.len() - 1;
+            let behind = target.saturating_sub(self.readings[last]);
+            let steps =
+                behind.as_nanos().div_ceil(EXTRAPOLATION_STEP.as_nanos());
+            let late_enough = last
+                .saturating_add(usize::try_from(steps).unwrap_or(usize::MAX));
+            next = next.max(late_enough);
+        }
+        self.cursor.store(next, Ordering::Relaxed);
+    }
+
+    /// How many readings have been taken or skipped.
+    pub fn position(&self) -> usize {
+        self.cursor.load(Ordering::Relaxed)
+    }
+
+    fn reading(&self, index: usize) -> Duration {
+        let last = self.readings.len() - 1;
+        match self.readings.get(index) {
+            Some(reading) => *reading,
+            None => {
+                let beyond = u32::try_from(index - last).unwrap_or(u32::MAX);
+                self.readings[last].saturating_add(EXTRAPOLATION_STEP * beyond)
+            }
+        }
+    }
+}
+
+impl Clone for SimClock {
+    fn clone(&self) -> Self {
+        Self {
+            readings: self.readings.clone(),
+            cursor: AtomicUsize::new(self.position()),
+        }
+    }
+}
+
+impl PartialEq for SimClock {
+    fn eq(&self, other: &Self) -> bool {
+        self.readings == other.readings && self.position() == other.position()
+    }
+}
+
+impl Eq for SimClock {}
+
+impl fmt::Debug for SimClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimClock")
+            .field("readings", &self.readings)
+            .field("position", &self.position())
+            .finish()
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+fn nanos_range(range: &RangeInclusive<Duration>) -> RangeInclusive<u64> {
+    nanos(*range.start())..=nanos(*range.end())
+}
+
+/// Generate [`SimClock`]s, see the [module documentation](self).
+#[derive(Clone)]
+pub struct SimClockStrategy {
+    readings: RangeInclusive<usize>,
+    jitter: RangeInclusive<Duration>,
+    jump_probability: f64,
+    jumps: RangeInclusive<Duration>,
+}
+
+impl SimClockStrategy {
+    /// Clocks with `readings` readings, at least one, that start at the
+    /// origin, move ahead by up to 10ms at a time and jump ahead by 1s to
+    /// 1min one step in twenty.
+    pub fn new<H: SizeHint>(readings: H) -> Self {
+        let readings = readings.to_inclusive();
+        assert!(*readings.start() > 0, "a clock needs at least one reading");
+        Self {
+            readings,
+            jitter: Duration::ZERO..=Duration::from_millis(10),
+            jump_probability: 0.05,
+            jumps: Duration::from_secs(1)..=Duration::from_secs(60),
+        }
+    }
+
+    /// How far apart consecutive readings are. A range starting at zero lets
+    /// the clock return the same reading twice.
+    pub fn with_jitter(mut self, jitter: RangeInclusive<Duration>) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// How likely a step is a jump, and how far jumps go.
+    pub fn with_jumps(
+        mut self,
+        probability: f64,
+        jumps: RangeInclusive<Duration>,
+    ) -> Self {
+        self.jump_probability = probability.clamp(0.0, 1.0);
+        self.jumps = jumps;
+        self
+    }
+}
+
+impl Default for SimClockStrategy {
+    fn default() -> Self {
+        Self::new(1usize..=32usize)
+    }
+}
+
+impl Strategy for SimClockStrategy {
+    type Value = SimClock;
+    type Tree = SimClockValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        const SCALE: f64 = 1_000_000.0;
+        let jump_weight = (self.jump_probability * SCALE) as u32;
+        let step = frequency(&[
+            (
+                SCALE as u32 - jump_weight,
+                AnyU64::new(nanos_range(&self.jitter)),
+            ),
+            (jump_weight, AnyU64::new(nanos_range(&self.jumps))),
+        ]);
+        // The first reading is the origin, the others are a step apart.
+        let steps_len =
+            (*self.readings.start() - 1)..=(*self.readings.end() - 1);
+
+        VecStrategy::new(step, steps_len)
+            .new_tree(generator)
+            .map(|steps| {
+                let current = clock(steps.current());
+                SimClockValueTree { steps, current }
+            })
+    }
+}
+
+fn clock(steps: &[u64]) -> SimClock {
+    let mut readings = vec![Duration::ZERO];
+    let mut now = Duration::ZERO;
+    for &step in steps {
+        now = now.saturating_add(Duration::from_nanos(step));
+        readings.push(now);
+    }
+    SimClock::new(readings)
+}
+
+pub struct SimClockValueTree {
+    steps: VecValueTree<FrequencyValueTree<IntValueTree<u64>>>,
+    current: SimClock,
+}
+
+impl ValueTree for SimClockValueTree {
+    type Value = SimClock;
+
+    fn current(&self) -> &SimClock {
+        &self.current
+    }
+
+    fn into_current(self) -> SimClock {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if !self.steps.simplify() {
+            return false;
+        }
+        self.current = clock(self.steps.current());
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.steps.complicate();
+        self.current = clock(self.steps.current());
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn readings_never_decrease() {
+        check_shrink_invariants_with(
+            SimClockStrategy::default(),
+            64,
+            |clock| {
+                clock.readings().is_sorted()
+                    && clock.readings()[0] == Duration::ZERO
+                    && clock.readings().len() <= 32
+            },
+        );
+    }
+
+    #[test]
+    fn jumps_shrink_to_the_smallest_one_that_fails() {
+        assert_shrinks_to!(
+            SimClockStrategy::default(),
+            |clock: &SimClock| {
+                clock
+                    .readings()
+                    .windows(2)
+                    .all(|pair| pair[1] - pair[0] < ms(1_000))
+            },
+            SimClock::new(vec![Duration::ZERO, ms(1_000)])
+        );
+    }
+
+    #[test]
+    fn readings_continue_past_the_end() {
+        let clock = SimClock::new(vec![Duration::ZERO, ms(5)]);
+        assert_eq!(clock.now(), Duration::ZERO);
+        assert_eq!(clock.peek(), ms(5));
+        assert_eq!(clock.now(), ms(5));
+        assert_eq!(clock.now(), ms(6));
+        assert_eq!(clock.now(), ms(7));
+        assert_eq!(clock.position(), 4);
+    }
+
+    #[test]
+    fn sleeping_skips_readings() {
+        let clock =
+            SimClock::new(vec![Duration::ZERO, ms(1), ms(2), ms(50), ms(51)]);
+        assert_eq!(clock.now(), Duration::ZERO);
+        clock.sleep(ms(10));
+        assert_eq!(clock.now(), ms(50));
+        clock.sleep(ms(20));
+        assert_eq!(clock.now(), ms(70));
+
+        let fresh = SimClock::new(vec![ms(3), ms(4)]);
+        fresh.sleep(Duration::ZERO);
+        assert_eq!(fresh.now(), ms(3));
+    }
+
+    #[test]
+    fn clones_are_read_independently() {
+        let clock = SimClock::new(vec![Duration::ZERO, ms(1)]);
+        clock.now();
+        let copy = clock.clone();
+        assert_eq!(copy.now(), ms(1));
+        assert_eq!(clock.now(), ms(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot decrease")]
+    fn readings_must_not_decrease() {
+        SimClock::new(vec![ms(2), ms(1)]);
+    }
+}
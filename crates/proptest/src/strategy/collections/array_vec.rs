@@ -0,0 +1,143 @@
+use arrayvec::ArrayVec;
+
+use super::vecs::{VecStrategy, VecValueTree};
+use crate::strategy::{
+    SizeHint,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+pub struct ArrayVecStrategy<S, const CAP: usize>
+where
+    S: Strategy,
+    S::Value: Clone,
+{
+    inner: VecStrategy<S>,
+}
+
+impl<S, const CAP: usize> ArrayVecStrategy<S, CAP>
+where
+    S: Strategy,
+    S::Value: Clone,
+{
+    /// Build a strategy whose lengths follow `size_hint`, capped at `CAP`.
+    pub fn new<H>(element: S, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        let min = size_hint.min();
+        if min > CAP {
+            panic!(
+                "size hint minimum {} exceeds ArrayVec capacity {}",
+                min, CAP
+            );
+        }
+        let max = size_hint.max().min(CAP);
+        Self {
+            inner: VecStrategy::new(element, min..=max),
+        }
+    }
+}
+
+pub struct ArrayVecValueTree<T, const CAP: usize>
+where
+    T: ValueTree,
+    T::Value: Clone,
+{
+    inner: VecValueTree<T>,
+    current: ArrayVec<T::Value, CAP>,
+}
+
+impl<T, const CAP: usize> ArrayVecValueTree<T, CAP>
+where
+    T: ValueTree,
+    T::Value: Clone,
+{
+    fn new(inner: VecValueTree<T>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: ArrayVec::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current = self.inner.current().iter().cloned().collect();
+    }
+}
+
+impl<S, const CAP: usize> Strategy for ArrayVecStrategy<S, CAP>
+where
+    S: Strategy,
+    S::Value: Clone,
+{
+    type Value = ArrayVec<S::Value, CAP>;
+    type Tree = ArrayVecValueTree<S::Tree, CAP>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(ArrayVecValueTree::new)
+    }
+}
+
+impl<T, const CAP: usize> ValueTree for ArrayVecValueTree<T, CAP>
+where
+    T: ValueTree,
+    T::Value: Clone,
+{
+    type Value = ArrayVec<T::Value, CAP>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::AnyU8;
+
+    #[test]
+    fn array_vec_strategy_caps_length_at_capacity() {
+        let mut strategy =
+            ArrayVecStrategy::<_, 3>::new(AnyU8::default(), 2usize..=10usize);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..32 {
+            let tree = match strategy.new_tree(&mut generator) {
+                Generation::Accepted { value, .. } => value,
+                Generation::Rejected { .. } => panic!("unexpected rejection"),
+            };
+            assert!((2..=3).contains(&tree.current().len()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds ArrayVec capacity")]
+    fn array_vec_strategy_rejects_minimum_above_capacity() {
+        let _ =
+            ArrayVecStrategy::<_, 2>::new(AnyU8::default(), 3usize..=4usize);
+    }
+}
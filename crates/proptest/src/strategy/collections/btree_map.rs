@@ -1,14 +1,18 @@
-use std::{
+use alloc::{
     collections::{BTreeMap, BTreeSet},
-    ops::RangeInclusive,
+    vec::Vec,
 };
-
-use super::vecs::{build_drop_plan, sample_length};
-use crate::strategy::{
-    SizeHint,
-    Strategy,
-    ValueTree,
-    runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS},
+use core::ops::RangeInclusive;
+
+use super::vecs::sample_length;
+use crate::{
+    shrink::{DedupShrinker, LengthChunk, LengthShrinker},
+    strategy::{
+        SizeHint,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS, RejectReason},
+    },
 };
 
 #[derive(Clone)]
@@ -51,18 +55,22 @@ where
     VT::Value: Clone,
 {
     entries: Vec<(KT, VT)>,
-    keys: Vec<KT::Value>,
+    keys: DedupShrinker<KT::Value, BTreeSet<KT::Value>>,
     values: Vec<VT::Value>,
     min_len: usize,
-    drop_plan: Vec<usize>,
+    length: LengthShrinker,
     stage: MapStage,
     history: Vec<MapHistory<KT, VT>>,
     current: BTreeMap<KT::Value, VT::Value>,
 }
 
+/// Shrink passes, in the order they run: drop chunks with a
+/// [`LengthShrinker`], drop single entries in key order, simplify keys while
+/// keeping them unique, then simplify values.
 #[derive(Clone, Copy)]
 enum MapStage {
-    Length { chunk_index: usize, offset: usize },
+    Length,
+    Remove { rank: usize },
     Keys { index: usize },
     Values { index: usize },
 }
@@ -73,12 +81,18 @@ where
     VT: ValueTree,
 {
     RemovedChunk {
-        index: usize,
-        chunk_index: usize,
+        chunk: LengthChunk,
         entries: Vec<(KT, VT)>,
         keys: Vec<KT::Value>,
         values: Vec<VT::Value>,
     },
+    RemovedEntry {
+        index: usize,
+        rank: usize,
+        entry: (KT, VT),
+        key: KT::Value,
+        value: VT::Value,
+    },
     Key {
         index: usize,
     },
@@ -100,23 +114,13 @@ where
         values: Vec<VT::Value>,
         min_len: usize,
     ) -> Self {
-        let drop_plan = build_drop_plan(entries.len());
-        let stage = if drop_plan.is_empty() {
-            MapStage::Keys { index: 0 }
-        } else {
-            MapStage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
-
         let mut tree = Self {
+            length: LengthShrinker::new(entries.len(), min_len),
             entries,
-            keys,
+            keys: DedupShrinker::new(keys),
             values,
             min_len,
-            drop_plan,
-            stage,
+            stage: MapStage::Length,
             history: Vec::new(),
             current: BTreeMap::new(),
         };
@@ -129,6 +133,21 @@ where
         self.entries.len()
     }
 
+    /// Move entry `index` to the key its tree currently holds.
+    fn refresh_key(&mut self, index: usize) {
+        let key = self.entries[index].0.current().clone();
+        let previous = self.keys.replace(index, key.clone());
+        self.current.remove(&previous);
+        self.current.insert(key, self.values[index].clone());
+    }
+
+    /// Pick up the value entry `index`'s tree currently holds.
+    fn refresh_value(&mut self, index: usize) {
+        let value = self.entries[index].1.current().clone();
+        self.values[index] = value.clone();
+        self.current.insert(self.keys[index].clone(), value);
+    }
+
     fn rebuild_current(&mut self) {
         self.current.clear();
         for (key, value) in
@@ -138,46 +157,11 @@ where
         }
     }
 
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            self.stage = MapStage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
-        }
-
-        self.stage = MapStage::Keys { index: 0 };
-        None
-    }
-
-    fn key_duplicate(&self, index: usize, candidate: &KT::Value) -> bool {
-        self.keys
-            .iter()
-            .enumerate()
-            .any(|(i, key)| i != index && key == candidate)
+    /// Index of the entry holding the `rank`-th smallest key.
+    fn index_by_rank(&self, rank: usize) -> Option<usize> {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by(|&a, &b| self.keys[a].cmp(&self.keys[b]));
+        order.get(rank).copied()
     }
 }
 
@@ -195,22 +179,30 @@ where
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let target_len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let target_len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut entries = Vec::with_capacity(target_len);
         let mut keys = Vec::with_capacity(target_len);
         let mut values = Vec::with_capacity(target_len);
         let mut seen = BTreeSet::new();
 
+        // Duplicates mean the element strategy is too small at the current
+        // size, so let it grow until enough distinct values fit.
+        let mut generator = generator.size_guard();
+
         let mut attempts_remaining = MAX_STRATEGY_ATTEMPTS * target_len.max(1);
 
         while entries.len() < target_len && attempts_remaining > 0 {
             attempts_remaining -= 1;
 
-            let key_tree = match self.key.new_tree(generator) {
+            let key_tree = match self.key.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => value,
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
                     let tree = BTreeMapValueTree::from_entries(
                         entries, keys, values, min_len,
@@ -218,6 +210,7 @@ where
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
@@ -225,13 +218,17 @@ where
 
             let candidate_key = key_tree.current().clone();
             if !seen.insert(candidate_key.clone()) {
+                generator.grow();
                 continue;
             }
 
-            let value_tree = match self.value.new_tree(generator) {
+            let value_tree = match self.value.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => value,
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
                     let tree = BTreeMapValueTree::from_entries(
                         entries, keys, values, min_len,
@@ -239,6 +236,7 @@ where
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
@@ -249,9 +247,14 @@ where
             entries.push((key_tree, value_tree));
         }
 
-        generator.accept(BTreeMapValueTree::from_entries(
-            entries, keys, values, min_len,
-        ))
+        let reached_min_len = entries.len() >= min_len;
+        let tree =
+            BTreeMapValueTree::from_entries(entries, keys, values, min_len);
+        if reached_min_len {
+            generator.accept(tree)
+        } else {
+            generator.reject_with(RejectReason::DuplicateKey, tree)
+        }
     }
 }
 
@@ -268,35 +271,57 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                MapStage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
-                    else {
+                MapStage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = MapStage::Remove { rank: 0 };
                         continue;
                     };
 
                     let entries: Vec<(KT, VT)> =
-                        self.entries.drain(off..off + chunk_size).collect();
-                    let keys: Vec<KT::Value> =
-                        self.keys.drain(off..off + chunk_size).collect();
+                        self.entries.drain(chunk.range()).collect();
+                    let keys: Vec<KT::Value> = self.keys.drain(chunk.range());
                     let values: Vec<VT::Value> =
-                        self.values.drain(off..off + chunk_size).collect();
-                    self.rebuild_current();
+                        self.values.drain(chunk.range()).collect();
+                    for key in &keys {
+                        self.current.remove(key);
+                    }
                     self.history.push(MapHistory::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
+                        chunk,
                         entries,
                         keys,
                         values,
                     });
                     return true;
                 }
+                MapStage::Remove { rank } => {
+                    let index = self
+                        .index_by_rank(rank)
+                        .filter(|_| self.len() > self.min_len);
+                    let Some(index) = index else {
+                        self.stage = MapStage::Keys { index: 0 };
+                        continue;
+                    };
+
+                    let entry = self.entries.remove(index);
+                    let key = self.keys.remove(index);
+                    let value = self.values.remove(index);
+                    self.current.remove(&key);
+                    self.history.push(MapHistory::RemovedEntry {
+                        index,
+                        rank,
+                        entry,
+                        key,
+                        value,
+                    });
+                    return true;
+                }
                 MapStage::Keys { index } => {
                     if index >= self.len() {
                         self.stage = MapStage::Values { index: 0 };
@@ -305,7 +330,7 @@ where
 
                     if self.entries[index].0.simplify() {
                         let candidate = self.entries[index].0.current().clone();
-                        if self.key_duplicate(index, &candidate) {
+                        if self.keys.is_duplicate(index, &candidate) {
                             if !self.entries[index].0.complicate() {
                                 self.stage =
                                     MapStage::Keys { index: index + 1 };
@@ -313,8 +338,7 @@ where
                             continue;
                         }
 
-                        self.keys[index] = candidate;
-                        self.rebuild_current();
+                        self.refresh_key(index);
                         self.history.push(MapHistory::Key { index });
                         return true;
                     } else {
@@ -327,9 +351,7 @@ where
                     }
 
                     if self.entries[index].1.simplify() {
-                        self.values[index] =
-                            self.entries[index].1.current().clone();
-                        self.rebuild_current();
+                        self.refresh_value(index);
                         self.history.push(MapHistory::Value { index });
                         return true;
                     } else {
@@ -347,33 +369,47 @@ where
 
         match entry {
             MapHistory::RemovedChunk {
-                index,
-                chunk_index,
+                chunk,
                 entries,
                 keys,
                 values,
             } => {
+                let index = chunk.range().start;
+                self.current
+                    .extend(keys.iter().cloned().zip(values.iter().cloned()));
                 self.entries.splice(index..index, entries);
-                self.keys.splice(index..index, keys);
+                self.keys.splice(index, keys);
                 self.values.splice(index..index, values);
-                self.rebuild_current();
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => {
-                        self.stage = MapStage::Keys { index: 0 };
-                        !self.entries.is_empty()
-                    }
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = MapStage::Length;
+                    true
+                } else {
+                    self.stage = MapStage::Remove { rank: 0 };
+                    !self.entries.is_empty()
                 }
             }
+            MapHistory::RemovedEntry {
+                index,
+                rank,
+                entry,
+                key,
+                value,
+            } => {
+                self.current.insert(key.clone(), value.clone());
+                self.entries.insert(index, entry);
+                self.keys.insert(index, key);
+                self.values.insert(index, value);
+                self.stage = MapStage::Remove { rank: rank + 1 };
+                true
+            }
             MapHistory::Key { index } => {
                 if self.entries[index].0.complicate() {
-                    self.keys[index] = self.entries[index].0.current().clone();
-                    self.rebuild_current();
+                    self.refresh_key(index);
                     self.history.push(MapHistory::Key { index });
                     true
                 } else {
-                    self.keys[index] = self.entries[index].0.current().clone();
-                    self.rebuild_current();
+                    self.refresh_key(index);
                     if index + 1 < self.len() {
                         self.stage = MapStage::Keys { index: index + 1 };
                         true
@@ -385,15 +421,11 @@ where
             }
             MapHistory::Value { index } => {
                 if self.entries[index].1.complicate() {
-                    self.values[index] =
-                        self.entries[index].1.current().clone();
-                    self.rebuild_current();
+                    self.refresh_value(index);
                     self.history.push(MapHistory::Value { index });
                     true
                 } else {
-                    self.values[index] =
-                        self.entries[index].1.current().clone();
-                    self.rebuild_current();
+                    self.refresh_value(index);
                     if index + 1 < self.len() {
                         self.stage = MapStage::Values { index: index + 1 };
                         true
@@ -449,6 +481,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn btree_map_removes_single_entries_in_key_order() {
+        let entries = [7, 3, 9]
+            .into_iter()
+            .map(|key| (make_tree(key, 0), make_tree(1, 0)))
+            .collect::<Vec<_>>();
+        let keys = vec![7, 3, 9];
+        let values = vec![1; 3];
+        let mut tree =
+            BTreeMapValueTree::from_entries(entries, keys, values, 0);
+        let sorted_keys = |tree: &BTreeMapValueTree<_, _>| {
+            let mut keys = tree.current().keys().copied().collect::<Vec<_>>();
+            keys.sort_unstable();
+            keys
+        };
+
+        // Exhaust the chunk pass, which drops one entry at each position.
+        for _ in 0..3 {
+            assert!(tree.simplify());
+            assert!(tree.complicate());
+        }
+        assert_eq!(tree.current().len(), 3);
+
+        assert!(tree.simplify());
+        assert_eq!(sorted_keys(&tree), vec![7, 9]);
+        assert!(tree.complicate());
+        assert!(tree.simplify());
+        assert_eq!(sorted_keys(&tree), vec![3, 9]);
+    }
+
+    #[test]
+    fn btree_map_current_tracks_every_shrink_step() {
+        let mut strategy = BTreeMapStrategy::new(
+            AnyI32::default(),
+            AnyI32::default(),
+            8usize..=16,
+        );
+        let mut tree = strategy
+            .new_tree(&mut Generator::build(crate::rng()))
+            .take();
+
+        for step in 0..256 {
+            if step % 3 == 2 {
+                tree.complicate();
+            } else {
+                tree.simplify();
+            }
+            let rebuilt = tree
+                .keys
+                .iter()
+                .cloned()
+                .zip(tree.values.iter().cloned())
+                .collect::<BTreeMap<_, _>>();
+            assert_eq!(tree.current(), &rebuilt);
+        }
+    }
+
     #[test]
     fn btree_map_strategy_honours_range() {
         let mut strategy = BTreeMapStrategy::new(
@@ -1,11 +1,15 @@
-use std::{collections::BTreeSet, ops::RangeInclusive};
-
-use super::vecs::{build_drop_plan, sample_length};
-use crate::strategy::{
-    SizeHint,
-    Strategy,
-    ValueTree,
-    runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS},
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::ops::RangeInclusive;
+
+use super::vecs::sample_length;
+use crate::{
+    shrink::{DedupShrinker, LengthChunk, LengthShrinker},
+    strategy::{
+        SizeHint,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS, RejectReason},
+    },
 };
 
 #[derive(Clone)]
@@ -40,24 +44,21 @@ where
     T::Value: Clone + Ord,
 {
     elements: Vec<T>,
-    raw_values: Vec<T::Value>,
-    min_len: usize,
-    drop_plan: Vec<usize>,
+    values: DedupShrinker<T::Value, BTreeSet<T::Value>>,
+    length: LengthShrinker,
     stage: Stage,
     history: Vec<History<T, T::Value>>,
-    current: BTreeSet<T::Value>,
 }
 
 #[derive(Clone, Copy)]
 enum Stage {
-    Length { chunk_index: usize, offset: usize },
+    Length,
     Elements { index: usize },
 }
 
 enum History<T, V> {
     RemovedChunk {
-        index: usize,
-        chunk_index: usize,
+        chunk: LengthChunk,
         trees: Vec<T>,
         values: Vec<V>,
     },
@@ -78,27 +79,37 @@ where
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let target_len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let target_len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut elements = Vec::with_capacity(target_len);
         let mut values = Vec::with_capacity(target_len);
         let mut seen = BTreeSet::new();
 
+        // Duplicates mean the element strategy is too small at the current
+        // size, so let it grow until enough distinct values fit.
+        let mut generator = generator.size_guard();
+
         let mut attempts_remaining = MAX_STRATEGY_ATTEMPTS * target_len.max(1);
 
         while elements.len() < target_len && attempts_remaining > 0 {
             attempts_remaining -= 1;
 
-            match self.element.new_tree(generator) {
+            match self.element.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => {
                     let candidate = value.current().clone();
                     if seen.insert(candidate.clone()) {
                         elements.push(value);
                         values.push(candidate);
+                    } else {
+                        generator.grow();
                     }
                 }
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
                     let tree = BTreeSetValueTree::from_elements(
                         elements, values, min_len,
@@ -106,14 +117,20 @@ where
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
             }
         }
 
-        generator
-            .accept(BTreeSetValueTree::from_elements(elements, values, min_len))
+        let reached_min_len = elements.len() >= min_len;
+        let tree = BTreeSetValueTree::from_elements(elements, values, min_len);
+        if reached_min_len {
+            generator.accept(tree)
+        } else {
+            generator.reject_with(RejectReason::DuplicateKey, tree)
+        }
     }
 }
 
@@ -127,81 +144,28 @@ where
         raw_values: Vec<T::Value>,
         min_len: usize,
     ) -> Self {
-        let drop_plan = build_drop_plan(elements.len());
-        let stage = if drop_plan.is_empty() {
-            Stage::Elements { index: 0 }
-        } else {
-            Stage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
-
-        let mut tree = Self {
+        Self {
+            length: LengthShrinker::new(elements.len(), min_len),
             elements,
-            raw_values,
-            min_len,
-            drop_plan,
-            stage,
+            values: DedupShrinker::new(raw_values),
+            stage: Stage::Length,
             history: Vec::new(),
-            current: BTreeSet::new(),
-        };
-
-        tree.rebuild_current();
-        tree
+        }
     }
 
     fn len(&self) -> usize {
         self.elements.len()
     }
 
-    fn rebuild_current(&mut self) {
-        self.current.clear();
-        for value in &self.raw_values {
-            self.current.insert(value.clone());
-        }
+    /// The elements of the set in the order they were generated.
+    pub(crate) fn values(&self) -> &[T::Value] {
+        &self.values
     }
 
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            self.stage = Stage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
-        }
-
-        self.stage = Stage::Elements { index: 0 };
-        None
-    }
-
-    fn element_duplicate(&self, index: usize, candidate: &T::Value) -> bool {
-        self.raw_values
-            .iter()
-            .enumerate()
-            .any(|(i, value)| i != index && value == candidate)
+    /// Replace element `index` with the value its tree currently holds.
+    fn refresh_element(&mut self, index: usize) {
+        let value = self.elements[index].current().clone();
+        self.values.replace(index, value);
     }
 }
 
@@ -213,30 +177,27 @@ where
     type Value = BTreeSet<T::Value>;
 
     fn current(&self) -> &Self::Value {
-        &self.current
+        self.values.index()
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.values.into_index()
     }
 
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                Stage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
-                    else {
+                Stage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = Stage::Elements { index: 0 };
                         continue;
                     };
 
                     let trees: Vec<T> =
-                        self.elements.drain(off..off + chunk_size).collect();
-                    let values: Vec<T::Value> =
-                        self.raw_values.drain(off..off + chunk_size).collect();
-                    self.rebuild_current();
+                        self.elements.drain(chunk.range()).collect();
+                    let values = self.values.drain(chunk.range());
                     self.history.push(History::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
+                        chunk,
                         trees,
                         values,
                     });
@@ -249,7 +210,7 @@ where
 
                     if self.elements[index].simplify() {
                         let candidate = self.elements[index].current().clone();
-                        if self.element_duplicate(index, &candidate) {
+                        if self.values.is_duplicate(index, &candidate) {
                             if !self.elements[index].complicate() {
                                 self.stage =
                                     Stage::Elements { index: index + 1 };
@@ -257,8 +218,7 @@ where
                             continue;
                         }
 
-                        self.raw_values[index] = candidate;
-                        self.rebuild_current();
+                        self.refresh_element(index);
                         self.history.push(History::Element { index });
                         return true;
                     } else {
@@ -276,30 +236,29 @@ where
 
         match entry {
             History::RemovedChunk {
-                index,
-                chunk_index,
+                chunk,
                 trees,
                 values,
             } => {
+                let index = chunk.range().start;
                 self.elements.splice(index..index, trees);
-                self.raw_values.splice(index..index, values);
-                self.rebuild_current();
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => !self.elements.is_empty(),
+                self.values.splice(index, values);
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = Stage::Length;
+                    true
+                } else {
+                    self.stage = Stage::Elements { index: 0 };
+                    !self.elements.is_empty()
                 }
             }
             History::Element { index } => {
                 if self.elements[index].complicate() {
-                    self.raw_values[index] =
-                        self.elements[index].current().clone();
-                    self.rebuild_current();
+                    self.refresh_element(index);
                     self.history.push(History::Element { index });
                     true
                 } else {
-                    self.raw_values[index] =
-                        self.elements[index].current().clone();
-                    self.rebuild_current();
+                    self.refresh_element(index);
                     if index + 1 < self.len() {
                         self.stage = Stage::Elements { index: index + 1 };
                         true
@@ -317,7 +276,7 @@ mod tests {
     use super::*;
     use crate::strategy::{
         ValueTree,
-        primitives::{AnyI32, IntValueTree},
+        primitives::{AnyBool, AnyI32, IntValueTree},
         runtime::Generator,
     };
 
@@ -352,4 +311,17 @@ mod tests {
         };
         assert!((1..=3).contains(&len));
     }
+
+    #[test]
+    fn btree_set_strategy_rejects_when_keys_run_out() {
+        let mut strategy = BTreeSetStrategy::new(AnyBool, 3usize..=3usize);
+        let mut generator = Generator::build(crate::rng());
+        match strategy.new_tree(&mut generator) {
+            Generation::Rejected { reason, value, .. } => {
+                assert_eq!(reason, Some(RejectReason::DuplicateKey));
+                assert_eq!(value.current().len(), 2);
+            }
+            Generation::Accepted { .. } => panic!("three distinct bools"),
+        }
+    }
 }
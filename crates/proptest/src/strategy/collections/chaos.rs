@@ -0,0 +1,109 @@
+use std::{
+    cell::Cell,
+    hash::{BuildHasher, DefaultHasher, Hasher},
+};
+
+thread_local! {
+    static ORDER_SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Set the ordering seed [`ChaosState::default`] uses on this thread,
+/// returning the one it replaces. The runner sets it for every case of a
+/// run with [`Config::with_chaos_order`](crate::runner::Config::with_chaos_order).
+pub(crate) fn set_order_seed(seed: Option<u64>) -> Option<u64> {
+    ORDER_SEED.with(|current| current.replace(seed))
+}
+
+/// A hasher for `HashMap`s and `HashSet`s whose iteration order is decided
+/// by a seed, to flush out code that depends on the order of a hash
+/// collection.
+///
+/// `RandomState` changes the order from one map to the next, so a bug that
+/// depends on it fails now and then and does not reproduce. Maps hashing
+/// with `ChaosState` iterate in an order picked by the ordering seed
+/// instead: the case's own one in a run with `chaos_order`, so every case
+/// tries another order and a failure names the seed, and a fixed one
+/// everywhere else. Generate them by naming the hasher in the argument's
+/// type, `HashMap<K, V, ChaosState>`, or with
+/// [`HashMapStrategy::with_hasher`](super::HashMapStrategy::with_hasher).
+///
+/// Rebuilding a map with the seed of a failure brings back its order:
+///
+/// ```
+/// use std::collections::HashSet;
+///
+/// use estoa_proptest::strategy::ChaosState;
+///
+/// let order = |seed| {
+///     let mut set = HashSet::with_hasher(ChaosState::new(seed));
+///     set.extend(0..16);
+///     set.into_iter().collect::<Vec<_>>()
+/// };
+/// assert_eq!(order(0x2a), order(0x2a));
+/// assert_ne!(order(0x2a), order(0x2b));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChaosState {
+    seed: u64,
+}
+
+impl ChaosState {
+    /// Iterate in the order of ordering seed `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// The ordering seed of the case running on this thread, or 0 outside a
+/// run with `chaos_order`.
+impl Default for ChaosState {
+    fn default() -> Self {
+        Self::new(ORDER_SEED.with(Cell::get).unwrap_or(0))
+    }
+}
+
+impl BuildHasher for ChaosState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.seed);
+        hasher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::strategy::{AnyU8, HashMapStrategy, Strategy, ValueTree};
+
+    #[test]
+    fn generated_maps_hash_with_the_order_seed_of_the_case() {
+        let mut strategy = HashMapStrategy::new(
+            AnyU8::default(),
+            AnyU8::default(),
+            8usize..=8usize,
+        )
+        .with_hasher::<ChaosState>();
+        let mut generate = || {
+            let rng = crate::rng();
+            let map: HashMap<u8, u8, ChaosState> = strategy
+                .new_tree(&mut crate::Generator::build(rng))
+                .take()
+                .into_current();
+            map.hasher().seed()
+        };
+
+        assert_eq!(generate(), 0);
+        let previous = set_order_seed(Some(7));
+        assert_eq!(generate(), 7);
+        set_order_seed(previous);
+        assert_eq!(generate(), 0);
+    }
+}
@@ -1,19 +1,24 @@
 use std::{
     collections::{HashMap, HashSet},
-    hash::Hash,
+    hash::{BuildHasher, Hash, RandomState},
+    marker::PhantomData,
     ops::RangeInclusive,
 };
 
-use super::vecs::{build_drop_plan, sample_length};
-use crate::strategy::{
-    SizeHint,
-    Strategy,
-    ValueTree,
-    runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS},
+use super::vecs::sample_length;
+use crate::{
+    shrink::{DedupShrinker, LengthChunk, LengthShrinker},
+    strategy::{
+        SizeHint,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS, RejectReason},
+    },
 };
 
+/// Strategy for `HashMap`s whose hasher is built with `S::default()`.
 #[derive(Clone)]
-pub struct HashMapStrategy<KS, VS>
+pub struct HashMapStrategy<KS, VS, S = RandomState>
 where
     KS: Strategy,
     VS: Strategy,
@@ -23,6 +28,7 @@ where
     key: KS,
     value: VS,
     len_range: RangeInclusive<usize>,
+    hasher: PhantomData<fn() -> S>,
 }
 
 impl<KS, VS> HashMapStrategy<KS, VS>
@@ -40,11 +46,34 @@ where
             key,
             value,
             len_range: size_hint.to_inclusive(),
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<KS, VS, S> HashMapStrategy<KS, VS, S>
+where
+    KS: Strategy,
+    VS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    VS::Value: Clone,
+{
+    /// Generate maps hashing with `T` instead, e.g.
+    /// `.with_hasher::<FxBuildHasher>()`.
+    pub fn with_hasher<T>(self) -> HashMapStrategy<KS, VS, T>
+    where
+        T: BuildHasher + Default,
+    {
+        HashMapStrategy {
+            key: self.key,
+            value: self.value,
+            len_range: self.len_range,
+            hasher: PhantomData,
         }
     }
 }
 
-pub struct HashMapValueTree<KT, VT>
+pub struct HashMapValueTree<KT, VT, S = RandomState>
 where
     KT: ValueTree,
     KT::Value: Clone + Eq + Hash,
@@ -52,18 +81,22 @@ where
     VT::Value: Clone,
 {
     entries: Vec<(KT, VT)>,
-    keys: Vec<KT::Value>,
+    keys: DedupShrinker<KT::Value, HashSet<KT::Value>>,
     values: Vec<VT::Value>,
     min_len: usize,
-    drop_plan: Vec<usize>,
+    length: LengthShrinker,
     stage: MapStage,
     history: Vec<MapHistory<KT, VT>>,
-    current: HashMap<KT::Value, VT::Value>,
+    current: HashMap<KT::Value, VT::Value, S>,
 }
 
+/// Shrink passes, in the order they run: drop chunks with a
+/// [`LengthShrinker`], drop single entries in generation order, simplify keys while
+/// keeping them unique, then simplify values.
 #[derive(Clone, Copy)]
 enum MapStage {
-    Length { chunk_index: usize, offset: usize },
+    Length,
+    Remove { rank: usize },
     Keys { index: usize },
     Values { index: usize },
 }
@@ -74,12 +107,18 @@ where
     VT: ValueTree,
 {
     RemovedChunk {
-        index: usize,
-        chunk_index: usize,
+        chunk: LengthChunk,
         entries: Vec<(KT, VT)>,
         keys: Vec<KT::Value>,
         values: Vec<VT::Value>,
     },
+    RemovedEntry {
+        index: usize,
+        rank: usize,
+        entry: (KT, VT),
+        key: KT::Value,
+        value: VT::Value,
+    },
     Key {
         index: usize,
     },
@@ -101,25 +140,33 @@ where
         values: Vec<VT::Value>,
         min_len: usize,
     ) -> Self {
-        let drop_plan = build_drop_plan(entries.len());
-        let stage = if drop_plan.is_empty() {
-            MapStage::Keys { index: 0 }
-        } else {
-            MapStage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
+        Self::build(entries, keys, values, min_len)
+    }
+}
 
+impl<KT, VT, S> HashMapValueTree<KT, VT, S>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    VT: ValueTree,
+    VT::Value: Clone,
+    S: BuildHasher + Default,
+{
+    fn build(
+        entries: Vec<(KT, VT)>,
+        keys: Vec<KT::Value>,
+        values: Vec<VT::Value>,
+        min_len: usize,
+    ) -> Self {
         let mut tree = Self {
+            length: LengthShrinker::new(entries.len(), min_len),
             entries,
-            keys,
+            keys: DedupShrinker::new(keys),
             values,
             min_len,
-            drop_plan,
-            stage,
+            stage: MapStage::Length,
             history: Vec::new(),
-            current: HashMap::new(),
+            current: HashMap::default(),
         };
 
         tree.rebuild_current();
@@ -130,6 +177,29 @@ where
         self.entries.len()
     }
 
+    /// Current keys and values in generation order.
+    #[cfg(feature = "indexmap")]
+    pub(crate) fn ordered_entries(
+        &self,
+    ) -> impl Iterator<Item = (&KT::Value, &VT::Value)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    /// Move entry `index` to the key its tree currently holds.
+    fn refresh_key(&mut self, index: usize) {
+        let key = self.entries[index].0.current().clone();
+        let previous = self.keys.replace(index, key.clone());
+        self.current.remove(&previous);
+        self.current.insert(key, self.values[index].clone());
+    }
+
+    /// Pick up the value entry `index`'s tree currently holds.
+    fn refresh_value(&mut self, index: usize) {
+        let value = self.entries[index].1.current().clone();
+        self.values[index] = value.clone();
+        self.current.insert(self.keys[index].clone(), value);
+    }
+
     fn rebuild_current(&mut self) {
         self.current.clear();
         for (key, value) in
@@ -139,86 +209,59 @@ where
         }
     }
 
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            self.stage = MapStage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
-        }
-
-        self.stage = MapStage::Keys { index: 0 };
-        None
-    }
-
-    fn key_duplicate(&self, index: usize, candidate: &KT::Value) -> bool {
-        self.keys
-            .iter()
-            .enumerate()
-            .any(|(i, key)| i != index && key == candidate)
+    /// Index of the `rank`-th entry. Keys are only `Hash + Eq`, so entries
+    /// are ranked in the order they were generated.
+    fn index_by_rank(&self, rank: usize) -> Option<usize> {
+        (rank < self.len()).then_some(rank)
     }
 }
 
-impl<KS, VS> Strategy for HashMapStrategy<KS, VS>
+impl<KS, VS, S> Strategy for HashMapStrategy<KS, VS, S>
 where
     KS: Strategy,
     VS: Strategy,
     KS::Value: Clone + Eq + Hash,
     VS::Value: Clone,
+    S: BuildHasher + Default,
 {
-    type Value = HashMap<KS::Value, VS::Value>;
-    type Tree = HashMapValueTree<KS::Tree, VS::Tree>;
+    type Value = HashMap<KS::Value, VS::Value, S>;
+    type Tree = HashMapValueTree<KS::Tree, VS::Tree, S>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let target_len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let target_len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut entries = Vec::with_capacity(target_len);
         let mut keys = Vec::with_capacity(target_len);
         let mut values = Vec::with_capacity(target_len);
         let mut seen = HashSet::with_capacity(target_len);
 
+        // Duplicates mean the element strategy is too small at the current
+        // size, so let it grow until enough distinct values fit.
+        let mut generator = generator.size_guard();
+
         let mut attempts_remaining = MAX_STRATEGY_ATTEMPTS * target_len.max(1);
 
         while entries.len() < target_len && attempts_remaining > 0 {
             attempts_remaining -= 1;
 
-            let key_tree = match self.key.new_tree(generator) {
+            let key_tree = match self.key.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => value,
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
-                    let tree = HashMapValueTree::from_entries(
-                        entries, keys, values, min_len,
-                    );
+                    let tree =
+                        HashMapValueTree::build(entries, keys, values, min_len);
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
@@ -226,20 +269,24 @@ where
 
             let candidate_key = key_tree.current().clone();
             if !seen.insert(candidate_key.clone()) {
+                generator.grow();
                 continue;
             }
 
-            let value_tree = match self.value.new_tree(generator) {
+            let value_tree = match self.value.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => value,
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
-                    let tree = HashMapValueTree::from_entries(
-                        entries, keys, values, min_len,
-                    );
+                    let tree =
+                        HashMapValueTree::build(entries, keys, values, min_len);
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
@@ -250,54 +297,81 @@ where
             entries.push((key_tree, value_tree));
         }
 
-        generator.accept(HashMapValueTree::from_entries(
-            entries, keys, values, min_len,
-        ))
+        let reached_min_len = entries.len() >= min_len;
+        let tree = HashMapValueTree::build(entries, keys, values, min_len);
+        if reached_min_len {
+            generator.accept(tree)
+        } else {
+            generator.reject_with(RejectReason::DuplicateKey, tree)
+        }
     }
 }
 
-impl<KT, VT> ValueTree for HashMapValueTree<KT, VT>
+impl<KT, VT, S> ValueTree for HashMapValueTree<KT, VT, S>
 where
     KT: ValueTree,
     KT::Value: Clone + Eq + Hash,
     VT: ValueTree,
     VT::Value: Clone,
+    S: BuildHasher + Default,
 {
-    type Value = HashMap<KT::Value, VT::Value>;
+    type Value = HashMap<KT::Value, VT::Value, S>;
 
     fn current(&self) -> &Self::Value {
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                MapStage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
-                    else {
+                MapStage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = MapStage::Remove { rank: 0 };
                         continue;
                     };
 
                     let entries: Vec<(KT, VT)> =
-                        self.entries.drain(off..off + chunk_size).collect();
-                    let keys: Vec<KT::Value> =
-                        self.keys.drain(off..off + chunk_size).collect();
+                        self.entries.drain(chunk.range()).collect();
+                    let keys: Vec<KT::Value> = self.keys.drain(chunk.range());
                     let values: Vec<VT::Value> =
-                        self.values.drain(off..off + chunk_size).collect();
-                    self.rebuild_current();
+                        self.values.drain(chunk.range()).collect();
+                    for key in &keys {
+                        self.current.remove(key);
+                    }
                     self.history.push(MapHistory::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
+                        chunk,
                         entries,
                         keys,
                         values,
                     });
                     return true;
                 }
+                MapStage::Remove { rank } => {
+                    let index = self
+                        .index_by_rank(rank)
+                        .filter(|_| self.len() > self.min_len);
+                    let Some(index) = index else {
+                        self.stage = MapStage::Keys { index: 0 };
+                        continue;
+                    };
+
+                    let entry = self.entries.remove(index);
+                    let key = self.keys.remove(index);
+                    let value = self.values.remove(index);
+                    self.current.remove(&key);
+                    self.history.push(MapHistory::RemovedEntry {
+                        index,
+                        rank,
+                        entry,
+                        key,
+                        value,
+                    });
+                    return true;
+                }
                 MapStage::Keys { index } => {
                     if index >= self.len() {
                         self.stage = MapStage::Values { index: 0 };
@@ -306,7 +380,7 @@ where
 
                     if self.entries[index].0.simplify() {
                         let candidate = self.entries[index].0.current().clone();
-                        if self.key_duplicate(index, &candidate) {
+                        if self.keys.is_duplicate(index, &candidate) {
                             if !self.entries[index].0.complicate() {
                                 self.stage =
                                     MapStage::Keys { index: index + 1 };
@@ -314,8 +388,7 @@ where
                             continue;
                         }
 
-                        self.keys[index] = candidate;
-                        self.rebuild_current();
+                        self.refresh_key(index);
                         self.history.push(MapHistory::Key { index });
                         return true;
                     } else {
@@ -328,9 +401,7 @@ where
                     }
 
                     if self.entries[index].1.simplify() {
-                        self.values[index] =
-                            self.entries[index].1.current().clone();
-                        self.rebuild_current();
+                        self.refresh_value(index);
                         self.history.push(MapHistory::Value { index });
                         return true;
                     } else {
@@ -348,33 +419,47 @@ where
 
         match entry {
             MapHistory::RemovedChunk {
-                index,
-                chunk_index,
+                chunk,
                 entries,
                 keys,
                 values,
             } => {
+                let index = chunk.range().start;
+                self.current
+                    .extend(keys.iter().cloned().zip(values.iter().cloned()));
                 self.entries.splice(index..index, entries);
-                self.keys.splice(index..index, keys);
+                self.keys.splice(index, keys);
                 self.values.splice(index..index, values);
-                self.rebuild_current();
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => {
-                        self.stage = MapStage::Keys { index: 0 };
-                        !self.entries.is_empty()
-                    }
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = MapStage::Length;
+                    true
+                } else {
+                    self.stage = MapStage::Remove { rank: 0 };
+                    !self.entries.is_empty()
                 }
             }
+            MapHistory::RemovedEntry {
+                index,
+                rank,
+                entry,
+                key,
+                value,
+            } => {
+                self.current.insert(key.clone(), value.clone());
+                self.entries.insert(index, entry);
+                self.keys.insert(index, key);
+                self.values.insert(index, value);
+                self.stage = MapStage::Remove { rank: rank + 1 };
+                true
+            }
             MapHistory::Key { index } => {
                 if self.entries[index].0.complicate() {
-                    self.keys[index] = self.entries[index].0.current().clone();
-                    self.rebuild_current();
+                    self.refresh_key(index);
                     self.history.push(MapHistory::Key { index });
                     true
                 } else {
-                    self.keys[index] = self.entries[index].0.current().clone();
-                    self.rebuild_current();
+                    self.refresh_key(index);
                     if index + 1 < self.len() {
                         self.stage = MapStage::Keys { index: index + 1 };
                         true
@@ -386,15 +471,11 @@ where
             }
             MapHistory::Value { index } => {
                 if self.entries[index].1.complicate() {
-                    self.values[index] =
-                        self.entries[index].1.current().clone();
-                    self.rebuild_current();
+                    self.refresh_value(index);
                     self.history.push(MapHistory::Value { index });
                     true
                 } else {
-                    self.values[index] =
-                        self.entries[index].1.current().clone();
-                    self.rebuild_current();
+                    self.refresh_value(index);
                     if index + 1 < self.len() {
                         self.stage = MapStage::Values { index: index + 1 };
                         true
@@ -409,6 +490,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::hash::{BuildHasherDefault, DefaultHasher};
+
     use super::*;
     use crate::strategy::{
         ValueTree,
@@ -447,6 +530,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hash_map_removes_single_entries_in_generation_order() {
+        let entries = [7, 3, 9]
+            .into_iter()
+            .map(|key| (make_tree(key, 0), make_tree(1, 0)))
+            .collect::<Vec<_>>();
+        let keys = vec![7, 3, 9];
+        let values = vec![1; 3];
+        let mut tree = HashMapValueTree::from_entries(entries, keys, values, 0);
+        let sorted_keys = |tree: &HashMapValueTree<_, _>| {
+            let mut keys = tree.current().keys().copied().collect::<Vec<_>>();
+            keys.sort_unstable();
+            keys
+        };
+
+        // Exhaust the chunk pass, which drops one entry at each position.
+        for _ in 0..3 {
+            assert!(tree.simplify());
+            assert!(tree.complicate());
+        }
+        assert_eq!(tree.current().len(), 3);
+
+        assert!(tree.simplify());
+        assert_eq!(sorted_keys(&tree), vec![3, 9]);
+        assert!(tree.complicate());
+        assert!(tree.simplify());
+        assert_eq!(sorted_keys(&tree), vec![7, 9]);
+    }
+
     #[test]
     fn hash_map_strategy_honours_range() {
         let mut strategy = HashMapStrategy::new(
@@ -461,4 +573,23 @@ mod tests {
         };
         assert!((1..=3).contains(&len));
     }
+
+    #[test]
+    fn hash_map_strategy_uses_the_requested_hasher() {
+        type Hasher = BuildHasherDefault<DefaultHasher>;
+
+        let mut strategy = HashMapStrategy::new(
+            AnyI32::default(),
+            AnyI32::default(),
+            4usize..=8usize,
+        )
+        .with_hasher::<Hasher>();
+        let mut generator = Generator::build(crate::rng());
+        let mut tree = strategy.new_tree(&mut generator).take();
+        while tree.simplify() {}
+
+        let map: HashMap<i32, i32, Hasher> = tree.into_current();
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec![0; 4]);
+    }
 }
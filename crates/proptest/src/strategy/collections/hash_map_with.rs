@@ -0,0 +1,372 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use super::hash_set::{HashSetStrategy, HashSetValueTree};
+use crate::strategy::{
+    SizeHint,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator, RejectReason},
+};
+
+/// Generate maps whose values are drawn from a strategy built from their
+/// key, so invariants such as "the value is as long as the key" hold for
+/// every entry.
+///
+/// Keys shrink like a [`HashSetStrategy`], and every key a shrink step
+/// produces gets its value regenerated from `value(&key)`. Values shrink
+/// once the keys cannot.
+pub fn hash_map_with<KS, F, VS, H>(
+    key: KS,
+    value: F,
+    size_hint: H,
+) -> HashMapWithStrategy<KS, F>
+where
+    KS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    F: Fn(&KS::Value) -> VS + Clone,
+    VS: Strategy,
+    VS::Value: Clone,
+    H: SizeHint,
+{
+    HashMapWithStrategy {
+        keys: HashSetStrategy::new(key, size_hint),
+        value,
+    }
+}
+
+#[derive(Clone)]
+pub struct HashMapWithStrategy<KS, F>
+where
+    KS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+{
+    keys: HashSetStrategy<KS>,
+    value: F,
+}
+
+impl<KS, F, VS> Strategy for HashMapWithStrategy<KS, F>
+where
+    KS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    F: Fn(&KS::Value) -> VS + Clone,
+    VS: Strategy,
+    VS::Value: Clone,
+{
+    type Value = HashMap<KS::Value, VS::Value>;
+    type Tree = HashMapWithValueTree<KS::Tree, F, VS>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let (keys, mut rejected) = match self.keys.new_tree(generator) {
+            Generation::Accepted { value, .. } => (value, None),
+            Generation::Rejected { reason, value, .. } => (value, Some(reason)),
+        };
+
+        let mut tree = HashMapWithValueTree {
+            keys,
+            value: self.value.clone(),
+            seed: generator.rng.random(),
+            size: generator.size(),
+            values: HashMap::new(),
+            stage: Stage::Keys,
+            history: Vec::new(),
+            current: HashMap::new(),
+        };
+        if let Some(reason) = tree.sync_values() {
+            rejected = rejected.or(Some(reason));
+        }
+
+        match rejected {
+            Some(Some(reason)) => generator.reject_with(reason, tree),
+            Some(None) => generator.reject(tree),
+            None => generator.accept(tree),
+        }
+    }
+}
+
+/// Shrink passes, in the order they run: shrink the keys, then the value of
+/// each entry in generation order.
+#[derive(Clone, Copy)]
+enum Stage {
+    Keys,
+    Values { index: usize },
+}
+
+#[derive(Clone, Copy)]
+enum History {
+    Keys,
+    Value { index: usize },
+}
+
+pub struct HashMapWithValueTree<KT, F, VS>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    VS: Strategy,
+{
+    keys: HashSetValueTree<KT>,
+    value: F,
+    /// Seed every value is generated from, together with its key.
+    seed: u64,
+    size: usize,
+    /// The value tree of every current key.
+    values: HashMap<KT::Value, VS::Tree>,
+    stage: Stage,
+    history: Vec<History>,
+    current: HashMap<KT::Value, VS::Value>,
+}
+
+impl<KT, F, VS> HashMapWithValueTree<KT, F, VS>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    F: Fn(&KT::Value) -> VS,
+    VS: Strategy,
+    VS::Value: Clone,
+{
+    /// Generate the value tree of `key`, from a seed derived from the key so
+    /// the same key always gets the same value.
+    fn value_tree(&self, key: &KT::Value) -> Generation<VS::Tree> {
+        let mut hasher = DefaultHasher::new();
+        self.seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        let rng = StdRng::seed_from_u64(hasher.finish());
+        let mut generator = Generator::build(rng).with_size(self.size);
+        (self.value)(key).new_tree(&mut generator)
+    }
+
+    /// Give every current key a value tree, dropping those of keys shrinking
+    /// removed, and rebuild the current map. Returns the reason of the first
+    /// value generation that was rejected, if any.
+    fn sync_values(&mut self) -> Option<Option<RejectReason>> {
+        let mut rejected = None;
+        let current_keys = self.keys.current();
+        self.values.retain(|key, _| current_keys.contains(key));
+        for key in self.keys.ordered_values() {
+            if self.values.contains_key(key) {
+                continue;
+            }
+            let tree = match self.value_tree(key) {
+                Generation::Accepted { value, .. } => value,
+                Generation::Rejected { reason, value, .. } => {
+                    rejected = rejected.or(Some(reason));
+                    value
+                }
+            };
+            self.values.insert(key.clone(), tree);
+        }
+
+        self.current = self
+            .values
+            .iter()
+            .map(|(key, tree)| (key.clone(), tree.current().clone()))
+            .collect();
+        rejected
+    }
+
+    fn refresh_value(&mut self, key: &KT::Value) {
+        let value = self.values[key].current().clone();
+        self.current.insert(key.clone(), value);
+    }
+
+    fn key_at(&self, index: usize) -> Option<KT::Value> {
+        self.keys.ordered_values().get(index).cloned()
+    }
+}
+
+impl<KT, F, VS> ValueTree for HashMapWithValueTree<KT, F, VS>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    F: Fn(&KT::Value) -> VS,
+    VS: Strategy,
+    VS::Value: Clone,
+{
+    type Value = HashMap<KT::Value, VS::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        loop {
+            match self.stage {
+                Stage::Keys => {
+                    if !self.keys.simplify() {
+                        self.stage = Stage::Values { index: 0 };
+                        continue;
+                    }
+                    if self.sync_values().is_none() {
+                        self.history.push(History::Keys);
+                        return true;
+                    }
+                    // A new key's value was rejected: undo the step and try
+                    // the next one.
+                    if !self.keys.complicate() {
+                        self.stage = Stage::Values { index: 0 };
+                    }
+                    self.sync_values();
+                }
+                Stage::Values { index } => {
+                    let Some(key) = self.key_at(index) else {
+                        return false;
+                    };
+                    let simplified = self
+                        .values
+                        .get_mut(&key)
+                        .is_some_and(ValueTree::simplify);
+                    if !simplified {
+                        self.stage = Stage::Values { index: index + 1 };
+                        continue;
+                    }
+                    self.refresh_value(&key);
+                    self.history.push(History::Value { index });
+                    return true;
+                }
+            }
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+
+        match entry {
+            History::Keys => {
+                let mut more = self.keys.complicate();
+                // Keep moving back while a new key's value is rejected.
+                while self.sync_values().is_some() && more {
+                    more = self.keys.complicate();
+                }
+                // The values still shrink once the keys are done.
+                more || !self.current.is_empty()
+            }
+            History::Value { index } => {
+                let Some(key) = self.key_at(index) else {
+                    return false;
+                };
+                let more = self
+                    .values
+                    .get_mut(&key)
+                    .is_some_and(ValueTree::complicate);
+                self.refresh_value(&key);
+                if more {
+                    self.history.push(History::Value { index });
+                    true
+                } else if index + 1 < self.keys.ordered_values().len() {
+                    self.stage = Stage::Values { index: index + 1 };
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        strategy::{AnyString, AnyU8, VecStrategy, runtime::from_fn},
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    fn lengths_match_keys()
+    -> HashMapWithStrategy<AnyU8, impl Fn(&u8) -> VecStrategy<AnyU8> + Clone>
+    {
+        hash_map_with(
+            AnyU8::new(0..=16),
+            |key: &u8| {
+                let len = usize::from(*key);
+                VecStrategy::new(AnyU8::default(), len..=len)
+            },
+            0usize..=8usize,
+        )
+    }
+
+    #[test]
+    fn values_follow_their_keys_while_shrinking() {
+        check_shrink_invariants_with(lengths_match_keys(), 64, |map| {
+            map.iter()
+                .all(|(key, value)| value.len() == usize::from(*key))
+        });
+    }
+
+    #[test]
+    fn shrinking_keys_regenerates_values() {
+        let mut strategy = lengths_match_keys();
+        let mut generator = Generator::build(crate::rng());
+        let mut tree = strategy.new_tree(&mut generator).take();
+
+        while tree.simplify() {
+            for (key, value) in tree.current() {
+                assert_eq!(value.len(), usize::from(*key));
+            }
+        }
+        assert!(tree.current().is_empty());
+    }
+
+    #[test]
+    fn values_shrink_once_keys_cannot() {
+        let mut strategy = hash_map_with(
+            AnyU8::new(3..=9),
+            |key: &u8| {
+                let len = usize::from(*key);
+                AnyString::new(len..=len)
+            },
+            2usize..=2usize,
+        );
+        let mut generator = Generator::build(crate::rng());
+        let mut tree = strategy.new_tree(&mut generator).take();
+        while tree.simplify() {}
+
+        assert_eq!(tree.current().len(), 2);
+        for (key, value) in tree.current() {
+            assert_eq!(value.chars().count(), usize::from(*key));
+        }
+    }
+
+    #[test]
+    fn shrinking_skips_keys_whose_values_are_rejected() {
+        let mut strategy = hash_map_with(
+            AnyU8::new(0..=16),
+            |key: &u8| {
+                let key = *key;
+                from_fn(move |generator| {
+                    if key.is_multiple_of(2) {
+                        generator.accept(key)
+                    } else {
+                        generator.reject(key)
+                    }
+                })
+            },
+            1usize..=4usize,
+        );
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..64 {
+            let Generation::Accepted {
+                value: mut tree, ..
+            } = strategy.new_tree(&mut generator)
+            else {
+                continue;
+            };
+            while tree.simplify() {
+                let keys = tree.current().keys();
+                assert!(keys.into_iter().all(|key| key.is_multiple_of(2)));
+            }
+        }
+    }
+}
@@ -1,21 +1,31 @@
-use std::{collections::HashSet, hash::Hash, ops::RangeInclusive};
-
-use super::vecs::{build_drop_plan, sample_length};
-use crate::strategy::{
-    SizeHint,
-    Strategy,
-    ValueTree,
-    runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS},
+use std::{
+    collections::HashSet,
+    hash::{BuildHasher, Hash, RandomState},
+    marker::PhantomData,
+    ops::RangeInclusive,
 };
 
+use super::vecs::sample_length;
+use crate::{
+    shrink::{DedupShrinker, LengthChunk, LengthShrinker},
+    strategy::{
+        SizeHint,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator, MAX_STRATEGY_ATTEMPTS, RejectReason},
+    },
+};
+
+/// Strategy for `HashSet`s whose hasher is built with `B::default()`.
 #[derive(Clone)]
-pub struct HashSetStrategy<S>
+pub struct HashSetStrategy<S, B = RandomState>
 where
     S: Strategy,
     S::Value: Clone + Eq + Hash,
 {
     element: S,
     len_range: RangeInclusive<usize>,
+    hasher: PhantomData<fn() -> B>,
 }
 
 impl<S> HashSetStrategy<S>
@@ -30,71 +40,106 @@ where
         Self {
             element,
             len_range: size_hint.to_inclusive(),
+            hasher: PhantomData,
         }
     }
 }
 
-impl<S> Strategy for HashSetStrategy<S>
+impl<S, B> HashSetStrategy<S, B>
 where
     S: Strategy,
     S::Value: Clone + Eq + Hash,
 {
-    type Value = HashSet<S::Value>;
-    type Tree = HashSetValueTree<S::Tree>;
+    /// Generate sets hashing with `T` instead, e.g.
+    /// `.with_hasher::<FxBuildHasher>()`.
+    pub fn with_hasher<T>(self) -> HashSetStrategy<S, T>
+    where
+        T: BuildHasher + Default,
+    {
+        HashSetStrategy {
+            element: self.element,
+            len_range: self.len_range,
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<S, B> Strategy for HashSetStrategy<S, B>
+where
+    S: Strategy,
+    S::Value: Clone + Eq + Hash,
+    B: BuildHasher + Default,
+{
+    type Value = HashSet<S::Value, B>;
+    type Tree = HashSetValueTree<S::Tree, B>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let target_len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let target_len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut elements = Vec::with_capacity(target_len);
         let mut values = Vec::with_capacity(target_len);
         let mut seen = HashSet::with_capacity(target_len);
 
+        // Duplicates mean the element strategy is too small at the current
+        // size, so let it grow until enough distinct values fit.
+        let mut generator = generator.size_guard();
+
         let mut attempts_remaining = MAX_STRATEGY_ATTEMPTS * target_len.max(1);
 
         while elements.len() < target_len && attempts_remaining > 0 {
             attempts_remaining -= 1;
 
-            match self.element.new_tree(generator) {
+            match self.element.new_tree(&mut generator) {
                 Generation::Accepted { value, .. } => {
                     let candidate = value.current().clone();
                     if seen.insert(candidate.clone()) {
                         elements.push(value);
                         values.push(candidate);
+                    } else {
+                        generator.grow();
                     }
                 }
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
-                    let tree = HashSetValueTree::from_elements(
-                        elements, values, min_len,
-                    );
+                    let tree =
+                        HashSetValueTree::build(elements, values, min_len);
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: tree,
                     };
                 }
             }
         }
 
-        generator
-            .accept(HashSetValueTree::from_elements(elements, values, min_len))
+        let reached_min_len = elements.len() >= min_len;
+        let tree = HashSetValueTree::build(elements, values, min_len);
+        if reached_min_len {
+            generator.accept(tree)
+        } else {
+            generator.reject_with(RejectReason::DuplicateKey, tree)
+        }
     }
 }
 
 #[derive(Clone, Copy)]
 enum Stage {
-    Length { chunk_index: usize, offset: usize },
+    Length,
     Elements { index: usize },
 }
 
 enum History<T, V> {
     RemovedChunk {
-        index: usize,
-        chunk_index: usize,
+        chunk: LengthChunk,
         trees: Vec<T>,
         values: Vec<V>,
     },
@@ -103,18 +148,16 @@ enum History<T, V> {
     },
 }
 
-pub struct HashSetValueTree<T>
+pub struct HashSetValueTree<T, B = RandomState>
 where
     T: ValueTree,
     T::Value: Clone + Eq + Hash,
 {
     elements: Vec<T>,
-    raw_values: Vec<T::Value>,
-    min_len: usize,
-    drop_plan: Vec<usize>,
+    values: DedupShrinker<T::Value, HashSet<T::Value, B>>,
+    length: LengthShrinker,
     stage: Stage,
     history: Vec<History<T, T::Value>>,
-    current: HashSet<T::Value>,
 }
 
 impl<T> HashSetValueTree<T>
@@ -127,116 +170,76 @@ where
         raw_values: Vec<T::Value>,
         min_len: usize,
     ) -> Self {
-        let drop_plan = build_drop_plan(elements.len());
-        let stage = if drop_plan.is_empty() {
-            Stage::Elements { index: 0 }
-        } else {
-            Stage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
+        Self::build(elements, raw_values, min_len)
+    }
+}
 
-        let mut tree = Self {
+impl<T, B> HashSetValueTree<T, B>
+where
+    T: ValueTree,
+    T::Value: Clone + Eq + Hash,
+    B: BuildHasher + Default,
+{
+    fn build(
+        elements: Vec<T>,
+        raw_values: Vec<T::Value>,
+        min_len: usize,
+    ) -> Self {
+        Self {
+            length: LengthShrinker::new(elements.len(), min_len),
             elements,
-            raw_values,
-            min_len,
-            drop_plan,
-            stage,
+            values: DedupShrinker::new(raw_values),
+            stage: Stage::Length,
             history: Vec::new(),
-            current: HashSet::new(),
-        };
-
-        tree.rebuild_current();
-        tree
+        }
     }
 
     fn len(&self) -> usize {
         self.elements.len()
     }
 
-    fn rebuild_current(&mut self) {
-        self.current.clear();
-        self.raw_values.iter().for_each(|value| {
-            self.current.insert(value.clone());
-        });
-    }
-
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            self.stage = Stage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
-        }
-
-        self.stage = Stage::Elements { index: 0 };
-        None
+    /// Current elements in generation order.
+    pub(crate) fn ordered_values(&self) -> &[T::Value] {
+        &self.values
     }
 
-    fn element_duplicate(&self, index: usize, candidate: &T::Value) -> bool {
-        self.raw_values
-            .iter()
-            .enumerate()
-            .any(|(i, value)| i != index && value == candidate)
+    /// Replace element `index` with the value its tree currently holds.
+    fn refresh_element(&mut self, index: usize) {
+        let value = self.elements[index].current().clone();
+        self.values.replace(index, value);
     }
 }
 
-impl<T> ValueTree for HashSetValueTree<T>
+impl<T, B> ValueTree for HashSetValueTree<T, B>
 where
     T: ValueTree,
     T::Value: Clone + Eq + Hash,
+    B: BuildHasher + Default,
 {
-    type Value = HashSet<T::Value>;
+    type Value = HashSet<T::Value, B>;
 
     fn current(&self) -> &Self::Value {
-        &self.current
+        self.values.index()
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.values.into_index()
     }
 
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                Stage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
-                    else {
+                Stage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = Stage::Elements { index: 0 };
                         continue;
                     };
 
                     let trees: Vec<T> =
-                        self.elements.drain(off..off + chunk_size).collect();
-                    let values: Vec<T::Value> =
-                        self.raw_values.drain(off..off + chunk_size).collect();
-                    self.rebuild_current();
+                        self.elements.drain(chunk.range()).collect();
+                    let values = self.values.drain(chunk.range());
                     self.history.push(History::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
+                        chunk,
                         trees,
                         values,
                     });
@@ -250,7 +253,7 @@ where
                     if self.elements[index].simplify() {
                         let candidate = self.elements[index].current().clone();
 
-                        if self.element_duplicate(index, &candidate) {
+                        if self.values.is_duplicate(index, &candidate) {
                             if !self.elements[index].complicate() {
                                 self.stage =
                                     Stage::Elements { index: index + 1 };
@@ -258,8 +261,7 @@ where
                             continue;
                         }
 
-                        self.raw_values[index] = candidate;
-                        self.rebuild_current();
+                        self.refresh_element(index);
                         self.history.push(History::Element { index });
                         return true;
                     } else {
@@ -277,30 +279,29 @@ where
 
         match entry {
             History::RemovedChunk {
-                index,
-                chunk_index,
+                chunk,
                 trees,
                 values,
             } => {
+                let index = chunk.range().start;
                 self.elements.splice(index..index, trees);
-                self.raw_values.splice(index..index, values);
-                self.rebuild_current();
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => !self.elements.is_empty(),
+                self.values.splice(index, values);
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = Stage::Length;
+                    true
+                } else {
+                    self.stage = Stage::Elements { index: 0 };
+                    !self.elements.is_empty()
                 }
             }
             History::Element { index } => {
                 if self.elements[index].complicate() {
-                    self.raw_values[index] =
-                        self.elements[index].current().clone();
-                    self.rebuild_current();
+                    self.refresh_element(index);
                     self.history.push(History::Element { index });
                     true
                 } else {
-                    self.raw_values[index] =
-                        self.elements[index].current().clone();
-                    self.rebuild_current();
+                    self.refresh_element(index);
                     if index + 1 < self.len() {
                         self.stage = Stage::Elements { index: index + 2 };
                         true
@@ -342,6 +343,28 @@ mod tests {
         assert!(current.contains(&1));
     }
 
+    #[test]
+    fn hash_set_current_tracks_every_shrink_step() {
+        let mut strategy = HashSetStrategy::new(AnyI32::default(), 8usize..=16);
+        let mut tree = strategy
+            .new_tree(&mut Generator::build(crate::rng()))
+            .take();
+
+        for step in 0..256 {
+            if step % 3 == 2 {
+                tree.complicate();
+            } else {
+                tree.simplify();
+            }
+            let rebuilt = tree
+                .elements
+                .iter()
+                .map(|element| *element.current())
+                .collect::<HashSet<_>>();
+            assert_eq!(tree.current(), &rebuilt);
+        }
+    }
+
     #[test]
     fn hash_set_strategy_honours_range() {
         let mut strategy =
@@ -353,4 +376,14 @@ mod tests {
         };
         assert!((1..=3).contains(&len));
     }
+
+    #[test]
+    fn hash_set_strategy_grows_size_to_find_distinct_values() {
+        let mut strategy =
+            HashSetStrategy::new(AnyI32::default(), 8usize..=8usize);
+        let mut generator = Generator::build(crate::rng()).with_size(0);
+        let tree = strategy.new_tree(&mut generator).take();
+        assert_eq!(tree.current().len(), 8);
+        assert_eq!(generator.size(), 0);
+    }
 }
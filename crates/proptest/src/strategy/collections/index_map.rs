@@ -0,0 +1,268 @@
+use core::hash::Hash;
+
+use indexmap::{IndexMap, IndexSet};
+
+use super::{
+    hash_map::{HashMapStrategy, HashMapValueTree},
+    hash_set::{HashSetStrategy, HashSetValueTree},
+};
+use crate::strategy::{
+    SizeHint,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Strategy for `IndexMap`s, shrinking like [`HashMapStrategy`] while keeping
+/// entries in generation order.
+pub struct IndexMapStrategy<KS, VS>
+where
+    KS: Strategy,
+    VS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    VS::Value: Clone,
+{
+    inner: HashMapStrategy<KS, VS>,
+}
+
+impl<KS, VS> IndexMapStrategy<KS, VS>
+where
+    KS: Strategy,
+    VS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    VS::Value: Clone,
+{
+    pub fn new<H>(key: KS, value: VS, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            inner: HashMapStrategy::new(key, value, size_hint),
+        }
+    }
+}
+
+pub struct IndexMapValueTree<KT, VT>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    VT: ValueTree,
+    VT::Value: Clone,
+{
+    inner: HashMapValueTree<KT, VT>,
+    current: IndexMap<KT::Value, VT::Value>,
+}
+
+impl<KT, VT> IndexMapValueTree<KT, VT>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    VT: ValueTree,
+    VT::Value: Clone,
+{
+    fn new(inner: HashMapValueTree<KT, VT>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: IndexMap::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current = self
+            .inner
+            .ordered_entries()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+    }
+}
+
+impl<KS, VS> Strategy for IndexMapStrategy<KS, VS>
+where
+    KS: Strategy,
+    VS: Strategy,
+    KS::Value: Clone + Eq + Hash,
+    VS::Value: Clone,
+{
+    type Value = IndexMap<KS::Value, VS::Value>;
+    type Tree = IndexMapValueTree<KS::Tree, VS::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(IndexMapValueTree::new)
+    }
+}
+
+impl<KT, VT> ValueTree for IndexMapValueTree<KT, VT>
+where
+    KT: ValueTree,
+    KT::Value: Clone + Eq + Hash,
+    VT: ValueTree,
+    VT::Value: Clone,
+{
+    type Value = IndexMap<KT::Value, VT::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+/// Strategy for `IndexSet`s, shrinking like [`HashSetStrategy`] while keeping
+/// elements in generation order.
+pub struct IndexSetStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Eq + Hash,
+{
+    inner: HashSetStrategy<S>,
+}
+
+impl<S> IndexSetStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Eq + Hash,
+{
+    pub fn new<H>(element: S, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            inner: HashSetStrategy::new(element, size_hint),
+        }
+    }
+}
+
+pub struct IndexSetValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Eq + Hash,
+{
+    inner: HashSetValueTree<T>,
+    current: IndexSet<T::Value>,
+}
+
+impl<T> IndexSetValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Eq + Hash,
+{
+    fn new(inner: HashSetValueTree<T>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: IndexSet::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current = self.inner.ordered_values().iter().cloned().collect();
+    }
+}
+
+impl<S> Strategy for IndexSetStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Eq + Hash,
+{
+    type Value = IndexSet<S::Value>;
+    type Tree = IndexSetValueTree<S::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(IndexSetValueTree::new)
+    }
+}
+
+impl<T> ValueTree for IndexSetValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Eq + Hash,
+{
+    type Value = IndexSet<T::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::primitives::IntValueTree;
+
+    #[test]
+    fn index_map_keeps_generation_order() {
+        let entries = vec![
+            (IntValueTree::new(9, vec![]), IntValueTree::new(1, vec![])),
+            (IntValueTree::new(2, vec![]), IntValueTree::new(2, vec![])),
+            (IntValueTree::new(5, vec![]), IntValueTree::new(3, vec![])),
+        ];
+        let keys = vec![9, 2, 5];
+        let values = vec![1, 2, 3];
+        let inner = HashMapValueTree::from_entries(entries, keys, values, 0);
+        let mut tree = IndexMapValueTree::new(inner);
+
+        assert_eq!(
+            tree.current().keys().copied().collect::<Vec<_>>(),
+            [9, 2, 5]
+        );
+        assert!(tree.simplify());
+        assert!(tree.current().len() < 3);
+    }
+
+    #[test]
+    fn index_set_keeps_generation_order() {
+        let elements =
+            vec![IntValueTree::new(7, vec![1]), IntValueTree::new(3, vec![1])];
+        let inner = HashSetValueTree::from_elements(elements, vec![7, 3], 2);
+        let mut tree = IndexSetValueTree::new(inner);
+
+        assert_eq!(tree.current().iter().copied().collect::<Vec<_>>(), [7, 3]);
+        assert!(tree.simplify());
+        assert_eq!(tree.current().iter().copied().collect::<Vec<_>>(), [1, 3]);
+    }
+}
@@ -1,11 +1,39 @@
+#[cfg(feature = "arrayvec")]
+mod array_vec;
 mod btree_map;
 mod btree_set;
+#[cfg(feature = "std")]
+mod chaos;
+#[cfg(feature = "std")]
 mod hash_map;
+#[cfg(feature = "std")]
+mod hash_map_with;
+#[cfg(feature = "std")]
 mod hash_set;
+#[cfg(feature = "indexmap")]
+mod index_map;
+mod shuffle;
+#[cfg(feature = "smallvec")]
+mod small_vec;
 mod vecs;
 
+#[cfg(feature = "arrayvec")]
+pub use array_vec::*;
 pub use btree_map::*;
 pub use btree_set::*;
+#[cfg(feature = "std")]
+pub use chaos::ChaosState;
+#[cfg(feature = "std")]
+pub(crate) use chaos::set_order_seed;
+#[cfg(feature = "std")]
 pub use hash_map::*;
+#[cfg(feature = "std")]
+pub use hash_map_with::*;
+#[cfg(feature = "std")]
 pub use hash_set::*;
+#[cfg(feature = "indexmap")]
+pub use index_map::*;
+pub use shuffle::*;
+#[cfg(feature = "smallvec")]
+pub use small_vec::*;
 pub use vecs::*;
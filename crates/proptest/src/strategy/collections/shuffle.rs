@@ -0,0 +1,445 @@
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use crate::{
+    shrink::{LengthChunk, LengthShrinker},
+    strategy::{
+        Strategy,
+        TupleValueTree2,
+        ValueTree,
+        runtime::{Generation, Generator},
+    },
+};
+
+/// Generate the vectors of `strategy` in a random order.
+///
+/// The order is a list of swaps applied to the generated vector, and
+/// shrinking drops swaps before it shrinks the vector, so failures that do
+/// not depend on the order shrink to the vector as generated. Swaps that
+/// point past the end of a shrunk vector are skipped.
+pub fn shuffled<S, T>(strategy: S) -> ShuffledStrategy<S>
+where
+    S: Strategy<Value = Vec<T>>,
+    T: Clone,
+{
+    ShuffledStrategy { inner: strategy }
+}
+
+#[derive(Clone)]
+pub struct ShuffledStrategy<S> {
+    inner: S,
+}
+
+impl<S, T> Strategy for ShuffledStrategy<S>
+where
+    S: Strategy<Value = Vec<T>>,
+    T: Clone,
+{
+    type Value = Vec<T>;
+    type Tree = ShuffledValueTree<S::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let inner = self.inner.new_tree(generator);
+        let len = match &inner {
+            Generation::Accepted { value, .. }
+            | Generation::Rejected { value, .. } => value.current().len(),
+        };
+        // Fisher-Yates, keeping only the swaps that move an element.
+        let swaps: Vec<(usize, usize)> = (1..len)
+            .rev()
+            .map(|i| (i, generator.rng.random_range(0..=i)))
+            .filter(|(i, j)| i != j)
+            .collect();
+
+        inner.map(|inner| {
+            let mut tree = ShuffledValueTree {
+                inner,
+                length: LengthShrinker::new(swaps.len(), 0),
+                swaps,
+                stage: ShuffleStage::Order,
+                history: Vec::new(),
+                current: Vec::new(),
+            };
+            tree.sync_current();
+            tree
+        })
+    }
+}
+
+/// Shrink passes, in the order they run: drop chunks of swaps with a
+/// [`LengthShrinker`], then shrink the vector.
+#[derive(Clone, Copy)]
+enum ShuffleStage {
+    Order,
+    Elements,
+}
+
+enum ShuffleHistory {
+    RemovedSwaps {
+        chunk: LengthChunk,
+        swaps: Vec<(usize, usize)>,
+    },
+    Elements,
+}
+
+pub struct ShuffledValueTree<V>
+where
+    V: ValueTree,
+{
+    inner: V,
+    swaps: Vec<(usize, usize)>,
+    length: LengthShrinker,
+    stage: ShuffleStage,
+    history: Vec<ShuffleHistory>,
+    current: V::Value,
+}
+
+impl<V, T> ShuffledValueTree<V>
+where
+    V: ValueTree<Value = Vec<T>>,
+    T: Clone,
+{
+    fn sync_current(&mut self) {
+        self.current.clone_from(self.inner.current());
+        let len = self.current.len();
+        for &(i, j) in &self.swaps {
+            if i < len && j < len {
+                self.current.swap(i, j);
+            }
+        }
+    }
+}
+
+impl<V, T> ValueTree for ShuffledValueTree<V>
+where
+    V: ValueTree<Value = Vec<T>>,
+    T: Clone,
+{
+    type Value = Vec<T>;
+
+    fn current(&self) -> &Vec<T> {
+        &self.current
+    }
+
+    fn into_current(self) -> Vec<T> {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if let ShuffleStage::Order = self.stage {
+            match self.length.seek(self.swaps.len()) {
+                Some(chunk) => {
+                    let swaps = self.swaps.drain(chunk.range()).collect();
+                    self.history
+                        .push(ShuffleHistory::RemovedSwaps { chunk, swaps });
+                    self.sync_current();
+                    return true;
+                }
+                None => self.stage = ShuffleStage::Elements,
+            }
+        }
+
+        if !self.inner.simplify() {
+            return false;
+        }
+        self.history.push(ShuffleHistory::Elements);
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+
+        match entry {
+            ShuffleHistory::RemovedSwaps { chunk, swaps } => {
+                let index = chunk.range().start;
+                self.swaps.splice(index..index, swaps);
+                self.length.backtrack(chunk);
+                if self.length.seek(self.swaps.len()).is_none() {
+                    self.stage = ShuffleStage::Elements;
+                }
+                self.sync_current();
+                true
+            }
+            ShuffleHistory::Elements => {
+                let complicated = self.inner.complicate();
+                self.sync_current();
+                complicated
+            }
+        }
+    }
+}
+
+/// Interleave the vectors of `a` and `b`, keeping the order of each.
+///
+/// Every element of `b` is placed after a random number of elements of `a`.
+/// Shrinking first moves the elements of `b` behind all of `a`, from the
+/// first one on, so failures that do not depend on the interleaving shrink
+/// to `a` followed by `b`, and then shrinks both vectors.
+pub fn interleave<A, B, T>(a: A, b: B) -> InterleaveStrategy<A, B>
+where
+    A: Strategy<Value = Vec<T>>,
+    B: Strategy<Value = Vec<T>>,
+    T: Clone,
+{
+    InterleaveStrategy { inner: (a, b) }
+}
+
+#[derive(Clone)]
+pub struct InterleaveStrategy<A, B> {
+    inner: (A, B),
+}
+
+impl<A, B, T> Strategy for InterleaveStrategy<A, B>
+where
+    A: Strategy<Value = Vec<T>>,
+    B: Strategy<Value = Vec<T>>,
+    T: Clone,
+{
+    type Value = Vec<T>;
+    type Tree = InterleaveValueTree<A::Tree, B::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let inner = self.inner.new_tree(generator);
+        let (a_len, b_len) = match &inner {
+            Generation::Accepted { value, .. }
+            | Generation::Rejected { value, .. } => {
+                let (a, b) = value.current();
+                (a.len(), b.len())
+            }
+        };
+        let mut positions: Vec<usize> = (0..b_len)
+            .map(|_| generator.rng.random_range(0..=a_len))
+            .collect();
+        positions.sort_unstable();
+
+        inner.map(|inner| {
+            let mut tree = InterleaveValueTree {
+                inner,
+                positions,
+                stage: InterleaveStage::Order { index: 0 },
+                history: Vec::new(),
+                current: Vec::new(),
+            };
+            tree.sync_current();
+            tree
+        })
+    }
+}
+
+/// Shrink passes, in the order they run: move each element of `b` behind
+/// all of `a`, then shrink both vectors.
+#[derive(Clone, Copy)]
+enum InterleaveStage {
+    Order { index: usize },
+    Elements,
+}
+
+enum InterleaveHistory {
+    Position { index: usize, previous: usize },
+    Elements,
+}
+
+pub struct InterleaveValueTree<A, B>
+where
+    A: ValueTree,
+    B: ValueTree,
+    A::Value: Clone,
+    B::Value: Clone,
+{
+    inner: TupleValueTree2<A, B>,
+    /// How many elements of `a` come before each element of `b`. Elements
+    /// of `b` never move before an earlier one, whatever their position.
+    positions: Vec<usize>,
+    stage: InterleaveStage,
+    history: Vec<InterleaveHistory>,
+    current: A::Value,
+}
+
+impl<A, B, T> InterleaveValueTree<A, B>
+where
+    A: ValueTree<Value = Vec<T>>,
+    B: ValueTree<Value = Vec<T>>,
+    T: Clone,
+{
+    fn sync_current(&mut self) {
+        let (a, b) = self.inner.current();
+        self.current.clear();
+        let mut b_index = 0;
+        for (a_index, element) in a.iter().enumerate() {
+            while b_index < b.len()
+                && self.positions.get(b_index).copied().unwrap_or(usize::MAX)
+                    <= a_index
+            {
+                self.current.push(b[b_index].clone());
+                b_index += 1;
+            }
+            self.current.push(element.clone());
+        }
+        self.current.extend(b[b_index..].iter().cloned());
+    }
+}
+
+impl<A, B, T> ValueTree for InterleaveValueTree<A, B>
+where
+    A: ValueTree<Value = Vec<T>>,
+    B: ValueTree<Value = Vec<T>>,
+    T: Clone,
+{
+    type Value = Vec<T>;
+
+    fn current(&self) -> &Vec<T> {
+        &self.current
+    }
+
+    fn into_current(self) -> Vec<T> {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        while let InterleaveStage::Order { index } = self.stage {
+            let Some(&previous) = self.positions.get(index) else {
+                self.stage = InterleaveStage::Elements;
+                break;
+            };
+            self.stage = InterleaveStage::Order { index: index + 1 };
+            if previous == usize::MAX {
+                continue;
+            }
+            self.positions[index] = usize::MAX;
+            self.history
+                .push(InterleaveHistory::Position { index, previous });
+            self.sync_current();
+            return true;
+        }
+
+        if !self.inner.simplify() {
+            return false;
+        }
+        self.history.push(InterleaveHistory::Elements);
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+
+        match entry {
+            InterleaveHistory::Position { index, previous } => {
+                self.positions[index] = previous;
+                self.sync_current();
+                true
+            }
+            InterleaveHistory::Elements => {
+                let complicated = self.inner.complicate();
+                self.sync_current();
+                complicated
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy::{AnyU8, VecStrategy, compose},
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    fn sorted(value: &[u8]) -> Vec<u8> {
+        let mut value = value.to_vec();
+        value.sort_unstable();
+        value
+    }
+
+    #[test]
+    fn shuffled_vectors_are_permutations() {
+        check_shrink_invariants_with(
+            shuffled(VecStrategy::new(AnyU8::default(), 0usize..=16usize)),
+            64,
+            |value| value.len() <= 16,
+        );
+
+        let mut strategy =
+            shuffled(VecStrategy::new(AnyU8::default(), 0usize..=16usize));
+        let mut generator = Generator::build(StdRng::seed_from_u64(11));
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            loop {
+                assert_eq!(
+                    sorted(tree.current()),
+                    sorted(tree.inner.current())
+                );
+                if !tree.simplify() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shuffles_shrink_to_the_generated_order() {
+        assert_shrinks_to!(
+            shuffled(VecStrategy::new(AnyU8::default(), 0usize..=16usize)),
+            |value: &Vec<u8>| value.len() < 2,
+            vec![0, 0]
+        );
+    }
+
+    fn sorted_vec(low: u8, high: u8) -> impl Strategy<Value = Vec<u8>> {
+        compose(
+            (VecStrategy::new(AnyU8::new(low..=high), 8usize..=8usize),),
+            |(value,)| sorted(&value),
+        )
+    }
+
+    #[test]
+    fn interleavings_keep_the_order_of_each_side() {
+        let is_ordered = |value: &Vec<u8>| {
+            let (low, high): (Vec<u8>, Vec<u8>) =
+                value.iter().partition(|value| **value < 100);
+            low.is_sorted() && high.is_sorted()
+        };
+        let mut strategy = interleave(sorted_vec(0, 99), sorted_vec(100, 199));
+        let mut generator = Generator::build(StdRng::seed_from_u64(13));
+        for _ in 0..64 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            assert_eq!(tree.current().len(), 16);
+            assert!(is_ordered(tree.current()));
+            while tree.simplify() {
+                assert!(is_ordered(tree.current()));
+            }
+        }
+    }
+
+    #[test]
+    fn interleavings_shrink_to_the_concatenation() {
+        let mut strategy = interleave(
+            VecStrategy::new(AnyU8::new(1..=1), 3usize..=3usize),
+            VecStrategy::new(AnyU8::new(2..=2), 3usize..=3usize),
+        );
+        let mut generator = Generator::build(StdRng::seed_from_u64(17));
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            while tree.simplify() {}
+            assert_eq!(tree.current(), &vec![1, 1, 1, 2, 2, 2]);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use core::marker::PhantomData;
+
+use smallvec::{Array, SmallVec};
+
+use super::vecs::{VecStrategy, VecValueTree};
+use crate::strategy::{
+    SizeHint,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+pub struct SmallVecStrategy<S, A>
+where
+    S: Strategy,
+    S::Value: Clone,
+    A: Array<Item = S::Value>,
+{
+    inner: VecStrategy<S>,
+    _array: PhantomData<A>,
+}
+
+impl<S, A> SmallVecStrategy<S, A>
+where
+    S: Strategy,
+    S::Value: Clone,
+    A: Array<Item = S::Value>,
+{
+    pub fn new<H>(element: S, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            inner: VecStrategy::new(element, size_hint),
+            _array: PhantomData,
+        }
+    }
+}
+
+pub struct SmallVecValueTree<T, A>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    A: Array<Item = T::Value>,
+{
+    inner: VecValueTree<T>,
+    current: SmallVec<A>,
+}
+
+impl<T, A> SmallVecValueTree<T, A>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    A: Array<Item = T::Value>,
+{
+    fn new(inner: VecValueTree<T>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: SmallVec::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current = self.inner.current().iter().cloned().collect();
+    }
+}
+
+impl<S, A> Strategy for SmallVecStrategy<S, A>
+where
+    S: Strategy,
+    S::Value: Clone,
+    A: Array<Item = S::Value>,
+{
+    type Value = SmallVec<A>;
+    type Tree = SmallVecValueTree<S::Tree, A>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(SmallVecValueTree::new)
+    }
+}
+
+impl<T, A> ValueTree for SmallVecValueTree<T, A>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    A: Array<Item = T::Value>,
+{
+    type Value = SmallVec<A>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{AnyU8, primitives::IntValueTree};
+
+    #[test]
+    fn small_vec_mirrors_vec_shrinking() {
+        let trees = vec![
+            IntValueTree::new(4u8, vec![0]),
+            IntValueTree::new(3u8, vec![0]),
+        ];
+        let inner = VecValueTree::from_trees(trees, 0);
+        let mut tree = SmallVecValueTree::<_, [u8; 4]>::new(inner);
+
+        assert_eq!(tree.current().as_slice(), &[4, 3]);
+        assert!(tree.simplify());
+        assert_eq!(tree.current().len(), 1);
+    }
+
+    #[test]
+    fn small_vec_strategy_yields_len_in_range() {
+        let mut strategy = SmallVecStrategy::<_, [u8; 2]>::new(
+            AnyU8::default(),
+            1usize..=6usize,
+        );
+        let mut generator = Generator::build(crate::rng());
+        let tree = match strategy.new_tree(&mut generator) {
+            Generation::Accepted { value, .. } => value,
+            Generation::Rejected { .. } => panic!("unexpected rejection"),
+        };
+        assert!((1..=6).contains(&tree.current().len()));
+    }
+}
@@ -1,31 +1,24 @@
-use std::{
+use alloc::{
     collections::{BinaryHeap, VecDeque},
-    ops::RangeInclusive,
+    format,
+    vec::Vec,
 };
+use core::ops::RangeInclusive;
 
-use super::super::primitives::AnyUsize;
-use crate::strategy::{
-    SizeHint,
-    Strategy,
-    ValueTree,
-    runtime::{Generation, Generator},
+use super::{
+    super::primitives::AnyUsize,
+    btree_set::{BTreeSetStrategy, BTreeSetValueTree},
+};
+use crate::{
+    shrink::{LengthChunk, LengthShrinker},
+    strategy::{
+        Provenance,
+        SizeHint,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator},
+    },
 };
-
-pub(crate) fn build_drop_plan(len: usize) -> Vec<usize> {
-    let mut plan = Vec::new();
-    let mut size = len / 2;
-
-    while size > 0 {
-        plan.push(size);
-        size /= 2;
-    }
-
-    if !plan.contains(&1) && len > 0 {
-        plan.push(1);
-    }
-
-    plan
-}
 
 pub(crate) fn sample_length<R: rand::RngCore + rand::CryptoRng>(
     rng: &mut R,
@@ -72,7 +65,8 @@ where
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut trees = Vec::with_capacity(len);
 
@@ -80,11 +74,15 @@ where
             match self.element.new_tree(generator) {
                 Generation::Accepted { value, .. } => trees.push(value),
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: VecValueTree::from_trees(trees, min_len),
                     };
                 }
@@ -95,21 +93,23 @@ where
     }
 }
 
+/// Shrink passes, in the order they run: drop chunks with a
+/// [`LengthShrinker`],
+/// drop the last element one at a time, simplify every element one notch at
+/// once, then simplify elements one by one.
 #[derive(Clone, Copy)]
 enum Stage {
-    Length { chunk_index: usize, offset: usize },
+    Length,
+    Tail,
+    AllElements,
     Elements { index: usize },
 }
 
 enum History<T> {
-    RemovedChunk {
-        index: usize,
-        chunk_index: usize,
-        chunk: Vec<T>,
-    },
-    Element {
-        index: usize,
-    },
+    RemovedChunk { chunk: LengthChunk, trees: Vec<T> },
+    RemovedTail { element: T },
+    AllElements { moved: Vec<usize> },
+    Element { index: usize },
 }
 
 pub struct VecValueTree<T>
@@ -120,7 +120,7 @@ where
     elements: Vec<T>,
     current: Vec<T::Value>,
     min_len: usize,
-    drop_plan: Vec<usize>,
+    length: LengthShrinker,
     stage: Stage,
     history: Vec<History<T>>,
 }
@@ -131,22 +131,12 @@ where
     T::Value: Clone,
 {
     pub fn from_trees(elements: Vec<T>, min_len: usize) -> Self {
-        let drop_plan = build_drop_plan(elements.len());
-        let stage = if drop_plan.is_empty() {
-            Stage::Elements { index: 0 }
-        } else {
-            Stage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
-
         let mut tree = Self {
+            length: LengthShrinker::new(elements.len(), min_len),
             elements,
             current: Vec::new(),
             min_len,
-            drop_plan,
-            stage,
+            stage: Stage::Length,
             history: Vec::new(),
         };
 
@@ -165,41 +155,6 @@ where
     fn len(&self) -> usize {
         self.elements.len()
     }
-
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
-            }
-
-            self.stage = Stage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
-        }
-
-        self.stage = Stage::Elements { index: 0 };
-        None
-    }
 }
 
 impl<T> ValueTree for VecValueTree<T>
@@ -213,27 +168,52 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                Stage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
-                    else {
+                Stage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = Stage::Tail;
                         continue;
                     };
 
-                    let removed: Vec<T> =
-                        self.elements.drain(off..off + chunk_size).collect();
-                    self.current.drain(off..off + chunk_size).count();
-                    self.history.push(History::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
-                        chunk: removed,
-                    });
+                    let trees: Vec<T> =
+                        self.elements.drain(chunk.range()).collect();
+                    self.current.drain(chunk.range());
+                    self.history.push(History::RemovedChunk { chunk, trees });
+                    return true;
+                }
+                Stage::Tail => {
+                    if self.len() <= self.min_len {
+                        self.stage = Stage::AllElements;
+                        continue;
+                    }
+
+                    let element =
+                        self.elements.pop().expect("length above minimum");
+                    self.current.pop();
+                    self.history.push(History::RemovedTail { element });
+                    return true;
+                }
+                Stage::AllElements => {
+                    let mut moved = Vec::new();
+                    for (index, element) in self.elements.iter_mut().enumerate()
+                    {
+                        if element.simplify() {
+                            self.current[index] = element.current().clone();
+                            moved.push(index);
+                        }
+                    }
+
+                    if moved.is_empty() {
+                        self.stage = Stage::Elements { index: 0 };
+                        continue;
+                    }
+                    self.history.push(History::AllElements { moved });
                     return true;
                 }
                 Stage::Elements { index } => {
@@ -260,20 +240,36 @@ where
         };
 
         match entry {
-            History::RemovedChunk {
-                index,
-                chunk_index,
-                chunk,
-            } => {
+            History::RemovedChunk { chunk, trees } => {
+                let index = chunk.range().start;
                 let values: Vec<T::Value> =
-                    chunk.iter().map(|tree| tree.current().clone()).collect();
-                self.elements.splice(index..index, chunk);
+                    trees.iter().map(|tree| tree.current().clone()).collect();
+                self.elements.splice(index..index, trees);
                 self.current.splice(index..index, values);
 
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => !self.current.is_empty(),
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = Stage::Length;
+                    true
+                } else {
+                    self.stage = Stage::Tail;
+                    !self.current.is_empty()
+                }
+            }
+            History::RemovedTail { element } => {
+                self.current.push(element.current().clone());
+                self.elements.push(element);
+                self.stage = Stage::AllElements;
+                true
+            }
+            History::AllElements { moved } => {
+                for index in moved {
+                    self.elements[index].complicate();
+                    self.current[index] =
+                        self.elements[index].current().clone();
                 }
+                self.stage = Stage::Elements { index: 0 };
+                !self.current.is_empty()
             }
             History::Element { index } => {
                 if self.elements[index].complicate() {
@@ -294,6 +290,18 @@ where
             }
         }
     }
+
+    /// The length, and the provenance of the elements that track it.
+    fn provenance(&self) -> Option<Provenance> {
+        let mut provenance =
+            Provenance::new(format!("VecStrategy len={}", self.len()));
+        for (index, element) in self.elements.iter().enumerate() {
+            if let Some(part) = element.provenance() {
+                provenance = provenance.with_part(format!("[{index}]"), part);
+            }
+        }
+        Some(provenance)
+    }
 }
 
 pub struct VecDequeStrategy<S>
@@ -374,6 +382,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         if self.inner.simplify() {
             self.sync_current();
@@ -384,12 +396,9 @@ where
     }
 
     fn complicate(&mut self) -> bool {
-        if self.inner.complicate() {
-            self.sync_current();
-            true
-        } else {
-            false
-        }
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
     }
 }
 
@@ -471,6 +480,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         if self.inner.simplify() {
             self.sync_current();
@@ -481,13 +494,343 @@ where
     }
 
     fn complicate(&mut self) -> bool {
-        if self.inner.complicate() {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+/// Vectors of `element` values in ascending order.
+pub fn vec_sorted<S, H>(element: S, size_hint: H) -> SortedVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+    H: SizeHint,
+{
+    SortedVecStrategy::new(element, size_hint)
+}
+
+/// Vectors of distinct `element` values, in the order they were generated.
+pub fn vec_unique<S, H>(element: S, size_hint: H) -> UniqueVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+    H: SizeHint,
+{
+    UniqueVecStrategy::new(element, size_hint)
+}
+
+/// Shrinks like [`VecStrategy`], sorting the vector after every step.
+pub struct SortedVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    inner: VecStrategy<S>,
+}
+
+impl<S> SortedVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    pub fn new<H>(element: S, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            inner: VecStrategy::new(element, size_hint),
+        }
+    }
+}
+
+pub struct SortedVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    inner: VecValueTree<T>,
+    current: Vec<T::Value>,
+}
+
+impl<T> SortedVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    fn new(inner: VecValueTree<T>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: Vec::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current.clone_from(self.inner.current());
+        self.current.sort();
+    }
+}
+
+impl<S> Strategy for SortedVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    type Value = Vec<S::Value>;
+    type Tree = SortedVecValueTree<S::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(SortedVecValueTree::new)
+    }
+}
+
+impl<T> ValueTree for SortedVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    type Value = Vec<T::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
             self.sync_current();
             true
         } else {
             false
         }
     }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+/// Generates and shrinks like [`BTreeSetStrategy`], so no shrink step
+/// introduces a duplicate, but keeps the elements in generation order.
+pub struct UniqueVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    inner: BTreeSetStrategy<S>,
+}
+
+impl<S> UniqueVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    pub fn new<H>(element: S, size_hint: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            inner: BTreeSetStrategy::new(element, size_hint),
+        }
+    }
+}
+
+pub struct UniqueVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    inner: BTreeSetValueTree<T>,
+    current: Vec<T::Value>,
+}
+
+impl<T> UniqueVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    fn new(inner: BTreeSetValueTree<T>) -> Self {
+        let mut tree = Self {
+            inner,
+            current: Vec::new(),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current.clear();
+        self.current.extend_from_slice(self.inner.values());
+    }
+}
+
+impl<S> Strategy for UniqueVecStrategy<S>
+where
+    S: Strategy,
+    S::Value: Clone + Ord,
+{
+    type Value = Vec<S::Value>;
+    type Tree = UniqueVecValueTree<S::Tree>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(UniqueVecValueTree::new)
+    }
+}
+
+impl<T> ValueTree for UniqueVecValueTree<T>
+where
+    T: ValueTree,
+    T::Value: Clone + Ord,
+{
+    type Value = Vec<T::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+/// Pairs of equally long vectors, drawn as one vector of `(a, b)` pairs so
+/// shrinking removes elements from both vectors together.
+pub fn zip<A, B, H>(
+    a: A,
+    b: B,
+    size_hint: H,
+) -> UnzipStrategy<VecStrategy<(A, B)>>
+where
+    A: Strategy,
+    A::Value: Clone,
+    B: Strategy,
+    B::Value: Clone,
+    H: SizeHint,
+{
+    unzip(VecStrategy::new((a, b), size_hint))
+}
+
+/// Split the vectors of pairs `pairs` generates into a pair of vectors.
+pub fn unzip<S, A, B>(pairs: S) -> UnzipStrategy<S>
+where
+    S: Strategy<Value = Vec<(A, B)>>,
+    A: Clone,
+    B: Clone,
+{
+    UnzipStrategy { inner: pairs }
+}
+
+#[derive(Clone)]
+pub struct UnzipStrategy<S> {
+    inner: S,
+}
+
+pub struct UnzipValueTree<T, A, B> {
+    inner: T,
+    current: (Vec<A>, Vec<B>),
+}
+
+impl<T, A, B> UnzipValueTree<T, A, B>
+where
+    T: ValueTree<Value = Vec<(A, B)>>,
+    A: Clone,
+    B: Clone,
+{
+    fn new(inner: T) -> Self {
+        let mut tree = Self {
+            inner,
+            current: (Vec::new(), Vec::new()),
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        let (left, right) = &mut self.current;
+        left.clear();
+        right.clear();
+        for (a, b) in self.inner.current() {
+            left.push(a.clone());
+            right.push(b.clone());
+        }
+    }
+}
+
+impl<S, A, B> Strategy for UnzipStrategy<S>
+where
+    S: Strategy<Value = Vec<(A, B)>>,
+    A: Clone,
+    B: Clone,
+{
+    type Value = (Vec<A>, Vec<B>);
+    type Tree = UnzipValueTree<S::Tree, A, B>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(UnzipValueTree::new)
+    }
+}
+
+impl<T, A, B> ValueTree for UnzipValueTree<T, A, B>
+where
+    T: ValueTree<Value = Vec<(A, B)>>,
+    A: Clone,
+    B: Clone,
+{
+    type Value = (Vec<A>, Vec<B>);
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
 }
 
 #[cfg(test)]
@@ -495,11 +838,6 @@ mod tests {
     use super::*;
     use crate::strategy::{AnyI32, ValueTree, runtime::Generator};
 
-    #[test]
-    fn vec_drop_plan_halves() {
-        assert_eq!(build_drop_plan(8), vec![4, 2, 1]);
-    }
-
     #[test]
     fn vec_shrinks_length_first() {
         let trees = vec![IntTree::new(3), IntTree::new(2), IntTree::new(1)];
@@ -561,6 +899,37 @@ mod tests {
         assert_eq!(tree.current(), &vec![0]);
     }
 
+    #[test]
+    fn vec_removes_the_tail_after_the_chunk_pass() {
+        let trees = vec![IntTree::new(1), IntTree::new(2), IntTree::new(3)];
+        let mut tree = VecValueTree::from_trees(trees, 0);
+
+        for _ in 0..3 {
+            assert!(tree.simplify());
+            assert!(tree.complicate());
+        }
+        assert_eq!(tree.current(), &vec![1, 2, 3]);
+
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), &vec![1, 2]);
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), &vec![1]);
+    }
+
+    #[test]
+    fn vec_simplifies_every_element_at_once() {
+        let trees = vec![IntTree::new(5), IntTree::new(9)];
+        let mut tree = VecValueTree::from_trees(trees, 2);
+
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), &vec![0, 0]);
+
+        assert!(tree.complicate());
+        assert_eq!(tree.current(), &vec![5, 9]);
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), &vec![0, 9]);
+    }
+
     #[test]
     fn vec_deque_mirrors_vec_shrinking() {
         let trees = vec![IntTree::new(4), IntTree::new(3), IntTree::new(2)];
@@ -629,4 +998,14 @@ mod tests {
         let len = tree.current().len();
         assert!((2..=4).contains(&len), "len out of range");
     }
+
+    #[test]
+    fn vec_strategy_scales_length_with_size() {
+        let mut strategy = VecStrategy::new(AnyI32::default(), 2usize..=102);
+        let mut generator = Generator::build(crate::rng()).with_size(10);
+        for _ in 0..32 {
+            let tree = strategy.new_tree(&mut generator).take();
+            assert!((2..=12).contains(&tree.current().len()));
+        }
+    }
 }
@@ -0,0 +1,185 @@
+//! Strategies built from other strategies by a function, and the
+//! [`compose_strategy!`](crate::compose_strategy) macro that writes them.
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Build values with `f` from the values of `strategy`.
+///
+/// Shrinking happens on the values `strategy` draws, so the built value
+/// shrinks as well and `f` runs again on every candidate.
+pub fn compose<S, F, T>(strategy: S, f: F) -> ComposedStrategy<S, F>
+where
+    S: Strategy,
+    S::Value: Clone,
+    F: Fn(S::Value) -> T + Clone,
+{
+    ComposedStrategy { strategy, f }
+}
+
+#[derive(Clone)]
+pub struct ComposedStrategy<S, F> {
+    strategy: S,
+    f: F,
+}
+
+impl<S, F, T> Strategy for ComposedStrategy<S, F>
+where
+    S: Strategy,
+    S::Value: Clone,
+    F: Fn(S::Value) -> T + Clone,
+{
+    type Value = T;
+    type Tree = ComposedValueTree<S::Tree, F, T>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let f = &self.f;
+        self.strategy.new_tree(generator).map(|inner| {
+            let current = f(inner.current().clone());
+            ComposedValueTree {
+                inner,
+                f: f.clone(),
+                current,
+            }
+        })
+    }
+}
+
+pub struct ComposedValueTree<V, F, T> {
+    inner: V,
+    f: F,
+    current: T,
+}
+
+impl<V, F, T> ComposedValueTree<V, F, T>
+where
+    V: ValueTree,
+    V::Value: Clone,
+    F: Fn(V::Value) -> T,
+{
+    fn sync_current(&mut self) {
+        self.current = (self.f)(self.inner.current().clone());
+    }
+}
+
+impl<V, F, T> ValueTree for ComposedValueTree<V, F, T>
+where
+    V: ValueTree,
+    V::Value: Clone,
+    F: Fn(V::Value) -> T,
+{
+    type Value = T;
+
+    fn current(&self) -> &T {
+        &self.current
+    }
+
+    fn into_current(self) -> T {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if !self.inner.simplify() {
+            return false;
+        }
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+/// Write a function returning a [`Strategy`] built from other strategies.
+///
+/// The first parameter list is the function's own, the second binds a
+/// pattern to a strategy for every value the body needs:
+///
+/// ```
+/// use estoa_proptest::{compose_strategy, strategy::AnyI32};
+///
+/// #[derive(Clone, Debug)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// compose_strategy! {
+///     fn point(max: i32)(
+///         x in AnyI32::new(0..=max),
+///         y in AnyI32::new(0..=max),
+///     ) -> Point {
+///         Point { x, y }
+///     }
+/// }
+/// ```
+///
+/// `point(100)` then draws `x` and `y` together through a tuple strategy and
+/// builds a `Point` from them, shrinking `x` first and `y` after it. The
+/// strategies need `Clone` values, and the body may run once per shrink step,
+/// so it can only use the function's parameters by reference or by copy.
+#[macro_export]
+macro_rules! compose_strategy {
+    (
+        $(#[$meta:meta])*
+        $vis:vis fn $name:ident($($param:ident: $param_ty:ty),* $(,)?)
+            ($($arg:pat in $strategy:expr),+ $(,)?)
+            -> $ret:ty { $($body:tt)* }
+    ) => {
+        $(#[$meta])*
+        $vis fn $name(
+            $($param: $param_ty),*
+        ) -> impl $crate::strategy::Strategy<Value = $ret> {
+            $crate::strategy::compose(
+                ($($strategy,)+),
+                move |($($arg,)+)| -> $ret { $($body)* },
+            )
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::{AnyI32, AnyU8};
+
+    #[test]
+    fn composed_values_shrink_through_the_inner_strategy() {
+        let mut strategy =
+            compose((AnyI32::new(-50..=50), AnyU8::new(1..=9)), |(a, b)| {
+                a * i32::from(b)
+            });
+        let mut generator = Generator::build(StdRng::seed_from_u64(3));
+
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            while tree.simplify() {}
+            assert_eq!(*tree.current(), 0);
+        }
+    }
+
+    #[test]
+    fn complicate_rebuilds_the_previous_value() {
+        let mut strategy = compose((AnyU8::new(10..=200),), |(a,)| a / 2);
+        let mut generator = Generator::build(StdRng::seed_from_u64(5));
+        let mut tree = strategy.new_tree(&mut generator).take();
+
+        let before = *tree.current();
+        assert!(tree.simplify());
+        let _ = tree.complicate();
+        assert_eq!(*tree.current(), before);
+    }
+}
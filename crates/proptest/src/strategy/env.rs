@@ -0,0 +1,285 @@
+//! Strategies for environment variables.
+//!
+//! [`EnvStrategy`] generates whole environments, maps from names to values
+//! as [`Command::envs`](std::process::Command::envs) takes them, for testing
+//! process-spawning and configuration-loading code. Names and values mix the
+//! usual (`PATH`, `HOME`, paths and `key=value` pairs) with the awkward:
+//! spaces, newlines, shell and batch syntax, non-ASCII characters and, on
+//! Unix and Windows, text that is not valid Unicode. Names that no platform
+//! accepts, empty ones and those containing `=` or NUL, are opt-in with
+//! [`EnvStrategy::with_invalid_names`].
+//!
+//! Shrinking removes variables, then shortens names and values and moves
+//! them towards `A` and the empty string.
+
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    ops::RangeInclusive,
+};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    FragmentValueTree,
+    HashMapStrategy,
+    HashMapValueTree,
+    SizeHint,
+    Strategy,
+    fragments::fragments,
+    runtime::{Generation, Generator},
+};
+
+#[derive(Clone, Copy)]
+enum Fragment {
+    Text(&'static str),
+    /// Text that is not valid Unicode where the platform allows it.
+    NotUnicode,
+}
+
+use Fragment::{NotUnicode, Text};
+
+/// Name fragments. The ones after [`VALID_NAME_FRAGMENTS`] make a name
+/// invalid on every platform.
+const NAME_FRAGMENTS: &[Fragment] = &[
+    Text("A"),
+    Text("PATH"),
+    Text("HOME"),
+    Text("LANG"),
+    Text("_"),
+    Text("X_1"),
+    Text("lower"),
+    Text("with space"),
+    Text("ü"),
+    NotUnicode,
+    Text(""),
+    Text("="),
+    Text("\0"),
+];
+
+const VALID_NAME_FRAGMENTS: usize = 10;
+
+/// Value fragments. The NUL after [`VALID_VALUE_FRAGMENTS`] is rejected by
+/// every platform as well, and only generated along with invalid names.
+const VALUE_FRAGMENTS: &[Fragment] = &[
+    Text("a"),
+    Text("1"),
+    Text("true"),
+    Text("="),
+    Text("a=b"),
+    Text("/usr/bin:/bin"),
+    Text("C:\\Windows;C:\\"),
+    Text(" "),
+    Text("\n"),
+    Text("$HOME"),
+    Text("%PATH%"),
+    Text("'\""),
+    Text("ü"),
+    NotUnicode,
+    Text("\0"),
+];
+
+const VALID_VALUE_FRAGMENTS: usize = 14;
+
+/// Whether every platform accepts `name` as the name of a variable: it is
+/// not empty and holds neither `=` nor NUL.
+pub fn is_valid_name(name: &OsStr) -> bool {
+    let bytes = name.as_encoded_bytes();
+    !bytes.is_empty() && !bytes.contains(&b'=') && !bytes.contains(&0)
+}
+
+#[cfg(unix)]
+fn not_unicode() -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    // A UTF-8 lead byte without its continuation.
+    OsString::from_vec(vec![b'x', 0xC3])
+}
+
+#[cfg(windows)]
+fn not_unicode() -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+
+    // An unpaired surrogate.
+    OsString::from_wide(&[u16::from(b'x'), 0xD800])
+}
+
+#[cfg(not(any(unix, windows)))]
+fn not_unicode() -> OsString {
+    OsString::from("x\u{FFFD}")
+}
+
+fn render(table: &[Fragment], fragments: &[usize]) -> OsString {
+    let mut text = OsString::new();
+    for &index in fragments {
+        match table[index] {
+            Text(fragment) => text.push(fragment),
+            NotUnicode => text.push(not_unicode()),
+        }
+    }
+    text
+}
+
+fn render_name(fragments: &[usize]) -> OsString {
+    render(NAME_FRAGMENTS, fragments)
+}
+
+fn render_value(fragments: &[usize]) -> OsString {
+    render(VALUE_FRAGMENTS, fragments)
+}
+
+/// The names of an [`EnvStrategy`].
+#[derive(Clone)]
+pub struct EnvNameStrategy {
+    invalid: bool,
+}
+
+impl Strategy for EnvNameStrategy {
+    type Value = OsString;
+    type Tree = FragmentValueTree<OsString>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let table_len = if self.invalid {
+            NAME_FRAGMENTS.len()
+        } else {
+            VALID_NAME_FRAGMENTS
+        };
+        fragments(generator, "env name", table_len, &(1..=3), render_name)
+    }
+}
+
+/// The values of an [`EnvStrategy`].
+#[derive(Clone)]
+pub struct EnvValueStrategy {
+    invalid: bool,
+}
+
+impl Strategy for EnvValueStrategy {
+    type Value = OsString;
+    type Tree = FragmentValueTree<OsString>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let table_len = if self.invalid {
+            VALUE_FRAGMENTS.len()
+        } else {
+            VALID_VALUE_FRAGMENTS
+        };
+        fragments(generator, "env value", table_len, &(0..=6), render_value)
+    }
+}
+
+/// Generate environments, see the [module documentation](self).
+#[derive(Clone)]
+pub struct EnvStrategy {
+    len_range: RangeInclusive<usize>,
+    invalid: bool,
+}
+
+impl EnvStrategy {
+    pub fn new<H: SizeHint>(size_hint: H) -> Self {
+        Self {
+            len_range: size_hint.to_inclusive(),
+            invalid: false,
+        }
+    }
+
+    /// Also generate names that are empty or hold `=` or NUL, and values
+    /// holding NUL. Setting those for a process fails or panics, which is
+    /// what code validating an environment needs to see.
+    pub fn with_invalid_names(mut self, invalid: bool) -> Self {
+        self.invalid = invalid;
+        self
+    }
+}
+
+impl Default for EnvStrategy {
+    fn default() -> Self {
+        Self::new(0usize..=8usize)
+    }
+}
+
+impl Strategy for EnvStrategy {
+    type Value = HashMap<OsString, OsString>;
+    type Tree = HashMapValueTree<
+        FragmentValueTree<OsString>,
+        FragmentValueTree<OsString>,
+    >;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        HashMapStrategy::new(
+            EnvNameStrategy {
+                invalid: self.invalid,
+            },
+            EnvValueStrategy {
+                invalid: self.invalid,
+            },
+            self.len_range.clone(),
+        )
+        .new_tree(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        strategy::ValueTree,
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    #[test]
+    fn names_are_valid_unless_asked_otherwise() {
+        check_shrink_invariants_with(EnvStrategy::default(), 64, |env| {
+            env.iter().all(|(name, value)| {
+                is_valid_name(name) && !value.as_encoded_bytes().contains(&0)
+            })
+        });
+    }
+
+    #[test]
+    fn invalid_names_and_odd_values_are_generated() {
+        let mut strategy = EnvStrategy::default().with_invalid_names(true);
+        let mut generator = Generator::build(StdRng::seed_from_u64(7));
+        let (mut invalid, mut equals, mut not_unicode) = (false, false, false);
+        for _ in 0..256 {
+            let env = strategy.new_tree(&mut generator).take().into_current();
+            for (name, value) in &env {
+                invalid |= !is_valid_name(name);
+                equals |= value.as_encoded_bytes().contains(&b'=');
+                not_unicode |=
+                    name.to_str().is_none() || value.to_str().is_none();
+            }
+        }
+        assert!(invalid && equals);
+        assert_eq!(not_unicode, cfg!(any(unix, windows)));
+    }
+
+    #[test]
+    fn environments_shrink_to_a_single_variable() {
+        let mut strategy = EnvStrategy::default();
+        let mut generator = Generator::build(StdRng::seed_from_u64(11));
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            if tree.current().is_empty() {
+                continue;
+            }
+            let shrunk =
+                crate::shrink::shrink(&mut tree, |env| !env.is_empty());
+            assert_eq!(
+                shrunk.value,
+                HashMap::from([(OsString::from("A"), OsString::new())])
+            );
+        }
+    }
+}
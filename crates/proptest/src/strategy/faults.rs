@@ -0,0 +1,381 @@
+//! Fault injection.
+//!
+//! [`FaultStrategy`] wraps a strategy and now and then turns its value into
+//! a failure: an `Err`, a `None` or a truncated collection, as a [`Fault`]
+//! decides. Generating the results of a sequence of calls, with
+//! [`VecStrategy`](crate::strategy::VecStrategy), makes a failure plan for
+//! a mocked dependency, so error-handling paths run as often as the happy
+//! one:
+//!
+//! ```
+//! use estoa_proptest::strategy::{
+//!     AnyU8,
+//!     VecStrategy,
+//!     faults::{ErrFault, FaultStrategy},
+//! };
+//!
+//! // The replies of eight calls, a third of them failing.
+//! let _replies = VecStrategy::new(
+//!     FaultStrategy::new(AnyU8::default(), ErrFault("timed out"))
+//!         .with_probability(0.3),
+//!     8usize..=8usize,
+//! );
+//! ```
+//!
+//! Shrinking takes failures out of the plan first, keeping only the ones the
+//! property needs to fail. It then makes the remaining failures simpler, such
+//! as truncating collections further, and then shrinks the values.
+
+use alloc::{string::String, vec::Vec};
+
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::strategy::{
+    IntValueTree,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// How a [`FaultStrategy`] makes its output from a generated value, for a
+/// call that goes through and for one that fails.
+pub trait Fault<T> {
+    type Output;
+
+    /// The output of a call that goes through.
+    fn pass(&self, value: T) -> Self::Output;
+
+    /// The output of a call that fails the `cut`th way, out of
+    /// [`cuts`](Self::cuts). Lower cuts are simpler failures.
+    fn inject(&self, value: T, cut: usize) -> Self::Output;
+
+    /// How many ways a call with `value` can fail.
+    fn cuts(&self, _value: &T) -> usize {
+        1
+    }
+}
+
+/// Fail calls with `Err` of the error it holds.
+#[derive(Clone, Debug)]
+pub struct ErrFault<E>(pub E);
+
+impl<T, E: Clone> Fault<T> for ErrFault<E> {
+    type Output = Result<T, E>;
+
+    fn pass(&self, value: T) -> Result<T, E> {
+        Ok(value)
+    }
+
+    fn inject(&self, _value: T, _cut: usize) -> Result<T, E> {
+        Err(self.0.clone())
+    }
+}
+
+/// Fail calls with `None`.
+#[derive(Clone, Copy, Debug)]
+pub struct NoneFault;
+
+impl<T> Fault<T> for NoneFault {
+    type Output = Option<T>;
+
+    fn pass(&self, value: T) -> Option<T> {
+        Some(value)
+    }
+
+    fn inject(&self, _value: T, _cut: usize) -> Option<T> {
+        None
+    }
+}
+
+/// Fail calls by cutting a collection short, as a partial read or an
+/// interrupted transfer does. Failures keep `cut` elements, and lose at
+/// least one of a collection that is not empty.
+#[derive(Clone, Copy, Debug)]
+pub struct Truncate;
+
+impl<T> Fault<Vec<T>> for Truncate {
+    type Output = Vec<T>;
+
+    fn pass(&self, value: Vec<T>) -> Vec<T> {
+        value
+    }
+
+    fn inject(&self, mut value: Vec<T>, cut: usize) -> Vec<T> {
+        value.truncate(cut.min(value.len().saturating_sub(1)));
+        value
+    }
+
+    fn cuts(&self, value: &Vec<T>) -> usize {
+        value.len()
+    }
+}
+
+impl Fault<String> for Truncate {
+    type Output = String;
+
+    fn pass(&self, value: String) -> String {
+        value
+    }
+
+    /// Keeps `cut` characters rather than bytes, so the output stays valid.
+    fn inject(&self, mut value: String, cut: usize) -> String {
+        let chars = value.chars().count();
+        let keep = cut.min(chars.saturating_sub(1));
+        if let Some((end, _)) = value.char_indices().nth(keep) {
+            value.truncate(end);
+        }
+        value
+    }
+
+    fn cuts(&self, value: &String) -> usize {
+        value.chars().count()
+    }
+}
+
+/// Wrap a strategy to inject failures, see the
+/// [module documentation](self).
+///
+/// A tenth of the calls fail unless
+/// [`with_probability`](Self::with_probability) says otherwise.
+#[derive(Clone)]
+pub struct FaultStrategy<S, F> {
+    inner: S,
+    fault: F,
+    probability: f64,
+}
+
+impl<S, F> FaultStrategy<S, F> {
+    pub fn new(inner: S, fault: F) -> Self {
+        Self {
+            inner,
+            fault,
+            probability: 0.1,
+        }
+    }
+
+    /// Fail calls with probability `probability`, clamped to `0..=1`.
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.probability = probability.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl<S, F> Strategy for FaultStrategy<S, F>
+where
+    S: Strategy,
+    S::Value: Clone,
+    F: Fault<S::Value> + Clone,
+{
+    type Value = F::Output;
+    type Tree = FaultValueTree<S::Tree, F>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let injected = generator.choose_weighted(
+            core::any::type_name::<Self>(),
+            &[1.0 - self.probability, self.probability],
+        ) == 1;
+
+        self.inner.new_tree(generator).map(|inner| {
+            let cuts = self.fault.cuts(inner.current()).max(1);
+            let cut = generator.rng.random_range(0..cuts);
+            let mut tree = FaultValueTree {
+                current: self.fault.pass(inner.current().clone()),
+                inner,
+                fault: self.fault.clone(),
+                injected,
+                cut: IntValueTree::new(cut, (0..cut).rev().collect()),
+                stage: Stage::Plan,
+                last: None,
+            };
+            tree.sync_current();
+            tree
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    /// Taking the failure out.
+    Plan,
+    /// Simplifying the failure.
+    Cut,
+    /// Shrinking the value.
+    Inner,
+}
+
+pub struct FaultValueTree<T: ValueTree, F: Fault<T::Value>> {
+    inner: T,
+    fault: F,
+    injected: bool,
+    cut: IntValueTree<usize>,
+    stage: Stage,
+    /// The stage of the last successful `simplify`.
+    last: Option<Stage>,
+    current: F::Output,
+}
+
+impl<T, F> FaultValueTree<T, F>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    F: Fault<T::Value>,
+{
+    fn sync_current(&mut self) {
+        let value = self.inner.current().clone();
+        self.current = if self.injected {
+            self.fault.inject(value, *self.cut.current())
+        } else {
+            self.fault.pass(value)
+        };
+    }
+}
+
+impl<T, F> ValueTree for FaultValueTree<T, F>
+where
+    T: ValueTree,
+    T::Value: Clone,
+    F: Fault<T::Value>,
+{
+    type Value = F::Output;
+
+    fn current(&self) -> &F::Output {
+        &self.current
+    }
+
+    fn into_current(self) -> F::Output {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        loop {
+            match self.stage {
+                Stage::Plan => {
+                    self.stage = Stage::Cut;
+                    if self.injected {
+                        self.injected = false;
+                        self.last = Some(Stage::Plan);
+                        self.sync_current();
+                        return true;
+                    }
+                }
+                Stage::Cut => {
+                    if self.injected && self.cut.simplify() {
+                        self.last = Some(Stage::Cut);
+                        self.sync_current();
+                        return true;
+                    }
+                    self.stage = Stage::Inner;
+                }
+                Stage::Inner => {
+                    if self.inner.simplify() {
+                        self.last = Some(Stage::Inner);
+                        self.sync_current();
+                        return true;
+                    }
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let more = match self.last.take() {
+            None => return false,
+            Some(Stage::Plan) => {
+                self.injected = true;
+                true
+            }
+            Some(Stage::Cut) => {
+                if !self.cut.complicate() {
+                    self.stage = Stage::Inner;
+                }
+                true
+            }
+            Some(Stage::Inner) => self.inner.complicate(),
+        };
+        self.sync_current();
+        more
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy::{AnyU8, VecStrategy},
+        strategy_test::check_shrink_invariants,
+    };
+
+    #[test]
+    fn shrink_invariants_hold() {
+        check_shrink_invariants(
+            FaultStrategy::new(AnyU8::default(), ErrFault("boom"))
+                .with_probability(0.5),
+            64,
+        );
+        check_shrink_invariants(
+            FaultStrategy::new(
+                VecStrategy::new(AnyU8::default(), 0usize..=8usize),
+                Truncate,
+            )
+            .with_probability(0.5),
+            64,
+        );
+    }
+
+    #[test]
+    fn plans_shrink_to_the_failures_needed() {
+        assert_shrinks_to!(
+            VecStrategy::new(
+                FaultStrategy::new(AnyU8::default(), ErrFault("boom"))
+                    .with_probability(0.3),
+                0usize..=8usize,
+            ),
+            |replies: &Vec<Result<u8, &str>>| {
+                replies.iter().filter(|reply| reply.is_err()).count() < 2
+            },
+            vec![Err("boom"), Err("boom")],
+        );
+    }
+
+    #[test]
+    fn truncation_shrinks_to_the_shortest_cut() {
+        assert_shrinks_to!(
+            FaultStrategy::new(
+                VecStrategy::new(AnyU8::default(), 4usize..=4usize),
+                Truncate,
+            ),
+            |bytes: &Vec<u8>| bytes.len() > 1,
+            Vec::<u8>::new(),
+        );
+    }
+
+    #[test]
+    fn strings_are_cut_between_characters() {
+        let text = "añb".to_string();
+        assert_eq!(Truncate.cuts(&text), 3);
+        assert_eq!(Truncate.inject(text.clone(), 2), "añ");
+        assert_eq!(Truncate.inject(text.clone(), 9), "añ");
+        assert_eq!(Truncate.inject(text, 0), "");
+        assert_eq!(Truncate.inject(String::new(), 0), "");
+    }
+
+    #[test]
+    fn probability_bounds_the_failures() {
+        let mut generator = Generator::build(crate::rng());
+        let mut never = FaultStrategy::new(AnyU8::default(), NoneFault)
+            .with_probability(0.0);
+        let mut always = FaultStrategy::new(AnyU8::default(), NoneFault)
+            .with_probability(1.0);
+        for _ in 0..256 {
+            assert!(never.new_tree(&mut generator).take().current().is_some());
+            assert!(always.new_tree(&mut generator).take().current().is_none());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Values assembled from the fragments of a curated table, such as the
+//! strings of [`sql`](super::sql).
+
+use core::ops::RangeInclusive;
+
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::strategy::{
+    IntValueTree,
+    ValueTree,
+    VecValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Draw between `len_range` fragments of a table of `table_len` entries.
+/// Fragments are chosen through [`Generator::choose`] under `key`, so swarm
+/// testing leaves some of them out of a case entirely.
+pub(crate) fn fragments<R, T>(
+    generator: &mut Generator<R>,
+    key: &str,
+    table_len: usize,
+    len_range: &RangeInclusive<usize>,
+    render: fn(&[usize]) -> T,
+) -> Generation<FragmentValueTree<T>>
+where
+    R: RngCore + CryptoRng,
+{
+    let len = generator
+        .rng
+        .random_range(generator.sized_len(len_range.clone()));
+
+    let trees = (0..len)
+        .map(|_| {
+            let index = generator.choose(key, table_len);
+            // Every earlier entry is a candidate, nearest first, so shrinking
+            // ends on the earliest entry that still fails.
+            IntValueTree::new(index, (0..index).rev().collect())
+        })
+        .collect();
+    let mut tree = FragmentValueTree {
+        inner: VecValueTree::from_trees(trees, *len_range.start()),
+        render,
+        current: render(&[]),
+    };
+    tree.sync_current();
+    generator.accept(tree)
+}
+
+/// A value assembled from the fragments of a table, which shrink like a
+/// vector of their indices.
+pub struct FragmentValueTree<T> {
+    inner: VecValueTree<IntValueTree<usize>>,
+    render: fn(&[usize]) -> T,
+    current: T,
+}
+
+impl<T> FragmentValueTree<T> {
+    fn sync_current(&mut self) {
+        self.current = (self.render)(self.inner.current());
+    }
+}
+
+impl<T> ValueTree for FragmentValueTree<T> {
+    type Value = T;
+
+    fn current(&self) -> &T {
+        &self.current
+    }
+
+    fn into_current(self) -> T {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if !self.inner.simplify() {
+            return false;
+        }
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
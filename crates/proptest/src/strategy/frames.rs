@@ -0,0 +1,451 @@
+//! Strategies for binary protocol frames.
+//!
+//! Every strategy here is a [`FrameStrategy`]: an inner strategy whose values
+//! are written out as bytes by an [`Encoding`]. The encoding runs again on
+//! every shrink candidate, so a length prefix always matches the payload it
+//! precedes and a checksum always matches the bytes it covers, however far
+//! the payload shrinks.
+//!
+//! [`int_field`] writes integers in either byte order, [`length_prefixed`]
+//! puts the length of a payload in front of it and [`checksummed`] appends a
+//! checksum such as [`crc32`]. Fields are joined with
+//! [`compose`](crate::strategy::compose):
+//!
+//! ```
+//! use estoa_proptest::strategy::{
+//!     AnyU8,
+//!     VecStrategy,
+//!     compose,
+//!     frames::{Endian, checksummed, crc32, int_field, length_prefixed},
+//! };
+//!
+//! let header = int_field(AnyU8::new(1..=4), Endian::Big);
+//! let body = length_prefixed::<u16, _>(
+//!     VecStrategy::new(AnyU8::default(), 0usize..=64usize),
+//!     Endian::Big,
+//! );
+//! let frame = checksummed(
+//!     compose((header, body), |(header, body)| [header, body].concat()),
+//!     crc32,
+//!     Endian::Little,
+//! );
+//! ```
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator, RejectReason},
+};
+
+/// The byte order integers are written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Big,
+    Little,
+}
+
+/// Integers that can be written into a frame.
+pub trait FrameInt: Copy + TryFrom<usize> {
+    fn write(self, endian: Endian, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_frame_int {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FrameInt for $ty {
+                fn write(self, endian: Endian, out: &mut Vec<u8>) {
+                    match endian {
+                        Endian::Big => {
+                            out.extend_from_slice(&self.to_be_bytes())
+                        }
+                        Endian::Little => {
+                            out.extend_from_slice(&self.to_le_bytes())
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_frame_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// How a [`FrameStrategy`] turns the values of its inner strategy into
+/// bytes.
+pub trait Encoding: Clone {
+    type Input;
+
+    fn encode(&self, input: &Self::Input, out: &mut Vec<u8>);
+
+    /// Whether `input` can be encoded at all. Generated values that cannot
+    /// are rejected.
+    fn accepts(&self, _input: &Self::Input) -> bool {
+        true
+    }
+}
+
+/// Writes an integer in a fixed byte order, see [`int_field`].
+#[derive(Clone, Copy, Debug)]
+pub struct IntField<T> {
+    endian: Endian,
+    int: PhantomData<fn() -> T>,
+}
+
+impl<T: FrameInt> Encoding for IntField<T> {
+    type Input = T;
+
+    fn encode(&self, input: &T, out: &mut Vec<u8>) {
+        input.write(self.endian, out);
+    }
+}
+
+/// Writes the length of a payload as a `T`, then the payload, see
+/// [`length_prefixed`].
+#[derive(Clone, Copy, Debug)]
+pub struct LengthPrefix<T> {
+    endian: Endian,
+    int: PhantomData<fn() -> T>,
+}
+
+impl<T: FrameInt> Encoding for LengthPrefix<T> {
+    type Input = Vec<u8>;
+
+    fn encode(&self, input: &Vec<u8>, out: &mut Vec<u8>) {
+        let Ok(len) = T::try_from(input.len()) else {
+            unreachable!("oversized payloads are rejected before encoding")
+        };
+        len.write(self.endian, out);
+        out.extend_from_slice(input);
+    }
+
+    fn accepts(&self, input: &Vec<u8>) -> bool {
+        T::try_from(input.len()).is_ok()
+    }
+}
+
+/// Writes a frame, then its checksum, see [`checksummed`].
+#[derive(Clone, Copy, Debug)]
+pub struct Checksum<F> {
+    checksum: F,
+    endian: Endian,
+}
+
+impl<F, C> Encoding for Checksum<F>
+where
+    F: Fn(&[u8]) -> C + Clone,
+    C: FrameInt,
+{
+    type Input = Vec<u8>;
+
+    fn encode(&self, input: &Vec<u8>, out: &mut Vec<u8>) {
+        out.extend_from_slice(input);
+        (self.checksum)(input).write(self.endian, out);
+    }
+}
+
+/// Write the integers of `strategy` in `endian` byte order.
+///
+/// Shrinking happens on the integers, so a field shrinks towards the
+/// smallest value of `strategy` rather than towards zero bytes.
+pub fn int_field<S, T>(
+    strategy: S,
+    endian: Endian,
+) -> FrameStrategy<S, IntField<T>>
+where
+    S: Strategy<Value = T>,
+    T: FrameInt,
+{
+    FrameStrategy::new(
+        strategy,
+        IntField {
+            endian,
+            int: PhantomData,
+        },
+    )
+}
+
+/// Put the length of every payload of `payload` in front of it, as a `T` in
+/// `endian` byte order.
+///
+/// Payloads longer than `T` can count are rejected, so `payload` should
+/// stay within that length. Shrinking only shortens payloads, so it never
+/// produces one.
+pub fn length_prefixed<T, S>(
+    payload: S,
+    endian: Endian,
+) -> FrameStrategy<S, LengthPrefix<T>>
+where
+    T: FrameInt,
+    S: Strategy<Value = Vec<u8>>,
+{
+    FrameStrategy::new(
+        payload,
+        LengthPrefix {
+            endian,
+            int: PhantomData,
+        },
+    )
+}
+
+/// Append `checksum` of every frame of `frame` to it, in `endian` byte
+/// order.
+///
+/// The checksum is computed again for every shrink candidate, so shrunk
+/// frames still pass the integrity check of the code under test and reach
+/// the logic behind it.
+pub fn checksummed<S, F, C>(
+    frame: S,
+    checksum: F,
+    endian: Endian,
+) -> FrameStrategy<S, Checksum<F>>
+where
+    S: Strategy<Value = Vec<u8>>,
+    F: Fn(&[u8]) -> C + Clone,
+    C: FrameInt,
+{
+    FrameStrategy::new(frame, Checksum { checksum, endian })
+}
+
+/// The CRC-32 of `bytes`, as used by Ethernet, zlib and PNG.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Generate the bytes `encoding` writes for the values of an inner
+/// strategy, see the [module documentation](self).
+#[derive(Clone)]
+pub struct FrameStrategy<S, E> {
+    inner: S,
+    encoding: E,
+}
+
+impl<S, E> FrameStrategy<S, E>
+where
+    S: Strategy,
+    E: Encoding<Input = S::Value>,
+{
+    pub fn new(inner: S, encoding: E) -> Self {
+        Self { inner, encoding }
+    }
+}
+
+impl<S, E> Strategy for FrameStrategy<S, E>
+where
+    S: Strategy,
+    E: Encoding<Input = S::Value>,
+{
+    type Value = Vec<u8>;
+    type Tree = FrameValueTree<S::Tree, E>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let (inner, rejected) = match self.inner.new_tree(generator) {
+            Generation::Accepted { value, .. } => (value, None),
+            Generation::Rejected { reason, value, .. } => (value, Some(reason)),
+        };
+        let encodable = self.encoding.accepts(inner.current());
+
+        let mut tree = FrameValueTree {
+            inner,
+            encoding: self.encoding.clone(),
+            current: Vec::new(),
+        };
+        if !encodable {
+            return generator.reject_with(RejectReason::Filter, tree);
+        }
+        tree.sync_current();
+
+        match rejected {
+            Some(Some(reason)) => generator.reject_with(reason, tree),
+            Some(None) => generator.reject(tree),
+            None => generator.accept(tree),
+        }
+    }
+}
+
+pub struct FrameValueTree<V, E> {
+    inner: V,
+    encoding: E,
+    current: Vec<u8>,
+}
+
+impl<V, E> FrameValueTree<V, E>
+where
+    V: ValueTree,
+    E: Encoding<Input = V::Value>,
+{
+    fn sync_current(&mut self) {
+        self.current.clear();
+        self.encoding
+            .encode(self.inner.current(), &mut self.current);
+    }
+}
+
+impl<V, E> ValueTree for FrameValueTree<V, E>
+where
+    V: ValueTree,
+    E: Encoding<Input = V::Value>,
+{
+    type Value = Vec<u8>;
+
+    fn current(&self) -> &Vec<u8> {
+        &self.current
+    }
+
+    fn into_current(self) -> Vec<u8> {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if !self.inner.simplify() {
+            return false;
+        }
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy::{AnyU8, AnyU32, VecStrategy, compose},
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    fn payload(max: usize) -> VecStrategy<AnyU8> {
+        VecStrategy::new(AnyU8::default(), 0usize..=max)
+    }
+
+    /// A frame is a `u16` big-endian length, the payload and its CRC-32 in
+    /// little-endian byte order.
+    fn frame() -> impl Strategy<Value = Vec<u8>> {
+        checksummed(
+            length_prefixed::<u16, _>(payload(32), Endian::Big),
+            crc32,
+            Endian::Little,
+        )
+    }
+
+    fn decode(frame: &[u8]) -> Option<&[u8]> {
+        let (body, crc) =
+            frame.split_at_checked(frame.len().checked_sub(4)?)?;
+        if crc32(body).to_le_bytes() != crc {
+            return None;
+        }
+        let (len, payload) = body.split_at_checked(2)?;
+        (usize::from(u16::from_be_bytes([len[0], len[1]])) == payload.len())
+            .then_some(payload)
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn int_fields_follow_the_byte_order() {
+        let mut generator = Generator::build(StdRng::seed_from_u64(3));
+        let mut big =
+            int_field(AnyU32::new(0x0102_0304..=0x0102_0304), Endian::Big);
+        let mut little =
+            int_field(AnyU32::new(0x0102_0304..=0x0102_0304), Endian::Little);
+
+        assert_eq!(
+            big.new_tree(&mut generator).take().current(),
+            &[1, 2, 3, 4]
+        );
+        assert_eq!(
+            little.new_tree(&mut generator).take().current(),
+            &[4, 3, 2, 1]
+        );
+    }
+
+    #[test]
+    fn frames_stay_valid_while_shrinking() {
+        check_shrink_invariants_with(frame(), 64, |frame| {
+            decode(frame).is_some()
+        });
+    }
+
+    #[test]
+    fn frames_shrink_to_the_smallest_valid_frame() {
+        let mut expected = vec![0, 1, 0];
+        expected.extend_from_slice(&crc32(&[0, 1, 0]).to_le_bytes());
+        assert_shrinks_to!(
+            frame(),
+            |frame: &Vec<u8>| decode(frame).is_some_and(|p| p.is_empty()),
+            expected
+        );
+    }
+
+    #[test]
+    fn payloads_longer_than_the_prefix_are_rejected() {
+        let mut strategy = length_prefixed::<u8, _>(
+            compose(
+                (VecStrategy::new(AnyU8::default(), 32usize..=32usize),),
+                |(payload,)| payload.repeat(8),
+            ),
+            Endian::Big,
+        );
+        let mut generator = Generator::build(StdRng::seed_from_u64(5));
+        assert!(matches!(
+            strategy.new_tree(&mut generator),
+            Generation::Rejected {
+                reason: Some(RejectReason::Filter),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn composed_fields_keep_their_encoding() {
+        let mut strategy = compose(
+            (
+                int_field(AnyU8::new(7..=7), Endian::Big),
+                length_prefixed::<u32, _>(payload(8), Endian::Little),
+            ),
+            |(kind, body)| [kind, body].concat(),
+        );
+        let mut generator = Generator::build(StdRng::seed_from_u64(7));
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            loop {
+                let frame = tree.current();
+                assert_eq!(frame[0], 7);
+                let len = u32::from_le_bytes(frame[1..5].try_into().unwrap());
+                assert_eq!(len as usize, frame.len() - 5);
+                if !tree.simplify() {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,505 @@
+//! Strategies for directory trees.
+//!
+//! [`DirTreeStrategy`] generates a [`DirTree`]: a description of nested
+//! directories and files with their contents, for testing code that walks,
+//! copies or archives a filesystem. With the `tempfile` feature,
+//! [`DirTree::materialize`] writes it into a fresh temporary directory that
+//! is removed again when the returned [`TempDir`](tempfile::TempDir) drops,
+//! so every case gets a tree of its own.
+//!
+//! Shrinking removes entries, a directory together with everything in it,
+//! then shrinks file contents and finally renames entries towards `a`.
+
+use std::{
+    collections::HashSet,
+    io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::strategy::{
+    AnyU8,
+    IntValueTree,
+    SizeHint,
+    Strategy,
+    ValueTree,
+    VecStrategy,
+    VecValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Entry names, the plainest first. The rest cover hidden files, extensions,
+/// spaces, leading dashes, mixed case and non-ASCII characters.
+const NAMES: &[&str] = &[
+    "a",
+    "b",
+    "c.txt",
+    "data.tar.gz",
+    ".hidden",
+    "with space",
+    "-dash",
+    "MixedCase",
+    "ünïcödé",
+    "日本語",
+    "trailing.",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Entry {
+    Dir { path: PathBuf },
+    File { path: PathBuf, contents: Vec<u8> },
+}
+
+impl Entry {
+    /// The path of the entry, relative to the root of its tree.
+    pub fn path(&self) -> &Path {
+        match self {
+            Entry::Dir { path } | Entry::File { path, .. } => path,
+        }
+    }
+}
+
+/// A tree of directories and files, relative to a root that is not part of
+/// it. Parents are listed before their children.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DirTree {
+    entries: Vec<Entry>,
+}
+
+impl DirTree {
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The paths and contents of the files in the tree.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &[u8])> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::File { path, contents } => {
+                Some((path.as_path(), &**contents))
+            }
+            Entry::Dir { .. } => None,
+        })
+    }
+
+    /// The paths of the directories in the tree.
+    pub fn dirs(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Dir { path } => Some(path.as_path()),
+            Entry::File { .. } => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Create every entry of the tree under `root`, which must exist.
+    pub fn write_to(&self, root: &Path) -> io::Result<()> {
+        for entry in &self.entries {
+            match entry {
+                Entry::Dir { path } => std::fs::create_dir(root.join(path))?,
+                Entry::File { path, contents } => {
+                    std::fs::write(root.join(path), contents)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the tree into a new temporary directory, which is deleted with
+    /// everything in it when the returned handle drops.
+    #[cfg(feature = "tempfile")]
+    pub fn materialize(&self) -> io::Result<tempfile::TempDir> {
+        let dir = tempfile::TempDir::new()?;
+        self.write_to(dir.path())?;
+        Ok(dir)
+    }
+}
+
+/// Generate [`DirTree`]s, see the [module documentation](self).
+#[derive(Clone)]
+pub struct DirTreeStrategy {
+    entries: RangeInclusive<usize>,
+    max_depth: usize,
+    contents: VecStrategy<AnyU8>,
+}
+
+impl DirTreeStrategy {
+    pub fn new<H: SizeHint>(entries: H) -> Self {
+        Self {
+            entries: entries.to_inclusive(),
+            max_depth: 4,
+            contents: VecStrategy::new(AnyU8::default(), 0usize..=64usize),
+        }
+    }
+
+    /// Bound how deeply directories nest. Entries at the root have depth
+    /// `1`, so a depth of `1` generates a flat directory.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        assert!(
+            max_depth > 0,
+            "a directory tree needs a depth of at least 1"
+        );
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Bound the size of every file in bytes.
+    pub fn with_file_size<H: SizeHint>(mut self, size_hint: H) -> Self {
+        self.contents = VecStrategy::new(AnyU8::default(), size_hint);
+        self
+    }
+}
+
+impl Default for DirTreeStrategy {
+    fn default() -> Self {
+        Self::new(0usize..=16usize)
+    }
+}
+
+impl Strategy for DirTreeStrategy {
+    type Value = DirTree;
+    type Tree = DirTreeValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let len = generator
+            .rng
+            .random_range(generator.sized_len(self.entries.clone()));
+        let mut nodes: Vec<Node> = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            let parents: Vec<usize> = nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| {
+                    node.contents.is_none() && node.depth < self.max_depth
+                })
+                .map(|(index, _)| index)
+                .collect();
+            let pick = generator.rng.random_range(0..=parents.len());
+            let parent = parents.get(pick).copied();
+            let depth = parent.map_or(1, |parent| nodes[parent].depth + 1);
+
+            let contents = if generator.choose("fs entry", 2) == 0 {
+                Some(self.contents.new_tree(generator).take())
+            } else {
+                None
+            };
+            let name = generator.choose("fs name", NAMES.len());
+            nodes.push(Node {
+                parent,
+                depth,
+                name: IntValueTree::new(name, (0..name).rev().collect()),
+                contents,
+            });
+        }
+
+        let mut tree = DirTreeValueTree {
+            removed: vec![false; nodes.len()],
+            nodes,
+            stage: Stage::Remove { index: 0 },
+            history: Vec::new(),
+            current: DirTree::default(),
+        };
+        tree.sync_current();
+        generator.accept(tree)
+    }
+}
+
+struct Node {
+    /// The directory the entry is in, `None` for the root.
+    parent: Option<usize>,
+    depth: usize,
+    /// An index into [`NAMES`].
+    name: IntValueTree<usize>,
+    /// The contents of a file, `None` for a directory.
+    contents: Option<VecValueTree<IntValueTree<u8>>>,
+}
+
+/// Shrink passes, in the order they run: remove entries in generation order,
+/// shrink the contents of every file, then rename every entry.
+#[derive(Clone, Copy)]
+enum Stage {
+    Remove { index: usize },
+    Contents { index: usize },
+    Names { index: usize },
+}
+
+#[derive(Clone, Copy)]
+enum History {
+    Removed { index: usize },
+    Contents { index: usize },
+    Name { index: usize },
+}
+
+pub struct DirTreeValueTree {
+    nodes: Vec<Node>,
+    /// Entries shrinking removed. Their children are gone with them.
+    removed: Vec<bool>,
+    stage: Stage,
+    history: Vec<History>,
+    current: DirTree,
+}
+
+impl DirTreeValueTree {
+    fn is_present(&self, mut index: usize) -> bool {
+        loop {
+            if self.removed[index] {
+                return false;
+            }
+            match self.nodes[index].parent {
+                Some(parent) => index = parent,
+                None => return true,
+            }
+        }
+    }
+
+    /// The first present entry at or after `index` that `keep` accepts.
+    fn next_present(
+        &self,
+        index: usize,
+        keep: impl Fn(&Node) -> bool,
+    ) -> Option<usize> {
+        (index..self.nodes.len())
+            .find(|&index| self.is_present(index) && keep(&self.nodes[index]))
+    }
+
+    /// Rebuild the current tree. Names that clash with an earlier sibling,
+    /// ignoring case as some filesystems do, get the entry's index appended.
+    fn sync_current(&mut self) {
+        let mut paths: Vec<Option<PathBuf>> = vec![None; self.nodes.len()];
+        let mut taken = HashSet::new();
+        let mut entries = Vec::new();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if !self.is_present(index) {
+                continue;
+            }
+            let parent = match node.parent {
+                Some(parent) => {
+                    paths[parent].clone().expect("parents come first")
+                }
+                None => PathBuf::new(),
+            };
+            let mut name = NAMES[*node.name.current()].to_owned();
+            if !taken.insert((node.parent, name.to_lowercase())) {
+                name = format!("{name}-{index}");
+                taken.insert((node.parent, name.to_lowercase()));
+            }
+            let path = parent.join(name);
+
+            entries.push(match &node.contents {
+                Some(contents) => Entry::File {
+                    path: path.clone(),
+                    contents: contents.current().clone(),
+                },
+                None => Entry::Dir { path: path.clone() },
+            });
+            paths[index] = Some(path);
+        }
+
+        self.current = DirTree { entries };
+    }
+}
+
+impl ValueTree for DirTreeValueTree {
+    type Value = DirTree;
+
+    fn current(&self) -> &DirTree {
+        &self.current
+    }
+
+    fn into_current(self) -> DirTree {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        loop {
+            match self.stage {
+                Stage::Remove { index } => {
+                    let Some(index) = self.next_present(index, |_| true) else {
+                        self.stage = Stage::Contents { index: 0 };
+                        continue;
+                    };
+                    self.removed[index] = true;
+                    self.stage = Stage::Remove { index: index + 1 };
+                    self.history.push(History::Removed { index });
+                }
+                Stage::Contents { index } => {
+                    let Some(index) = self
+                        .next_present(index, |node| node.contents.is_some())
+                    else {
+                        self.stage = Stage::Names { index: 0 };
+                        continue;
+                    };
+                    let contents =
+                        self.nodes[index].contents.as_mut().expect("a file");
+                    if !contents.simplify() {
+                        self.stage = Stage::Contents { index: index + 1 };
+                        continue;
+                    }
+                    self.stage = Stage::Contents { index };
+                    self.history.push(History::Contents { index });
+                }
+                Stage::Names { index } => {
+                    let Some(index) = self.next_present(index, |_| true) else {
+                        return false;
+                    };
+                    if !self.nodes[index].name.simplify() {
+                        self.stage = Stage::Names { index: index + 1 };
+                        continue;
+                    }
+                    self.stage = Stage::Names { index };
+                    self.history.push(History::Name { index });
+                }
+            }
+            self.sync_current();
+            return true;
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+
+        let more = match entry {
+            History::Removed { index } => {
+                self.removed[index] = false;
+                // Later entries, the files left to shrink and the names.
+                true
+            }
+            History::Contents { index } => {
+                let contents =
+                    self.nodes[index].contents.as_mut().expect("a file");
+                if contents.complicate() {
+                    self.history.push(History::Contents { index });
+                } else {
+                    self.stage = Stage::Contents { index: index + 1 };
+                }
+                true
+            }
+            History::Name { index } => {
+                if self.nodes[index].name.complicate() {
+                    self.history.push(History::Name { index });
+                    true
+                } else {
+                    self.stage = Stage::Names { index: index + 1 };
+                    self.next_present(index + 1, |_| true).is_some()
+                }
+            }
+        };
+        self.sync_current();
+        more
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy_test::{check_shrink_invariants_with, minimal_counterexample},
+    };
+
+    fn is_well_formed(tree: &DirTree) -> bool {
+        let dirs: HashSet<&Path> = tree.dirs().collect();
+        let mut seen = HashSet::new();
+        tree.entries().iter().all(|entry| {
+            let path = entry.path();
+            let parent = path.parent().expect("entries have a name");
+            (parent.as_os_str().is_empty() || dirs.contains(parent))
+                && seen.insert(path.to_string_lossy().to_lowercase())
+        })
+    }
+
+    #[test]
+    fn trees_stay_well_formed_while_shrinking() {
+        check_shrink_invariants_with(
+            DirTreeStrategy::default(),
+            64,
+            is_well_formed,
+        );
+    }
+
+    #[test]
+    fn depth_is_bounded() {
+        check_shrink_invariants_with(
+            DirTreeStrategy::default().with_max_depth(2),
+            64,
+            |tree| {
+                tree.entries()
+                    .iter()
+                    .all(|entry| entry.path().components().count() <= 2)
+            },
+        );
+    }
+
+    #[test]
+    fn trees_shrink_to_a_single_entry() {
+        assert_shrinks_to!(
+            DirTreeStrategy::default().with_max_depth(1),
+            |tree: &DirTree| {
+                tree.files().all(|(_, contents)| contents.is_empty())
+            },
+            DirTree {
+                entries: vec![Entry::File {
+                    path: PathBuf::from("a"),
+                    contents: vec![0],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn removing_a_directory_removes_its_contents() {
+        let minimal = minimal_counterexample(
+            DirTreeStrategy::default(),
+            |tree: &DirTree| {
+                tree.entries()
+                    .iter()
+                    .all(|entry| entry.path().components().count() == 1)
+            },
+        )
+        .expect("some tree nests an entry");
+
+        let [Entry::Dir { path: dir }, child] = minimal.entries() else {
+            panic!("expected a directory and its child, got {minimal:?}");
+        };
+        assert_eq!(dir, Path::new("a"));
+        assert_eq!(child.path(), Path::new("a/a"));
+    }
+
+    #[cfg(feature = "tempfile")]
+    #[test]
+    fn materialized_trees_match_their_description() {
+        let mut strategy = DirTreeStrategy::default();
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..16 {
+            let tree = strategy.new_tree(&mut generator).take().into_current();
+            let dir = tree.materialize().unwrap();
+            for (path, contents) in tree.files() {
+                assert_eq!(
+                    std::fs::read(dir.path().join(path)).unwrap(),
+                    contents
+                );
+            }
+            for path in tree.dirs() {
+                assert!(dir.path().join(path).is_dir());
+            }
+
+            let root = dir.path().to_owned();
+            drop(dir);
+            assert!(!root.exists());
+        }
+    }
+}
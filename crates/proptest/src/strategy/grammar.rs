@@ -0,0 +1,612 @@
+//! Strategies for token sequences described by a context-free grammar.
+//!
+//! A [`Grammar`] names a start rule and lists the alternatives of every
+//! rule, each a sequence of tokens and other rules. [`GrammarStrategy`]
+//! expands the start rule into a derivation tree and produces the tokens at
+//! its leaves, so every value is a sentence of the grammar:
+//!
+//! ```
+//! use estoa_proptest::strategy::grammar::{Grammar, GrammarStrategy, rule, token};
+//!
+//! let grammar = Grammar::new("expr")
+//!     .rule("expr", [vec![rule("num")], vec![rule("expr"), token("+"), rule("expr")]])
+//!     .rule("num", [[token("0")], [token("1")], [token("2")]]);
+//! let strategy = GrammarStrategy::new(grammar);
+//! ```
+//!
+//! Shrinking works on the derivation tree, so every candidate is a sentence
+//! too: a subtree is replaced by the smallest derivation of its rule, by a
+//! subtree of the same rule nested inside it, or by an earlier alternative,
+//! and the subtrees are then shrunk in turn. List the simplest alternative of
+//! each rule first.
+//!
+//! Transition tables, such as the states of a protocol or a Markov chain
+//! over events, are grammars whose alternatives are a token followed by the
+//! next state; [`Grammar::transition`] and [`Grammar::accepting`] declare
+//! them one edge at a time.
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc, vec, vec::Vec};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// One element of an alternative: a token that ends up in the generated
+/// sequence, or a rule expanded in its place.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Symbol {
+    Token(String),
+    Rule(String),
+}
+
+pub fn token(token: impl Into<String>) -> Symbol {
+    Symbol::Token(token.into())
+}
+
+pub fn rule(name: impl Into<String>) -> Symbol {
+    Symbol::Rule(name.into())
+}
+
+#[derive(Clone, Debug)]
+pub struct Grammar {
+    start: String,
+    rules: BTreeMap<String, Vec<Vec<Symbol>>>,
+}
+
+impl Grammar {
+    /// A grammar whose sentences derive from the rule `start`.
+    pub fn new(start: impl Into<String>) -> Self {
+        Self {
+            start: start.into(),
+            rules: BTreeMap::new(),
+        }
+    }
+
+    /// Add `alternatives` to the rule `name`, after any it already has.
+    pub fn rule<A, S>(
+        mut self,
+        name: impl Into<String>,
+        alternatives: A,
+    ) -> Self
+    where
+        A: IntoIterator<Item = S>,
+        S: IntoIterator<Item = Symbol>,
+    {
+        self.rules.entry(name.into()).or_default().extend(
+            alternatives
+                .into_iter()
+                .map(|symbols| symbols.into_iter().collect()),
+        );
+        self
+    }
+
+    /// Let the state `from` emit `token` and move on to the state `to`.
+    pub fn transition(
+        self,
+        from: impl Into<String>,
+        token: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        self.rule(from, [[Symbol::Token(token.into()), rule(to)]])
+    }
+
+    /// Let the sequence end in the state `state`.
+    pub fn accepting(self, state: impl Into<String>) -> Self {
+        self.rule(state, [Vec::new()])
+    }
+}
+
+#[derive(Debug)]
+enum Compiled {
+    Token(String),
+    Rule(usize),
+}
+
+/// A grammar with rules referred to by index, and the alternative of every
+/// rule with the shallowest derivation.
+#[derive(Debug)]
+struct Rules {
+    names: Vec<String>,
+    alternatives: Vec<Vec<Vec<Compiled>>>,
+    shallowest: Vec<usize>,
+}
+
+impl Rules {
+    fn compile(grammar: Grammar) -> Self {
+        let names: Vec<String> = grammar.rules.keys().cloned().collect();
+        let index_of = |name: &str| {
+            names
+                .binary_search_by(|probe| probe.as_str().cmp(name))
+                .ok()
+        };
+        assert!(
+            index_of(&grammar.start).is_some(),
+            "the start rule `{}` has no alternatives",
+            grammar.start,
+        );
+
+        let alternatives: Vec<Vec<Vec<Compiled>>> = grammar
+            .rules
+            .iter()
+            .map(|(name, alternatives)| {
+                alternatives
+                    .iter()
+                    .map(|symbols| {
+                        symbols
+                            .iter()
+                            .map(|symbol| match symbol {
+                                Symbol::Token(token) => {
+                                    Compiled::Token(token.clone())
+                                }
+                                Symbol::Rule(used) => Compiled::Rule(
+                                    index_of(used).unwrap_or_else(|| {
+                                        panic!(
+                                            "rule `{name}` uses `{used}`, \
+                                             which has no alternatives"
+                                        )
+                                    }),
+                                ),
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Derivation heights, lowered until nothing changes.
+        let mut heights = vec![usize::MAX; names.len()];
+        let mut shallowest = vec![0; names.len()];
+        loop {
+            let mut changed = false;
+            for (index, rule) in alternatives.iter().enumerate() {
+                for (alternative, symbols) in rule.iter().enumerate() {
+                    let height = symbols
+                        .iter()
+                        .map(|symbol| match symbol {
+                            Compiled::Token(_) => 0,
+                            Compiled::Rule(used) => heights[*used],
+                        })
+                        .max()
+                        .unwrap_or(0)
+                        .saturating_add(1);
+                    if height < heights[index] {
+                        heights[index] = height;
+                        shallowest[index] = alternative;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if let Some(index) = heights.iter().position(|&h| h == usize::MAX) {
+            panic!("rule `{}` never finishes a derivation", names[index]);
+        }
+
+        Self {
+            names,
+            alternatives,
+            shallowest,
+        }
+    }
+
+    fn start(&self, grammar_start: &str) -> usize {
+        self.names
+            .iter()
+            .position(|name| name == grammar_start)
+            .expect("the start rule was checked when compiling")
+    }
+
+    /// The shallowest derivation of `rule`.
+    fn smallest(&self, rule: usize) -> Node {
+        self.expand(rule, self.shallowest[rule], |rules, used| {
+            rules.smallest(used)
+        })
+    }
+
+    /// Derive `rule` through `alternative`, deriving the rules it uses with
+    /// `child`.
+    fn expand<F>(&self, rule: usize, alternative: usize, mut child: F) -> Node
+    where
+        F: FnMut(&Self, usize) -> Node,
+    {
+        let children = self.alternatives[rule][alternative]
+            .iter()
+            .map(|symbol| match symbol {
+                Compiled::Token(token) => Child::Token(token.clone()),
+                Compiled::Rule(used) => Child::Node(child(self, *used)),
+            })
+            .collect();
+        Node {
+            rule,
+            alternative,
+            children,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Child {
+    Token(String),
+    Node(Node),
+}
+
+/// A node of a derivation tree: the alternative a rule was derived through,
+/// with the derivations of the rules it uses.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Node {
+    rule: usize,
+    alternative: usize,
+    children: Vec<Child>,
+}
+
+impl Node {
+    fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Child::Node(node) => Some(node),
+            Child::Token(_) => None,
+        })
+    }
+
+    fn tokens(&self, tokens: &mut Vec<String>) {
+        for child in &self.children {
+            match child {
+                Child::Token(token) => tokens.push(token.clone()),
+                Child::Node(node) => node.tokens(tokens),
+            }
+        }
+    }
+
+    /// The number of proper descendants derived from `rule`.
+    fn count_descendants(&self, rule: usize) -> usize {
+        self.nodes()
+            .map(|node| {
+                usize::from(node.rule == rule) + node.count_descendants(rule)
+            })
+            .sum()
+    }
+
+    /// The `n`th proper descendant derived from `rule`, in preorder.
+    fn descendant(&self, rule: usize, n: &mut usize) -> Option<&Node> {
+        for node in self.nodes() {
+            if node.rule == rule {
+                if *n == 0 {
+                    return Some(node);
+                }
+                *n -= 1;
+            }
+            if let Some(found) = node.descendant(rule, n) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// The number of nodes, then the alternatives in preorder. Replacing a
+    /// subtree with one of lower weight lowers the weight of the whole tree,
+    /// so shrinking terminates.
+    fn weight(&self) -> (usize, Vec<usize>) {
+        let mut alternatives = Vec::new();
+        self.alternatives(&mut alternatives);
+        (alternatives.len(), alternatives)
+    }
+
+    fn alternatives(&self, alternatives: &mut Vec<usize>) {
+        alternatives.push(self.alternative);
+        for node in self.nodes() {
+            node.alternatives(alternatives);
+        }
+    }
+}
+
+/// A derivation that can replace a node: the shallowest derivation of its
+/// rule, a descendant derived from the same rule, or an earlier alternative
+/// of the rule, most aggressive first.
+#[derive(Clone, Copy)]
+enum Shrink {
+    Smallest,
+    Descendant(usize),
+    Alternative(usize),
+}
+
+impl Shrink {
+    fn apply(self, rules: &Rules, node: &Node) -> Node {
+        match self {
+            Shrink::Smallest => rules.smallest(node.rule),
+            Shrink::Descendant(mut n) => node
+                .descendant(node.rule, &mut n)
+                .expect("the descendant was counted")
+                .clone(),
+            Shrink::Alternative(alternative) => {
+                rules.expand(node.rule, alternative, Rules::smallest)
+            }
+        }
+    }
+}
+
+/// The replacements of `node` itself that lower its weight.
+fn shrinks(rules: &Rules, node: &Node) -> Vec<Shrink> {
+    let weight = node.weight();
+    let lighter = |shrink: &Shrink| shrink.apply(rules, node).weight() < weight;
+
+    let mut shrinks = Vec::new();
+    shrinks.extend(Some(Shrink::Smallest).filter(lighter));
+    // Descendants have fewer nodes, so they are always lighter.
+    shrinks
+        .extend((0..node.count_descendants(node.rule)).map(Shrink::Descendant));
+    shrinks.extend(
+        (0..node.alternative)
+            .map(Shrink::Alternative)
+            .filter(lighter),
+    );
+    shrinks
+}
+
+/// The `n`th candidate simpler than `node`: the shrinks of `node` itself,
+/// then those of its subtrees in preorder.
+fn nth_candidate(rules: &Rules, node: &Node, n: &mut usize) -> Option<Node> {
+    let shrinks = shrinks(rules, node);
+    if let Some(shrink) = shrinks.get(*n) {
+        return Some(shrink.apply(rules, node));
+    }
+    *n -= shrinks.len();
+
+    for (index, child) in node.children.iter().enumerate() {
+        let Child::Node(child) = child else {
+            continue;
+        };
+        if let Some(simpler) = nth_candidate(rules, child, n) {
+            let mut replaced = node.clone();
+            replaced.children[index] = Child::Node(simpler);
+            return Some(replaced);
+        }
+    }
+    None
+}
+
+/// Generate the token sequences of a [`Grammar`].
+///
+/// Every expansion of a rule picks one of its alternatives uniformly through
+/// [`Generator::choose`], keyed by the rule's name, and goes one level
+/// deeper. Once [`Generator::recursion_exhausted`] says the value has grown
+/// deep enough for the current size, rules only take their shallowest
+/// alternative, so derivations stay finite.
+///
+/// # Panics
+///
+/// When the start rule or a rule an alternative uses has no alternatives, or
+/// when a rule cannot finish a derivation, such as `a -> a b`.
+#[derive(Clone, Debug)]
+pub struct GrammarStrategy {
+    rules: Arc<Rules>,
+    start: usize,
+}
+
+impl GrammarStrategy {
+    pub fn new(grammar: Grammar) -> Self {
+        let start = grammar.start.clone();
+        let rules = Rules::compile(grammar);
+        Self {
+            start: rules.start(&start),
+            rules: Arc::new(rules),
+        }
+    }
+
+    fn derive<R: RngCore + CryptoRng>(
+        &self,
+        rule: usize,
+        generator: &mut Generator<R>,
+    ) -> Node {
+        let alternative = if generator.recursion_exhausted() {
+            self.rules.shallowest[rule]
+        } else {
+            let count = self.rules.alternatives[rule].len();
+            generator.choose(&self.rules.names[rule], count)
+        };
+        generator.recurse(|generator| {
+            self.rules.expand(rule, alternative, |_, used| {
+                self.derive(used, generator)
+            })
+        })
+    }
+}
+
+impl Strategy for GrammarStrategy {
+    type Value = Vec<String>;
+    type Tree = GrammarValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let root = self.derive(self.start, generator);
+        generator.accept(GrammarValueTree::new(self.rules.clone(), root))
+    }
+}
+
+pub struct GrammarValueTree {
+    rules: Arc<Rules>,
+    root: Node,
+    current: Vec<String>,
+    previous: Option<(Node, usize)>,
+    next_candidate: usize,
+}
+
+impl GrammarValueTree {
+    fn new(rules: Arc<Rules>, root: Node) -> Self {
+        let mut tree = Self {
+            rules,
+            root,
+            current: Vec::new(),
+            previous: None,
+            next_candidate: 0,
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        self.current.clear();
+        self.root.tokens(&mut self.current);
+    }
+}
+
+impl ValueTree for GrammarValueTree {
+    type Value = Vec<String>;
+
+    fn current(&self) -> &Vec<String> {
+        &self.current
+    }
+
+    fn into_current(self) -> Vec<String> {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        let mut n = self.next_candidate;
+        let Some(candidate) = nth_candidate(&self.rules, &self.root, &mut n)
+        else {
+            return false;
+        };
+
+        let previous = core::mem::replace(&mut self.root, candidate);
+        self.previous = Some((previous, self.next_candidate));
+        self.next_candidate = 0;
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some((previous, candidate)) = self.previous.take() else {
+            return false;
+        };
+
+        self.root = previous;
+        self.next_candidate = candidate + 1;
+        self.sync_current();
+        let mut n = self.next_candidate;
+        nth_candidate(&self.rules, &self.root, &mut n).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{assert_shrinks_to, strategy_test::check_shrink_invariants};
+
+    fn arithmetic() -> Grammar {
+        Grammar::new("expr")
+            .rule(
+                "expr",
+                [
+                    vec![rule("num")],
+                    vec![rule("expr"), token("+"), rule("expr")],
+                    vec![token("("), rule("expr"), token(")")],
+                ],
+            )
+            .rule("num", [[token("0")], [token("1")], [token("2")]])
+    }
+
+    /// Whether `tokens` is a sentence of [`arithmetic`].
+    fn parses(tokens: &[String]) -> bool {
+        fn expr(tokens: &[String], at: &mut usize) -> bool {
+            let operand = match tokens.get(*at).map(String::as_str) {
+                Some("0" | "1" | "2") => {
+                    *at += 1;
+                    true
+                }
+                Some("(") => {
+                    *at += 1;
+                    let inner = expr(tokens, at);
+                    let closed =
+                        tokens.get(*at).map(String::as_str) == Some(")");
+                    *at += 1;
+                    inner && closed
+                }
+                _ => false,
+            };
+            if operand && tokens.get(*at).map(String::as_str) == Some("+") {
+                *at += 1;
+                return expr(tokens, at);
+            }
+            operand
+        }
+
+        let mut at = 0;
+        expr(tokens, &mut at) && at == tokens.len()
+    }
+
+    #[test]
+    fn generated_sequences_are_sentences() {
+        let mut strategy = GrammarStrategy::new(arithmetic());
+        let mut generator = Generator::build(StdRng::seed_from_u64(19));
+        for _ in 0..128 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            assert!(parses(tree.current()), "{:?}", tree.current());
+            while tree.simplify() {
+                assert!(parses(tree.current()), "{:?}", tree.current());
+            }
+            assert_eq!(tree.current(), &["0"]);
+        }
+        check_shrink_invariants(GrammarStrategy::new(arithmetic()), 64);
+    }
+
+    #[test]
+    fn derivations_shrink_to_the_smallest_failing_sentence() {
+        assert_shrinks_to!(
+            GrammarStrategy::new(arithmetic()),
+            |tokens: &Vec<String>| !tokens.iter().any(|token| token == "("),
+            ["(", "0", ")"]
+        );
+        assert_shrinks_to!(
+            GrammarStrategy::new(arithmetic()),
+            |tokens: &Vec<String>| tokens.len() < 5,
+            ["(", "0", "+", "0", ")"]
+        );
+    }
+
+    #[test]
+    fn transition_tables_generate_walks() {
+        let grammar = Grammar::new("closed")
+            .accepting("closed")
+            .transition("closed", "open", "opened")
+            .transition("opened", "send", "opened")
+            .transition("opened", "close", "closed");
+        let mut strategy = GrammarStrategy::new(grammar);
+        let mut generator = Generator::build(StdRng::seed_from_u64(23));
+        for _ in 0..64 {
+            let tree = strategy.new_tree(&mut generator).take();
+            let mut open = false;
+            for event in tree.current() {
+                match event.as_str() {
+                    "open" => assert!(!core::mem::replace(&mut open, true)),
+                    "send" => assert!(open),
+                    "close" => assert!(core::mem::replace(&mut open, false)),
+                    _ => unreachable!(),
+                }
+            }
+            assert!(!open);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "rule `loop` never finishes a derivation")]
+    fn rules_without_a_finite_derivation_are_rejected() {
+        let grammar = Grammar::new("loop").rule("loop", [[rule("loop")]]);
+        let _ = GrammarStrategy::new(grammar);
+    }
+
+    #[test]
+    #[should_panic(expected = "uses `missing`, which has no alternatives")]
+    fn undefined_rules_are_rejected() {
+        let grammar = Grammar::new("start").rule("start", [[rule("missing")]]);
+        let _ = GrammarStrategy::new(grammar);
+    }
+}
@@ -0,0 +1,499 @@
+//! Strategies for graphs over the nodes `0..n`.
+//!
+//! [`GraphStrategy`] generates a [`Graph`] of a chosen class: directed or
+//! undirected, optionally acyclic, connected (weakly, for directed graphs)
+//! or limited to a maximum degree. Shrinking removes nodes, then edges, and
+//! skips every removal that would take the graph out of its class, so a
+//! failing tree keeps being a tree and a DAG keeps being a DAG. Graphs never
+//! have self-loops or parallel edges.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::{mem, ops::RangeInclusive};
+
+use rand::{Rng, seq::SliceRandom};
+
+use crate::strategy::{
+    AnyUsize,
+    SizeHint,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator, RejectReason},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Graph {
+    directed: bool,
+    node_count: usize,
+    edges: Vec<(usize, usize)>,
+}
+
+impl Graph {
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// The edges in ascending order. Undirected edges are listed once, with
+    /// the smaller node first.
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// The neighbours of every node, including nodes without any. Directed
+    /// edges are listed under their source, undirected edges under both
+    /// ends.
+    pub fn adjacency(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut adjacency: BTreeMap<usize, Vec<usize>> = (0..self.node_count)
+            .map(|node| (node, Vec::new()))
+            .collect();
+
+        for &(from, to) in &self.edges {
+            adjacency.entry(from).or_default().push(to);
+            if !self.directed {
+                adjacency.entry(to).or_default().push(from);
+            }
+        }
+
+        for neighbours in adjacency.values_mut() {
+            neighbours.sort_unstable();
+        }
+        adjacency
+    }
+}
+
+#[derive(Clone)]
+pub struct GraphStrategy {
+    nodes: RangeInclusive<usize>,
+    directed: bool,
+    acyclic: bool,
+    connected: bool,
+    max_degree: Option<usize>,
+}
+
+impl GraphStrategy {
+    /// Undirected graphs with any number of edges.
+    pub fn new<H>(nodes: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self {
+            nodes: nodes.to_inclusive(),
+            directed: false,
+            acyclic: false,
+            connected: false,
+            max_degree: None,
+        }
+    }
+
+    /// Directed acyclic graphs.
+    pub fn dag<H>(nodes: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self::new(nodes).with_directed(true).with_acyclic(true)
+    }
+
+    /// Undirected trees spanning every node.
+    pub fn tree<H>(nodes: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self::new(nodes).with_connected(true).with_acyclic(true)
+    }
+
+    /// Connected undirected graphs.
+    pub fn connected<H>(nodes: H) -> Self
+    where
+        H: SizeHint,
+    {
+        Self::new(nodes).with_connected(true)
+    }
+
+    pub fn with_directed(mut self, directed: bool) -> Self {
+        self.directed = directed;
+        self
+    }
+
+    pub fn with_acyclic(mut self, acyclic: bool) -> Self {
+        self.acyclic = acyclic;
+        self
+    }
+
+    pub fn with_connected(mut self, connected: bool) -> Self {
+        self.connected = connected;
+        self
+    }
+
+    /// Limit the number of edges touching a node, counting both directions
+    /// of a directed graph. Connected graphs with more than two nodes need a
+    /// maximum degree of at least two; the strategy rejects nodes it cannot
+    /// attach.
+    pub fn with_max_degree(mut self, max_degree: usize) -> Self {
+        self.max_degree = Some(max_degree);
+        self
+    }
+
+    fn has_room(&self, degree: &[usize], node: usize) -> bool {
+        self.max_degree.is_none_or(|max| degree[node] < max)
+    }
+}
+
+impl Strategy for GraphStrategy {
+    type Value = Graph;
+    type Tree = GraphValueTree;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let nodes = generator.sized_len(self.nodes.clone());
+        let node_count = AnyUsize::sample(&mut generator.rng, nodes);
+
+        // Nodes are generated in an internal order that every edge of an
+        // acyclic directed graph follows; `labels` hides that order from the
+        // generated graph.
+        let mut labels: Vec<usize> = (0..node_count).collect();
+        labels.shuffle(&mut generator.rng);
+        let mut degree = vec![0; node_count];
+        let mut edges = Vec::new();
+        let mut stranded = false;
+
+        // Attach every node to an earlier one to span the graph, or with a
+        // coin flip to grow a forest for acyclic undirected graphs.
+        if self.connected || (self.acyclic && !self.directed) {
+            for node in 1..node_count {
+                if !self.connected && generator.rng.random_bool(0.5) {
+                    continue;
+                }
+
+                let parents: Vec<usize> = (0..node)
+                    .filter(|&parent| self.has_room(&degree, parent))
+                    .collect();
+                if parents.is_empty() || !self.has_room(&degree, node) {
+                    stranded |= self.connected;
+                    continue;
+                }
+
+                let parent =
+                    parents[generator.rng.random_range(0..parents.len())];
+                let flip = self.directed
+                    && !self.acyclic
+                    && generator.rng.random_bool(0.5);
+                edges.push(if flip { (node, parent) } else { (parent, node) });
+                degree[parent] += 1;
+                degree[node] += 1;
+            }
+        }
+
+        // Acyclic undirected graphs are forests, which the spanning pass
+        // already produced; every other class gets extra edges.
+        if node_count > 1 && !(self.acyclic && !self.directed) {
+            let pairs = node_count * (node_count - 1) / 2;
+            let extra = generator.sized_len(0..=pairs);
+            let extra = AnyUsize::sample(&mut generator.rng, extra);
+
+            for _ in 0..extra {
+                let from = generator.rng.random_range(0..node_count);
+                let to = generator.rng.random_range(0..node_count);
+                if from == to
+                    || !self.has_room(&degree, from)
+                    || !self.has_room(&degree, to)
+                {
+                    continue;
+                }
+
+                let edge = if self.directed && !self.acyclic {
+                    (from, to)
+                } else {
+                    (from.min(to), from.max(to))
+                };
+                let present = edges.iter().any(|&(a, b)| {
+                    (a, b) == edge || (!self.directed && (b, a) == edge)
+                });
+                if present {
+                    continue;
+                }
+
+                edges.push(edge);
+                degree[from] += 1;
+                degree[to] += 1;
+            }
+        }
+
+        let tree = GraphValueTree::new(
+            self.clone(),
+            *self.nodes.start(),
+            labels,
+            edges,
+        );
+        if stranded {
+            generator.reject_with(RejectReason::Filter, tree)
+        } else {
+            generator.accept(tree)
+        }
+    }
+}
+
+/// Shrink passes, in the order they run: remove nodes, then remove edges.
+/// Removing edges can turn cut nodes into leaves, so both passes repeat
+/// while the previous round kept a removal.
+#[derive(Clone, Copy)]
+enum Stage {
+    Nodes { index: usize },
+    Edges { index: usize },
+}
+
+#[derive(Clone)]
+struct Shape {
+    labels: Vec<usize>,
+    edges: Vec<(usize, usize)>,
+}
+
+pub struct GraphValueTree {
+    strategy: GraphStrategy,
+    min_nodes: usize,
+    shape: Shape,
+    stage: Stage,
+    history: Vec<(Shape, Stage)>,
+    /// Removals kept since the current round started.
+    kept: usize,
+    current: Graph,
+}
+
+impl GraphValueTree {
+    fn new(
+        strategy: GraphStrategy,
+        min_nodes: usize,
+        labels: Vec<usize>,
+        edges: Vec<(usize, usize)>,
+    ) -> Self {
+        let mut tree = Self {
+            strategy,
+            min_nodes,
+            shape: Shape { labels, edges },
+            stage: Stage::Nodes { index: 0 },
+            history: Vec::new(),
+            kept: 0,
+            current: Graph {
+                directed: false,
+                node_count: 0,
+                edges: Vec::new(),
+            },
+        };
+        tree.sync_current();
+        tree
+    }
+
+    fn sync_current(&mut self) {
+        let labels = &self.shape.labels;
+        let directed = self.strategy.directed;
+        let mut edges: Vec<(usize, usize)> = self
+            .shape
+            .edges
+            .iter()
+            .map(|&(from, to)| {
+                let (from, to) = (labels[from], labels[to]);
+                if directed {
+                    (from, to)
+                } else {
+                    (from.min(to), from.max(to))
+                }
+            })
+            .collect();
+        edges.sort_unstable();
+
+        self.current = Graph {
+            directed,
+            node_count: labels.len(),
+            edges,
+        };
+    }
+
+    /// Remove node `index` and its edges, closing the gap it leaves in both
+    /// the internal order and the labels.
+    fn remove_node(&mut self, index: usize) {
+        let label = self.shape.labels.remove(index);
+        for other in &mut self.shape.labels {
+            if *other > label {
+                *other -= 1;
+            }
+        }
+
+        self.shape
+            .edges
+            .retain(|&(from, to)| from != index && to != index);
+        for (from, to) in &mut self.shape.edges {
+            if *from > index {
+                *from -= 1;
+            }
+            if *to > index {
+                *to -= 1;
+            }
+        }
+    }
+
+    /// Whether every node is reachable from every other one, ignoring edge
+    /// directions.
+    fn is_connected(&self) -> bool {
+        let node_count = self.shape.labels.len();
+        if node_count == 0 {
+            return true;
+        }
+
+        let mut adjacency = vec![Vec::new(); node_count];
+        for &(from, to) in &self.shape.edges {
+            adjacency[from].push(to);
+            adjacency[to].push(from);
+        }
+
+        let mut seen = vec![false; node_count];
+        let mut stack = vec![0];
+        seen[0] = true;
+        while let Some(node) = stack.pop() {
+            for &next in &adjacency[node] {
+                if !mem::replace(&mut seen[next], true) {
+                    stack.push(next);
+                }
+            }
+        }
+        seen.iter().all(|&seen| seen)
+    }
+
+    /// Apply the removal `stage` points at, keeping it only when the graph
+    /// stays in its class.
+    fn try_remove(&mut self, stage: Stage) -> bool {
+        let previous = self.shape.clone();
+        match stage {
+            Stage::Nodes { index } => self.remove_node(index),
+            Stage::Edges { index } => {
+                self.shape.edges.remove(index);
+            }
+        }
+
+        if self.strategy.connected && !self.is_connected() {
+            self.shape = previous;
+            return false;
+        }
+
+        self.history.push((previous, stage));
+        self.kept += 1;
+        true
+    }
+}
+
+impl ValueTree for GraphValueTree {
+    type Value = Graph;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        loop {
+            let stage = self.stage;
+            match stage {
+                Stage::Nodes { index } => {
+                    if index >= self.shape.labels.len()
+                        || self.shape.labels.len() <= self.min_nodes
+                    {
+                        self.stage = Stage::Edges { index: 0 };
+                        continue;
+                    }
+                    if self.try_remove(stage) {
+                        self.sync_current();
+                        return true;
+                    }
+                    self.stage = Stage::Nodes { index: index + 1 };
+                }
+                Stage::Edges { index } => {
+                    if index >= self.shape.edges.len() {
+                        if self.kept == 0 {
+                            return false;
+                        }
+                        self.kept = 0;
+                        self.stage = Stage::Nodes { index: 0 };
+                        continue;
+                    }
+                    if self.try_remove(stage) {
+                        self.sync_current();
+                        return true;
+                    }
+                    self.stage = Stage::Edges { index: index + 1 };
+                }
+            }
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some((shape, stage)) = self.history.pop() else {
+            return false;
+        };
+
+        self.shape = shape;
+        self.kept = self.kept.saturating_sub(1);
+        self.stage = match stage {
+            Stage::Nodes { index } => Stage::Nodes { index: index + 1 },
+            Stage::Edges { index } => Stage::Edges { index: index + 1 },
+        };
+        self.sync_current();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn shape(edges: &[(usize, usize)], node_count: usize) -> GraphValueTree {
+        GraphValueTree::new(
+            GraphStrategy::tree(0usize..=8usize),
+            0,
+            (0..node_count).collect(),
+            edges.to_vec(),
+        )
+    }
+
+    #[test]
+    fn removing_a_node_renumbers_the_rest() {
+        let mut tree = shape(&[(0, 1), (1, 2), (1, 3)], 4);
+        tree.remove_node(1);
+        tree.sync_current();
+        assert_eq!(tree.current().node_count(), 3);
+        assert!(tree.current().edges().is_empty());
+    }
+
+    #[test]
+    fn tree_shrinking_only_removes_leaves() {
+        let mut tree = shape(&[(0, 1), (1, 2)], 3);
+
+        assert!(tree.simplify());
+        assert_eq!(tree.current().node_count(), 2);
+        assert_eq!(tree.current().edges(), &[(0, 1)]);
+
+        assert!(tree.complicate());
+        assert_eq!(tree.current().node_count(), 3);
+    }
+
+    #[test]
+    fn generated_dags_follow_a_topological_order() {
+        let mut strategy = GraphStrategy::dag(0usize..=16usize);
+        let mut generator = Generator::build(StdRng::seed_from_u64(17));
+
+        for _ in 0..64 {
+            let tree = strategy.new_tree(&mut generator).take();
+            let mut order = tree.shape.labels.clone();
+            order.sort_unstable();
+            assert!(tree.shape.edges.iter().all(|&(from, to)| from < to));
+            assert_eq!(order, (0..tree.shape.labels.len()).collect::<Vec<_>>());
+        }
+    }
+}
@@ -1,20 +1,48 @@
+mod boxed;
+pub mod clock;
 mod collections;
+mod composed;
+#[cfg(feature = "std")]
+pub mod env;
+pub mod faults;
+mod fragments;
+pub mod frames;
+#[cfg(feature = "std")]
+pub mod fs;
+pub mod grammar;
+pub mod graphs;
 mod primitives;
+mod provenance;
 pub mod runtime;
+pub mod schedule;
 mod size_hint;
+pub mod sql;
 mod traits;
 
+pub use boxed::*;
 pub use collections::*;
+pub use composed::*;
+pub use fragments::FragmentValueTree;
 pub use primitives::*;
+pub use provenance::{BREAKDOWN_LINES, Labeled, LabeledValueTree, Provenance};
 pub use runtime::{
     ConstantValueTree,
+    CountingRng,
     DefaultGenerator,
+    DynGenerator,
+    EntropySource,
+    FnStrategy,
     Generation,
     Generator,
+    GeneratorStats,
     IntegratedAdapter,
+    RejectReason,
+    SwarmConfig,
     adapt,
     execute,
+    execute_tree,
     from_arbitrary,
+    from_fn,
 };
 pub use size_hint::SizeHint;
 pub use traits::{Strategy, ValueTree};
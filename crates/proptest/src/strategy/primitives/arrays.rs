@@ -1,4 +1,5 @@
-use std::{array, convert::TryInto};
+use alloc::vec::Vec;
+use core::{array, convert::TryInto};
 
 use crate::strategy::{
     Strategy,
@@ -95,6 +96,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         for idx in 0..N {
             if self.trees[idx].simplify() {
@@ -0,0 +1,156 @@
+use num_bigint::{BigInt, BigUint, Sign};
+use rand::{CryptoRng, Rng, RngCore};
+
+use super::IntValueTree;
+use crate::strategy::{
+    Strategy,
+    runtime::{Generation, Generator},
+};
+
+const DEFAULT_BITS: usize = 256;
+
+/// Unsigned big integers below `2^bits`, shrinking by halving toward zero.
+#[derive(Clone)]
+pub struct BigUintStrategy {
+    bits: usize,
+}
+
+impl BigUintStrategy {
+    pub fn new(bits: usize) -> Self {
+        Self { bits }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> BigUint
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        // Pick the bit length first so small magnitudes are as likely as
+        // huge ones.
+        let bits = rng.random_range(0..=self.bits);
+        let mut bytes = vec![0u8; bits.div_ceil(8)];
+        rng.fill_bytes(&mut bytes);
+        if bits % 8 != 0
+            && let Some(last) = bytes.last_mut()
+        {
+            *last &= (1u8 << (bits % 8)) - 1;
+        }
+        BigUint::from_bytes_le(&bytes)
+    }
+}
+
+impl Default for BigUintStrategy {
+    fn default() -> Self {
+        Self::new(DEFAULT_BITS)
+    }
+}
+
+impl Strategy for BigUintStrategy {
+    type Value = BigUint;
+    type Tree = IntValueTree<BigUint>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let value = self.sample(&mut generator.rng);
+        let candidates = halvings(&value, |n| n / 2u8, |n| *n == BigUint::ZERO);
+        generator.accept(IntValueTree::new(value, candidates))
+    }
+}
+
+/// Signed big integers with magnitude below `2^bits`, shrinking by halving
+/// toward zero.
+#[derive(Clone)]
+pub struct BigIntStrategy {
+    magnitude: BigUintStrategy,
+}
+
+impl BigIntStrategy {
+    pub fn new(bits: usize) -> Self {
+        Self {
+            magnitude: BigUintStrategy::new(bits),
+        }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> BigInt
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        let sign = if rng.random() {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        };
+        BigInt::from_biguint(sign, self.magnitude.sample(rng))
+    }
+}
+
+impl Default for BigIntStrategy {
+    fn default() -> Self {
+        Self::new(DEFAULT_BITS)
+    }
+}
+
+impl Strategy for BigIntStrategy {
+    type Value = BigInt;
+    type Tree = IntValueTree<BigInt>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let value = self.sample(&mut generator.rng);
+        let candidates =
+            halvings(&value, |n| n / 2, |n| n.sign() == Sign::NoSign);
+        generator.accept(IntValueTree::new(value, candidates))
+    }
+}
+
+fn halvings<T, H, Z>(value: &T, halve: H, is_zero: Z) -> Vec<T>
+where
+    H: Fn(&T) -> T,
+    Z: Fn(&T) -> bool,
+{
+    if is_zero(value) {
+        return Vec::new();
+    }
+
+    let mut candidates = vec![halve(value)];
+    while let Some(last) = candidates.last()
+        && !is_zero(last)
+    {
+        let next = halve(last);
+        candidates.push(next);
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::ValueTree;
+
+    #[test]
+    fn halvings_end_at_zero() {
+        let value = BigInt::from(-1000);
+        let candidates =
+            halvings(&value, |n| n / 2, |n| n.sign() == Sign::NoSign);
+        assert_eq!(candidates.first(), Some(&BigInt::from(-500)));
+        assert_eq!(candidates.last(), Some(&BigInt::ZERO));
+    }
+
+    #[test]
+    fn big_uints_respect_bit_bound_and_shrink_to_zero() {
+        let mut strategy = BigUintStrategy::new(70);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..32 {
+            let mut tree = match strategy.new_tree(&mut generator) {
+                Generation::Accepted { value, .. } => value,
+                Generation::Rejected { .. } => panic!("unexpected rejection"),
+            };
+            assert!(tree.current().bits() <= 70);
+            while tree.simplify() {}
+            assert_eq!(*tree.current(), BigUint::ZERO);
+        }
+    }
+}
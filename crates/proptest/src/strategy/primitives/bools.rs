@@ -31,6 +31,10 @@ impl ValueTree for BoolValueTree {
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         if self.tried_false {
             return false;
@@ -0,0 +1,214 @@
+use core::ops::{Deref, RangeInclusive};
+
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::{
+    arbitrary::Arbitrary,
+    strategy::{
+        AnyI8,
+        AnyI16,
+        AnyI32,
+        AnyI64,
+        AnyI128,
+        AnyIsize,
+        AnyU8,
+        AnyU16,
+        AnyU32,
+        AnyU64,
+        AnyU128,
+        AnyUsize,
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator},
+    },
+};
+
+/// An integer of type `T` that lies within `MIN..=MAX`.
+///
+/// Takes the place of a `#[strategy]` attribute on `#[proptest]` arguments:
+/// `value: Bounded<u8, 1, 10>` only ever receives values from `1..=10`, both
+/// when generated through [`Arbitrary`] and while its
+/// [`strategy`](Bounded::strategy) shrinks. The bounds are `i128`s so one
+/// wrapper covers every integer type; bounds outside of `T` fail to compile,
+/// which also limits `u128` to `i128::MAX`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bounded<T, const MIN: i128, const MAX: i128>(T);
+
+impl<T, const MIN: i128, const MAX: i128> Bounded<T, MIN, MAX> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Copy, const MIN: i128, const MAX: i128> Bounded<T, MIN, MAX> {
+    pub fn get(self) -> T {
+        self.0
+    }
+}
+
+impl<T, const MIN: i128, const MAX: i128> Deref for Bounded<T, MIN, MAX> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Strategy for [`Bounded`] values, shrinking like the integer strategy for
+/// its bounds.
+#[derive(Clone)]
+pub struct BoundedStrategy<S, const MIN: i128, const MAX: i128> {
+    inner: S,
+}
+
+impl<S, const MIN: i128, const MAX: i128> Strategy
+    for BoundedStrategy<S, MIN, MAX>
+where
+    S: Strategy,
+    S::Value: Copy,
+{
+    type Value = Bounded<S::Value, MIN, MAX>;
+    type Tree = BoundedValueTree<S::Tree, MIN, MAX>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.inner.new_tree(generator).map(|inner| {
+            let current = Bounded(*inner.current());
+            BoundedValueTree { inner, current }
+        })
+    }
+}
+
+pub struct BoundedValueTree<V: ValueTree, const MIN: i128, const MAX: i128> {
+    inner: V,
+    current: Bounded<V::Value, MIN, MAX>,
+}
+
+impl<V, const MIN: i128, const MAX: i128> ValueTree
+    for BoundedValueTree<V, MIN, MAX>
+where
+    V: ValueTree,
+    V::Value: Copy,
+{
+    type Value = Bounded<V::Value, MIN, MAX>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        let simplified = self.inner.simplify();
+        self.current = Bounded(*self.inner.current());
+        simplified
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.current = Bounded(*self.inner.current());
+        complicated
+    }
+}
+
+macro_rules! impl_bounded {
+    ($($ty:ty => $strategy:ident),+ $(,)?) => {
+        $(
+            impl<const MIN: i128, const MAX: i128> Bounded<$ty, MIN, MAX> {
+                /// The bounds as a `$ty` range, rejecting at compile time
+                /// bounds that are empty or do not fit the type.
+                pub const RANGE: RangeInclusive<$ty> = {
+                    assert!(MIN <= MAX, "Bounded requires MIN <= MAX");
+                    assert!(
+                        MIN >= <$ty>::MIN as i128
+                            && (MIN as $ty) as i128 == MIN
+                            && (MAX as $ty) as i128 == MAX,
+                        "Bounded bounds must fit the wrapped type",
+                    );
+                    (MIN as $ty)..=(MAX as $ty)
+                };
+
+                /// Wrap `value`, or `None` when it lies outside the bounds.
+                pub fn new(value: $ty) -> Option<Self> {
+                    Self::RANGE.contains(&value).then_some(Self(value))
+                }
+
+                pub fn strategy() -> BoundedStrategy<$strategy, MIN, MAX> {
+                    BoundedStrategy {
+                        inner: $strategy::new(Self::RANGE),
+                    }
+                }
+            }
+
+            impl<const MIN: i128, const MAX: i128> Arbitrary
+                for Bounded<$ty, MIN, MAX>
+            {
+                fn arbitrary<R: RngCore + CryptoRng + ?Sized>(
+                    rng: &mut R,
+                ) -> Self {
+                    // `isize` and `usize` cannot be sampled directly, so
+                    // draw from the `i128` bounds; `new` still evaluates
+                    // `RANGE` and its compile-time checks.
+                    let value = rng.random_range(MIN..=MAX) as $ty;
+                    Self::new(value).expect("drawn within the bounds")
+                }
+
+                fn generate<R: RngCore + CryptoRng>(
+                    generator: &mut Generator<R>,
+                ) -> Generation<Self> {
+                    Self::strategy()
+                        .new_tree(generator)
+                        .map(ValueTree::into_current)
+                }
+            }
+        )+
+    };
+}
+
+impl_bounded!(
+    i8 => AnyI8,
+    i16 => AnyI16,
+    i32 => AnyI32,
+    i64 => AnyI64,
+    i128 => AnyI128,
+    isize => AnyIsize,
+    u8 => AnyU8,
+    u16 => AnyU16,
+    u32 => AnyU32,
+    u64 => AnyU64,
+    u128 => AnyU128,
+    usize => AnyUsize,
+);
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    #[test]
+    fn new_rejects_values_outside_the_bounds() {
+        assert_eq!(Bounded::<u8, 3, 7>::new(3).map(Bounded::get), Some(3));
+        assert_eq!(Bounded::<u8, 3, 7>::new(7).map(Bounded::get), Some(7));
+        assert_eq!(Bounded::<u8, 3, 7>::new(2), None);
+        assert_eq!(Bounded::<i16, -5, -1>::new(0), None);
+    }
+
+    #[test]
+    fn shrinking_stays_within_the_bounds() {
+        let mut strategy = Bounded::<i32, 10, 1000>::strategy();
+        let mut generator = Generator::build(StdRng::seed_from_u64(7));
+
+        for _ in 0..64 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            while tree.simplify() {
+                assert!((10..=1000).contains(&tree.current().get()));
+            }
+            assert_eq!(tree.current().get(), 10);
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use std::ops::RangeInclusive;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
 
 use rand::Rng;
 
@@ -0,0 +1,309 @@
+use core::{marker::PhantomData, ops::RangeInclusive};
+
+use rand::{CryptoRng, Rng, RngCore};
+
+use super::integers::{AnyI128, IntValueTree};
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Date-time types generated as a signed offset from the Unix epoch.
+///
+/// The offset unit is up to the implementation (days for dates, nanoseconds
+/// for instants); shrinking moves the offset toward zero, i.e. toward
+/// `1970-01-01T00:00:00Z`.
+pub trait EpochOffset: Sized {
+    /// Widest range the type can represent.
+    fn full_range() -> RangeInclusive<Self>;
+
+    fn to_epoch_offset(&self) -> i128;
+
+    /// Inverse of [`to_epoch_offset`](Self::to_epoch_offset); only called
+    /// with offsets between two representable values.
+    fn from_epoch_offset(offset: i128) -> Self;
+}
+
+pub struct DateTimeStrategy<T>
+where
+    T: EpochOffset,
+{
+    offsets: AnyI128,
+    range: RangeInclusive<i128>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> DateTimeStrategy<T>
+where
+    T: EpochOffset,
+{
+    pub fn new(range: RangeInclusive<T>) -> Self {
+        let start = range.start().to_epoch_offset();
+        let end = range.end().to_epoch_offset();
+        assert!(start <= end, "date-time range must not be empty");
+        Self {
+            offsets: AnyI128::new(start..=end),
+            range: start..=end,
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> T
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        T::from_epoch_offset(rng.random_range(self.range.clone()))
+    }
+}
+
+impl<T> Default for DateTimeStrategy<T>
+where
+    T: EpochOffset,
+{
+    fn default() -> Self {
+        Self::new(T::full_range())
+    }
+}
+
+pub struct DateTimeValueTree<T> {
+    inner: IntValueTree<i128>,
+    current: T,
+}
+
+impl<T> DateTimeValueTree<T>
+where
+    T: EpochOffset,
+{
+    fn new(inner: IntValueTree<i128>) -> Self {
+        let current = T::from_epoch_offset(*inner.current());
+        Self { inner, current }
+    }
+
+    fn sync_current(&mut self) {
+        self.current = T::from_epoch_offset(*self.inner.current());
+    }
+}
+
+impl<T> Strategy for DateTimeStrategy<T>
+where
+    T: EpochOffset,
+{
+    type Value = T;
+    type Tree = DateTimeValueTree<T>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.offsets.new_tree(generator).map(DateTimeValueTree::new)
+    }
+}
+
+impl<T> ValueTree for DateTimeValueTree<T>
+where
+    T: EpochOffset,
+{
+    type Value = T;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if self.inner.simplify() {
+            self.sync_current();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use core::ops::RangeInclusive;
+
+    use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
+
+    use super::EpochOffset;
+
+    const NANOS_PER_SEC: i128 = 1_000_000_000;
+    /// `NaiveDate::num_days_from_ce` of 1970-01-01.
+    const EPOCH_DAYS_FROM_CE: i128 = 719_163;
+
+    pub type NaiveDateStrategy = super::DateTimeStrategy<NaiveDate>;
+    pub type NaiveDateTimeStrategy = super::DateTimeStrategy<NaiveDateTime>;
+    pub type UtcDateTimeStrategy = super::DateTimeStrategy<DateTime<Utc>>;
+
+    impl EpochOffset for NaiveDate {
+        fn full_range() -> RangeInclusive<Self> {
+            NaiveDate::MIN..=NaiveDate::MAX
+        }
+
+        fn to_epoch_offset(&self) -> i128 {
+            self.num_days_from_ce() as i128 - EPOCH_DAYS_FROM_CE
+        }
+
+        fn from_epoch_offset(offset: i128) -> Self {
+            i32::try_from(offset + EPOCH_DAYS_FROM_CE)
+                .ok()
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .expect("day offset outside NaiveDate range")
+        }
+    }
+
+    impl EpochOffset for DateTime<Utc> {
+        fn full_range() -> RangeInclusive<Self> {
+            DateTime::<Utc>::MIN_UTC..=DateTime::<Utc>::MAX_UTC
+        }
+
+        fn to_epoch_offset(&self) -> i128 {
+            self.timestamp() as i128 * NANOS_PER_SEC
+                + self.timestamp_subsec_nanos() as i128
+        }
+
+        fn from_epoch_offset(offset: i128) -> Self {
+            let secs = offset.div_euclid(NANOS_PER_SEC) as i64;
+            let nanos = offset.rem_euclid(NANOS_PER_SEC) as u32;
+            DateTime::from_timestamp(secs, nanos)
+                .expect("nanosecond offset outside DateTime range")
+        }
+    }
+
+    impl EpochOffset for NaiveDateTime {
+        fn full_range() -> RangeInclusive<Self> {
+            NaiveDateTime::MIN..=NaiveDateTime::MAX
+        }
+
+        fn to_epoch_offset(&self) -> i128 {
+            self.and_utc().to_epoch_offset()
+        }
+
+        fn from_epoch_offset(offset: i128) -> Self {
+            DateTime::<Utc>::from_epoch_offset(offset).naive_utc()
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+pub use chrono_impls::*;
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use core::ops::RangeInclusive;
+
+    use time::{OffsetDateTime, PrimitiveDateTime};
+
+    use super::EpochOffset;
+
+    pub type OffsetDateTimeStrategy = super::DateTimeStrategy<OffsetDateTime>;
+
+    /// Values are generated in UTC; ranges with other offsets are compared
+    /// by the instant they denote.
+    impl EpochOffset for OffsetDateTime {
+        fn full_range() -> RangeInclusive<Self> {
+            PrimitiveDateTime::MIN.assume_utc()
+                ..=PrimitiveDateTime::MAX.assume_utc()
+        }
+
+        fn to_epoch_offset(&self) -> i128 {
+            self.unix_timestamp_nanos()
+        }
+
+        fn from_epoch_offset(offset: i128) -> Self {
+            OffsetDateTime::from_unix_timestamp_nanos(offset)
+                .expect("nanosecond offset outside OffsetDateTime range")
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+pub use time_impls::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shrink_fully<T: ValueTree>(tree: &mut T) {
+        while tree.simplify() {}
+    }
+
+    fn tree_for<T: EpochOffset>(
+        strategy: &mut DateTimeStrategy<T>,
+    ) -> DateTimeValueTree<T> {
+        let mut generator = Generator::build(crate::rng());
+        match strategy.new_tree(&mut generator) {
+            Generation::Accepted { value, .. } => value,
+            Generation::Rejected { .. } => panic!("unexpected rejection"),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_dates_shrink_toward_epoch() {
+        use chrono::NaiveDate;
+
+        let mut strategy = NaiveDateStrategy::default();
+        let mut tree = tree_for(&mut strategy);
+        shrink_fully(&mut tree);
+        assert_eq!(
+            *tree.current(),
+            NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_date_times_respect_bounds() {
+        use chrono::{TimeZone, Utc};
+
+        let lo = Utc.with_ymd_and_hms(2024, 2, 28, 0, 0, 0).unwrap();
+        let hi = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let mut strategy = UtcDateTimeStrategy::new(lo..=hi);
+        for _ in 0..64 {
+            let mut tree = tree_for(&mut strategy);
+            assert!((lo..=hi).contains(tree.current()));
+            shrink_fully(&mut tree);
+            assert_eq!(*tree.current(), lo);
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_offsets_round_trip() {
+        use chrono::{NaiveDate, NaiveDateTime};
+
+        for date in [NaiveDate::MIN, NaiveDate::MAX] {
+            assert_eq!(
+                NaiveDate::from_epoch_offset(date.to_epoch_offset()),
+                date
+            );
+        }
+        for instant in [NaiveDateTime::MIN, NaiveDateTime::MAX] {
+            let offset = instant.to_epoch_offset();
+            assert_eq!(NaiveDateTime::from_epoch_offset(offset), instant);
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_offset_date_times_shrink_toward_epoch() {
+        use time::OffsetDateTime;
+
+        let mut strategy = OffsetDateTimeStrategy::default();
+        let mut tree = tree_for(&mut strategy);
+        shrink_fully(&mut tree);
+        assert_eq!(*tree.current(), OffsetDateTime::UNIX_EPOCH);
+    }
+}
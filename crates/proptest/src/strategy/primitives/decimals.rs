@@ -0,0 +1,87 @@
+use rand::{CryptoRng, Rng, RngCore};
+use rust_decimal::Decimal;
+
+use super::IntValueTree;
+use crate::strategy::{
+    Strategy,
+    runtime::{Generation, Generator},
+};
+
+/// Largest mantissa a `Decimal` can hold (96 bits).
+const MAX_MANTISSA: i128 = (1 << 96) - 1;
+
+/// Arbitrary `Decimal`s with a scale of at most `max_scale`.
+///
+/// Shrinking halves the mantissa toward zero while keeping the scale.
+#[derive(Clone)]
+pub struct DecimalStrategy {
+    max_scale: u32,
+}
+
+impl DecimalStrategy {
+    pub fn new(max_scale: u32) -> Self {
+        Self {
+            max_scale: max_scale.min(Decimal::MAX_SCALE),
+        }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> Decimal
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        // Same bit-length trick as the big integers, so short mantissas show
+        // up regularly.
+        let bits = rng.random_range(0..=96);
+        let mantissa = rng.random_range(0..=MAX_MANTISSA) >> (96 - bits);
+        let mantissa = if rng.random() { mantissa } else { -mantissa };
+        let scale = rng.random_range(0..=self.max_scale);
+        Decimal::from_i128_with_scale(mantissa, scale)
+    }
+}
+
+impl Default for DecimalStrategy {
+    fn default() -> Self {
+        Self::new(Decimal::MAX_SCALE)
+    }
+}
+
+impl Strategy for DecimalStrategy {
+    type Value = Decimal;
+    type Tree = IntValueTree<Decimal>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let value = self.sample(&mut generator.rng);
+        let scale = value.scale();
+        let mut mantissa = value.mantissa();
+        let mut candidates = Vec::new();
+        while mantissa != 0 {
+            mantissa /= 2;
+            candidates.push(Decimal::from_i128_with_scale(mantissa, scale));
+        }
+        generator.accept(IntValueTree::new(value, candidates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::ValueTree;
+
+    #[test]
+    fn decimals_respect_scale_and_shrink_to_zero() {
+        let mut strategy = DecimalStrategy::new(4);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..32 {
+            let mut tree = match strategy.new_tree(&mut generator) {
+                Generation::Accepted { value, .. } => value,
+                Generation::Rejected { .. } => panic!("unexpected rejection"),
+            };
+            assert!(tree.current().scale() <= 4);
+            while tree.simplify() {}
+            assert!(tree.current().is_zero());
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use core::cmp::Ordering;
 
 use rand::Rng;
@@ -110,6 +111,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         let candidate = match self.candidates.get(self.index) {
             Some(candidate) => *candidate,
@@ -0,0 +1,300 @@
+use std::{
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use rand::{CryptoRng, Rng, RngCore, SeedableRng, rngs::StdRng};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+type Outputs<B> = Arc<dyn Fn(u64) -> B + Send + Sync>;
+
+/// A deterministic pure function generated by [`function`].
+///
+/// Every input is hashed together with the function's seed and the result
+/// seeds the output strategy, so equal inputs always map to equal outputs.
+/// Calls are recorded, and the `Debug` output lists the observed
+/// input/output table, which makes `{f:?}` useful in assertion messages.
+pub struct GeneratedFn<A, B> {
+    seed: u64,
+    constant: Option<B>,
+    outputs: Outputs<B>,
+    calls: Arc<Mutex<Vec<(String, String)>>>,
+    _input: PhantomData<fn(&A)>,
+}
+
+impl<A, B> GeneratedFn<A, B>
+where
+    A: Hash + fmt::Debug,
+    B: Clone + fmt::Debug,
+{
+    pub fn call(&self, input: &A) -> B {
+        let output = match &self.constant {
+            Some(constant) => constant.clone(),
+            None => {
+                let mut hasher = DefaultHasher::new();
+                self.seed.hash(&mut hasher);
+                input.hash(&mut hasher);
+                (self.outputs)(hasher.finish())
+            }
+        };
+
+        let input = format!("{input:?}");
+        let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        if !calls.iter().any(|(seen, _)| *seen == input) {
+            calls.push((input, format!("{output:?}")));
+        }
+        output
+    }
+
+    /// Inputs observed so far with the outputs they produced, formatted
+    /// with `Debug`.
+    pub fn calls(&self) -> Vec<(String, String)> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Clones share the call table, so calls made through a clone show up when
+/// the original is printed.
+impl<A, B> Clone for GeneratedFn<A, B>
+where
+    B: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            seed: self.seed,
+            constant: self.constant.clone(),
+            outputs: Arc::clone(&self.outputs),
+            calls: Arc::clone(&self.calls),
+            _input: PhantomData,
+        }
+    }
+}
+
+impl<A, B> fmt::Debug for GeneratedFn<A, B>
+where
+    B: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        let mut map = f.debug_map();
+        for (input, output) in calls.iter() {
+            map.key(&format_args!("{input}"))
+                .value(&format_args!("{output}"));
+        }
+        if let Some(constant) = &self.constant {
+            map.key(&format_args!("_")).value(constant);
+        }
+        map.finish()
+    }
+}
+
+pub struct FunctionStrategy<A, S> {
+    output: S,
+    _input: PhantomData<fn(&A)>,
+}
+
+/// Generate pure functions from `A` whose outputs are drawn from `output`.
+pub fn function<A, S>(output: S) -> FunctionStrategy<A, S>
+where
+    S: Strategy + Clone,
+{
+    FunctionStrategy {
+        output,
+        _input: PhantomData,
+    }
+}
+
+impl<A, S> Clone for FunctionStrategy<A, S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            output: self.output.clone(),
+            _input: PhantomData,
+        }
+    }
+}
+
+fn output_for<S>(strategy: &S, seed: u64) -> S::Tree
+where
+    S: Strategy + Clone,
+{
+    let mut generator = Generator::build(StdRng::seed_from_u64(seed));
+    strategy.clone().new_tree(&mut generator).take()
+}
+
+impl<A, S> Strategy for FunctionStrategy<A, S>
+where
+    A: Hash + fmt::Debug,
+    S: Strategy + Clone + Send + Sync + 'static,
+    S::Value: Clone + fmt::Debug,
+{
+    type Value = GeneratedFn<A, S::Value>;
+    type Tree = FunctionValueTree<A, S>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let seed = generator.rng.random();
+        let strategy = self.output.clone();
+        let outputs: Outputs<S::Value> =
+            Arc::new(move |seed| output_for(&strategy, seed).into_current());
+
+        generator.accept(FunctionValueTree {
+            strategy: self.output.clone(),
+            current: GeneratedFn {
+                seed,
+                constant: None,
+                outputs,
+                calls: Arc::default(),
+                _input: PhantomData,
+            },
+            constant: None,
+            just_collapsed: false,
+        })
+    }
+}
+
+/// Shrinks a generated function by first collapsing it into a constant
+/// function and then shrinking that constant with the output strategy.
+pub struct FunctionValueTree<A, S>
+where
+    S: Strategy,
+{
+    strategy: S,
+    current: GeneratedFn<A, S::Value>,
+    constant: Option<S::Tree>,
+    just_collapsed: bool,
+}
+
+impl<A, S> FunctionValueTree<A, S>
+where
+    S: Strategy,
+    S::Value: Clone,
+{
+    fn set_constant(&mut self, constant: Option<S::Value>) {
+        self.current = GeneratedFn {
+            seed: self.current.seed,
+            constant,
+            outputs: Arc::clone(&self.current.outputs),
+            calls: Arc::default(),
+            _input: PhantomData,
+        };
+    }
+}
+
+impl<A, S> ValueTree for FunctionValueTree<A, S>
+where
+    S: Strategy + Clone,
+    S::Value: Clone,
+{
+    type Value = GeneratedFn<A, S::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        match &mut self.constant {
+            None => {
+                let tree = output_for(&self.strategy, self.current.seed);
+                self.set_constant(Some(tree.current().clone()));
+                self.constant = Some(tree);
+                self.just_collapsed = true;
+                true
+            }
+            Some(tree) => {
+                if !tree.simplify() {
+                    return false;
+                }
+                let value = tree.current().clone();
+                self.set_constant(Some(value));
+                self.just_collapsed = false;
+                true
+            }
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        if self.just_collapsed {
+            self.just_collapsed = false;
+            self.constant = None;
+            self.set_constant(None);
+            return false;
+        }
+
+        let Some(tree) = &mut self.constant else {
+            return false;
+        };
+        let more = tree.complicate();
+        let value = tree.current().clone();
+        self.set_constant(Some(value));
+        more
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::AnyU8;
+
+    fn tree() -> FunctionValueTree<u32, AnyU8> {
+        let mut generator = Generator::build(crate::rng());
+        function::<u32, _>(AnyU8::default())
+            .new_tree(&mut generator)
+            .take()
+    }
+
+    #[test]
+    fn generated_functions_are_deterministic() {
+        let f = tree().current().clone();
+        for input in 0..32 {
+            assert_eq!(f.call(&input), f.call(&input));
+        }
+        assert_eq!(f.calls().len(), 32);
+    }
+
+    #[test]
+    fn shrinking_collapses_to_a_constant_then_shrinks_it() {
+        let mut tree = tree();
+        assert!(tree.simplify());
+        let f = tree.current().clone();
+        assert_eq!(f.call(&1), f.call(&2));
+
+        while tree.simplify() {}
+        assert_eq!(tree.current().call(&7), 0);
+    }
+
+    #[test]
+    fn complicate_restores_the_original_function() {
+        let mut tree = tree();
+        let original: Vec<u8> =
+            (0..16).map(|input| tree.current().call(&input)).collect();
+
+        assert!(tree.simplify());
+        assert!(!tree.complicate());
+        let restored: Vec<u8> =
+            (0..16).map(|input| tree.current().call(&input)).collect();
+        assert_eq!(original, restored);
+    }
+
+    #[test]
+    fn debug_lists_observed_calls() {
+        let f = tree().current().clone();
+        let output = f.call(&3);
+        assert_eq!(format!("{f:?}"), format!("{{3: {output}}}"));
+    }
+}
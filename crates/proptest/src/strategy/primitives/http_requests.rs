@@ -0,0 +1,551 @@
+use std::ops::RangeInclusive;
+
+use rand::{CryptoRng, RngCore};
+
+use super::{AnyU8, IntValueTree};
+use crate::strategy::{
+    SizeHint,
+    Strategy,
+    ValueTree,
+    VecStrategy,
+    VecValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Every table lists its plainest entry first, which is what shrinking moves
+/// towards. `get` is an extension method of its own, methods are case
+/// sensitive.
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "CONNECT",
+    "TRACE", "PROPFIND", "get",
+];
+
+const SEGMENTS: &[&str] = &[
+    "a",
+    "api",
+    "v1",
+    "users",
+    "42",
+    "index.html",
+    "",
+    ".",
+    "..",
+    "%20",
+    "a%2Fb",
+    "~user",
+    "caf%C3%A9",
+];
+
+const QUERY_KEYS: &[&str] = &["q", "id", "page", "a%5B%5D", "utf8"];
+
+const QUERY_VALUES: &[&str] = &["", "1", "-1", "true", "%00", "a%26b"];
+
+const HEADER_NAMES: &[&str] = &[
+    "Host",
+    "Accept",
+    "Content-Type",
+    "Content-Length",
+    "Transfer-Encoding",
+    "Connection",
+    "Authorization",
+    "Cookie",
+    "X-Forwarded-For",
+    "X-Request-Id",
+];
+
+const HEADER_VALUES: &[&str] = &[
+    "",
+    "0",
+    "1",
+    "-1",
+    "99999999999999999999",
+    "*/*",
+    "text/plain",
+    "application/json; charset=utf-8",
+    "chunked",
+    "gzip, chunked",
+    "keep-alive",
+    "close",
+    " padded ",
+    "a=1; b=2",
+];
+
+/// How a header name is cased: as listed, lowercase, uppercase or with
+/// alternating case.
+const CASINGS: usize = 4;
+
+/// A plain HTTP request: the method, the request target, every header line
+/// in order and the body.
+///
+/// Header names keep their casing and duplicates are kept, which a
+/// [`http::HeaderMap`] would normalize away; [`to_bytes`](Self::to_bytes)
+/// writes them out as generated. Headers are not made consistent with the
+/// body, so a `Content-Length` may well disagree with it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HttpRequest {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// The path and query, such as `/api/users?id=1`.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// The values of every header called `name`, ignoring case.
+    pub fn header_values<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// The request as an HTTP/1.1 message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!("{} {} HTTP/1.1\r\n", self.method, self.target)
+            .into_bytes();
+        for (name, value) in &self.headers {
+            bytes.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        bytes.extend_from_slice(b"\r\n");
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// The request as an [`http::Request`], for routers and middleware built
+    /// on the `http` crate. Header names are lowercased by
+    /// [`http::HeaderName`], duplicates are kept.
+    pub fn to_request(&self) -> http::Request<Vec<u8>> {
+        let mut builder = http::Request::builder()
+            .method(self.method.as_str())
+            .uri(self.target.as_str());
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder
+            .body(self.body.clone())
+            .expect("generated requests are valid")
+    }
+}
+
+/// An index into a table of `len` entries, chosen through
+/// [`Generator::choose`] under `key` and shrinking towards the first entry.
+#[derive(Clone)]
+struct Pick {
+    key: &'static str,
+    len: usize,
+}
+
+impl Pick {
+    fn new(key: &'static str, len: usize) -> Self {
+        Self { key, len }
+    }
+}
+
+impl Strategy for Pick {
+    type Value = usize;
+    type Tree = IntValueTree<usize>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let index = generator.choose(self.key, self.len);
+        generator.accept(IntValueTree::new(index, (0..index).rev().collect()))
+    }
+}
+
+/// A fixed number of [`Pick`]s, such as the name, casing and value of a
+/// header, which shrink one after the other.
+#[derive(Clone)]
+struct Picks<const N: usize>([Pick; N]);
+
+impl<const N: usize> Strategy for Picks<N> {
+    type Value = [usize; N];
+    type Tree = PicksValueTree<N>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let trees = self
+            .0
+            .each_mut()
+            .map(|pick| pick.new_tree(generator).take());
+        let current = trees.each_ref().map(|tree| *tree.current());
+        generator.accept(PicksValueTree {
+            trees,
+            current,
+            index: 0,
+            history: Vec::new(),
+        })
+    }
+}
+
+struct PicksValueTree<const N: usize> {
+    trees: [IntValueTree<usize>; N],
+    current: [usize; N],
+    /// The pick being shrunk, the earlier ones are done.
+    index: usize,
+    history: Vec<usize>,
+}
+
+impl<const N: usize> ValueTree for PicksValueTree<N> {
+    type Value = [usize; N];
+
+    fn current(&self) -> &[usize; N] {
+        &self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        while self.index < N {
+            let index = self.index;
+            if self.trees[index].simplify() {
+                self.current[index] = *self.trees[index].current();
+                self.history.push(index);
+                return true;
+            }
+            self.index += 1;
+        }
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(index) = self.history.pop() else {
+            return false;
+        };
+        let more = self.trees[index].complicate();
+        self.current[index] = *self.trees[index].current();
+        if !more {
+            self.index = index + 1;
+        }
+        self.index < N
+    }
+}
+
+fn cased(name: &str, casing: usize) -> String {
+    match casing {
+        0 => name.to_owned(),
+        1 => name.to_ascii_lowercase(),
+        2 => name.to_ascii_uppercase(),
+        _ => name
+            .chars()
+            .enumerate()
+            .map(|(index, ch)| {
+                if index % 2 == 0 {
+                    ch.to_ascii_lowercase()
+                } else {
+                    ch.to_ascii_uppercase()
+                }
+            })
+            .collect(),
+    }
+}
+
+/// The parts of a request, in the order they shrink.
+struct Parts {
+    headers: VecValueTree<PicksValueTree<3>>,
+    body: VecValueTree<IntValueTree<u8>>,
+    query: VecValueTree<PicksValueTree<2>>,
+    segments: VecValueTree<IntValueTree<usize>>,
+    method: IntValueTree<usize>,
+}
+
+const PARTS: usize = 5;
+
+impl Parts {
+    fn simplify(&mut self, part: usize) -> bool {
+        match part {
+            0 => self.headers.simplify(),
+            1 => self.body.simplify(),
+            2 => self.query.simplify(),
+            3 => self.segments.simplify(),
+            _ => self.method.simplify(),
+        }
+    }
+
+    fn complicate(&mut self, part: usize) -> bool {
+        match part {
+            0 => self.headers.complicate(),
+            1 => self.body.complicate(),
+            2 => self.query.complicate(),
+            3 => self.segments.complicate(),
+            _ => self.method.complicate(),
+        }
+    }
+
+    fn build(&self) -> HttpRequest {
+        build(
+            self.headers.current(),
+            self.body.current(),
+            self.query.current(),
+            self.segments.current(),
+            *self.method.current(),
+        )
+    }
+}
+
+fn build(
+    headers: &[[usize; 3]],
+    body: &[u8],
+    query: &[[usize; 2]],
+    segments: &[usize],
+    method: usize,
+) -> HttpRequest {
+    let mut target = String::from("/");
+    let path: Vec<&str> =
+        segments.iter().map(|&index| SEGMENTS[index]).collect();
+    target.push_str(&path.join("/"));
+    for (position, &[key, value]) in query.iter().enumerate() {
+        target.push(if position == 0 { '?' } else { '&' });
+        target.push_str(QUERY_KEYS[key]);
+        target.push('=');
+        target.push_str(QUERY_VALUES[value]);
+    }
+
+    HttpRequest {
+        method: METHODS[method].to_owned(),
+        target,
+        headers: headers
+            .iter()
+            .map(|&[name, casing, value]| {
+                (
+                    cased(HEADER_NAMES[name], casing),
+                    HEADER_VALUES[value].to_owned(),
+                )
+            })
+            .collect(),
+        body: body.to_vec(),
+    }
+}
+
+/// Generate [`HttpRequest`]s for testing routers and middleware.
+///
+/// Methods, path segments, query parameters and headers come from curated
+/// tables mixing the common with the awkward: extension methods,
+/// percent-encoded and dot segments, duplicate headers in odd casing and
+/// values such as `chunked` or an overflowing `Content-Length`. Shrinking
+/// drops headers, body bytes, query parameters and path segments, then moves
+/// what is left towards `GET /` with plain headers.
+#[derive(Clone)]
+pub struct HttpRequestStrategy {
+    segments: RangeInclusive<usize>,
+    query_params: RangeInclusive<usize>,
+    headers: RangeInclusive<usize>,
+    body: RangeInclusive<usize>,
+}
+
+impl HttpRequestStrategy {
+    pub fn new() -> Self {
+        Self {
+            segments: 0..=4,
+            query_params: 0..=3,
+            headers: 0..=8,
+            body: 0..=64,
+        }
+    }
+
+    /// Bound the number of path segments.
+    pub fn with_segments<H: SizeHint>(mut self, segments: H) -> Self {
+        self.segments = segments.to_inclusive();
+        self
+    }
+
+    /// Bound the number of `key=value` query parameters.
+    pub fn with_query_params<H: SizeHint>(mut self, query_params: H) -> Self {
+        self.query_params = query_params.to_inclusive();
+        self
+    }
+
+    /// Bound the number of header lines, duplicates included.
+    pub fn with_headers<H: SizeHint>(mut self, headers: H) -> Self {
+        self.headers = headers.to_inclusive();
+        self
+    }
+
+    /// Bound the size of the body in bytes.
+    pub fn with_body_size<H: SizeHint>(mut self, body: H) -> Self {
+        self.body = body.to_inclusive();
+        self
+    }
+}
+
+impl Default for HttpRequestStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strategy for HttpRequestStrategy {
+    type Value = HttpRequest;
+    type Tree = HttpRequestValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let parts = Parts {
+            headers: VecStrategy::new(
+                Picks([
+                    Pick::new("http header name", HEADER_NAMES.len()),
+                    Pick::new("http header casing", CASINGS),
+                    Pick::new("http header value", HEADER_VALUES.len()),
+                ]),
+                self.headers.clone(),
+            )
+            .new_tree(generator)
+            .take(),
+            body: VecStrategy::new(AnyU8::default(), self.body.clone())
+                .new_tree(generator)
+                .take(),
+            query: VecStrategy::new(
+                Picks([
+                    Pick::new("http query key", QUERY_KEYS.len()),
+                    Pick::new("http query value", QUERY_VALUES.len()),
+                ]),
+                self.query_params.clone(),
+            )
+            .new_tree(generator)
+            .take(),
+            segments: VecStrategy::new(
+                Pick::new("http path segment", SEGMENTS.len()),
+                self.segments.clone(),
+            )
+            .new_tree(generator)
+            .take(),
+            method: Pick::new("http method", METHODS.len())
+                .new_tree(generator)
+                .take(),
+        };
+        generator.accept(HttpRequestValueTree {
+            current: parts.build(),
+            parts,
+            part: 0,
+            history: Vec::new(),
+        })
+    }
+}
+
+pub struct HttpRequestValueTree {
+    parts: Parts,
+    current: HttpRequest,
+    /// The part being shrunk, the earlier ones are done.
+    part: usize,
+    history: Vec<usize>,
+}
+
+impl ValueTree for HttpRequestValueTree {
+    type Value = HttpRequest;
+
+    fn current(&self) -> &HttpRequest {
+        &self.current
+    }
+
+    fn into_current(self) -> HttpRequest {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        while self.part < PARTS {
+            if self.parts.simplify(self.part) {
+                self.history.push(self.part);
+                self.current = self.parts.build();
+                return true;
+            }
+            self.part += 1;
+        }
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some(part) = self.history.pop() else {
+            return false;
+        };
+        let more = self.parts.complicate(part);
+        self.current = self.parts.build();
+        if !more {
+            self.part = part + 1;
+        }
+        self.part < PARTS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    #[test]
+    fn requests_convert_to_the_http_crate() {
+        check_shrink_invariants_with(
+            HttpRequestStrategy::new(),
+            64,
+            |request| {
+                let converted = request.to_request();
+                converted.method().as_str() == request.method()
+                    && converted.uri() == request.target()
+                    && converted.headers().len() == request.headers().len()
+                    && converted.body() == request.body()
+            },
+        );
+    }
+
+    #[test]
+    fn requests_shrink_to_the_header_that_matters() {
+        assert_shrinks_to!(
+            HttpRequestStrategy::new(),
+            |request: &HttpRequest| {
+                request.header_values("content-length").count() < 2
+            },
+            HttpRequest {
+                method: "GET".to_owned(),
+                target: "/".to_owned(),
+                headers: vec![
+                    ("Content-Length".to_owned(), String::new()),
+                    ("Content-Length".to_owned(), String::new()),
+                ],
+                body: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn wire_format_keeps_casing_and_duplicates() {
+        let request = HttpRequest {
+            method: "POST".to_owned(),
+            target: "/a?q=1".to_owned(),
+            headers: vec![
+                ("cOnTeNt-lEnGtH".to_owned(), "1".to_owned()),
+                ("Content-Length".to_owned(), "2".to_owned()),
+            ],
+            body: b"x".to_vec(),
+        };
+        assert_eq!(
+            request.to_bytes(),
+            b"POST /a?q=1 HTTP/1.1\r\ncOnTeNt-lEnGtH: 1\r\n\
+              Content-Length: 2\r\n\r\nx"
+        );
+        assert_eq!(cased("Content-Length", 3), "cOnTeNt-lEnGtH");
+    }
+}
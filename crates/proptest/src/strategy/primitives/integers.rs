@@ -1,26 +1,48 @@
-use std::ops::RangeInclusive;
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::ops::RangeInclusive;
 
 use rand::Rng;
 
 use crate::strategy::{
+    Provenance,
     Strategy,
     ValueTree,
-    runtime::{Generation, Generator},
+    runtime::{Generation, Generator, MAX_SIZE},
 };
 
+/// Largest distance from the shrink target a value with `bits` significant
+/// bits can have.
+fn size_radius(bits: u32) -> u128 {
+    1u128.checked_shl(bits).map_or(u128::MAX, |limit| limit - 1)
+}
+
+/// Narrow `lo..=hi` to the values within [`size_radius`] of `target`.
+fn sized_signed(lo: i128, hi: i128, target: i128, bits: u32) -> (i128, i128) {
+    let radius = size_radius(bits).min(i128::MAX as u128) as i128;
+    let lo = target.saturating_sub(radius).max(lo);
+    let hi = target.saturating_add(radius).min(hi);
+    (lo, hi)
+}
+
+/// Narrow `lo..=hi` to the values within [`size_radius`] of `lo`.
+fn sized_unsigned(lo: u128, hi: u128, bits: u32) -> (u128, u128) {
+    (lo, lo.saturating_add(size_radius(bits)).min(hi))
+}
+
 pub struct IntValueTree<T>
 where
-    T: Copy,
+    T: Clone,
 {
     current: T,
     history: Vec<T>,
     candidates: Vec<T>,
     next_index: usize,
+    describe: Option<fn(&T) -> String>,
 }
 
 impl<T> IntValueTree<T>
 where
-    T: Copy,
+    T: Clone,
 {
     pub fn new(current: T, candidates: Vec<T>) -> Self {
         Self {
@@ -28,13 +50,31 @@ where
             history: Vec::new(),
             candidates,
             next_index: 0,
+            describe: None,
         }
     }
+
+    /// Track the provenance of the tree's values, labeled by `describe`.
+    pub fn with_provenance(mut self, describe: fn(&T) -> String) -> Self {
+        self.describe = Some(describe);
+        self
+    }
+}
+
+/// `name`, noting when `value` is one of the bounds of its type.
+fn describe<T: PartialEq>(name: &str, value: T, min: T, max: T) -> String {
+    if value == min {
+        format!("{name} edge-case MIN")
+    } else if value == max {
+        format!("{name} edge-case MAX")
+    } else {
+        name.to_owned()
+    }
 }
 
 impl<T> ValueTree for IntValueTree<T>
 where
-    T: Copy,
+    T: Clone,
 {
     type Value = T;
 
@@ -42,14 +82,18 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         let candidate = match self.candidates.get(self.next_index) {
-            Some(candidate) => *candidate,
+            Some(candidate) => candidate.clone(),
             None => return false,
         };
 
-        self.history.push(self.current);
-        self.current = candidate;
+        let previous = core::mem::replace(&mut self.current, candidate);
+        self.history.push(previous);
         self.next_index += 1;
         true
     }
@@ -62,6 +106,11 @@ where
         self.current = previous;
         self.next_index < self.candidates.len()
     }
+
+    fn provenance(&self) -> Option<Provenance> {
+        self.describe
+            .map(|describe| Provenance::new(describe(&self.current)))
+    }
 }
 
 macro_rules! impl_signed_int_strategy {
@@ -76,6 +125,28 @@ macro_rules! impl_signed_int_strategy {
                 Self { range }
             }
 
+            /// A tree holding `value`, shrinking it like the trees this strategy
+            /// generates, e.g. to minimize a value captured outside a test run with
+            /// [`shrink`](crate::shrink::shrink).
+            ///
+            /// # Panics
+            ///
+            /// When `value` is outside the strategy's range.
+            pub fn tree_for(&self, value: $ty) -> IntValueTree<$ty> {
+                assert!(
+                    self.range.contains(&value),
+                    "{value} is outside {:?}",
+                    self.range,
+                );
+                let candidates = Self::build_candidates(
+                    value,
+                    Self::anchor(*self.range.start(), *self.range.end()),
+                );
+                IntValueTree::new(value, candidates).with_provenance(|value| {
+                    describe(stringify!($name), *value, <$ty>::MIN, <$ty>::MAX)
+                })
+            }
+
             #[inline]
             fn anchor(lo: $ty, hi: $ty) -> $ty {
                 if lo <= $zero && hi >= $zero {
@@ -87,6 +158,25 @@ macro_rules! impl_signed_int_strategy {
                 }
             }
 
+            /// The part of the range a generator of its size draws from.
+            fn sized_range<R: rand::RngCore + rand::CryptoRng>(
+                &self,
+                generator: &Generator<R>,
+            ) -> RangeInclusive<$ty> {
+                if generator.size() >= MAX_SIZE {
+                    return self.range.clone();
+                }
+
+                let (lo, hi) = (*self.range.start(), *self.range.end());
+                let (lo, hi) = sized_signed(
+                    lo as i128,
+                    hi as i128,
+                    Self::anchor(lo, hi) as i128,
+                    generator.sized_bits(<$ty>::BITS),
+                );
+                (lo as $ty)..=(hi as $ty)
+            }
+
             fn build_candidates(value: $ty, target: $ty) -> Vec<$ty> {
                 let mut current = value as i128;
                 let target = target as i128;
@@ -137,12 +227,9 @@ macro_rules! impl_signed_int_strategy {
                 &mut self,
                 generator: &mut Generator<R>,
             ) -> Generation<Self::Tree> {
-                let value = generator.rng.random_range(self.range.clone());
-                let lo = *self.range.start();
-                let hi = *self.range.end();
-                let target = Self::anchor(lo, hi);
-                let candidates = Self::build_candidates(value, target);
-                generator.accept(IntValueTree::new(value, candidates))
+                let range = self.sized_range(generator);
+                let value = generator.rng.random_range(range);
+                generator.accept(self.tree_for(value))
             }
         }
     };
@@ -160,11 +247,51 @@ macro_rules! impl_unsigned_int_strategy {
                 Self { range }
             }
 
+            /// A tree holding `value`, shrinking it like the trees this strategy
+            /// generates, e.g. to minimize a value captured outside a test run with
+            /// [`shrink`](crate::shrink::shrink).
+            ///
+            /// # Panics
+            ///
+            /// When `value` is outside the strategy's range.
+            pub fn tree_for(&self, value: $ty) -> IntValueTree<$ty> {
+                assert!(
+                    self.range.contains(&value),
+                    "{value} is outside {:?}",
+                    self.range,
+                );
+                let candidates = Self::build_candidates(
+                    value,
+                    Self::anchor(*self.range.start()),
+                );
+                IntValueTree::new(value, candidates).with_provenance(|value| {
+                    describe(stringify!($name), *value, <$ty>::MIN, <$ty>::MAX)
+                })
+            }
+
             #[inline]
             fn anchor(lo: $ty) -> $ty {
                 if lo == 0 { 0 } else { lo }
             }
 
+            /// The part of the range a generator of its size draws from.
+            fn sized_range<R: rand::RngCore + rand::CryptoRng>(
+                &self,
+                generator: &Generator<R>,
+            ) -> RangeInclusive<$ty> {
+                if generator.size() >= MAX_SIZE {
+                    return self.range.clone();
+                }
+
+                let (lo, hi) = (*self.range.start(), *self.range.end());
+                let (lo, hi) = sized_unsigned(
+                    lo as u128,
+                    hi as u128,
+                    generator.sized_bits(<$ty>::BITS),
+                );
+                (lo as $ty)..=(hi as $ty)
+            }
+
             fn build_candidates(value: $ty, target: $ty) -> Vec<$ty> {
                 let mut current = value as u128;
                 let target = target as u128;
@@ -214,11 +341,9 @@ macro_rules! impl_unsigned_int_strategy {
                 &mut self,
                 generator: &mut Generator<R>,
             ) -> Generation<Self::Tree> {
-                let value = generator.rng.random_range(self.range.clone());
-                let lo = *self.range.start();
-                let target = Self::anchor(lo);
-                let candidates = Self::build_candidates(value, target);
-                generator.accept(IntValueTree::new(value, candidates))
+                let range = self.sized_range(generator);
+                let value = generator.rng.random_range(range);
+                generator.accept(self.tree_for(value))
             }
         }
     };
@@ -246,6 +371,28 @@ impl AnyIsize {
         Self { range }
     }
 
+    /// A tree holding `value`, shrinking it like the trees this strategy
+    /// generates, e.g. to minimize a value captured outside a test run with
+    /// [`shrink`](crate::shrink::shrink).
+    ///
+    /// # Panics
+    ///
+    /// When `value` is outside the strategy's range.
+    pub fn tree_for(&self, value: isize) -> IntValueTree<isize> {
+        assert!(
+            self.range.contains(&value),
+            "{value} is outside {:?}",
+            self.range,
+        );
+        let candidates = Self::build_candidates(
+            value,
+            Self::anchor(*self.range.start(), *self.range.end()),
+        );
+        IntValueTree::new(value, candidates).with_provenance(|value| {
+            describe("AnyIsize", *value, <isize>::MIN, <isize>::MAX)
+        })
+    }
+
     #[inline]
     fn anchor(lo: isize, hi: isize) -> isize {
         if lo <= 0 && hi >= 0 {
@@ -257,6 +404,25 @@ impl AnyIsize {
         }
     }
 
+    /// The part of the range a generator of its size draws from.
+    fn sized_range<R: rand::RngCore + rand::CryptoRng>(
+        &self,
+        generator: &Generator<R>,
+    ) -> RangeInclusive<isize> {
+        if generator.size() >= MAX_SIZE {
+            return self.range.clone();
+        }
+
+        let (lo, hi) = (*self.range.start(), *self.range.end());
+        let (lo, hi) = sized_signed(
+            lo as i128,
+            hi as i128,
+            Self::anchor(lo, hi) as i128,
+            generator.sized_bits(isize::BITS),
+        );
+        (lo as isize)..=(hi as isize)
+    }
+
     fn build_candidates(value: isize, target: isize) -> Vec<isize> {
         let mut current = value as i128;
         let target = target as i128;
@@ -325,12 +491,9 @@ impl Strategy for AnyIsize {
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let value = Self::sample(&mut generator.rng, self.range.clone());
-        let lo = *self.range.start();
-        let hi = *self.range.end();
-        let target = Self::anchor(lo, hi);
-        let candidates = Self::build_candidates(value, target);
-        generator.accept(IntValueTree::new(value, candidates))
+        let range = self.sized_range(generator);
+        let value = Self::sample(&mut generator.rng, range);
+        generator.accept(self.tree_for(value))
     }
 }
 
@@ -344,11 +507,49 @@ impl AnyUsize {
         Self { range }
     }
 
+    /// A tree holding `value`, shrinking it like the trees this strategy
+    /// generates, e.g. to minimize a value captured outside a test run with
+    /// [`shrink`](crate::shrink::shrink).
+    ///
+    /// # Panics
+    ///
+    /// When `value` is outside the strategy's range.
+    pub fn tree_for(&self, value: usize) -> IntValueTree<usize> {
+        assert!(
+            self.range.contains(&value),
+            "{value} is outside {:?}",
+            self.range,
+        );
+        let candidates =
+            Self::build_candidates(value, Self::anchor(*self.range.start()));
+        IntValueTree::new(value, candidates).with_provenance(|value| {
+            describe("AnyUsize", *value, <usize>::MIN, <usize>::MAX)
+        })
+    }
+
     #[inline]
     fn anchor(lo: usize) -> usize {
         if lo == 0 { 0 } else { lo }
     }
 
+    /// The part of the range a generator of its size draws from.
+    fn sized_range<R: rand::RngCore + rand::CryptoRng>(
+        &self,
+        generator: &Generator<R>,
+    ) -> RangeInclusive<usize> {
+        if generator.size() >= MAX_SIZE {
+            return self.range.clone();
+        }
+
+        let (lo, hi) = (*self.range.start(), *self.range.end());
+        let (lo, hi) = sized_unsigned(
+            lo as u128,
+            hi as u128,
+            generator.sized_bits(usize::BITS),
+        );
+        (lo as usize)..=(hi as usize)
+    }
+
     fn build_candidates(value: usize, target: usize) -> Vec<usize> {
         let mut current = value as u128;
         let target = target as u128;
@@ -412,11 +613,9 @@ impl Strategy for AnyUsize {
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let value = Self::sample(&mut generator.rng, self.range.clone());
-        let lo = *self.range.start();
-        let target = Self::anchor(lo);
-        let candidates = Self::build_candidates(value, target);
-        generator.accept(IntValueTree::new(value, candidates))
+        let range = self.sized_range(generator);
+        let value = Self::sample(&mut generator.rng, range);
+        generator.accept(self.tree_for(value))
     }
 }
 
@@ -424,6 +623,23 @@ impl Strategy for AnyUsize {
 mod tests {
     use super::*;
 
+    #[test]
+    fn trees_for_values_shrink_like_generated_ones() {
+        let mut tree = AnyI16::new(-100..=-10).tree_for(-73);
+        assert_eq!(tree.current(), &-73);
+        while tree.simplify() {}
+        assert_eq!(tree.current(), &-10);
+
+        let tree = AnyUsize::new(3..=9).tree_for(9);
+        assert_eq!(tree.provenance().unwrap().label, "AnyUsize");
+    }
+
+    #[test]
+    #[should_panic(expected = "12 is outside 0..=10")]
+    fn trees_for_values_stay_in_range() {
+        AnyU8::new(0..=10).tree_for(12);
+    }
+
     #[test]
     fn signed_sequence_shrinks_toward_zero() {
         let candidates = AnyI32::build_candidates(23, 0);
@@ -481,4 +697,28 @@ mod tests {
         assert!(!tree.complicate());
         assert_eq!(*tree.current(), 4);
     }
+
+    #[test]
+    fn small_sizes_draw_values_near_the_target() {
+        let mut generator = Generator::build(crate::rng()).with_size(0);
+        let value = AnyI64::default().new_tree(&mut generator).take();
+        assert_eq!(*value.current(), 0);
+
+        let mut generator = Generator::build(crate::rng()).with_size(25);
+        let mut strategy = AnyU32::new(10..=u32::MAX);
+        for _ in 0..64 {
+            let tree = strategy.new_tree(&mut generator).take();
+            assert!((10..=10 + 255).contains(tree.current()));
+        }
+    }
+
+    #[test]
+    fn full_size_keeps_the_whole_range() {
+        let mut generator = Generator::build(crate::rng());
+        let mut strategy = AnyI8::default();
+        let reached_extremes = (0..4096)
+            .map(|_| *strategy.new_tree(&mut generator).take().current())
+            .any(|value| value == i8::MIN);
+        assert!(reached_extremes);
+    }
 }
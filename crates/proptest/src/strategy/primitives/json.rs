@@ -0,0 +1,308 @@
+use rand::{CryptoRng, Rng, RngCore};
+use serde_json::{Map, Number, Value};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+const KEY_CHARS: &str = "abcxyz_01";
+const STRING_CHARS: &str = "abzAZ09 \"\\/\n\t\u{0}\u{e9}\u{2028}\u{1f600}";
+
+/// Arbitrary `serde_json::Value` trees.
+///
+/// `max_depth` bounds how deeply arrays and objects nest and `max_width`
+/// bounds how many entries each of them holds. Shrinking replaces subtrees
+/// with `null`, hoists children, drops entries and then simplifies scalars.
+#[derive(Clone)]
+pub struct JsonValueStrategy {
+    max_depth: usize,
+    max_width: usize,
+}
+
+impl JsonValueStrategy {
+    pub fn new(max_depth: usize, max_width: usize) -> Self {
+        Self {
+            max_depth,
+            max_width,
+        }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> Value
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        self.sample_at(rng, self.max_depth)
+    }
+
+    fn sample_at<R>(&self, rng: &mut R, depth: usize) -> Value
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        let kinds = if depth == 0 { 4 } else { 6 };
+        match rng.random_range(0..kinds) {
+            0 => Value::Null,
+            1 => Value::Bool(rng.random()),
+            2 => Value::Number(sample_number(rng)),
+            3 => Value::String(sample_string(rng, STRING_CHARS, 0..=16)),
+            4 => {
+                let len = rng.random_range(0..=self.max_width);
+                Value::Array(
+                    (0..len).map(|_| self.sample_at(rng, depth - 1)).collect(),
+                )
+            }
+            _ => {
+                let len = rng.random_range(0..=self.max_width);
+                let mut map = Map::new();
+                for _ in 0..len {
+                    let key = sample_string(rng, KEY_CHARS, 1..=6);
+                    map.insert(key, self.sample_at(rng, depth - 1));
+                }
+                Value::Object(map)
+            }
+        }
+    }
+}
+
+impl Default for JsonValueStrategy {
+    fn default() -> Self {
+        Self::new(4, 6)
+    }
+}
+
+impl Strategy for JsonValueStrategy {
+    type Value = Value;
+    type Tree = JsonValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let value = self.sample(&mut generator.rng);
+        generator.accept(JsonValueTree::new(value))
+    }
+}
+
+fn sample_number<R>(rng: &mut R) -> Number
+where
+    R: RngCore + CryptoRng + ?Sized,
+{
+    match rng.random_range(0..3) {
+        0 => Number::from(rng.random_range(-1_000i64..=1_000)),
+        1 => Number::from(rng.random::<i64>()),
+        _ => Number::from_f64(rng.random_range(-1.0e9..=1.0e9))
+            .unwrap_or_else(|| Number::from(0)),
+    }
+}
+
+fn sample_string<R>(
+    rng: &mut R,
+    alphabet: &str,
+    len: std::ops::RangeInclusive<usize>,
+) -> String
+where
+    R: RngCore + CryptoRng + ?Sized,
+{
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    let len = rng.random_range(len);
+    (0..len)
+        .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+        .collect()
+}
+
+/// Simpler variants of `value`, most aggressive first.
+fn shrinks(value: &Value) -> Vec<Value> {
+    let mut candidates = Vec::new();
+    if !value.is_null() {
+        candidates.push(Value::Null);
+    }
+
+    match value {
+        Value::Null => {}
+        Value::Bool(flag) => {
+            if *flag {
+                candidates.push(Value::Bool(false));
+            }
+        }
+        Value::Number(number) => candidates
+            .extend(shrink_number(number).into_iter().map(Value::Number)),
+        Value::String(string) => {
+            let chars: Vec<char> = string.chars().collect();
+            if !chars.is_empty() {
+                candidates.push(Value::String(String::new()));
+            }
+            if chars.len() > 1 {
+                let half = chars[..chars.len() / 2].iter().collect();
+                candidates.push(Value::String(half));
+            }
+        }
+        Value::Array(items) => {
+            candidates.extend(items.iter().cloned());
+            for index in 0..items.len() {
+                let mut smaller = items.clone();
+                smaller.remove(index);
+                candidates.push(Value::Array(smaller));
+            }
+            for (index, item) in items.iter().enumerate() {
+                for simpler in shrinks(item) {
+                    let mut replaced = items.clone();
+                    replaced[index] = simpler;
+                    candidates.push(Value::Array(replaced));
+                }
+            }
+        }
+        Value::Object(map) => {
+            candidates.extend(map.values().cloned());
+            for key in map.keys() {
+                let mut smaller = map.clone();
+                smaller.remove(key);
+                candidates.push(Value::Object(smaller));
+            }
+            for (key, item) in map {
+                for simpler in shrinks(item) {
+                    let mut replaced = map.clone();
+                    replaced.insert(key.clone(), simpler);
+                    candidates.push(Value::Object(replaced));
+                }
+            }
+        }
+    }
+
+    candidates
+}
+
+fn shrink_number(number: &Number) -> Vec<Number> {
+    if let Some(value) = number.as_i64() {
+        match value {
+            0 => vec![],
+            -1 | 1 => vec![Number::from(0)],
+            _ => vec![Number::from(0), Number::from(value / 2)],
+        }
+    } else if let Some(value) = number.as_u64() {
+        vec![Number::from(0), Number::from(value / 2)]
+    } else {
+        let value = number.as_f64().unwrap_or(0.0);
+        let mut candidates = vec![Number::from(0)];
+        if value.fract() != 0.0 {
+            candidates.extend(Number::from_f64(value.trunc()));
+        }
+        candidates
+    }
+}
+
+pub struct JsonValueTree {
+    current: Value,
+    previous: Option<(Value, usize)>,
+    next_candidate: usize,
+}
+
+impl JsonValueTree {
+    fn new(current: Value) -> Self {
+        Self {
+            current,
+            previous: None,
+            next_candidate: 0,
+        }
+    }
+}
+
+impl ValueTree for JsonValueTree {
+    type Value = Value;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        let Some(candidate) =
+            shrinks(&self.current).into_iter().nth(self.next_candidate)
+        else {
+            return false;
+        };
+
+        let previous = std::mem::replace(&mut self.current, candidate);
+        self.previous = Some((previous, self.next_candidate));
+        self.next_candidate = 0;
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some((previous, candidate)) = self.previous.take() else {
+            return false;
+        };
+
+        self.current = previous;
+        self.next_candidate = candidate + 1;
+        self.next_candidate < shrinks(&self.current).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn depth(value: &Value) -> usize {
+        match value {
+            Value::Array(items) => {
+                1 + items.iter().map(depth).max().unwrap_or(0)
+            }
+            Value::Object(map) => {
+                1 + map.values().map(depth).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn generated_values_respect_depth_and_width() {
+        let mut strategy = JsonValueStrategy::new(2, 3);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..64 {
+            let tree = match strategy.new_tree(&mut generator) {
+                Generation::Accepted { value, .. } => value,
+                Generation::Rejected { .. } => panic!("unexpected rejection"),
+            };
+            assert!(depth(tree.current()) <= 2);
+            match tree.current() {
+                Value::Array(items) => assert!(items.len() <= 3),
+                Value::Object(map) => assert!(map.len() <= 3),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn shrinking_finds_minimal_counterexample() {
+        let mut tree = JsonValueTree::new(json!({
+            "a": [1, 2, {"deep": "needle"}],
+            "b": true,
+        }));
+        let fails = |value: &Value| value.to_string().contains("needle");
+
+        while tree.simplify() {
+            if !fails(tree.current()) {
+                tree.complicate();
+            }
+        }
+
+        assert_eq!(*tree.current(), json!("needle"));
+    }
+
+    #[test]
+    fn scalars_shrink_toward_simple_values() {
+        let mut tree = JsonValueTree::new(json!(1000));
+        while tree.simplify() {
+            if tree.current().as_i64().is_none_or(|n| n < 10) {
+                tree.complicate();
+            }
+        }
+        assert_eq!(*tree.current(), json!(15));
+    }
+}
@@ -1,46 +1,66 @@
 mod arrays;
+#[cfg(feature = "num-bigint")]
+mod bigints;
 mod bools;
+mod bounded;
 mod chars;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod date_times;
+#[cfg(feature = "rust_decimal")]
+mod decimals;
 mod floats;
+#[cfg(feature = "std")]
+mod functions;
+#[cfg(feature = "http")]
+mod http_requests;
 mod integers;
+#[cfg(feature = "serde_json")]
+mod json;
 mod options;
+#[cfg(feature = "ordered-float")]
+mod ordered_floats;
+mod ranges;
 mod results;
 mod strings;
 mod tuples;
+mod unions;
+#[cfg(feature = "url")]
+mod urls;
+#[cfg(feature = "uuid")]
+mod uuids;
 
 pub use arrays::*;
+#[cfg(feature = "num-bigint")]
+pub use bigints::*;
 pub use bools::*;
+pub use bounded::*;
 pub use chars::*;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use date_times::*;
+#[cfg(feature = "rust_decimal")]
+pub use decimals::*;
 pub use floats::*;
+#[cfg(feature = "std")]
+pub use functions::*;
+#[cfg(feature = "http")]
+pub use http_requests::*;
 pub use integers::*;
+#[cfg(feature = "serde_json")]
+pub use json::*;
 pub use options::*;
+#[cfg(feature = "ordered-float")]
+pub use ordered_floats::*;
+pub use ranges::*;
 pub use results::*;
 pub use strings::*;
 pub use tuples::*;
+pub use unions::*;
+#[cfg(feature = "url")]
+pub use urls::*;
+#[cfg(feature = "uuid")]
+pub use uuids::*;
 
-#[derive(Default)]
-pub struct StaticTree<T> {
-    value: T,
-}
-
-impl<T> StaticTree<T> {
-    pub fn new(value: T) -> Self {
-        Self { value }
-    }
-}
-
-impl<T: Clone> super::ValueTree for StaticTree<T> {
-    type Value = T;
-
-    fn current(&self) -> &Self::Value {
-        &self.value
-    }
-
-    fn simplify(&mut self) -> bool {
-        false
-    }
-
-    fn complicate(&mut self) -> bool {
-        false
-    }
-}
+/// Former name of [`ConstantValueTree`](crate::strategy::ConstantValueTree),
+/// kept so existing strategies keep compiling.
+#[deprecated(note = "use `ConstantValueTree` instead")]
+pub type StaticTree<T> = crate::strategy::runtime::ConstantValueTree<T>;
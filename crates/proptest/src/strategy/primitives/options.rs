@@ -1,5 +1,3 @@
-use rand::Rng;
-
 use crate::strategy::{
     Strategy,
     ValueTree,
@@ -7,13 +5,26 @@ use crate::strategy::{
 };
 
 /// Strategy producing `Option` values from an inner strategy.
+///
+/// Values are `Some` half of the time unless
+/// [`with_some_probability`](Self::with_some_probability) says otherwise.
 pub struct OptionStrategy<S> {
     inner: S,
+    some_probability: f64,
 }
 
 impl<S> OptionStrategy<S> {
     pub fn new(inner: S) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            some_probability: 0.5,
+        }
+    }
+
+    /// Generate `Some` with probability `probability`, clamped to `0..=1`.
+    pub fn with_some_probability(mut self, probability: f64) -> Self {
+        self.some_probability = probability.clamp(0.0, 1.0);
+        self
     }
 }
 
@@ -29,7 +40,10 @@ where
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let choose_some = generator.rng.random::<bool>();
+        let choose_some = generator.choose_weighted(
+            core::any::type_name::<Self>(),
+            &[1.0 - self.some_probability, self.some_probability],
+        ) == 1;
         if choose_some {
             match self.inner.new_tree(generator) {
                 Generation::Accepted {
@@ -47,10 +61,12 @@ where
                 Generation::Rejected {
                     iteration,
                     depth,
+                    reason,
                     value,
                 } => Generation::Rejected {
                     iteration,
                     depth,
+                    reason,
                     value: OptionValueTree::from_inner(value),
                 },
             }
@@ -121,6 +137,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         match &mut self.state {
             OptionState::None => false,
@@ -171,7 +191,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::strategy::primitives::IntValueTree;
+    use crate::strategy::{
+        SwarmConfig,
+        primitives::{AnyU8, IntValueTree},
+    };
 
     #[test]
     fn option_prefers_none_first() {
@@ -189,4 +212,44 @@ mod tests {
         assert!(tree.complicate());
         assert_eq!(tree.current(), &Some(5));
     }
+
+    #[test]
+    fn swarm_cases_can_disable_either_variant() {
+        let mut only_some = false;
+        let mut only_none = false;
+
+        for seed in 0..64 {
+            let mut generator = Generator::build(crate::rng())
+                .with_swarm(SwarmConfig::default(), seed);
+            let mut strategy = OptionStrategy::new(AnyU8::default());
+            let somes = (0..32)
+                .filter(|_| {
+                    strategy.new_tree(&mut generator).take().current().is_some()
+                })
+                .count();
+            only_some |= somes == 32;
+            only_none |= somes == 0;
+        }
+
+        assert!(only_some && only_none);
+    }
+
+    #[test]
+    fn some_probability_biases_the_variant() {
+        let mut generator = Generator::build(crate::rng());
+        let mut rare =
+            OptionStrategy::new(AnyU8::default()).with_some_probability(0.05);
+        let somes = (0..1000)
+            .filter(|_| {
+                rare.new_tree(&mut generator).take().current().is_some()
+            })
+            .count();
+        assert!((10..=120).contains(&somes), "{somes} somes");
+
+        let mut always =
+            OptionStrategy::new(AnyU8::default()).with_some_probability(1.0);
+        assert!((0..100).all(|_| {
+            always.new_tree(&mut generator).take().current().is_some()
+        }));
+    }
 }
@@ -0,0 +1,287 @@
+use ordered_float::{FloatCore, NotNan, OrderedFloat};
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator, RejectReason},
+};
+
+/// Floats of `strategy` wrapped in [`NotNan`], which implements `Ord` and so
+/// fits ordered collections such as `BTreeMap` keys or a
+/// [`BinaryHeapStrategy`](crate::strategy::BinaryHeapStrategy).
+///
+/// A NaN drawn from `strategy` is rejected, and shrinking skips NaN
+/// candidates.
+#[derive(Clone)]
+pub struct NotNanStrategy<S> {
+    strategy: S,
+}
+
+impl<S> NotNanStrategy<S> {
+    pub fn new(strategy: S) -> Self {
+        Self { strategy }
+    }
+}
+
+impl<S> Strategy for NotNanStrategy<S>
+where
+    S: Strategy,
+    S::Value: FloatCore,
+{
+    type Value = NotNan<S::Value>;
+    type Tree = NotNanValueTree<S::Tree>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        match self.strategy.new_tree(generator) {
+            Generation::Accepted { value: inner, .. } => {
+                match NotNan::new(*inner.current()) {
+                    Ok(current) => {
+                        generator.accept(NotNanValueTree { inner, current })
+                    }
+                    Err(_) => generator.reject_with(
+                        RejectReason::Filter,
+                        NotNanValueTree {
+                            inner,
+                            current: placeholder(),
+                        },
+                    ),
+                }
+            }
+            Generation::Rejected {
+                iteration,
+                depth,
+                reason,
+                value: inner,
+            } => Generation::Rejected {
+                iteration,
+                depth,
+                reason,
+                value: NotNanValueTree {
+                    current: NotNan::new(*inner.current())
+                        .unwrap_or_else(|_| placeholder()),
+                    inner,
+                },
+            },
+        }
+    }
+}
+
+/// Stands in for a NaN in rejected trees, whose value is never used.
+fn placeholder<T: FloatCore>() -> NotNan<T> {
+    NotNan::new(T::infinity()).expect("infinity is not NaN")
+}
+
+pub struct NotNanValueTree<V>
+where
+    V: ValueTree,
+    V::Value: FloatCore,
+{
+    inner: V,
+    current: NotNan<V::Value>,
+}
+
+impl<V> ValueTree for NotNanValueTree<V>
+where
+    V: ValueTree,
+    V::Value: FloatCore,
+{
+    type Value = NotNan<V::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        while self.inner.simplify() {
+            if let Ok(current) = NotNan::new(*self.inner.current()) {
+                self.current = current;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        if let Ok(current) = NotNan::new(*self.inner.current()) {
+            self.current = current;
+        }
+        complicated
+    }
+}
+
+/// Floats of `strategy` wrapped in [`OrderedFloat`], which orders every
+/// float, NaN included, so floats can be kept in ordered collections without
+/// leaving NaN out.
+#[derive(Clone)]
+pub struct OrderedFloatStrategy<S> {
+    strategy: S,
+}
+
+impl<S> OrderedFloatStrategy<S> {
+    pub fn new(strategy: S) -> Self {
+        Self { strategy }
+    }
+}
+
+impl<S> Strategy for OrderedFloatStrategy<S>
+where
+    S: Strategy,
+    S::Value: FloatCore,
+{
+    type Value = OrderedFloat<S::Value>;
+    type Tree = OrderedFloatValueTree<S::Tree>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.strategy.new_tree(generator).map(|inner| {
+            let current = OrderedFloat(*inner.current());
+            OrderedFloatValueTree { inner, current }
+        })
+    }
+}
+
+pub struct OrderedFloatValueTree<V>
+where
+    V: ValueTree,
+    V::Value: FloatCore,
+{
+    inner: V,
+    current: OrderedFloat<V::Value>,
+}
+
+impl<V> OrderedFloatValueTree<V>
+where
+    V: ValueTree,
+    V::Value: FloatCore,
+{
+    fn sync_current(&mut self) {
+        self.current = OrderedFloat(*self.inner.current());
+    }
+}
+
+impl<V> ValueTree for OrderedFloatValueTree<V>
+where
+    V: ValueTree,
+    V::Value: FloatCore,
+{
+    type Value = OrderedFloat<V::Value>;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        let simplified = self.inner.simplify();
+        self.sync_current();
+        simplified
+    }
+
+    fn complicate(&mut self) -> bool {
+        let complicated = self.inner.complicate();
+        self.sync_current();
+        complicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{collections::BTreeSet, vec};
+
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::{
+        AnyF64,
+        BinaryHeapStrategy,
+        FloatValueTree,
+        ValueTree,
+    };
+
+    /// Yields a fixed list of floats, NaN included.
+    struct Floats(Vec<f64>);
+
+    impl Strategy for Floats {
+        type Value = f64;
+        type Tree = FloatValueTree<f64>;
+
+        fn new_tree<R: RngCore + CryptoRng>(
+            &mut self,
+            generator: &mut Generator<R>,
+        ) -> Generation<Self::Tree> {
+            let current = self.0[0];
+            generator.accept(FloatValueTree::new(current, self.0[1..].to_vec()))
+        }
+    }
+
+    #[test]
+    fn not_nan_floats_shrink_like_the_floats_they_wrap() {
+        let mut strategy = NotNanStrategy::new(AnyF64::new(-100.0..=100.0));
+        let mut generator = Generator::build(StdRng::seed_from_u64(11));
+        let mut keys = BTreeSet::new();
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            keys.insert(*tree.current());
+            while tree.simplify() {}
+            assert_eq!(tree.current().into_inner(), 0.0);
+        }
+        assert!(keys.len() > 1);
+    }
+
+    #[test]
+    fn not_nan_floats_fill_binary_heaps() {
+        let mut strategy = BinaryHeapStrategy::new(
+            NotNanStrategy::new(AnyF64::new(-1.0..=1.0)),
+            1usize..=8usize,
+        );
+        let mut generator = Generator::build(StdRng::seed_from_u64(19));
+        for _ in 0..16 {
+            let heap = strategy.new_tree(&mut generator).take().into_current();
+            let max = heap.iter().max().copied();
+            assert_eq!(heap.peek().copied(), max);
+        }
+    }
+
+    #[test]
+    fn not_nan_rejects_nan_and_skips_nan_candidates() {
+        let mut generator = Generator::build(StdRng::seed_from_u64(13));
+        let rejected = NotNanStrategy::new(Floats(vec![f64::NAN]))
+            .new_tree(&mut generator);
+        assert!(matches!(rejected, Generation::Rejected { .. }));
+
+        let mut tree =
+            NotNanStrategy::new(Floats(vec![8.0, f64::NAN, 2.0, f64::NAN]))
+                .new_tree(&mut generator)
+                .take();
+        assert!(tree.simplify());
+        assert_eq!(tree.current().into_inner(), 2.0);
+        assert!(!tree.simplify());
+        assert_eq!(tree.current().into_inner(), 2.0);
+    }
+
+    #[test]
+    fn ordered_floats_keep_nan() {
+        let mut generator = Generator::build(StdRng::seed_from_u64(17));
+        let mut tree = OrderedFloatStrategy::new(Floats(vec![f64::NAN, 1.0]))
+            .new_tree(&mut generator)
+            .take();
+        assert!(tree.current().is_nan());
+        assert!(tree.simplify());
+        assert_eq!(*tree.current(), OrderedFloat(1.0));
+        assert!(OrderedFloat(f64::NAN) > *tree.current());
+    }
+}
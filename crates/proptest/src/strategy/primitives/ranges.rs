@@ -0,0 +1,228 @@
+use core::ops::{Range, RangeInclusive};
+
+use paste::paste;
+use rand::{CryptoRng, RngCore};
+
+use super::TupleValueTree2;
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Order two bounds, leaving them as they are when they do not compare.
+fn ordered<T: PartialOrd>(a: T, b: T) -> (T, T) {
+    if b < a { (b, a) } else { (a, b) }
+}
+
+macro_rules! range_impl {
+    ($($range:ident => |$start:ident, $end:ident| $build:expr),+ $(,)?) => {
+        paste! {
+            $(
+                #[doc = concat!(
+                    "Well-formed `", stringify!($range), "`s whose bounds are ",
+                    "drawn from `element`, with the start never after the end.",
+                )]
+                ///
+                /// Shrinking first collapses the range onto its start, then
+                /// shrinks both bounds through `element`, so ranges end up as
+                /// small as the property allows and as close to the element's
+                /// simplest value as it does.
+                #[derive(Clone)]
+                pub struct [<$range Strategy>]<S> {
+                    bounds: (S, S),
+                }
+
+                impl<S: Clone> [<$range Strategy>]<S> {
+                    pub fn new(element: S) -> Self {
+                        Self {
+                            bounds: (element.clone(), element),
+                        }
+                    }
+                }
+
+                impl<S> Strategy for [<$range Strategy>]<S>
+                where
+                    S: Strategy,
+                    S::Value: Clone + PartialOrd,
+                {
+                    type Value = $range<S::Value>;
+                    type Tree = [<$range ValueTree>]<S::Tree>;
+
+                    fn new_tree<R: RngCore + CryptoRng>(
+                        &mut self,
+                        generator: &mut Generator<R>,
+                    ) -> Generation<Self::Tree> {
+                        self.bounds.new_tree(generator).map(|inner| {
+                            [<$range ValueTree>] {
+                                current: [<$range ValueTree>]::<S::Tree>::range(
+                                    inner.current(),
+                                    false,
+                                ),
+                                inner,
+                                collapse: Collapse::Untried,
+                            }
+                        })
+                    }
+                }
+
+                pub struct [<$range ValueTree>]<V>
+                where
+                    V: ValueTree,
+                    V::Value: Clone + PartialOrd,
+                {
+                    inner: TupleValueTree2<V, V>,
+                    collapse: Collapse,
+                    current: $range<V::Value>,
+                }
+
+                impl<V> [<$range ValueTree>]<V>
+                where
+                    V: ValueTree,
+                    V::Value: Clone + PartialOrd,
+                {
+                    /// The range between `bounds`, or the empty or unit range
+                    /// at its start when `collapsed`.
+                    fn range(
+                        bounds: &(V::Value, V::Value),
+                        collapsed: bool,
+                    ) -> $range<V::Value> {
+                        let ($start, $end) =
+                            ordered(bounds.0.clone(), bounds.1.clone());
+                        let $end = if collapsed { $start.clone() } else { $end };
+                        $build
+                    }
+
+                    fn sync_current(&mut self) {
+                        self.current = Self::range(
+                            self.inner.current(),
+                            self.collapse.applies(),
+                        );
+                    }
+                }
+
+                impl<V> ValueTree for [<$range ValueTree>]<V>
+                where
+                    V: ValueTree,
+                    V::Value: Clone + PartialOrd,
+                {
+                    type Value = $range<V::Value>;
+
+                    fn current(&self) -> &Self::Value {
+                        &self.current
+                    }
+
+                    fn into_current(self) -> Self::Value {
+                        self.current
+                    }
+
+                    fn simplify(&mut self) -> bool {
+                        if self.collapse == Collapse::Untried {
+                            let (a, b) = self.inner.current();
+                            if a != b {
+                                self.collapse = Collapse::Trying;
+                                self.sync_current();
+                                return true;
+                            }
+                            self.collapse = Collapse::Rejected;
+                        } else if self.collapse == Collapse::Trying {
+                            self.collapse = Collapse::Kept;
+                        }
+
+                        if !self.inner.simplify() {
+                            return false;
+                        }
+                        self.sync_current();
+                        true
+                    }
+
+                    fn complicate(&mut self) -> bool {
+                        if self.collapse == Collapse::Trying {
+                            self.collapse = Collapse::Rejected;
+                            self.sync_current();
+                            return true;
+                        }
+
+                        let complicated = self.inner.complicate();
+                        self.sync_current();
+                        complicated
+                    }
+                }
+            )+
+        }
+    };
+}
+
+/// Progress of the first shrink step, which collapses the range onto its
+/// start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Collapse {
+    Untried,
+    /// The last `simplify` collapsed the range.
+    Trying,
+    /// The collapsed range was kept, so later bounds stay collapsed.
+    Kept,
+    Rejected,
+}
+
+impl Collapse {
+    fn applies(self) -> bool {
+        matches!(self, Collapse::Trying | Collapse::Kept)
+    }
+}
+
+range_impl! {
+    Range => |start, end| start..end,
+    RangeInclusive => |start, end| start..=end,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy::{AnyI32, AnyU8},
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    #[test]
+    fn ranges_keep_the_contract_and_stay_ordered() {
+        check_shrink_invariants_with(
+            RangeStrategy::new(AnyI32::new(-50..=50)),
+            64,
+            |range| range.start <= range.end,
+        );
+        check_shrink_invariants_with(
+            RangeInclusiveStrategy::new(AnyI32::new(-50..=50)),
+            64,
+            |range| range.start() <= range.end(),
+        );
+    }
+
+    #[test]
+    fn ranges_shrink_to_empty_and_unit_ranges() {
+        let mut generator = Generator::build(StdRng::seed_from_u64(23));
+        let mut range = RangeStrategy::new(AnyU8::new(10..=200))
+            .new_tree(&mut generator)
+            .take();
+        while range.simplify() {}
+        assert_eq!(*range.current(), 10..10);
+
+        let mut range = RangeInclusiveStrategy::new(AnyU8::new(10..=200))
+            .new_tree(&mut generator)
+            .take();
+        while range.simplify() {}
+        assert_eq!(*range.current(), 10..=10);
+    }
+
+    #[test]
+    fn rejected_collapses_shrink_the_bounds() {
+        assert_shrinks_to!(
+            RangeStrategy::new(AnyU8::default()),
+            |range| range.is_empty(),
+            0..1,
+        );
+    }
+}
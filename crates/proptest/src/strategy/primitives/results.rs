@@ -1,19 +1,33 @@
-use rand::Rng;
-
 use crate::strategy::{
     Strategy,
     ValueTree,
     runtime::{Generation, Generator},
 };
 
+/// Strategy producing `Result` values from a strategy for each side.
+///
+/// Values are `Ok` half of the time unless
+/// [`with_ok_probability`](Self::with_ok_probability) says otherwise.
 pub struct ResultStrategy<OS, ES> {
     ok: OS,
     err: ES,
+    ok_probability: f64,
 }
 
 impl<OS, ES> ResultStrategy<OS, ES> {
     pub fn new(ok: OS, err: ES) -> Self {
-        Self { ok, err }
+        Self {
+            ok,
+            err,
+            ok_probability: 0.5,
+        }
+    }
+
+    /// Generate `Ok` with probability `probability`, clamped to `0..=1`, so
+    /// errors can be made rare or frequent.
+    pub fn with_ok_probability(mut self, probability: f64) -> Self {
+        self.ok_probability = probability.clamp(0.0, 1.0);
+        self
     }
 }
 
@@ -41,7 +55,10 @@ where
                     value: err_tree, ..
                 },
             ) => {
-                let choose_ok = generator.rng.random::<bool>();
+                let choose_ok = generator.choose_weighted(
+                    core::any::type_name::<Self>(),
+                    &[self.ok_probability, 1.0 - self.ok_probability],
+                ) == 0;
                 let current = if choose_ok {
                     Ok(ok_tree.current().clone())
                 } else {
@@ -60,6 +77,7 @@ where
                 Generation::Rejected {
                     iteration,
                     depth,
+                    reason,
                     value: ok_tree,
                 },
                 Generation::Accepted {
@@ -68,6 +86,7 @@ where
             ) => Generation::Rejected {
                 iteration,
                 depth,
+                reason,
                 value: {
                     let ok_current = ok_tree.current().clone();
                     ResultValueTree::new(
@@ -83,11 +102,13 @@ where
                 Generation::Rejected {
                     iteration,
                     depth,
+                    reason,
                     value: err_tree,
                 },
             ) => Generation::Rejected {
                 iteration,
                 depth,
+                reason,
                 value: {
                     let err_current = err_tree.current().clone();
                     ResultValueTree::new(
@@ -102,6 +123,7 @@ where
                 Generation::Rejected {
                     iteration,
                     depth,
+                    reason,
                     value: ok_tree,
                 },
                 Generation::Rejected {
@@ -110,6 +132,7 @@ where
             ) => Generation::Rejected {
                 iteration,
                 depth,
+                reason,
                 value: {
                     let ok_current = ok_tree.current().clone();
                     ResultValueTree::new(
@@ -180,6 +203,10 @@ where
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         match self.current_variant {
             Variant::Ok => {
@@ -236,7 +263,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::strategy::primitives::IntValueTree;
+    use crate::strategy::{
+        SwarmConfig,
+        primitives::{AnyU8, IntValueTree},
+    };
 
     #[test]
     fn result_prefers_err() {
@@ -256,4 +286,23 @@ mod tests {
         assert!(tree.complicate());
         assert!(matches!(tree.current(), Ok(5)));
     }
+
+    #[test]
+    fn ok_probability_makes_errors_rare() {
+        let mut strategy =
+            ResultStrategy::new(AnyU8::default(), AnyU8::default())
+                .with_ok_probability(0.95);
+
+        for seed in 0..16 {
+            let mut generator = Generator::build(crate::rng())
+                .with_swarm(SwarmConfig::default(), seed);
+            let errs = (0..1000)
+                .filter(|_| {
+                    strategy.new_tree(&mut generator).take().current().is_err()
+                })
+                .count();
+            // Swarm testing may disable `Ok` for the whole case.
+            assert!(errs <= 120 || errs == 1000, "{errs} errors");
+        }
+    }
 }
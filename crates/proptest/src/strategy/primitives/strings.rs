@@ -1,10 +1,12 @@
-use std::ops::RangeInclusive;
+use alloc::{string::String, vec::Vec};
+use core::ops::RangeInclusive;
 
 use rand::Rng;
 
 use super::{AnyChar, IntValueTree};
 use crate::{
     arbitrary::STRING_MAX_LEN,
+    shrink::{LengthChunk, LengthShrinker},
     strategy::{
         SizeHint,
         Strategy,
@@ -13,22 +15,6 @@ use crate::{
     },
 };
 
-fn build_drop_plan(len: usize) -> Vec<usize> {
-    let mut plan = Vec::new();
-    let mut size = len / 2;
-
-    while size > 0 {
-        plan.push(size);
-        size /= 2;
-    }
-
-    if !plan.contains(&1) && len > 0 {
-        plan.push(1);
-    }
-
-    plan
-}
-
 fn sample_length<R: rand::RngCore + rand::CryptoRng>(
     rng: &mut R,
     range: &RangeInclusive<usize>,
@@ -47,6 +33,35 @@ fn sample_length<R: rand::RngCore + rand::CryptoRng>(
     (lo_u + offset) as usize
 }
 
+/// Whether `ch` is a combining mark, joiner, variation selector or sits next
+/// to the surrogate gap: characters that render confusingly in a failing
+/// input and are usually irrelevant to it, so shrinking drops them first.
+fn is_awkward(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{200C}'..='\u{200D}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{D700}'..='\u{D7FF}'
+            | '\u{E000}'..='\u{E0FF}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// The character a run of `ch`'s class collapses to, if it has one.
+fn collapse_target(ch: char) -> Option<char> {
+    if ch.is_alphabetic() {
+        Some('a')
+    } else if ch.is_numeric() {
+        Some('0')
+    } else {
+        None
+    }
+}
+
 #[derive(Clone)]
 pub struct AnyString {
     char_strategy: AnyChar,
@@ -79,7 +94,8 @@ impl Strategy for AnyString {
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        let len = sample_length(&mut generator.rng, &self.len_range);
+        let len_range = generator.sized_len(self.len_range.clone());
+        let len = sample_length(&mut generator.rng, &len_range);
         let min_len = *self.len_range.start();
         let mut char_trees = Vec::with_capacity(len);
 
@@ -87,11 +103,15 @@ impl Strategy for AnyString {
             match self.char_strategy.new_tree(generator) {
                 Generation::Accepted { value, .. } => char_trees.push(value),
                 Generation::Rejected {
-                    iteration, depth, ..
+                    iteration,
+                    depth,
+                    reason,
+                    ..
                 } => {
                     return Generation::Rejected {
                         iteration,
                         depth,
+                        reason,
                         value: StringValueTree::from_trees(char_trees, min_len),
                     };
                 }
@@ -102,17 +122,29 @@ impl Strategy for AnyString {
     }
 }
 
+/// Shrink passes, in the order they run: drop awkward characters one by
+/// one, drop chunks with a [`LengthShrinker`], collapse runs of letters or digits to
+/// `'a'` or `'0'`, then simplify characters one by one.
 #[derive(Clone, Copy)]
 enum Stage {
-    Length { chunk_index: usize, offset: usize },
+    Awkward { index: usize },
+    Length,
+    Runs { index: usize },
     Elements { index: usize },
 }
 
 enum History {
-    RemovedChunk {
+    RemovedChar {
         index: usize,
-        chunk_index: usize,
-        chunk: Vec<IntValueTree<char>>,
+        tree: IntValueTree<char>,
+    },
+    Collapsed {
+        start: usize,
+        trees: Vec<IntValueTree<char>>,
+    },
+    RemovedChunk {
+        chunk: LengthChunk,
+        trees: Vec<IntValueTree<char>>,
     },
     Element {
         index: usize,
@@ -124,30 +156,20 @@ pub struct StringValueTree {
     current_chars: Vec<char>,
     current: String,
     min_len: usize,
-    drop_plan: Vec<usize>,
+    length: LengthShrinker,
     stage: Stage,
     history: Vec<History>,
 }
 
 impl StringValueTree {
     pub fn from_trees(chars: Vec<IntValueTree<char>>, min_len: usize) -> Self {
-        let drop_plan = build_drop_plan(chars.len());
-        let stage = if drop_plan.is_empty() {
-            Stage::Elements { index: 0 }
-        } else {
-            Stage::Length {
-                chunk_index: 0,
-                offset: 0,
-            }
-        };
-
         let mut tree = Self {
+            length: LengthShrinker::new(chars.len(), min_len),
             chars,
             current_chars: Vec::new(),
             current: String::new(),
             min_len,
-            drop_plan,
-            stage,
+            stage: Stage::Awkward { index: 0 },
             history: Vec::new(),
         };
 
@@ -172,38 +194,29 @@ impl StringValueTree {
         self.chars.len()
     }
 
-    fn seek_length_from(
-        &mut self,
-        mut chunk_index: usize,
-        mut offset: usize,
-    ) -> Option<(usize, usize, usize)> {
-        while chunk_index < self.drop_plan.len() {
-            let chunk_size = self.drop_plan[chunk_index];
-
-            if chunk_size == 0
-                || self.len() <= self.min_len
-                || chunk_size > self.len()
-                || self.len().saturating_sub(chunk_size) < self.min_len
-            {
-                chunk_index += 1;
-                offset = 0;
+    /// The next run of letters or digits at or after `index` that does not
+    /// already consist of its collapse target, with that target.
+    fn next_run(&self, mut index: usize) -> Option<(usize, usize, char)> {
+        while index < self.len() {
+            let Some(target) = collapse_target(self.current_chars[index])
+            else {
+                index += 1;
                 continue;
-            }
+            };
 
-            if offset + chunk_size > self.len() {
-                chunk_index += 1;
-                offset = 0;
-                continue;
+            let start = index;
+            while index < self.len()
+                && collapse_target(self.current_chars[index]) == Some(target)
+            {
+                index += 1;
+            }
+            if self.current_chars[start..index]
+                .iter()
+                .any(|&ch| ch != target)
+            {
+                return Some((start, index, target));
             }
-
-            self.stage = Stage::Length {
-                chunk_index,
-                offset,
-            };
-            return Some((chunk_index, offset, chunk_size));
         }
-
-        self.stage = Stage::Elements { index: 0 };
         None
     }
 }
@@ -215,28 +228,58 @@ impl ValueTree for StringValueTree {
         &self.current
     }
 
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
     fn simplify(&mut self) -> bool {
         loop {
             match self.stage {
-                Stage::Length {
-                    chunk_index,
-                    offset,
-                } => {
-                    let Some((ci, off, chunk_size)) =
-                        self.seek_length_from(chunk_index, offset)
+                Stage::Awkward { index } => {
+                    let next = (index..self.len())
+                        .find(|&i| is_awkward(self.current_chars[i]));
+                    let Some(index) =
+                        next.filter(|_| self.len() > self.min_len)
+                    else {
+                        self.stage = Stage::Length;
+                        continue;
+                    };
+
+                    let tree = self.chars.remove(index);
+                    self.current_chars.remove(index);
+                    self.rebuild_string();
+                    self.history.push(History::RemovedChar { index, tree });
+                    self.stage = Stage::Awkward { index };
+                    return true;
+                }
+                Stage::Length => {
+                    let Some(chunk) = self.length.seek(self.len()) else {
+                        self.stage = Stage::Runs { index: 0 };
+                        continue;
+                    };
+
+                    let trees: Vec<IntValueTree<char>> =
+                        self.chars.drain(chunk.range()).collect();
+                    self.current_chars.drain(chunk.range());
+                    self.rebuild_string();
+                    self.history.push(History::RemovedChunk { chunk, trees });
+                    return true;
+                }
+                Stage::Runs { index } => {
+                    let Some((start, end, target)) = self.next_run(index)
                     else {
+                        self.stage = Stage::Elements { index: 0 };
                         continue;
                     };
 
-                    let removed: Vec<IntValueTree<char>> =
-                        self.chars.drain(off..off + chunk_size).collect();
-                    self.current_chars.drain(off..off + chunk_size).count();
+                    let collapsed = (start..end)
+                        .map(|_| IntValueTree::new(target, Vec::new()));
+                    let trees =
+                        self.chars.splice(start..end, collapsed).collect();
+                    self.current_chars[start..end].fill(target);
                     self.rebuild_string();
-                    self.history.push(History::RemovedChunk {
-                        index: off,
-                        chunk_index: ci,
-                        chunk: removed,
-                    });
+                    self.history.push(History::Collapsed { start, trees });
+                    self.stage = Stage::Runs { index: end };
                     return true;
                 }
                 Stage::Elements { index } => {
@@ -264,20 +307,38 @@ impl ValueTree for StringValueTree {
         };
 
         match entry {
-            History::RemovedChunk {
-                index,
-                chunk_index,
-                chunk,
-            } => {
+            History::RemovedChar { index, tree } => {
+                self.current_chars.insert(index, *tree.current());
+                self.chars.insert(index, tree);
+                self.rebuild_string();
+                self.stage = Stage::Awkward { index: index + 1 };
+                true
+            }
+            History::Collapsed { start, trees } => {
+                let end = start + trees.len();
+                for (index, tree) in (start..end).zip(&trees) {
+                    self.current_chars[index] = *tree.current();
+                }
+                self.chars.splice(start..end, trees);
+                self.rebuild_string();
+                self.stage = Stage::Runs { index: end };
+                true
+            }
+            History::RemovedChunk { chunk, trees } => {
+                let index = chunk.range().start;
                 let values: Vec<char> =
-                    chunk.iter().map(|tree| *tree.current()).collect();
-                self.chars.splice(index..index, chunk);
+                    trees.iter().map(|tree| *tree.current()).collect();
+                self.chars.splice(index..index, trees);
                 self.current_chars.splice(index..index, values);
                 self.rebuild_string();
 
-                match self.seek_length_from(chunk_index, index + 1) {
-                    Some(_) => true,
-                    None => !self.current_chars.is_empty(),
+                self.length.backtrack(chunk);
+                if self.length.seek(self.len()).is_some() {
+                    self.stage = Stage::Length;
+                    true
+                } else {
+                    self.stage = Stage::Runs { index: 0 };
+                    !self.current_chars.is_empty()
                 }
             }
             History::Element { index } => {
@@ -310,12 +371,6 @@ mod tests {
         IntValueTree::new(c, Vec::new())
     }
 
-    #[test]
-    fn string_drop_plan_halves() {
-        let plan = build_drop_plan(8);
-        assert_eq!(plan, vec![4, 2, 1]);
-    }
-
     #[test]
     fn string_shrinks_length_first() {
         let mut tree = StringValueTree::from_trees(
@@ -334,9 +389,45 @@ mod tests {
     #[test]
     fn string_respects_min_len() {
         let mut tree = StringValueTree::from_trees(
-            vec![make_char_tree('x'), make_char_tree('y')],
+            vec![make_char_tree('x'), make_char_tree('\u{0301}')],
             2,
         );
+        while tree.simplify() {
+            assert_eq!(tree.current().chars().count(), 2);
+        }
+    }
+
+    #[test]
+    fn string_drops_combining_characters_first() {
+        let mut tree = StringValueTree::from_trees(
+            vec![
+                make_char_tree('e'),
+                make_char_tree('\u{0301}'),
+                make_char_tree('x'),
+                make_char_tree('\u{200D}'),
+            ],
+            0,
+        );
+
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), "ex\u{200D}");
+        assert!(tree.complicate());
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), "e\u{0301}x");
+    }
+
+    #[test]
+    fn string_collapses_runs_of_letters_and_digits() {
+        let chars = "Zq-987".chars().map(make_char_tree).collect();
+        let mut tree = StringValueTree::from_trees(chars, 6);
+
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), "aa-987");
+        assert!(tree.simplify());
+        assert_eq!(tree.current(), "aa-000");
+
+        assert!(tree.complicate());
+        assert_eq!(tree.current(), "aa-987");
         assert!(!tree.simplify());
     }
 
@@ -1,6 +1,9 @@
+use alloc::format;
+
 use paste::paste;
 
 use crate::strategy::{
+    Provenance,
     Strategy,
     ValueTree,
     runtime::{Generation, Generator},
@@ -56,6 +59,13 @@ macro_rules! tuple_impl {
                         &self.current
                     }
 
+
+                    fn into_current(self) -> Self::Value {
+
+                        self.current
+
+                    }
+
                     fn simplify(&mut self) -> bool {
                         $(
                             if self.trees.$idx.simplify() {
@@ -88,6 +98,16 @@ macro_rules! tuple_impl {
                             _ => unreachable!(),
                         }
                     }
+
+                    fn provenance(&self) -> Option<Provenance> {
+                        let mut provenance = Provenance::new(concat!("tuple of ", $len));
+                        $(
+                            if let Some(part) = self.trees.$idx.provenance() {
+                                provenance = provenance.with_part(format!(".{}", $idx), part);
+                            }
+                        )+
+                        (!provenance.parts.is_empty()).then_some(provenance)
+                    }
                 }
 
                 impl<$($field),+> Strategy for ($($field,)+)
@@ -0,0 +1,567 @@
+use alloc::vec::Vec;
+
+use paste::paste;
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Provenance,
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator, RejectReason},
+};
+
+/// One of two strategies or value trees producing the same value type.
+///
+/// Picking a strategy at runtime, e.g. `if wide { Either::Left(a) } else {
+/// Either::Right(b) }`, keeps both types known to the compiler instead of
+/// boxing them, and the resulting tree shrinks like the side it holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<A, B> Strategy for Either<A, B>
+where
+    A: Strategy,
+    B: Strategy<Value = A::Value>,
+{
+    type Value = A::Value;
+    type Tree = Either<A::Tree, B::Tree>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        match self {
+            Either::Left(left) => left.new_tree(generator).map(Either::Left),
+            Either::Right(right) => {
+                right.new_tree(generator).map(Either::Right)
+            }
+        }
+    }
+}
+
+impl<A, B> ValueTree for Either<A, B>
+where
+    A: ValueTree,
+    B: ValueTree<Value = A::Value>,
+{
+    type Value = A::Value;
+
+    fn current(&self) -> &Self::Value {
+        match self {
+            Either::Left(left) => left.current(),
+            Either::Right(right) => right.current(),
+        }
+    }
+
+    fn into_current(self) -> Self::Value
+    where
+        Self::Value: Clone,
+    {
+        match self {
+            Either::Left(left) => left.into_current(),
+            Either::Right(right) => right.into_current(),
+        }
+    }
+
+    fn simplify(&mut self) -> bool {
+        match self {
+            Either::Left(left) => left.simplify(),
+            Either::Right(right) => right.simplify(),
+        }
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self {
+            Either::Left(left) => left.complicate(),
+            Either::Right(right) => right.complicate(),
+        }
+    }
+
+    fn provenance(&self) -> Option<Provenance> {
+        match self {
+            Either::Left(left) => left.provenance(),
+            Either::Right(right) => right.provenance(),
+        }
+    }
+}
+
+/// Uniform choice between the strategies of a tuple with a common value
+/// type, without boxing them.
+///
+/// A case draws trees from the chosen strategy and from every strategy
+/// before it, so shrinking can first move to an earlier alternative, from
+/// the first one onwards, and then simplify the alternative it settled on.
+/// List the simplest alternatives first. The choice goes through
+/// [`Generator::choose`], so swarm testing can disable alternatives.
+#[derive(Clone)]
+pub struct TupleUnion<T>(T);
+
+impl<T> TupleUnion<T> {
+    pub fn new(strategies: T) -> Self {
+        Self(strategies)
+    }
+}
+
+macro_rules! tuple_union_impl {
+    ($($len:literal => { $($idx:tt : $field:ident),+ } ),+ $(,)?) => {
+        paste! {
+            $(
+                pub struct [<TupleUnionValueTree $len>]<$($field),+> {
+                    trees: ($(Option<$field>,)+),
+                    index: usize,
+                    /// First alternative before `index` not tried yet.
+                    next_switch: usize,
+                    /// Alternative the last `simplify` moved away from.
+                    switched_from: Option<usize>,
+                }
+
+                impl<T, $($field),+> [<TupleUnionValueTree $len>]<$($field),+>
+                where
+                    $( $field: ValueTree<Value = T> ),+
+                {
+                    fn has(&self, index: usize) -> bool {
+                        match index {
+                            $( $idx => self.trees.$idx.is_some(), )+
+                            _ => false,
+                        }
+                    }
+
+                    fn simplify_at(&mut self, index: usize) -> bool {
+                        match index {
+                            $(
+                                $idx => self
+                                    .trees
+                                    .$idx
+                                    .as_mut()
+                                    .is_some_and(ValueTree::simplify),
+                            )+
+                            _ => unreachable!(),
+                        }
+                    }
+
+                    fn complicate_at(&mut self, index: usize) -> bool {
+                        match index {
+                            $(
+                                $idx => self
+                                    .trees
+                                    .$idx
+                                    .as_mut()
+                                    .is_some_and(ValueTree::complicate),
+                            )+
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+
+                impl<T, $($field),+> ValueTree
+                    for [<TupleUnionValueTree $len>]<$($field),+>
+                where
+                    $( $field: ValueTree<Value = T> ),+
+                {
+                    type Value = T;
+
+                    fn current(&self) -> &T {
+                        match self.index {
+                            $(
+                                $idx => self
+                                    .trees
+                                    .$idx
+                                    .as_ref()
+                                    .expect("the chosen alternative has a tree")
+                                    .current(),
+                            )+
+                            _ => unreachable!(),
+                        }
+                    }
+
+                    fn into_current(self) -> T
+                    where
+                        T: Clone,
+                    {
+                        match self.index {
+                            $(
+                                $idx => self
+                                    .trees
+                                    .$idx
+                                    .expect("the chosen alternative has a tree")
+                                    .into_current(),
+                            )+
+                            _ => unreachable!(),
+                        }
+                    }
+
+                    fn simplify(&mut self) -> bool {
+                        while self.next_switch < self.index {
+                            let candidate = self.next_switch;
+                            self.next_switch += 1;
+                            if self.has(candidate) {
+                                self.switched_from = Some(self.index);
+                                self.index = candidate;
+                                return true;
+                            }
+                        }
+
+                        self.switched_from = None;
+                        self.simplify_at(self.index)
+                    }
+
+                    fn complicate(&mut self) -> bool {
+                        match self.switched_from.take() {
+                            Some(previous) => {
+                                self.index = previous;
+                                true
+                            }
+                            None => self.complicate_at(self.index),
+                        }
+                    }
+
+                    fn provenance(&self) -> Option<Provenance> {
+                        match self.index {
+                            $(
+                                $idx => self
+                                    .trees
+                                    .$idx
+                                    .as_ref()
+                                    .and_then(ValueTree::provenance),
+                            )+
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+
+                impl<T, $($field),+> Strategy for TupleUnion<($($field,)+)>
+                where
+                    $( $field: Strategy<Value = T> ),+
+                {
+                    type Value = T;
+                    type Tree = [<TupleUnionValueTree $len>]<$($field::Tree),+>;
+
+                    fn new_tree<R: RngCore + CryptoRng>(
+                        &mut self,
+                        generator: &mut Generator<R>,
+                    ) -> Generation<Self::Tree> {
+                        let index = generator
+                            .choose(core::any::type_name::<Self>(), $len);
+                        let mut rejected:
+                            Option<(usize, usize, Option<RejectReason>)> = None;
+                        let trees = (
+                            $(
+                                if (0..=index).contains(&$idx) {
+                                    match self.0.$idx.new_tree(generator) {
+                                        Generation::Accepted { value, .. } => {
+                                            Some(value)
+                                        }
+                                        Generation::Rejected {
+                                            iteration,
+                                            depth,
+                                            reason,
+                                            value,
+                                        } => {
+                                            // Earlier alternatives only serve
+                                            // as shrink targets, so a rejected
+                                            // one is left out.
+                                            if $idx == index {
+                                                rejected =
+                                                    Some((iteration, depth, reason));
+                                                Some(value)
+                                            } else {
+                                                None
+                                            }
+                                        }
+                                    }
+                                } else {
+                                    None
+                                },
+                            )+
+                        );
+
+                        let tree = [<TupleUnionValueTree $len>] {
+                            trees,
+                            index,
+                            next_switch: 0,
+                            switched_from: None,
+                        };
+                        match rejected {
+                            Some((iteration, depth, reason)) => {
+                                Generation::Rejected {
+                                    iteration,
+                                    depth,
+                                    reason,
+                                    value: tree,
+                                }
+                            }
+                            None => generator.accept(tree),
+                        }
+                    }
+                }
+            )+
+        }
+    };
+}
+
+tuple_union_impl! {
+    2 => { 0: A, 1: B },
+    3 => { 0: A, 1: B, 2: C },
+    4 => { 0: A, 1: B, 2: C, 3: D },
+    5 => { 0: A, 1: B, 2: C, 3: D, 4: E },
+    6 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F },
+    7 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G },
+    8 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H },
+    9 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I },
+    10 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J },
+    11 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K },
+    12 => { 0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H, 8: I, 9: J, 10: K, 11: L },
+}
+
+/// Pick one of `entries` with a probability proportional to its weight,
+/// like QuickCheck's `frequency`.
+///
+/// `frequency(&[(9, valid), (1, malformed)])` draws from `valid` nine times
+/// out of ten. Shrinking moves to earlier entries first, like
+/// [`TupleUnion`], so list the simplest entry first. Entries of weight zero
+/// are never picked while another entry has a positive weight. Strategies of
+/// differing types can share the slice through [`Strategy::boxed`].
+///
+/// # Panics
+///
+/// When `entries` is empty.
+pub fn frequency<S>(entries: &[(u32, S)]) -> Frequency<S>
+where
+    S: Strategy + Clone,
+{
+    assert!(!entries.is_empty(), "frequency needs at least one entry");
+    Frequency {
+        weights: entries
+            .iter()
+            .map(|(weight, _)| f64::from(*weight))
+            .collect(),
+        strategies: entries
+            .iter()
+            .map(|(_, strategy)| strategy.clone())
+            .collect(),
+    }
+}
+
+#[derive(Clone)]
+pub struct Frequency<S> {
+    weights: Vec<f64>,
+    strategies: Vec<S>,
+}
+
+pub struct FrequencyValueTree<T> {
+    /// Trees of the chosen entry and the entries before it.
+    trees: Vec<Option<T>>,
+    index: usize,
+    /// First entry before `index` not tried yet.
+    next_switch: usize,
+    /// Entry the last `simplify` moved away from.
+    switched_from: Option<usize>,
+}
+
+impl<T: ValueTree> FrequencyValueTree<T> {
+    fn chosen(&self) -> &T {
+        self.trees[self.index]
+            .as_ref()
+            .expect("the chosen entry has a tree")
+    }
+}
+
+impl<T: ValueTree> ValueTree for FrequencyValueTree<T> {
+    type Value = T::Value;
+
+    fn current(&self) -> &T::Value {
+        self.chosen().current()
+    }
+
+    fn into_current(mut self) -> T::Value
+    where
+        T::Value: Clone,
+    {
+        self.trees
+            .swap_remove(self.index)
+            .expect("the chosen entry has a tree")
+            .into_current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        while self.next_switch < self.index {
+            let candidate = self.next_switch;
+            self.next_switch += 1;
+            if self.trees[candidate].is_some() {
+                self.switched_from = Some(self.index);
+                self.index = candidate;
+                return true;
+            }
+        }
+
+        self.switched_from = None;
+        self.trees[self.index]
+            .as_mut()
+            .is_some_and(ValueTree::simplify)
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.switched_from.take() {
+            Some(previous) => {
+                self.index = previous;
+                true
+            }
+            None => self.trees[self.index]
+                .as_mut()
+                .is_some_and(ValueTree::complicate),
+        }
+    }
+
+    fn provenance(&self) -> Option<Provenance> {
+        self.chosen().provenance()
+    }
+}
+
+impl<S: Strategy> Strategy for Frequency<S> {
+    type Value = S::Value;
+    type Tree = FrequencyValueTree<S::Tree>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let index = generator
+            .choose_weighted(core::any::type_name::<Self>(), &self.weights);
+        let mut rejected = None;
+        let mut trees = Vec::with_capacity(index + 1);
+        for (position, strategy) in
+            self.strategies[..=index].iter_mut().enumerate()
+        {
+            match strategy.new_tree(generator) {
+                Generation::Accepted { value, .. } => trees.push(Some(value)),
+                Generation::Rejected {
+                    iteration,
+                    depth,
+                    reason,
+                    value,
+                } => {
+                    // Earlier entries only serve as shrink targets, so a
+                    // rejected one is left out.
+                    if position == index {
+                        rejected = Some((iteration, depth, reason));
+                        trees.push(Some(value));
+                    } else {
+                        trees.push(None);
+                    }
+                }
+            }
+        }
+
+        let tree = FrequencyValueTree {
+            trees,
+            index,
+            next_switch: 0,
+            switched_from: None,
+        };
+        match rejected {
+            Some((iteration, depth, reason)) => Generation::Rejected {
+                iteration,
+                depth,
+                reason,
+                value: tree,
+            },
+            None => generator.accept(tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::primitives::{AnyU8, integers::IntValueTree};
+
+    fn union_of_three() -> TupleUnionValueTree3<
+        IntValueTree<u8>,
+        IntValueTree<u8>,
+        IntValueTree<u8>,
+    > {
+        TupleUnionValueTree3 {
+            trees: (
+                Some(IntValueTree::new(1, vec![0])),
+                None,
+                Some(IntValueTree::new(30, vec![20])),
+            ),
+            index: 2,
+            next_switch: 0,
+            switched_from: None,
+        }
+    }
+
+    #[test]
+    fn union_tries_earlier_alternatives_first() {
+        let mut tree = union_of_three();
+        assert!(tree.simplify());
+        assert_eq!(*tree.current(), 1);
+        assert!(tree.simplify());
+        assert_eq!(*tree.current(), 0);
+    }
+
+    #[test]
+    fn union_skips_missing_alternatives_after_a_rejected_switch() {
+        let mut tree = union_of_three();
+        assert!(tree.simplify());
+        assert!(tree.complicate());
+        assert_eq!(*tree.current(), 30);
+        assert!(tree.simplify());
+        assert_eq!(*tree.current(), 20);
+    }
+
+    #[test]
+    fn either_delegates_to_its_side() {
+        let mut tree: Either<IntValueTree<u8>, IntValueTree<u8>> =
+            Either::Right(IntValueTree::new(9, vec![4]));
+        assert!(tree.simplify());
+        assert_eq!(*tree.current(), 4);
+        let _ = tree.complicate();
+        assert_eq!(*tree.current(), 9);
+    }
+
+    #[test]
+    fn frequency_follows_the_weights() {
+        let mut strategy =
+            frequency(&[(9, AnyU8::new(0..=9)), (1, AnyU8::new(100..=109))]);
+        let mut generator = Generator::build(crate::rng());
+        let large = (0..1000)
+            .filter(|_| {
+                *strategy.new_tree(&mut generator).take().current() >= 100
+            })
+            .count();
+        assert!((50..=150).contains(&large), "{large} large values");
+    }
+
+    #[test]
+    fn frequency_never_picks_entries_of_weight_zero() {
+        let mut strategy =
+            frequency(&[(0, AnyU8::new(0..=9)), (1, AnyU8::new(100..=109))]);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..100 {
+            assert!(*strategy.new_tree(&mut generator).take().current() >= 100);
+        }
+    }
+
+    #[test]
+    fn frequency_shrinks_toward_the_first_entry() {
+        let mut strategy =
+            frequency(&[(1, AnyU8::new(0..=9)), (9, AnyU8::new(100..=109))]);
+        let mut generator = Generator::build(crate::rng());
+        for _ in 0..32 {
+            let mut tree = strategy.new_tree(&mut generator).take();
+            while tree.simplify() {}
+            assert_eq!(*tree.current(), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one entry")]
+    fn frequency_rejects_an_empty_table() {
+        let _ = frequency::<AnyU8>(&[]);
+    }
+}
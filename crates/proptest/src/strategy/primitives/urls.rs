@@ -0,0 +1,287 @@
+use std::ops::RangeInclusive;
+
+use rand::{CryptoRng, Rng, RngCore, seq::IndexedRandom};
+use url::Url;
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+const SCHEMES: &[&str] = &["http", "https"];
+const TLDS: &[&str] = &["com", "org", "net", "io", "test"];
+const WORD_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Structurally valid `http`/`https` URLs.
+///
+/// Shrinking drops query parameters first, then path segments, then the port
+/// and finally extra host labels.
+#[derive(Clone)]
+pub struct UrlStrategy {
+    segments: RangeInclusive<usize>,
+    query_params: RangeInclusive<usize>,
+}
+
+impl UrlStrategy {
+    pub fn new() -> Self {
+        Self {
+            segments: 0..=4,
+            query_params: 0..=3,
+        }
+    }
+
+    /// Bound the number of path segments.
+    pub fn with_segments(mut self, segments: RangeInclusive<usize>) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    /// Bound the number of `key=value` query parameters.
+    pub fn with_query_params(
+        mut self,
+        query_params: RangeInclusive<usize>,
+    ) -> Self {
+        self.query_params = query_params;
+        self
+    }
+
+    fn sample_parts<R>(&self, rng: &mut R) -> UrlParts
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        let labels = rng.random_range(0..=2);
+        let segments = rng.random_range(self.segments.clone());
+        let query = rng.random_range(self.query_params.clone());
+
+        UrlParts {
+            scheme: SCHEMES.choose(rng).copied().unwrap_or("http"),
+            labels: (0..labels).map(|_| word(rng)).collect(),
+            domain: word(rng),
+            tld: TLDS.choose(rng).copied().unwrap_or("com"),
+            port: rng.random_bool(0.25).then(|| rng.random_range(1..=65535)),
+            segments: (0..segments).map(|_| word(rng)).collect(),
+            query: (0..query).map(|_| (word(rng), word(rng))).collect(),
+        }
+    }
+
+    pub(crate) fn sample<R>(&self, rng: &mut R) -> Url
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        self.sample_parts(rng).to_url()
+    }
+}
+
+impl Default for UrlStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Strategy for UrlStrategy {
+    type Value = Url;
+    type Tree = UrlValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let parts = self.sample_parts(&mut generator.rng);
+        generator.accept(UrlValueTree::new(parts))
+    }
+}
+
+fn word<R>(rng: &mut R) -> String
+where
+    R: RngCore + CryptoRng + ?Sized,
+{
+    let len = rng.random_range(1..=8);
+    (0..len)
+        .map(|_| WORD_CHARS[rng.random_range(0..WORD_CHARS.len())] as char)
+        .collect()
+}
+
+#[derive(Clone)]
+struct UrlParts {
+    scheme: &'static str,
+    labels: Vec<String>,
+    domain: String,
+    tld: &'static str,
+    port: Option<u16>,
+    segments: Vec<String>,
+    query: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy)]
+enum Removal {
+    Query(usize),
+    Segment(usize),
+    Port,
+    Label(usize),
+}
+
+impl UrlParts {
+    fn to_url(&self) -> Url {
+        let mut url = String::new();
+        url.push_str(self.scheme);
+        url.push_str("://");
+        for label in &self.labels {
+            url.push_str(label);
+            url.push('.');
+        }
+        url.push_str(&self.domain);
+        url.push('.');
+        url.push_str(self.tld);
+        if let Some(port) = self.port {
+            url.push_str(&format!(":{port}"));
+        }
+        url.push('/');
+        url.push_str(&self.segments.join("/"));
+        if !self.query.is_empty() {
+            let pairs: Vec<String> = self
+                .query
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect();
+            url.push('?');
+            url.push_str(&pairs.join("&"));
+        }
+
+        Url::parse(&url).expect("generated URL must parse")
+    }
+
+    /// Removals to try, in shrinking order.
+    fn removals(&self) -> Vec<Removal> {
+        let mut removals = Vec::new();
+        removals.extend((0..self.query.len()).rev().map(Removal::Query));
+        removals.extend((0..self.segments.len()).rev().map(Removal::Segment));
+        if self.port.is_some() {
+            removals.push(Removal::Port);
+        }
+        removals.extend((0..self.labels.len()).map(Removal::Label));
+        removals
+    }
+
+    fn apply(&mut self, removal: Removal) {
+        match removal {
+            Removal::Query(index) => {
+                self.query.remove(index);
+            }
+            Removal::Segment(index) => {
+                self.segments.remove(index);
+            }
+            Removal::Port => self.port = None,
+            Removal::Label(index) => {
+                self.labels.remove(index);
+            }
+        }
+    }
+}
+
+pub struct UrlValueTree {
+    parts: UrlParts,
+    current: Url,
+    previous: Option<(UrlParts, usize)>,
+    next_removal: usize,
+}
+
+impl UrlValueTree {
+    fn new(parts: UrlParts) -> Self {
+        let current = parts.to_url();
+        Self {
+            parts,
+            current,
+            previous: None,
+            next_removal: 0,
+        }
+    }
+}
+
+impl ValueTree for UrlValueTree {
+    type Value = Url;
+
+    fn current(&self) -> &Self::Value {
+        &self.current
+    }
+
+    fn into_current(self) -> Self::Value {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        let Some(removal) =
+            self.parts.removals().get(self.next_removal).copied()
+        else {
+            return false;
+        };
+
+        self.previous = Some((self.parts.clone(), self.next_removal));
+        self.parts.apply(removal);
+        self.current = self.parts.to_url();
+        self.next_removal = 0;
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let Some((parts, removal)) = self.previous.take() else {
+            return false;
+        };
+
+        self.parts = parts;
+        self.current = self.parts.to_url();
+        self.next_removal = removal + 1;
+        self.next_removal < self.parts.removals().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> UrlValueTree {
+        let mut generator = Generator::build(crate::rng());
+        let mut strategy = UrlStrategy::new()
+            .with_segments(2..=4)
+            .with_query_params(1..=3);
+        match strategy.new_tree(&mut generator) {
+            Generation::Accepted { value, .. } => value,
+            Generation::Rejected { .. } => panic!("unexpected rejection"),
+        }
+    }
+
+    #[test]
+    fn urls_respect_component_bounds() {
+        for _ in 0..32 {
+            let tree = tree();
+            let url = tree.current();
+            let segments = url.path_segments().unwrap().count();
+            assert!((2..=4).contains(&segments));
+            assert!((1..=3).contains(&url.query_pairs().count()));
+        }
+    }
+
+    #[test]
+    fn urls_shrink_to_a_bare_host() {
+        let mut tree = tree();
+        while tree.simplify() {}
+
+        let url = tree.current();
+        assert_eq!(url.query(), None);
+        assert_eq!(url.path(), "/");
+        assert_eq!(url.port(), None);
+        assert_eq!(url.host_str().unwrap().matches('.').count(), 1);
+    }
+
+    #[test]
+    fn complicate_skips_rejected_removal() {
+        let mut tree = tree();
+        let original = tree.current().clone();
+        assert!(tree.simplify());
+        assert!(tree.current().query_pairs().count() < 3);
+        tree.complicate();
+        assert_eq!(*tree.current(), original);
+        assert!(tree.simplify());
+        assert_ne!(*tree.current(), original);
+    }
+}
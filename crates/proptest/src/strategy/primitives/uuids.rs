@@ -0,0 +1,62 @@
+use alloc::vec;
+
+use rand::{CryptoRng, RngCore};
+use uuid::{Builder, Uuid};
+
+use super::IntValueTree;
+use crate::strategy::{
+    Strategy,
+    runtime::{Generation, Generator},
+};
+
+/// Random (version 4) UUIDs that shrink straight to [`Uuid::nil`].
+#[derive(Clone, Default)]
+pub struct UuidStrategy;
+
+impl UuidStrategy {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub(crate) fn sample<R>(rng: &mut R) -> Uuid
+    where
+        R: RngCore + CryptoRng + ?Sized,
+    {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+impl Strategy for UuidStrategy {
+    type Value = Uuid;
+    type Tree = IntValueTree<Uuid>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let value = Self::sample(&mut generator.rng);
+        generator.accept(IntValueTree::new(value, vec![Uuid::nil()]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::ValueTree;
+
+    #[test]
+    fn uuids_are_v4_and_shrink_to_nil() {
+        let mut generator = Generator::build(crate::rng());
+        let mut tree = match UuidStrategy::new().new_tree(&mut generator) {
+            Generation::Accepted { value, .. } => value,
+            Generation::Rejected { .. } => panic!("unexpected rejection"),
+        };
+
+        assert_eq!(tree.current().get_version_num(), 4);
+        assert!(tree.simplify());
+        assert!(tree.current().is_nil());
+        assert!(!tree.simplify());
+    }
+}
@@ -0,0 +1,204 @@
+//! Which strategy produced a value, down to the parts of composite values.
+//!
+//! Value trees describe their current value with
+//! [`ValueTree::provenance`]: the strategy behind it, what it decided for
+//! the value, such as the length of a vector or an integer at the bound of
+//! its type, and the same for every part that tracks it. `#[proptest]`
+//! prints the [`breakdown`](Provenance::breakdown) of each argument next to
+//! its value when a failing case was observed:
+//!
+//! ```text
+//! items (VecStrategy len=3) -> [1] (AnyU8 edge-case MAX)
+//! ```
+//!
+//! [`Strategy::labeled`] names the values of any strategy in that output.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    Strategy,
+    ValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// Lines [`Provenance::breakdown`] prints before summing up the rest.
+pub const BREAKDOWN_LINES: usize = 16;
+
+/// Where a value came from, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    /// The strategy and what it decided for the value, e.g.
+    /// `VecStrategy len=3`.
+    pub label: String,
+    /// The parts of the value that track their provenance, by their path
+    /// from the value, such as `[1]` or `.0`.
+    pub parts: Vec<(String, Provenance)>,
+}
+
+impl Provenance {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn with_part(mut self, path: impl Into<String>, part: Self) -> Self {
+        self.parts.push((path.into(), part));
+        self
+    }
+
+    /// One line for every part without parts of its own, leading to it from
+    /// the value called `name`, e.g. `items (VecStrategy len=3) -> [1]
+    /// (AnyU8)`. Lines past [`BREAKDOWN_LINES`] are counted instead.
+    pub fn breakdown(&self, name: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.leaves(format!("{name} ({})", self.label), &mut lines);
+        if lines.len() > BREAKDOWN_LINES {
+            let more = lines.len() - BREAKDOWN_LINES;
+            lines.truncate(BREAKDOWN_LINES);
+            lines.push(format!("... and {more} more"));
+        }
+        lines
+    }
+
+    fn leaves(&self, path: String, lines: &mut Vec<String>) {
+        if self.parts.is_empty() {
+            lines.push(path);
+            return;
+        }
+        for (name, part) in &self.parts {
+            part.leaves(format!("{path} -> {name} ({})", part.label), lines);
+        }
+    }
+}
+
+/// The values of a strategy, under a label of their own, see
+/// [`Strategy::labeled`].
+#[derive(Clone, Debug)]
+pub struct Labeled<S> {
+    strategy: S,
+    label: &'static str,
+}
+
+impl<S> Labeled<S> {
+    pub fn new(strategy: S, label: &'static str) -> Self {
+        Self { strategy, label }
+    }
+}
+
+impl<S: Strategy> Strategy for Labeled<S> {
+    type Value = S::Value;
+    type Tree = LabeledValueTree<S::Tree>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let label = self.label;
+        self.strategy
+            .new_tree(generator)
+            .map(|tree| LabeledValueTree { tree, label })
+    }
+}
+
+/// The tree of a [`Labeled`] strategy, which shrinks like the tree it wraps.
+pub struct LabeledValueTree<T> {
+    tree: T,
+    label: &'static str,
+}
+
+impl<T: ValueTree> ValueTree for LabeledValueTree<T> {
+    type Value = T::Value;
+
+    fn current(&self) -> &T::Value {
+        self.tree.current()
+    }
+
+    fn into_current(self) -> T::Value
+    where
+        T::Value: Clone,
+    {
+        self.tree.into_current()
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.tree.simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        self.tree.complicate()
+    }
+
+    /// The wrapped tree's provenance with the label in front, or just the
+    /// label when it has none.
+    fn provenance(&self) -> Option<Provenance> {
+        Some(match self.tree.provenance() {
+            Some(inner) => Provenance {
+                label: format!("{}: {}", self.label, inner.label),
+                ..inner
+            },
+            None => Provenance::new(self.label.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::strategy::{AnyU8, VecStrategy};
+
+    #[test]
+    fn breakdowns_lead_to_every_part() {
+        let provenance = Provenance::new("VecStrategy len=2")
+            .with_part("[0]", Provenance::new("AnyU8"))
+            .with_part("[1]", Provenance::new("AnyU8 edge-case MAX"));
+        assert_eq!(
+            provenance.breakdown("items"),
+            [
+                "items (VecStrategy len=2) -> [0] (AnyU8)",
+                "items (VecStrategy len=2) -> [1] (AnyU8 edge-case MAX)",
+            ]
+        );
+
+        let mut long = Provenance::new("VecStrategy len=20");
+        for index in 0..20 {
+            long = long.with_part(format!("[{index}]"), Provenance::new("x"));
+        }
+        let lines = long.breakdown("items");
+        assert_eq!(lines.len(), BREAKDOWN_LINES + 1);
+        assert_eq!(lines[BREAKDOWN_LINES], "... and 4 more");
+    }
+
+    #[test]
+    fn trees_track_the_provenance_of_their_current_value() {
+        let mut strategy = VecStrategy::new(AnyU8::default(), 3usize..=3usize)
+            .labeled("bytes");
+        let rng = StdRng::seed_from_u64(1);
+        let mut tree = strategy.new_tree(&mut Generator::build(rng)).take();
+
+        let provenance = tree.provenance().unwrap();
+        assert_eq!(provenance.label, "bytes: VecStrategy len=3");
+        assert_eq!(provenance.parts.len(), 3);
+        for (index, (path, part)) in provenance.parts.iter().enumerate() {
+            assert_eq!(*path, format!("[{index}]"));
+            assert!(part.label.starts_with("AnyU8"));
+        }
+
+        while tree.simplify() {}
+        let provenance = tree.provenance().unwrap();
+        assert_eq!(tree.current(), &[0, 0, 0]);
+        assert_eq!(
+            provenance.breakdown("bytes")[0],
+            "bytes (bytes: VecStrategy len=3) -> [0] (AnyU8 edge-case MIN)"
+        );
+    }
+}
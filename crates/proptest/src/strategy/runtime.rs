@@ -1,12 +1,55 @@
-use std::ops::{Deref, DerefMut};
+use alloc::{collections::BTreeMap, format};
+use core::{
+    cell::Cell,
+    fmt,
+    ops::{Deref, DerefMut, RangeInclusive},
+};
 
-use rand::{CryptoRng, RngCore, rngs::ThreadRng};
+use rand::{CryptoRng, Rng, RngCore, SeedableRng, rngs::StdRng};
 
 use super::{Strategy, ValueTree};
 use crate::arbitrary::Arbitrary;
 
 pub(crate) const MAX_STRATEGY_ATTEMPTS: usize = 64;
 
+/// Largest generator size; strategies draw from their full range at this
+/// size.
+pub const MAX_SIZE: usize = 100;
+
+/// Points of size per level of depth recursive values may reach, so values
+/// generated at [`MAX_SIZE`] nest at most ten levels deep.
+pub const RECURSION_SIZE_STEP: usize = 10;
+
+/// Why a strategy rejected a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum RejectReason {
+    /// The value failed a filter or precondition of the strategy.
+    Filter,
+    /// A collection kept drawing keys it already held and could not reach
+    /// its minimum length.
+    DuplicateKey,
+    /// Generating the value would have exceeded the recursion budget.
+    RecursionBudget,
+    /// An assumption about the inputs of a property did not hold.
+    Assume,
+    /// [`Generator::gen_where`] found no value its predicate accepts within
+    /// its attempts.
+    AttemptsExhausted,
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RejectReason::Filter => "filter failed",
+            RejectReason::DuplicateKey => "duplicate key",
+            RejectReason::RecursionBudget => "recursion budget spent",
+            RejectReason::Assume => "assumption failed",
+            RejectReason::AttemptsExhausted => "attempts exhausted",
+        })
+    }
+}
+
 pub enum Generation<T> {
     Accepted {
         iteration: usize,
@@ -16,6 +59,8 @@ pub enum Generation<T> {
     Rejected {
         iteration: usize,
         depth: usize,
+        /// Why the value was rejected, when the strategy said so.
+        reason: Option<RejectReason>,
         value: T,
     },
 }
@@ -45,21 +90,207 @@ impl<T> Generation<T> {
             Generation::Rejected {
                 iteration,
                 depth,
+                reason,
                 value,
             } => Generation::Rejected {
                 iteration,
                 depth,
+                reason,
                 value: f(value),
             },
         }
     }
 }
 
+/// Swarm testing settings.
+///
+/// With swarm testing every case disables a random subset of the
+/// alternatives at each choice point (for example `None` in an
+/// [`OptionStrategy`](crate::strategy::OptionStrategy)), so some cases
+/// exercise unusual combinations such as "every option is `Some`" that
+/// uniform choices almost never produce.
+#[derive(Clone, Copy, Debug)]
+pub struct SwarmConfig {
+    disable_probability: f64,
+}
+
+impl SwarmConfig {
+    pub fn new() -> Self {
+        Self {
+            disable_probability: 0.5,
+        }
+    }
+
+    /// Probability with which each alternative is disabled in a case.
+    pub fn with_disable_probability(mut self, probability: f64) -> Self {
+        self.disable_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Whether `alternative` of the choice point `key` is enabled in the case
+    /// whose swarm seed is `seed`.
+    pub fn enabled(&self, seed: u64, key: &str, alternative: usize) -> bool {
+        let z = keyed_hash(seed, key, alternative as u64);
+        let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+        unit >= self.disable_probability
+    }
+}
+
+/// FNV-1a over `key`, then a SplitMix64 finaliser, so that every
+/// `(seed, key, index)` triple gets an independent, platform-stable hash.
+fn keyed_hash(seed: u64, key: &str, index: u64) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64 ^ seed;
+    for byte in key.bytes() {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    let mut z = hash.wrapping_add(index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Default for SwarmConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counters describing the work done by a generator.
+///
+/// Read them with [`Generator::stats`]; the test runner sums them over every
+/// case of a run into [`Report::stats`](crate::runner::Report::stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GeneratorStats {
+    /// Bytes of randomness drawn from the RNG.
+    pub entropy_bytes: u64,
+    /// Values accepted, including values nested inside other values.
+    pub accepted: usize,
+    /// Values rejected.
+    pub rejected: usize,
+    /// Deepest [`Generator::recurse`] nesting reached.
+    pub max_depth: usize,
+}
+
+impl GeneratorStats {
+    /// Add the counters of `other` to these, keeping the deeper maximum.
+    pub fn merge(&mut self, other: &GeneratorStats) {
+        self.entropy_bytes += other.entropy_bytes;
+        self.accepted += other.accepted;
+        self.rejected += other.rejected;
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+}
+
+/// An RNG that counts the bytes of randomness drawn from it.
+///
+/// It can also cap them: a strategy whose `new_tree` retries without end,
+/// like a loop drawing until two values differ from a strategy that only
+/// has one, keeps drawing randomness, so the RNG panics once the cap is
+/// exceeded, naming the strategy it draws for, instead of letting the case
+/// hang. The RNGs of generators split off with [`Generator::fork`] and
+/// [`Generator::draw_labeled`] are seeded from it but count their draws on
+/// their own, without a cap.
+pub struct CountingRng<R> {
+    inner: R,
+    bytes: u64,
+    limit: Option<u64>,
+    /// The strategy drawing, named when the limit is exceeded.
+    strategy: Option<&'static str>,
+}
+
+impl<R> CountingRng<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            bytes: 0,
+            limit: None,
+            strategy: None,
+        }
+    }
+
+    /// Panic once more than `limit` bytes were drawn.
+    pub fn with_limit(mut self, limit: Option<u64>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    /// Name the strategy the following draws are for in the panic of an
+    /// exceeded limit.
+    pub fn set_strategy(&mut self, strategy: &'static str) {
+        self.strategy = Some(strategy);
+    }
+
+    pub fn bytes_drawn(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn count(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        if let Some(limit) = self.limit
+            && self.bytes > limit
+        {
+            let strategy = match self.strategy {
+                Some(strategy) => format!("strategy `{strategy}`"),
+                None => "a strategy".into(),
+            };
+            panic!(
+                "#[proptest] {strategy} drew past the entropy limit of \
+                 {limit} bytes in one case; does its `new_tree` loop \
+                 without end?"
+            );
+        }
+    }
+}
+
+impl<R: RngCore> RngCore for CountingRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        self.count(4);
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.count(8);
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.count(dst.len() as u64);
+        self.inner.fill_bytes(dst);
+    }
+}
+
+impl<R: CryptoRng> CryptoRng for CountingRng<R> {}
+
+impl<R: SeedableRng> SeedableRng for CountingRng<R> {
+    type Seed = R::Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(R::from_seed(seed))
+    }
+}
+
 pub struct Generator<R> {
     pub rng: R,
     iteration: usize,
     depth: usize,
     recursion_limit: usize,
+    size: usize,
+    swarm: Option<(SwarmConfig, u64)>,
+    /// Seed of the labeled draws of the current case, drawn on first use.
+    label_seed: Option<u64>,
+    /// Labeled draws so far in the current case, by hash of the label.
+    label_draws: BTreeMap<u64, u64>,
+    accepted: Cell<usize>,
+    rejected: Cell<usize>,
+    max_depth: usize,
 }
 
 impl<R: RngCore + CryptoRng> Generator<R> {
@@ -69,6 +300,13 @@ impl<R: RngCore + CryptoRng> Generator<R> {
             iteration: 0,
             depth: 0,
             recursion_limit: 10000,
+            size: MAX_SIZE,
+            swarm: None,
+            label_seed: None,
+            label_draws: BTreeMap::new(),
+            accepted: Cell::new(0),
+            rejected: Cell::new(0),
+            max_depth: 0,
         }
     }
 
@@ -77,6 +315,144 @@ impl<R: RngCore + CryptoRng> Generator<R> {
         self
     }
 
+    /// Bound how large generated values may be, from `0` to [`MAX_SIZE`].
+    ///
+    /// Collection and string strategies scale their maximum length with the
+    /// size and integer strategies scale how far values may stray from their
+    /// shrink target, so small sizes produce small values. Generators start
+    /// at [`MAX_SIZE`]; the test runner grows the size over the first cases
+    /// of a run.
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.min(MAX_SIZE);
+        self
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Shrink the upper end of `range` in proportion to the current size.
+    ///
+    /// The lower end is kept, so the result always lies within `range`.
+    pub fn sized_len(
+        &self,
+        range: RangeInclusive<usize>,
+    ) -> RangeInclusive<usize> {
+        let (lo, hi) = range.into_inner();
+        let span = hi.saturating_sub(lo) as u128;
+        let scaled = span * self.size as u128 / MAX_SIZE as u128;
+        lo..=lo + scaled as usize
+    }
+
+    /// Number of significant bits allowed out of `bits` at the current size.
+    pub(crate) fn sized_bits(&self, bits: u32) -> u32 {
+        (bits as usize * self.size).div_ceil(MAX_SIZE) as u32
+    }
+
+    /// Enable swarm testing, deciding which alternatives are disabled from
+    /// `seed`; the test runner derives a fresh seed for every case.
+    pub fn with_swarm(mut self, swarm: SwarmConfig, seed: u64) -> Self {
+        self.swarm = Some((swarm, seed));
+        self
+    }
+
+    /// Prepare the generator for another case instead of building a new one.
+    ///
+    /// Swaps in `rng` and `size`, moves the swarm to `swarm_seed` when swarm
+    /// testing is on, and clears the iteration, depth and counters. The
+    /// recursion limit and swarm configuration carry over.
+    pub fn reset(&mut self, rng: R, size: usize, swarm_seed: u64) {
+        self.rng = rng;
+        self.size = size.min(MAX_SIZE);
+        if let Some((_, seed)) = &mut self.swarm {
+            *seed = swarm_seed;
+        }
+        self.iteration = 0;
+        self.depth = 0;
+        self.label_seed = None;
+        self.label_draws.clear();
+        self.accepted.set(0);
+        self.rejected.set(0);
+        self.max_depth = 0;
+    }
+
+    /// Pick one of `alternatives` choices of the choice point `key`.
+    ///
+    /// Without swarm testing the choice is uniform. With it, alternatives
+    /// disabled for `key` in this case are skipped, unless that would leave
+    /// nothing to choose from.
+    pub fn choose(&mut self, key: &str, alternatives: usize) -> usize {
+        let Some((swarm, seed)) = self.swarm else {
+            return self.rng.random_range(0..alternatives);
+        };
+
+        let enabled = (0..alternatives)
+            .filter(|&alternative| swarm.enabled(seed, key, alternative))
+            .count();
+        if enabled == 0 {
+            return self.rng.random_range(0..alternatives);
+        }
+
+        let pick = self.rng.random_range(0..enabled);
+        (0..alternatives)
+            .filter(|&alternative| swarm.enabled(seed, key, alternative))
+            .nth(pick)
+            .unwrap_or(0)
+    }
+
+    /// Pick one of the choices of the choice point `key`, each with a
+    /// probability proportional to its weight in `weights`.
+    ///
+    /// Swarm testing skips disabled choices like [`Generator::choose`] does,
+    /// unless that would leave no weight to choose from. Choices of weight
+    /// zero are never picked while another has a positive weight.
+    pub fn choose_weighted(&mut self, key: &str, weights: &[f64]) -> usize {
+        let swarm = self.swarm;
+        let enabled = |alternative: usize| match swarm {
+            Some((swarm, seed)) => swarm.enabled(seed, key, alternative),
+            None => true,
+        };
+        let weight_of = |only_enabled: bool, alternative: usize| {
+            if only_enabled && !enabled(alternative) {
+                0.0
+            } else {
+                weights[alternative].max(0.0)
+            }
+        };
+
+        let mut only_enabled = true;
+        let mut total: f64 =
+            (0..weights.len()).map(|i| weight_of(true, i)).sum();
+        if total == 0.0 {
+            only_enabled = false;
+            total = (0..weights.len()).map(|i| weight_of(false, i)).sum();
+        }
+        if total == 0.0 {
+            return self.rng.random_range(0..weights.len());
+        }
+
+        let mut target = self.rng.random::<f64>() * total;
+        let mut last = 0;
+        for alternative in 0..weights.len() {
+            let weight = weight_of(only_enabled, alternative);
+            if weight == 0.0 {
+                continue;
+            }
+            if target < weight {
+                return alternative;
+            }
+            target -= weight;
+            last = alternative;
+        }
+        last
+    }
+
+    /// Borrow the generator so its size can grow temporarily, e.g. while a
+    /// set keeps drawing duplicates from a small range.
+    pub(crate) fn size_guard(&mut self) -> SizeGuard<'_, R> {
+        SizeGuard::new(self)
+    }
+
     pub fn iteration(&self) -> usize {
         self.iteration
     }
@@ -89,7 +465,23 @@ impl<R: RngCore + CryptoRng> Generator<R> {
         self.depth
     }
 
+    pub fn recursion_limit(&self) -> usize {
+        self.recursion_limit
+    }
+
+    /// Whether recursive values should stop growing at the current depth.
+    ///
+    /// That is the case once another [`recurse`](Self::recurse) would exceed
+    /// the recursion limit, or once the depth reaches one level per
+    /// [`RECURSION_SIZE_STEP`] points of size, which keeps recursive values
+    /// as small as the current size asks for.
+    pub fn recursion_exhausted(&self) -> bool {
+        self.depth >= self.recursion_limit
+            || self.depth >= self.size.div_ceil(RECURSION_SIZE_STEP)
+    }
+
     pub fn accept<T>(&self, value: T) -> Generation<T> {
+        self.accepted.set(self.accepted.get() + 1);
         Generation::Accepted {
             iteration: self.iteration,
             depth: self.depth,
@@ -98,13 +490,101 @@ impl<R: RngCore + CryptoRng> Generator<R> {
     }
 
     pub fn reject<T>(&self, value: T) -> Generation<T> {
+        self.rejected.set(self.rejected.get() + 1);
+        Generation::Rejected {
+            iteration: self.iteration,
+            depth: self.depth,
+            reason: None,
+            value,
+        }
+    }
+
+    /// Reject `value`, recording why so retry loops and rejection reports
+    /// can explain it.
+    pub fn reject_with<T>(
+        &self,
+        reason: RejectReason,
+        value: T,
+    ) -> Generation<T> {
+        self.rejected.set(self.rejected.get() + 1);
         Generation::Rejected {
             iteration: self.iteration,
             depth: self.depth,
+            reason: Some(reason),
             value,
         }
     }
 
+    /// Generate trees from `strategy` until one holds a value `pred`
+    /// accepts, trying at most `max_attempts` times.
+    ///
+    /// Trees the strategy rejects count as attempts too. Once the attempts
+    /// are spent the last tree comes back rejected with
+    /// [`RejectReason::AttemptsExhausted`], which callers hand on like any
+    /// rejection, instead of retrying in a loop that never ends when `pred`
+    /// accepts nothing. Strategies that need a value `pred` accepts, such as
+    /// two distinct values, use this rather than retrying by hand. The tree
+    /// shrinks like any tree of `strategy`, to values `pred` may reject.
+    pub fn gen_where<S, P>(
+        &mut self,
+        strategy: &mut S,
+        mut pred: P,
+        max_attempts: usize,
+    ) -> Generation<S::Tree>
+    where
+        S: Strategy,
+        P: FnMut(&S::Value) -> bool,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let generation = strategy.new_tree(self);
+            if let Generation::Accepted { value, .. } = &generation
+                && pred(value.current())
+            {
+                return generation;
+            }
+            if attempts >= max_attempts {
+                return self.reject_with(
+                    RejectReason::AttemptsExhausted,
+                    generation.take(),
+                );
+            }
+        }
+    }
+
+    /// Run `f` with a type-erased view of this generator.
+    ///
+    /// The erased generator shares the RNG and iteration/depth counters, so
+    /// code written against [`DynGenerator`] behaves exactly as if it had been
+    /// handed `self`.
+    pub fn erased<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut DynGenerator<'_>) -> T,
+    {
+        let mut erased = Generator {
+            rng: &mut self.rng as &mut dyn CryptoRng,
+            iteration: self.iteration,
+            depth: self.depth,
+            recursion_limit: self.recursion_limit,
+            size: self.size,
+            swarm: self.swarm,
+            label_seed: self.label_seed,
+            label_draws: core::mem::take(&mut self.label_draws),
+            accepted: self.accepted.clone(),
+            rejected: self.rejected.clone(),
+            max_depth: self.max_depth,
+        };
+        let value = f(&mut erased);
+        self.iteration = erased.iteration;
+        self.label_seed = erased.label_seed;
+        self.label_draws = erased.label_draws;
+        self.accepted = erased.accepted;
+        self.rejected = erased.rejected;
+        self.max_depth = erased.max_depth;
+        value
+    }
+
     pub fn recurse<F, T>(&mut self, f: F) -> T
     where
         F: FnOnce(&mut Generator<R>) -> T,
@@ -114,6 +594,91 @@ impl<R: RngCore + CryptoRng> Generator<R> {
     }
 }
 
+impl<R: RngCore + CryptoRng + SeedableRng> Generator<R> {
+    /// Split off a child generator with its own RNG stream.
+    ///
+    /// The child's RNG is seeded from this generator's, so it is as
+    /// reproducible as the parent, while forking draws a fixed amount of
+    /// randomness from the parent whatever the child goes on to draw. A
+    /// strategy that hands each part of its value a fork can change how
+    /// much one part draws without changing the others. The child keeps the
+    /// size, depth, recursion limit and swarm of the parent and starts with
+    /// fresh counters, which the parent's [`stats`](Generator::stats) do not
+    /// include.
+    pub fn fork(&mut self) -> Generator<R> {
+        let rng = R::from_rng(&mut self.rng);
+        self.child(rng)
+    }
+
+    /// Generate a tree from `strategy` with randomness that only depends on
+    /// the case and on `label`.
+    ///
+    /// Values drawn with [`new_tree`](Strategy::new_tree) depend on
+    /// everything drawn before them, so adding a field to a strategy shifts
+    /// the value of every field after it and old regression seeds stop
+    /// reproducing their failures. A labeled draw is seeded from a per-case
+    /// seed, the label and how many draws with that label came before it in
+    /// the case, so new labels, or draws in a different order, leave the
+    /// values of existing labels alone. The first labeled draw of a case
+    /// takes the per-case seed from the generator's RNG.
+    pub fn draw_labeled<S: Strategy>(
+        &mut self,
+        label: &str,
+        strategy: &mut S,
+    ) -> Generation<S::Tree> {
+        let seed = match self.label_seed {
+            Some(seed) => seed,
+            None => *self.label_seed.insert(self.rng.next_u64()),
+        };
+        let draws =
+            self.label_draws.entry(keyed_hash(0, label, 0)).or_default();
+        let index = *draws;
+        *draws += 1;
+
+        let mut child =
+            self.child(R::seed_from_u64(keyed_hash(seed, label, index)));
+        let generation = strategy.new_tree(&mut child);
+        self.iteration = child.iteration;
+        self.accepted
+            .set(self.accepted.get() + child.accepted.get());
+        self.rejected
+            .set(self.rejected.get() + child.rejected.get());
+        self.max_depth = self.max_depth.max(child.max_depth);
+        generation
+    }
+
+    fn child(&self, rng: R) -> Generator<R> {
+        Generator {
+            rng,
+            iteration: self.iteration,
+            depth: self.depth,
+            recursion_limit: self.recursion_limit,
+            size: self.size,
+            swarm: self.swarm,
+            label_seed: None,
+            label_draws: BTreeMap::new(),
+            accepted: Cell::new(0),
+            rejected: Cell::new(0),
+            max_depth: self.depth,
+        }
+    }
+}
+
+impl<R: RngCore + CryptoRng> Generator<CountingRng<R>> {
+    /// Counters of the work done so far by this generator.
+    ///
+    /// Only generators over a [`CountingRng`] can report the randomness they
+    /// drew, as the generators built by the test runner do.
+    pub fn stats(&self) -> GeneratorStats {
+        GeneratorStats {
+            entropy_bytes: self.rng.bytes_drawn(),
+            accepted: self.accepted.get(),
+            rejected: self.rejected.get(),
+            max_depth: self.max_depth,
+        }
+    }
+}
+
 struct DepthGuard<'a, R: RngCore + CryptoRng> {
     generator: &'a mut Generator<R>,
 }
@@ -127,6 +692,7 @@ impl<'a, R: RngCore + CryptoRng> DepthGuard<'a, R> {
             );
         }
         generator.depth += 1;
+        generator.max_depth = generator.max_depth.max(generator.depth);
         Self { generator }
     }
 }
@@ -151,7 +717,62 @@ impl<'a, R: RngCore + CryptoRng> DerefMut for DepthGuard<'a, R> {
     }
 }
 
-pub type DefaultGenerator = Generator<ThreadRng>;
+/// Restores the generator size it was created with when dropped.
+pub(crate) struct SizeGuard<'a, R: RngCore + CryptoRng> {
+    generator: &'a mut Generator<R>,
+    size: usize,
+}
+
+impl<'a, R: RngCore + CryptoRng> SizeGuard<'a, R> {
+    fn new(generator: &'a mut Generator<R>) -> Self {
+        let size = generator.size;
+        Self { generator, size }
+    }
+
+    /// Double the size, up to [`MAX_SIZE`].
+    pub(crate) fn grow(&mut self) {
+        self.generator.size = (self.generator.size * 2).clamp(1, MAX_SIZE);
+    }
+}
+
+impl<'a, R: RngCore + CryptoRng> Drop for SizeGuard<'a, R> {
+    fn drop(&mut self) {
+        self.generator.size = self.size;
+    }
+}
+
+impl<'a, R: RngCore + CryptoRng> Deref for SizeGuard<'a, R> {
+    type Target = Generator<R>;
+
+    fn deref(&self) -> &Self::Target {
+        self.generator
+    }
+}
+
+impl<'a, R: RngCore + CryptoRng> DerefMut for SizeGuard<'a, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.generator
+    }
+}
+
+pub type DefaultGenerator = Generator<CountingRng<StdRng>>;
+
+/// A generator whose RNG type has been erased.
+pub type DynGenerator<'a> = Generator<&'a mut dyn CryptoRng>;
+
+/// A generator of any RNG type behind a `&mut dyn`, as taken by
+/// [`ErasedStrategy`](crate::strategy::ErasedStrategy).
+pub trait EntropySource {
+    /// Call `f` with a type-erased view of this generator, see
+    /// [`Generator::erased`].
+    fn with_erased(&mut self, f: &mut dyn FnMut(&mut DynGenerator<'_>));
+}
+
+impl<R: RngCore + CryptoRng> EntropySource for Generator<R> {
+    fn with_erased(&mut self, f: &mut dyn FnMut(&mut DynGenerator<'_>)) {
+        self.erased(f);
+    }
+}
 
 pub struct IntegratedAdapter<S>
 where
@@ -170,30 +791,22 @@ where
         Self { strategy }
     }
 
+    /// Generate a value, moving it out of its tree.
     pub fn generate(
         &mut self,
         generator: &mut DefaultGenerator,
     ) -> Generation<S::Value> {
-        match self.strategy.new_tree(generator) {
-            Generation::Accepted {
-                iteration,
-                depth,
-                value,
-            } => Generation::Accepted {
-                iteration,
-                depth,
-                value: value.current().clone(),
-            },
-            Generation::Rejected {
-                iteration,
-                depth,
-                value,
-            } => Generation::Rejected {
-                iteration,
-                depth,
-                value: value.current().clone(),
-            },
-        }
+        self.generate_tree(generator).map(ValueTree::into_current)
+    }
+
+    /// Generate a value tree, leaving its value in place so callers can
+    /// borrow it without a clone and keep the tree around for shrinking.
+    pub fn generate_tree(
+        &mut self,
+        generator: &mut DefaultGenerator,
+    ) -> Generation<S::Tree> {
+        generator.rng.set_strategy(core::any::type_name::<S>());
+        self.strategy.new_tree(generator)
     }
 }
 
@@ -216,13 +829,27 @@ where
     adapter.generate(generator)
 }
 
+pub fn execute_tree<S>(
+    adapter: &mut IntegratedAdapter<S>,
+    generator: &mut DefaultGenerator,
+) -> Generation<S::Tree>
+where
+    S: Strategy,
+    S::Value: Clone,
+{
+    adapter.generate_tree(generator)
+}
+
 pub fn from_arbitrary<T>(generator: &mut DefaultGenerator) -> Generation<T>
 where
     T: Arbitrary,
 {
+    generator.rng.set_strategy(core::any::type_name::<T>());
     T::generate(generator)
 }
 
+/// A value tree holding a single value that cannot be simplified.
+#[derive(Default)]
 pub struct ConstantValueTree<T> {
     value: T,
 }
@@ -240,6 +867,10 @@ impl<T> ValueTree for ConstantValueTree<T> {
         &self.value
     }
 
+    fn into_current(self) -> Self::Value {
+        self.value
+    }
+
     fn simplify(&mut self) -> bool {
         false
     }
@@ -248,3 +879,36 @@ impl<T> ValueTree for ConstantValueTree<T> {
         false
     }
 }
+
+/// A [`Strategy`] backed by a generation function.
+///
+/// The produced values do not shrink; see [`from_fn`].
+pub struct FnStrategy<F> {
+    f: F,
+}
+
+impl<F, T> Strategy for FnStrategy<F>
+where
+    F: FnMut(&mut DynGenerator<'_>) -> Generation<T>,
+{
+    type Value = T;
+    type Tree = ConstantValueTree<T>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        generator
+            .erased(|erased| (self.f)(erased))
+            .map(ConstantValueTree::new)
+    }
+}
+
+/// Use a closure producing a [`Generation`] wherever a [`Strategy`] is
+/// expected, e.g. `#[strategy(from_fn(|g| g.accept(g.rng.random::<u8>())))]`.
+pub fn from_fn<F, T>(f: F) -> FnStrategy<F>
+where
+    F: FnMut(&mut DynGenerator<'_>) -> Generation<T>,
+{
+    FnStrategy { f }
+}
@@ -0,0 +1,375 @@
+//! Deterministic task interleavings.
+//!
+//! [`ScheduleStrategy`] generates a [`Schedule`]: the order in which an
+//! [`Executor`] polls a handful of futures. The executor runs on the test's
+//! thread and picks the next task to poll from the ones that were woken,
+//! as the schedule says, so an interleaving that breaks the code under test
+//! is found like any other value and replays from the seed. Shrinking moves
+//! the schedule towards polling the earliest woken task first, running each
+//! task as far as it goes before switching, so a minimal counterexample
+//! shows only the switches the race needs.
+//!
+//! Tasks only switch where they return `Pending`; [`yield_now`] adds a
+//! switch point between two steps that do not wait on anything:
+//!
+//! ```
+//! use core::cell::Cell;
+//!
+//! use estoa_proptest::strategy::schedule::{Schedule, yield_now};
+//!
+//! let counter = Cell::new(0);
+//! let increment = || async {
+//!     let read = counter.get();
+//!     yield_now().await;
+//!     counter.set(read + 1);
+//! };
+//!
+//! // Both tasks read before either writes, and an increment is lost.
+//! let mut executor = Schedule::new(vec![0, 1]).executor();
+//! executor.spawn(increment());
+//! executor.spawn(increment());
+//! executor.run().unwrap();
+//! assert_eq!(counter.get(), 1);
+//! ```
+//!
+//! Properties are plain functions, so the property builds the executor,
+//! spawns its tasks and runs them to completion in its body.
+
+use alloc::{boxed::Box, sync::Arc, task::Wake, vec::Vec};
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    AnyUsize,
+    IntValueTree,
+    SizeHint,
+    Strategy,
+    ValueTree,
+    VecStrategy,
+    VecValueTree,
+    runtime::{Generation, Generator},
+};
+
+/// The order an [`Executor`] polls its tasks in, see the
+/// [module documentation](self).
+///
+/// Each pick chooses among the woken tasks, in the order they were spawned,
+/// wrapping around when it is larger than their number. Once the picks run
+/// out, the earliest woken task runs.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Schedule {
+    picks: Vec<usize>,
+}
+
+impl Schedule {
+    pub fn new(picks: Vec<usize>) -> Self {
+        Self { picks }
+    }
+
+    pub fn picks(&self) -> &[usize] {
+        &self.picks
+    }
+
+    /// An executor following this schedule.
+    pub fn executor<'a, T>(&self) -> Executor<'a, T> {
+        Executor {
+            picks: self.picks.clone(),
+            tasks: Vec::new(),
+        }
+    }
+}
+
+/// Returned by [`Executor::run`] when tasks are left waiting with none of
+/// them woken, so none of them can make progress.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Deadlock {
+    /// The number of tasks that did not finish.
+    pub pending: usize,
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} tasks are waiting and none was woken", self.pending)
+    }
+}
+
+impl core::error::Error for Deadlock {}
+
+struct Woken(AtomicBool);
+
+impl Wake for Woken {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Task<'a, T> {
+    future: Pin<Box<dyn Future<Output = T> + 'a>>,
+    woken: Arc<Woken>,
+    output: Option<T>,
+}
+
+/// A single-threaded executor polling its tasks in the order of a
+/// [`Schedule`].
+pub struct Executor<'a, T> {
+    picks: Vec<usize>,
+    tasks: Vec<Task<'a, T>>,
+}
+
+impl<'a, T> Executor<'a, T> {
+    /// Add a task. Tasks start out woken.
+    pub fn spawn<F: Future<Output = T> + 'a>(&mut self, future: F) {
+        self.tasks.push(Task {
+            future: Box::pin(future),
+            woken: Arc::new(Woken(AtomicBool::new(true))),
+            output: None,
+        });
+    }
+
+    /// Poll the tasks until all of them finish, returning their outputs in
+    /// the order they were spawned.
+    ///
+    /// # Errors
+    ///
+    /// [`Deadlock`] when unfinished tasks remain and none of them is woken.
+    pub fn run(mut self) -> Result<Vec<T>, Deadlock> {
+        let mut picks = self.picks.iter().copied();
+        loop {
+            let woken: Vec<usize> = self
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| {
+                    task.output.is_none()
+                        && task.woken.0.load(Ordering::Relaxed)
+                })
+                .map(|(index, _)| index)
+                .collect();
+            if woken.is_empty() {
+                break;
+            }
+
+            let pick = picks.next().unwrap_or(0);
+            let task = &mut self.tasks[woken[pick % woken.len()]];
+            task.woken.0.store(false, Ordering::Relaxed);
+            let waker = Waker::from(task.woken.clone());
+            if let Poll::Ready(output) =
+                task.future.as_mut().poll(&mut Context::from_waker(&waker))
+            {
+                task.output = Some(output);
+            }
+        }
+
+        let pending = self
+            .tasks
+            .iter()
+            .filter(|task| task.output.is_none())
+            .count();
+        if pending > 0 {
+            return Err(Deadlock { pending });
+        }
+        Ok(self
+            .tasks
+            .into_iter()
+            .filter_map(|task| task.output)
+            .collect())
+    }
+}
+
+/// Return `Pending` once, waking the task right away, so the executor can
+/// switch to another task.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// The future of [`yield_now`].
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Generate [`Schedule`]s, see the [module documentation](self).
+#[derive(Clone)]
+pub struct ScheduleStrategy {
+    picks: VecStrategy<AnyUsize>,
+}
+
+impl ScheduleStrategy {
+    /// Schedules of `size_hint` picks, each among up to `tasks` woken tasks.
+    ///
+    /// # Panics
+    ///
+    /// When `tasks` is zero.
+    pub fn new<H: SizeHint>(tasks: usize, size_hint: H) -> Self {
+        assert!(tasks > 0, "a schedule needs at least one task to pick");
+        Self {
+            picks: VecStrategy::new(AnyUsize::new(0..=tasks - 1), size_hint),
+        }
+    }
+}
+
+impl Default for ScheduleStrategy {
+    fn default() -> Self {
+        Self::new(4, 0usize..=32usize)
+    }
+}
+
+impl Strategy for ScheduleStrategy {
+    type Value = Schedule;
+    type Tree = ScheduleValueTree;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        self.picks.new_tree(generator).map(|picks| {
+            let current = Schedule::new(picks.current().clone());
+            ScheduleValueTree { picks, current }
+        })
+    }
+}
+
+pub struct ScheduleValueTree {
+    picks: VecValueTree<IntValueTree<usize>>,
+    current: Schedule,
+}
+
+impl ScheduleValueTree {
+    fn sync_current(&mut self) {
+        self.current = Schedule::new(self.picks.current().clone());
+    }
+}
+
+impl ValueTree for ScheduleValueTree {
+    type Value = Schedule;
+
+    fn current(&self) -> &Schedule {
+        &self.current
+    }
+
+    fn into_current(self) -> Schedule {
+        self.current
+    }
+
+    fn simplify(&mut self) -> bool {
+        if !self.picks.simplify() {
+            return false;
+        }
+        self.sync_current();
+        true
+    }
+
+    fn complicate(&mut self) -> bool {
+        let more = self.picks.complicate();
+        self.sync_current();
+        more
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::cell::{Cell, RefCell};
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    /// Two read-modify-write increments of a shared counter.
+    fn lost_update(schedule: &Schedule) -> usize {
+        let counter = Cell::new(0);
+        let increment = || async {
+            let read = counter.get();
+            yield_now().await;
+            counter.set(read + 1);
+        };
+        let mut executor = schedule.executor();
+        executor.spawn(increment());
+        executor.spawn(increment());
+        executor.run().unwrap();
+        counter.get()
+    }
+
+    #[test]
+    fn picks_stay_within_the_tasks() {
+        check_shrink_invariants_with(ScheduleStrategy::default(), 64, |s| {
+            s.picks().iter().all(|pick| *pick < 4)
+        });
+    }
+
+    #[test]
+    fn races_shrink_to_the_switches_they_need() {
+        assert_shrinks_to!(
+            ScheduleStrategy::new(2, 0usize..=8usize),
+            |schedule: &Schedule| lost_update(schedule) == 2,
+            Schedule::new(vec![1]),
+        );
+    }
+
+    #[test]
+    fn tasks_run_in_the_order_picked() {
+        let order = RefCell::new(Vec::new());
+        let task = |name| {
+            let order = &order;
+            async move {
+                order.borrow_mut().push((name, 0));
+                yield_now().await;
+                order.borrow_mut().push((name, 1));
+                name
+            }
+        };
+        let mut executor = Schedule::new(vec![1, 0, 3]).executor();
+        executor.spawn(task('a'));
+        executor.spawn(task('b'));
+        assert_eq!(executor.run(), Ok(vec!['a', 'b']));
+        assert_eq!(
+            order.into_inner(),
+            [('b', 0), ('a', 0), ('b', 1), ('a', 1)]
+        );
+    }
+
+    #[test]
+    fn tasks_never_woken_deadlock() {
+        struct Forever;
+
+        impl Future for Forever {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+                Poll::Pending
+            }
+        }
+
+        let finished = Cell::new(false);
+        let mut executor = Schedule::default().executor();
+        executor.spawn(Box::pin(Forever) as Pin<Box<dyn Future<Output = ()>>>);
+        executor.spawn(Box::pin(async { finished.set(true) }));
+        assert_eq!(executor.run(), Err(Deadlock { pending: 1 }));
+        assert!(finished.get());
+    }
+}
@@ -0,0 +1,407 @@
+//! Strings for testing query builders and sanitizers.
+//!
+//! [`SqlStrategy::identifier`] generates names that are safe to splice into
+//! a query unquoted, [`SqlStrategy::quoted_identifier`] names that only
+//! survive when quoted, and [`SqlStrategy::literal`] the contents of string
+//! literals, full of quotes, comment markers and other characters that break
+//! naive escaping. [`quote_identifier`] and [`quote_literal`] quote them the
+//! way standard SQL does, as a reference for the code under test.
+//!
+//! [`NormalizationStrategy`] generates the same text in both of its Unicode
+//! normalization forms, for code that must treat `"é"` and `"e\u{301}"` as
+//! equal or must keep them apart.
+//!
+//! Every string is assembled from a table of curated fragments. Shrinking
+//! drops fragments and moves the remaining ones towards the blandest entry of
+//! the table, so a failing input shrinks to the few fragments that matter.
+
+use alloc::string::String;
+use core::ops::RangeInclusive;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::strategy::{
+    FragmentValueTree,
+    SizeHint,
+    Strategy,
+    fragments::fragments,
+    runtime::{Generation, Generator},
+};
+
+/// Keywords reserved by at least one common SQL dialect, in lowercase.
+const RESERVED: &[&str] = &[
+    "all", "and", "as", "by", "case", "create", "delete", "drop", "from",
+    "group", "in", "insert", "into", "is", "join", "like", "limit", "not",
+    "null", "on", "or", "order", "select", "set", "table", "to", "union",
+    "update", "user", "values", "where",
+];
+
+const IDENTIFIER: &[&str] = &[
+    "a", "b", "z", "A", "Z", "_", "0", "9", "id", "x1", "user_", "Order_",
+];
+
+const QUOTED_IDENTIFIER: &[&str] = &[
+    "a",
+    "select",
+    "Order",
+    "user",
+    "TABLE",
+    " ",
+    "\"",
+    "\"\"",
+    "'",
+    ".",
+    "a.b",
+    ";",
+    "-",
+    "1",
+    "$1",
+    "`",
+    "[",
+    "]",
+    "é",
+    "e\u{301}",
+    "\u{200B}",
+    "\u{1F600}",
+];
+
+const LITERAL: &[&str] = &[
+    "a",
+    " ",
+    "'",
+    "''",
+    "\\",
+    "\\'",
+    "\"",
+    ";",
+    "--",
+    "/*",
+    "*/",
+    "%",
+    "_",
+    "?",
+    ":name",
+    "$$",
+    "\0",
+    "\n",
+    "\r\n",
+    "\t",
+    "\u{2019}",
+    "\u{02BC}",
+    "\u{FF07}",
+    "\u{FEFF}",
+    "\u{1F600}",
+    "' OR '1'='1",
+    "'; DROP TABLE users; --",
+];
+
+/// Text in normalization form C, then the same text in form D. The forms
+/// differ in precomposed characters, Hangul syllables, the canonical order
+/// of combining marks and marks without a precomposed form. `"ﬁ"` only
+/// changes under compatibility normalization, so both forms keep it.
+const NORMALIZATION: &[(&str, &str)] = &[
+    ("a", "a"),
+    ("é", "e\u{301}"),
+    ("Å", "A\u{30A}"),
+    ("ñ", "n\u{303}"),
+    ("ö", "o\u{308}"),
+    ("ǖ", "u\u{308}\u{304}"),
+    ("ệ", "e\u{323}\u{302}"),
+    ("ṩ", "s\u{323}\u{307}"),
+    ("ἄ", "\u{3B1}\u{313}\u{301}"),
+    ("가", "\u{1100}\u{1161}"),
+    ("한", "\u{1112}\u{1161}\u{11AB}"),
+    ("q\u{307}", "q\u{307}"),
+    ("é\u{301}", "e\u{301}\u{301}"),
+    ("ﬁ", "ﬁ"),
+];
+
+/// Whether `name` is a reserved keyword, in any case.
+pub fn is_reserved(name: &str) -> bool {
+    RESERVED
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(name))
+}
+
+/// Quote `name` as a standard SQL identifier, doubling its double quotes.
+pub fn quote_identifier(name: &str) -> String {
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('"');
+    quoted.push_str(&name.replace('"', "\"\""));
+    quoted.push('"');
+    quoted
+}
+
+/// Quote `text` as a standard SQL string literal, doubling its single
+/// quotes. Backslashes are ordinary characters in standard SQL, as in
+/// SQLite and in PostgreSQL with `standard_conforming_strings`.
+pub fn quote_literal(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('\'');
+    quoted.push_str(&text.replace('\'', "''"));
+    quoted.push('\'');
+    quoted
+}
+
+fn render_identifier(fragments: &[usize]) -> String {
+    let mut name: String =
+        fragments.iter().map(|&index| IDENTIFIER[index]).collect();
+    if name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    if is_reserved(&name) {
+        name.push('_');
+    }
+    name
+}
+
+fn render_quoted_identifier(fragments: &[usize]) -> String {
+    fragments
+        .iter()
+        .map(|&index| QUOTED_IDENTIFIER[index])
+        .collect()
+}
+
+fn render_literal(fragments: &[usize]) -> String {
+    fragments.iter().map(|&index| LITERAL[index]).collect()
+}
+
+fn render_normalization(fragments: &[usize]) -> (String, String) {
+    fragments.iter().map(|&index| NORMALIZATION[index]).fold(
+        (String::new(), String::new()),
+        |(mut c, mut d), (nfc, nfd)| {
+            c.push_str(nfc);
+            d.push_str(nfd);
+            (c, d)
+        },
+    )
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Identifier,
+    QuotedIdentifier,
+    Literal,
+}
+
+/// SQL identifiers and literal contents, see the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct SqlStrategy {
+    kind: Kind,
+    len_range: RangeInclusive<usize>,
+}
+
+impl SqlStrategy {
+    /// Identifiers matching `[A-Za-z_][A-Za-z0-9_]*` that are not reserved
+    /// keywords, so they are valid without quotes. Shrinks towards `"a"`.
+    pub fn identifier() -> Self {
+        Self {
+            kind: Kind::Identifier,
+            len_range: 1..=8,
+        }
+    }
+
+    /// Identifiers that are only valid quoted: keywords, spaces, quotes,
+    /// dots, leading digits and non-ASCII characters. Shrinks towards
+    /// `"a"`.
+    pub fn quoted_identifier() -> Self {
+        Self {
+            kind: Kind::QuotedIdentifier,
+            len_range: 1..=8,
+        }
+    }
+
+    /// The unquoted contents of string literals: quotes of every kind,
+    /// backslashes, comment markers, placeholders, control characters and
+    /// classic injection payloads. Shrinks towards `""`.
+    pub fn literal() -> Self {
+        Self {
+            kind: Kind::Literal,
+            len_range: 0..=16,
+        }
+    }
+
+    /// Bound the number of fragments a value is assembled from.
+    pub fn with_len<H: SizeHint>(mut self, len_hint: H) -> Self {
+        let len_range = len_hint.to_inclusive();
+        let min = match self.kind {
+            Kind::Identifier | Kind::QuotedIdentifier => 1,
+            Kind::Literal => 0,
+        };
+        assert!(*len_range.start() >= min, "identifiers cannot be empty");
+        self.len_range = len_range;
+        self
+    }
+}
+
+impl Strategy for SqlStrategy {
+    type Value = String;
+    type Tree = FragmentValueTree<String>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let (key, table_len, render): (_, _, fn(&[usize]) -> String) =
+            match self.kind {
+                Kind::Identifier => {
+                    ("sql identifier", IDENTIFIER.len(), render_identifier)
+                }
+                Kind::QuotedIdentifier => (
+                    "sql quoted identifier",
+                    QUOTED_IDENTIFIER.len(),
+                    render_quoted_identifier,
+                ),
+                Kind::Literal => ("sql literal", LITERAL.len(), render_literal),
+            };
+        fragments(generator, key, table_len, &self.len_range, render)
+    }
+}
+
+/// Pairs of canonically equivalent strings, the first in normalization form
+/// C and the second in form D, see the [module documentation](self).
+///
+/// Shrinks towards a pair of empty strings.
+#[derive(Clone)]
+pub struct NormalizationStrategy {
+    len_range: RangeInclusive<usize>,
+}
+
+impl NormalizationStrategy {
+    pub fn new<H: SizeHint>(len_hint: H) -> Self {
+        Self {
+            len_range: len_hint.to_inclusive(),
+        }
+    }
+}
+
+impl Default for NormalizationStrategy {
+    fn default() -> Self {
+        Self::new(0usize..=8usize)
+    }
+}
+
+impl Strategy for NormalizationStrategy {
+    type Value = (String, String);
+    type Tree = FragmentValueTree<(String, String)>;
+
+    fn new_tree<R: RngCore + CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        fragments(
+            generator,
+            "unicode normalization",
+            NORMALIZATION.len(),
+            &self.len_range,
+            render_normalization,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+    use crate::{
+        assert_shrinks_to,
+        strategy_test::check_shrink_invariants_with,
+    };
+
+    fn is_plain_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+            && !is_reserved(name)
+    }
+
+    /// Undo [`quote_literal`], or `None` when `quoted` is not a single
+    /// well-formed literal.
+    fn unquote_literal(quoted: &str) -> Option<String> {
+        let inner = quoted.strip_prefix('\'')?.strip_suffix('\'')?;
+        let mut text = String::new();
+        let mut chars = inner.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\'' && chars.next() != Some('\'') {
+                return None;
+            }
+            text.push(ch);
+        }
+        Some(text)
+    }
+
+    /// Rewrite `nfc` fragment by fragment into form D, matching the longest
+    /// fragment first.
+    fn decompose(mut nfc: &str) -> Option<String> {
+        let mut nfd = String::new();
+        while !nfc.is_empty() {
+            let (composed, decomposed) = NORMALIZATION
+                .iter()
+                .filter(|(composed, _)| nfc.starts_with(composed))
+                .max_by_key(|(composed, _)| composed.len())?;
+            nfd.push_str(decomposed);
+            nfc = &nfc[composed.len()..];
+        }
+        Some(nfd)
+    }
+
+    #[test]
+    fn identifiers_stay_valid_unquoted() {
+        check_shrink_invariants_with(SqlStrategy::identifier(), 64, |name| {
+            is_plain_identifier(name)
+        });
+        assert!(!is_plain_identifier("Select"));
+        assert!(!is_plain_identifier("1a"));
+    }
+
+    #[test]
+    fn quoted_identifiers_shrink_to_the_fragment_that_matters() {
+        assert_shrinks_to!(
+            SqlStrategy::quoted_identifier(),
+            |name: &String| !name.contains('"'),
+            "\"".to_string()
+        );
+    }
+
+    #[test]
+    fn literals_survive_quoting() {
+        check_shrink_invariants_with(SqlStrategy::literal(), 64, |text| {
+            unquote_literal(&quote_literal(text)).as_ref() == Some(text)
+        });
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn literals_shrink_to_a_single_quote() {
+        assert_shrinks_to!(
+            SqlStrategy::literal(),
+            |text: &String| !text.contains('\''),
+            "'".to_string()
+        );
+    }
+
+    #[test]
+    fn normalization_forms_stay_equivalent() {
+        check_shrink_invariants_with(
+            NormalizationStrategy::default(),
+            64,
+            |(nfc, nfd)| decompose(nfc).as_ref() == Some(nfd),
+        );
+        assert_shrinks_to!(
+            NormalizationStrategy::default(),
+            |(nfc, nfd): &(String, String)| nfc == nfd,
+            ("é".to_string(), "e\u{301}".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "identifiers cannot be empty")]
+    fn identifiers_need_a_fragment() {
+        let _ = SqlStrategy::identifier().with_len(0usize..=4usize);
+    }
+}
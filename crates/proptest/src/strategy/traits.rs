@@ -1,6 +1,11 @@
 use rand::{CryptoRng, RngCore};
 
-use crate::strategy::runtime::{Generation, Generator};
+use crate::strategy::{
+    BoxedStrategy,
+    Labeled,
+    Provenance,
+    runtime::{Generation, Generator},
+};
 
 /// A shrinkable search space for values produced by a [`Strategy`].
 pub trait ValueTree {
@@ -20,6 +25,25 @@ pub trait ValueTree {
     /// Returns `true` when there are more alternatives remaining from the
     /// current node in the tree.
     fn complicate(&mut self) -> bool;
+
+    /// Consume the tree and return its current value.
+    ///
+    /// The default clones [`current`](Self::current); trees that own their
+    /// current value move it out instead.
+    fn into_current(self) -> Self::Value
+    where
+        Self: Sized,
+        Self::Value: Clone,
+    {
+        self.current().clone()
+    }
+
+    /// Which strategy produced the current value, see [`Provenance`].
+    ///
+    /// `None` by default, for trees that do not track it.
+    fn provenance(&self) -> Option<Provenance> {
+        None
+    }
 }
 
 /// A generator of [`ValueTree`] instances.
@@ -31,4 +55,22 @@ pub trait Strategy {
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree>;
+
+    /// Erase the type of this strategy, see [`BoxedStrategy`].
+    fn boxed(self) -> BoxedStrategy<Self::Value>
+    where
+        Self: Sized + 'static,
+        Self::Tree: 'static,
+        Self::Value: 'static,
+    {
+        BoxedStrategy::new(self)
+    }
+
+    /// Name the values of this strategy in their [`Provenance`].
+    fn labeled(self, label: &'static str) -> Labeled<Self>
+    where
+        Self: Sized,
+    {
+        Labeled::new(self, label)
+    }
 }
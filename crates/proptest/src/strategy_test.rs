@@ -0,0 +1,385 @@
+//! Checks that strategies keep the [`ValueTree`] contract.
+//!
+//! The runner and the shrink driver rely on every value tree behaving the
+//! same way: regenerating a tree from the same seed gives the same value and
+//! the same shrink steps, `simplify` eventually runs out of candidates, and
+//! `complicate` after a rejected simplification goes back to the value before
+//! it. [`check_shrink_invariants`] drives a strategy through those situations
+//! from fixed seeds, at sizes spread over `0..=MAX_SIZE`, and panics with the
+//! offending case and value when a tree breaks one, so strategy authors can
+//! call it from an ordinary `#[test]`.
+//!
+//! [`assert_shrinks_to!`](crate::assert_shrinks_to) checks the quality of
+//! shrinking instead: it generates values until one falsifies a property,
+//! shrinks it and compares the minimal counterexample with the expected one.
+
+use core::fmt::Debug;
+
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::{
+    shrink::shrink,
+    strategy::{
+        Strategy,
+        ValueTree,
+        runtime::{Generation, Generator, MAX_SIZE},
+    },
+};
+
+/// Seed the generator of case `n` is built from, offset by `n`.
+pub const CHECK_SEED: u64 = 0xc4ec;
+
+/// Simplifications a single walk may take before the tree counts as never
+/// running out of candidates.
+pub const MAX_SHRINK_STEPS: usize = 100_000;
+
+/// Cases [`minimal_counterexample`] generates before giving up on finding a
+/// value that falsifies the property.
+pub const MAX_SEARCH_CASES: usize = 1024;
+
+/// Check the value trees of `cases` generations of `strategy`.
+///
+/// Rejected generations are skipped, but at least one case must be
+/// accepted.
+///
+/// # Panics
+///
+/// Panics when a tree differs from a tree regenerated from the same seed,
+/// takes more than [`MAX_SHRINK_STEPS`] simplifications, or ends up anywhere
+/// but its previous value after `complicate` undoes a simplification.
+pub fn check_shrink_invariants<S>(strategy: S, cases: usize)
+where
+    S: Strategy,
+    S::Value: Clone + Debug + PartialEq,
+{
+    check_shrink_invariants_with(strategy, cases, |_| true);
+}
+
+/// Like [`check_shrink_invariants`], also checking that `valid` holds for
+/// every value a tree takes, such as a range or an invariant the strategy
+/// promises to keep while shrinking.
+pub fn check_shrink_invariants_with<S, F>(
+    mut strategy: S,
+    cases: usize,
+    valid: F,
+) where
+    S: Strategy,
+    S::Value: Clone + Debug + PartialEq,
+    F: Fn(&S::Value) -> bool,
+{
+    let mut accepted = 0;
+
+    for case in 0..cases {
+        let size = if cases > 1 {
+            case * MAX_SIZE / (cases - 1)
+        } else {
+            MAX_SIZE
+        };
+        let mut generate = || {
+            let rng =
+                StdRng::seed_from_u64(CHECK_SEED.wrapping_add(case as u64));
+            let mut generator = Generator::build(rng).with_size(size);
+            strategy.new_tree(&mut generator)
+        };
+
+        let Generation::Accepted {
+            value: mut tree, ..
+        } = generate()
+        else {
+            continue;
+        };
+        let mut twin = generate().take();
+        let mut rejecting = generate().take();
+        accepted += 1;
+
+        let check = Check {
+            case,
+            size,
+            valid: &valid,
+        };
+        check.keeping_every_step(&mut tree, &mut twin);
+        check.rejecting_every_step(&mut rejecting);
+    }
+
+    assert!(accepted > 0, "the strategy rejected all {cases} cases");
+}
+
+/// Shrink the first value of `strategy` for which `property` does not hold.
+///
+/// Cases are generated from the same fixed seeds as in
+/// [`check_shrink_invariants`], at sizes growing to `MAX_SIZE` over
+/// [`MAX_SEARCH_CASES`] cases, and the first falsifying one is shrunk with
+/// [`shrink`]. Returns `None` when no case falsifies `property`.
+pub fn minimal_counterexample<S, P>(
+    mut strategy: S,
+    property: P,
+) -> Option<S::Value>
+where
+    S: Strategy,
+    S::Value: Clone,
+    P: Fn(&S::Value) -> bool,
+{
+    (0..MAX_SEARCH_CASES).find_map(|case| {
+        let size = case * MAX_SIZE / (MAX_SEARCH_CASES - 1);
+        let rng = StdRng::seed_from_u64(CHECK_SEED.wrapping_add(case as u64));
+        let mut generator = Generator::build(rng).with_size(size);
+        let Generation::Accepted {
+            value: mut tree, ..
+        } = strategy.new_tree(&mut generator)
+        else {
+            return None;
+        };
+        if property(tree.current()) {
+            return None;
+        }
+        Some(shrink(&mut tree, |value| !property(value)).value)
+    })
+}
+
+/// Assert that shrinking the first counterexample `strategy` generates for a
+/// property ends at `expected`.
+///
+/// The property is a closure taking a reference to a value and returning
+/// whether it holds. Counterexamples are found and shrunk by
+/// [`minimal_counterexample`](crate::strategy_test::minimal_counterexample),
+/// from fixed seeds, so the assertion gives the same result on every run:
+///
+/// ```
+/// use estoa_proptest::{
+///     assert_shrinks_to,
+///     strategy::{AnyU8, VecStrategy},
+/// };
+///
+/// assert_shrinks_to!(
+///     VecStrategy::new(AnyU8::default(), 0usize..=16usize),
+///     |values: &Vec<u8>| values.iter().all(|value| *value == 0),
+///     vec![1],
+/// );
+/// ```
+///
+/// # Panics
+///
+/// Panics when the minimal counterexample differs from `expected`, or when no
+/// generated value falsifies the property.
+#[macro_export]
+macro_rules! assert_shrinks_to {
+    ($strategy:expr, $property:expr, $expected:expr $(,)?) => {
+        match $crate::strategy_test::minimal_counterexample(
+            $strategy, $property,
+        ) {
+            ::core::option::Option::Some(minimal) => {
+                ::core::assert_eq!(
+                    minimal,
+                    $expected,
+                    "the counterexample did not shrink to the expected value",
+                )
+            }
+            ::core::option::Option::None => ::core::panic!(
+                "no generated value falsified the property in {} cases",
+                $crate::strategy_test::MAX_SEARCH_CASES,
+            ),
+        }
+    };
+}
+
+struct Check<'a, F> {
+    case: usize,
+    size: usize,
+    valid: &'a F,
+}
+
+impl<F> Check<'_, F> {
+    fn fail<V: Debug>(&self, problem: &str, value: &V) -> ! {
+        panic!(
+            "case {} (size {}): {problem}, at value {value:?}",
+            self.case, self.size,
+        );
+    }
+
+    fn check_valid<T>(&self, tree: &T)
+    where
+        T: ValueTree,
+        T::Value: Debug,
+        F: Fn(&T::Value) -> bool,
+    {
+        if !(self.valid)(tree.current()) {
+            self.fail(
+                "the value breaks the strategy's invariant",
+                tree.current(),
+            );
+        }
+    }
+
+    /// Simplify `tree` until it runs out of candidates, as when every
+    /// simplification keeps the property failing, stepping `twin` alongside
+    /// it.
+    fn keeping_every_step<T>(&self, tree: &mut T, twin: &mut T)
+    where
+        T: ValueTree,
+        T::Value: Debug + PartialEq,
+        F: Fn(&T::Value) -> bool,
+    {
+        for _ in 0..=MAX_SHRINK_STEPS {
+            self.check_valid(tree);
+            if tree.current() != twin.current() {
+                self.fail(
+                    "a tree regenerated from the same seed holds a different value",
+                    tree.current(),
+                );
+            }
+
+            let simplified = tree.simplify();
+            if simplified != twin.simplify() {
+                self.fail(
+                    "a tree regenerated from the same seed shrinks differently",
+                    tree.current(),
+                );
+            }
+            if !simplified {
+                return;
+            }
+        }
+
+        self.fail("simplify never ran out of candidates", tree.current());
+    }
+
+    /// Undo every simplification of `tree` with `complicate`, as when every
+    /// simplification makes the property pass.
+    fn rejecting_every_step<T>(&self, tree: &mut T)
+    where
+        T: ValueTree,
+        T::Value: Clone + Debug + PartialEq,
+        F: Fn(&T::Value) -> bool,
+    {
+        for _ in 0..=MAX_SHRINK_STEPS {
+            self.check_valid(tree);
+            let before = tree.current().clone();
+            if !tree.simplify() {
+                return;
+            }
+
+            self.check_valid(tree);
+            let more = tree.complicate();
+            if *tree.current() != before {
+                self.fail(
+                    "complicate did not restore the value before simplify",
+                    tree.current(),
+                );
+            }
+            if !more {
+                return;
+            }
+        }
+
+        self.fail("simplify never ran out of candidates", tree.current());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::strategy::{
+        AnyI32,
+        AnyU8,
+        VecStrategy,
+        graphs::GraphStrategy,
+        vec_sorted,
+    };
+
+    #[test]
+    fn builtin_strategies_keep_the_contract() {
+        check_shrink_invariants(AnyI32::new(-1000..=1000), 64);
+        check_shrink_invariants(
+            VecStrategy::new(AnyU8::default(), 0usize..=16usize),
+            64,
+        );
+        check_shrink_invariants(GraphStrategy::dag(0usize..=8usize), 64);
+        check_shrink_invariants(
+            vec_sorted(AnyU8::default(), 0usize..=16usize),
+            64,
+        );
+    }
+
+    #[test]
+    fn valid_is_checked_on_every_value() {
+        check_shrink_invariants_with(AnyI32::new(5..=50), 64, |value| {
+            (5..=50).contains(value)
+        });
+    }
+
+    /// Counts down by one, but `complicate` forgets the value it came from.
+    struct Forgetful(u8);
+
+    impl ValueTree for Forgetful {
+        type Value = u8;
+
+        fn current(&self) -> &u8 {
+            &self.0
+        }
+
+        fn simplify(&mut self) -> bool {
+            if self.0 == 0 {
+                return false;
+            }
+            self.0 -= 1;
+            true
+        }
+
+        fn complicate(&mut self) -> bool {
+            false
+        }
+    }
+
+    struct ForgetfulStrategy;
+
+    impl Strategy for ForgetfulStrategy {
+        type Value = u8;
+        type Tree = Forgetful;
+
+        fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+            &mut self,
+            generator: &mut Generator<R>,
+        ) -> Generation<Self::Tree> {
+            generator.accept(Forgetful(10))
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "complicate did not restore")]
+    fn complicate_that_does_not_restore_fails() {
+        check_shrink_invariants(ForgetfulStrategy, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "breaks the strategy's invariant")]
+    fn values_outside_the_invariant_fail() {
+        check_shrink_invariants_with(
+            VecStrategy::new(AnyU8::default(), 1usize..=8usize),
+            16,
+            |values: &Vec<u8>| values.len() > 1,
+        );
+    }
+
+    #[test]
+    fn counterexamples_shrink_to_the_boundary() {
+        assert_shrinks_to!(AnyI32::new(-1000..=1000), |value| *value <= 0, 1);
+        assert_shrinks_to!(
+            VecStrategy::new(AnyU8::default(), 0usize..=16usize),
+            |values: &Vec<u8>| values.len() < 3,
+            vec![0, 0, 0],
+        );
+    }
+
+    #[test]
+    fn properties_that_always_hold_have_no_counterexample() {
+        assert_eq!(minimal_counterexample(AnyU8::default(), |_| true), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "did not shrink to the expected value")]
+    fn a_different_minimal_value_fails() {
+        assert_shrinks_to!(AnyU8::default(), |value| *value < 10, 11);
+    }
+}
@@ -2,18 +2,31 @@
     clippy::type_complexity,
     unused_macro_rules,
     clippy::absurd_extreme_comparisons,
-    clippy::too_many_arguments
+    clippy::too_many_arguments,
+    clippy::boxed_local
 )]
 
 use std::{
     collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
+    hash::{BuildHasherDefault, DefaultHasher},
     hint::black_box,
     rc::Rc,
-    sync::Arc,
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
-use estoa_proptest::{Arbitrary, proptest, random};
-use rand::{CryptoRng, RngCore};
+use estoa_proptest::{
+    Arbitrary,
+    Generator,
+    MAX_SIZE,
+    proptest,
+    random,
+    runner::{Config, run_cases},
+};
+use rand::{CryptoRng, RngCore, SeedableRng, rngs::StdRng};
 
 #[allow(unused)]
 struct User {
@@ -72,6 +85,9 @@ fn test_generate_generic_types() {
     let _: HashSet<u32> = random().take();
     let _: BTreeSet<i64> = random().take();
     let _: HashMap<u8, u16> = random().take();
+    let _: HashMap<u8, u16, BuildHasherDefault<DefaultHasher>> =
+        random().take();
+    let _: HashSet<u32, BuildHasherDefault<DefaultHasher>> = random().take();
     let _: BTreeMap<u32, i32> = random().take();
     let _: [u8; 8] = random().take();
     let _: (u8, String) = random().take();
@@ -128,3 +144,142 @@ fn test_arbitrary_generation_with_macro(
 
     assert!(string_value.capacity() >= string_value.len());
 }
+
+fn draw_chars(size: usize) -> Vec<char> {
+    let rng = StdRng::seed_from_u64(3);
+    let mut generator = Generator::build(rng).with_size(size);
+    (0..2_000)
+        .map(|_| char::generate(&mut generator).take())
+        .collect()
+}
+
+#[test]
+fn chars_stay_ascii_at_size_zero() {
+    assert!(draw_chars(0).iter().all(char::is_ascii));
+}
+
+#[test]
+fn chars_cover_every_tier_at_full_size() {
+    let chars = draw_chars(MAX_SIZE);
+    let in_tier = |range: std::ops::RangeInclusive<u32>| {
+        chars
+            .iter()
+            .filter(|&&ch| range.contains(&(ch as u32)))
+            .count()
+    };
+
+    assert!(in_tier(0..=0x7F) > chars.len() / 2);
+    assert!(in_tier(0x80..=0xFF) > 0);
+    assert!(in_tier(0x100..=0xFFFF) > 0);
+    assert!(in_tier(0x1_0000..=0x10_FFFF) > 0);
+}
+
+type Nested = Option<Option<Option<Option<Option<u8>>>>>;
+
+fn nesting(value: &Nested) -> usize {
+    match value {
+        None => 0,
+        Some(None) => 1,
+        Some(Some(None)) => 2,
+        Some(Some(Some(None))) => 3,
+        Some(Some(Some(Some(None)))) => 4,
+        Some(Some(Some(Some(Some(_))))) => 5,
+    }
+}
+
+#[test]
+fn nested_options_stop_at_the_depth_the_size_allows() {
+    let mut generator =
+        Generator::build(StdRng::seed_from_u64(7)).with_size(10);
+    for _ in 0..256 {
+        let value: Nested = Arbitrary::generate(&mut generator).take();
+        assert!(nesting(&value) <= 1, "{value:?}");
+    }
+}
+
+#[test]
+fn nested_options_get_rarer_with_depth() {
+    let mut generator =
+        Generator::build(StdRng::seed_from_u64(8)).with_size(MAX_SIZE);
+    let mut counts = [0usize; 6];
+    for _ in 0..2_000 {
+        let value: Nested = Arbitrary::generate(&mut generator).take();
+        counts[nesting(&value)] += 1;
+    }
+
+    assert!((800..=1_200).contains(&counts[0]), "{counts:?}");
+    assert!(counts[1] > counts[2] && counts[2] > counts[3], "{counts:?}");
+    // Fair coins at every level would nest five deep one time in 32.
+    assert!(counts[5] < 2_000 / 32 / 2, "{counts:?}");
+}
+
+#[test]
+fn weak_references_are_dangling_or_upgrade_during_the_case() {
+    let upgraded = AtomicUsize::new(0);
+    let dangling = AtomicUsize::new(0);
+    let weaks = Mutex::new(Vec::new());
+    run_cases(&Config::new(64), 5, &|generator| {
+        let weak: std::rc::Weak<u8> = Arbitrary::generate(generator).take();
+        let counter = match weak.upgrade() {
+            Some(_) => &upgraded,
+            None => &dangling,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        let weak: std::sync::Weak<u8> = Arbitrary::generate(generator).take();
+        weaks.lock().unwrap().push(weak);
+    });
+    assert!(upgraded.load(Ordering::Relaxed) > 0);
+    assert!(dangling.load(Ordering::Relaxed) > 0);
+
+    // Nothing outlives its case.
+    let weaks = weaks.into_inner().unwrap();
+    assert!(weaks.iter().all(|weak| weak.upgrade().is_none()));
+}
+
+#[test]
+fn weak_references_dangle_outside_a_run() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let weaks: Vec<std::rc::Weak<u8>> =
+        (0..64).map(|_| Arbitrary::arbitrary(&mut rng)).collect();
+    assert!(weaks.iter().all(|weak| weak.upgrade().is_none()));
+}
+
+#[test]
+fn once_cells_are_empty_or_set() {
+    let mut generator = Generator::build(StdRng::seed_from_u64(6));
+    let cells: Vec<std::cell::OnceCell<u8>> = (0..64)
+        .map(|_| Arbitrary::generate(&mut generator).take())
+        .collect();
+    assert!(cells.iter().any(|cell| cell.get().is_some()));
+    assert!(cells.iter().any(|cell| cell.get().is_none()));
+
+    let lock: std::sync::OnceLock<u8> = random().take();
+    black_box(lock.get());
+}
+
+#[proptest(cases = 64)]
+fn string_like_types_are_arbitrary(
+    boxed: Box<str>,
+    shared: Rc<str>,
+    atomic: Arc<str>,
+    cow: std::borrow::Cow<'static, str>,
+    bytes: Box<[u8]>,
+    letters: [char; 4],
+) {
+    assert!(boxed.chars().count() <= 128);
+    assert!(shared.chars().count() <= 128);
+    assert!(atomic.chars().count() <= 128);
+    assert!(matches!(cow, std::borrow::Cow::Owned(_)));
+    assert!(bytes.len() <= 32);
+    black_box(letters);
+}
+
+#[proptest(cases = 256)]
+fn ranges_are_never_reversed(
+    range: std::ops::Range<i16>,
+    inclusive: std::ops::RangeInclusive<u8>,
+) {
+    assert!(range.start <= range.end);
+    assert!(inclusive.start() <= inclusive.end());
+}
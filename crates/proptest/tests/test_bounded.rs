@@ -0,0 +1,43 @@
+use estoa_proptest::{
+    Arbitrary,
+    proptest,
+    strategy::{Bounded, runtime::Generator},
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+#[proptest]
+fn test_bounded_arguments_stay_within_their_bounds(
+    percent: Bounded<u8, 0, 100>,
+    offset: Bounded<i64, -3, 3>,
+) {
+    assert!(*percent <= 100);
+    assert!((-3..=3).contains(&offset.get()));
+}
+
+#[test]
+fn arbitrary_bounded_values_cover_their_bounds() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut seen = [false; 5];
+
+    for _ in 0..256 {
+        let value = Bounded::<usize, 10, 14>::arbitrary(&mut rng);
+        seen[value.into_inner() - 10] = true;
+    }
+
+    assert!(seen.iter().all(|seen| *seen));
+}
+
+#[test]
+fn generated_bounded_values_respect_bounds_at_every_size() {
+    let mut generator = Generator::build(StdRng::seed_from_u64(3));
+
+    for size in [0, 1, 10, 50, 100] {
+        generator = generator.with_size(size);
+        for _ in 0..32 {
+            let value = Bounded::<u128, 1000, 2000>::generate(&mut generator)
+                .take()
+                .get();
+            assert!((1000..=2000).contains(&value));
+        }
+    }
+}
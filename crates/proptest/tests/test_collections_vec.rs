@@ -1,4 +1,15 @@
-use estoa_proptest::strategy::{ValueTree, VecValueTree};
+use estoa_proptest::strategy::{
+    AnyI32,
+    AnyU8,
+    Strategy,
+    ValueTree,
+    VecValueTree,
+    runtime::Generator,
+    vec_sorted,
+    vec_unique,
+    zip,
+};
+use rand::{SeedableRng, rngs::StdRng};
 
 #[derive(Clone)]
 struct IntTree {
@@ -67,3 +78,62 @@ fn vec_complicate_can_restore_length() {
         "complicate should not decrease length further"
     );
 }
+
+#[test]
+fn vec_sorted_stays_sorted_through_shrinking() {
+    let mut strategy = vec_sorted(AnyU8::default(), 4usize..=16usize);
+    let mut generator = Generator::build(StdRng::seed_from_u64(5));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        loop {
+            let values = tree.current();
+            assert!(values.len() >= 4);
+            assert!(values.is_sorted());
+            if !tree.simplify() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn vec_unique_never_shrinks_into_duplicates() {
+    let mut strategy = vec_unique(AnyU8::default(), 4usize..=16usize);
+    let mut generator = Generator::build(StdRng::seed_from_u64(9));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        loop {
+            let values = tree.current();
+            assert!(values.len() >= 4);
+            for (index, value) in values.iter().enumerate() {
+                assert!(!values[index + 1..].contains(value));
+            }
+            if !tree.simplify() {
+                break;
+            }
+        }
+        assert_eq!(tree.current().len(), 4);
+    }
+}
+
+#[test]
+fn zip_keeps_both_vectors_the_same_length_through_shrinking() {
+    let mut strategy =
+        zip(AnyU8::default(), AnyI32::new(-50..=50), 2usize..=16usize);
+    let mut generator = Generator::build(StdRng::seed_from_u64(13));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        loop {
+            let (left, right) = tree.current();
+            assert_eq!(left.len(), right.len());
+            assert!(left.len() >= 2);
+            if !tree.simplify() {
+                break;
+            }
+        }
+        assert_eq!(tree.current(), &(vec![0, 0], vec![0, 0]));
+    }
+}
@@ -0,0 +1,81 @@
+use estoa_proptest::{
+    compose_strategy,
+    proptest,
+    strategy::{
+        AnyI32,
+        AnyU8,
+        Strategy,
+        ValueTree,
+        VecStrategy,
+        runtime::Generator,
+    },
+    strategy_test::check_shrink_invariants,
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+compose_strategy! {
+    /// Points inside the square from the origin to `(max, max)`.
+    fn point(max: i32)(
+        x in AnyI32::new(0..=max),
+        y in AnyI32::new(0..=max),
+    ) -> Point {
+        Point { x, y }
+    }
+}
+
+compose_strategy! {
+    pub fn segment()(from in point(100), to in point(100)) -> (Point, Point) {
+        (from, to)
+    }
+}
+
+compose_strategy! {
+    fn offset_bytes(offset: u8)(
+        bytes in VecStrategy::new(AnyU8::new(0..=100), 0usize..=8usize),
+    ) -> Vec<u8> {
+        bytes.into_iter().map(|byte| byte + offset).collect()
+    }
+}
+
+#[test]
+fn composed_points_shrink_to_the_origin() {
+    let mut strategy = point(100);
+    let mut generator = Generator::build(StdRng::seed_from_u64(11));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        assert!((0..=100).contains(&tree.current().x));
+        assert!((0..=100).contains(&tree.current().y));
+        while tree.simplify() {}
+        assert_eq!(*tree.current(), Point { x: 0, y: 0 });
+    }
+}
+
+#[test]
+fn composed_strategies_nest() {
+    check_shrink_invariants(segment(), 64);
+}
+
+#[test]
+fn the_body_sees_the_function_parameters() {
+    let mut strategy = offset_bytes(7);
+    let mut generator = Generator::build(StdRng::seed_from_u64(13));
+
+    for _ in 0..32 {
+        let tree = strategy.new_tree(&mut generator).take();
+        assert!(tree.current().iter().all(|byte| (7..=107).contains(byte)));
+    }
+}
+
+#[proptest]
+fn composed_strategies_drive_proptest_arguments(
+    #[strategy(point(10))] point: Point,
+) {
+    assert!(point.x <= 10 && point.y <= 10);
+}
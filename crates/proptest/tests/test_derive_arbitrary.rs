@@ -0,0 +1,389 @@
+use std::collections::BTreeSet;
+
+use estoa_proptest::{
+    Arbitrary,
+    ConstantValueTree,
+    CountingRng,
+    Generation,
+    Generator,
+    RejectReason,
+    proptest,
+    random,
+    strategy::{AnyI32, AnyU8, Strategy, ValueTree, VecStrategy},
+};
+
+/// A member that has no `Arbitrary` impl, like a channel or file handle.
+#[derive(Debug, Default, PartialEq)]
+struct Handle {
+    id: u32,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Account {
+    name: String,
+    balance: i64,
+    #[arbitrary(skip)]
+    handle: Handle,
+    #[arbitrary(default)]
+    tags: Vec<String>,
+    #[arbitrary(value = Handle { id: 7 })]
+    owner: Handle,
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Shape {
+    Empty,
+    Circle(u8),
+    Rect { width: u8, height: u8 },
+}
+
+#[derive(Arbitrary, Debug)]
+enum Response {
+    #[weight(8)]
+    Ok,
+    Timeout,
+    #[weight(0)]
+    #[allow(dead_code)]
+    Unreachable,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Expr {
+    Literal(i8),
+    Add(Box<Expr>, Box<Expr>),
+    #[weight(2)]
+    Negate(Box<Self>),
+    Let {
+        value: Box<Expr>,
+        body: Option<Box<Expr>>,
+    },
+}
+
+impl Expr {
+    fn depth(&self) -> usize {
+        match self {
+            Expr::Literal(_) => 0,
+            Expr::Add(left, right) => 1 + left.depth().max(right.depth()),
+            Expr::Negate(inner) => 1 + inner.depth(),
+            Expr::Let { value, body } => {
+                1 + value.depth().max(body.as_ref().map_or(0, |b| b.depth()))
+            }
+        }
+    }
+
+    fn eval(&self) -> i64 {
+        match self {
+            Expr::Literal(value) => (*value).into(),
+            Expr::Add(left, right) => left.eval().wrapping_add(right.eval()),
+            Expr::Negate(inner) => inner.eval().wrapping_neg(),
+            Expr::Let { value, body } => body
+                .as_ref()
+                .map_or_else(|| value.eval(), |body| body.eval()),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Tree {
+    Leaf,
+    Node(Vec<Tree>),
+}
+
+impl Tree {
+    fn depth(&self) -> usize {
+        match self {
+            Tree::Leaf => 0,
+            Tree::Node(children) => {
+                1 + children.iter().map(Tree::depth).max().unwrap_or(0)
+            }
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct Rating {
+    #[strategy(AnyU8::new(1..=10))]
+    stars: u8,
+    #[strategy(VecStrategy::new(AnyI32::new(-5..=5), 1usize..=4usize))]
+    deltas: Vec<i32>,
+    comment: Option<String>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Review {
+    Rated(#[strategy(AnyU8::new(1..=10))] u8),
+    Skipped,
+}
+
+/// A strategy that rejects every value it generates.
+struct Unlucky;
+
+impl Strategy for Unlucky {
+    type Value = u8;
+    type Tree = ConstantValueTree<u8>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        generator.reject(ConstantValueTree::new(0))
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+struct Rejected {
+    #[strategy(Unlucky)]
+    value: u8,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Pair<T>(T, T);
+
+/// A linked node whose back reference may be dangling, the shape of a
+/// parent-pointer tree or doubly linked list.
+#[derive(Arbitrary, Debug)]
+struct Node {
+    value: u8,
+    next: Option<Box<Node>>,
+    parent: std::rc::Weak<u8>,
+}
+
+#[derive(Arbitrary, Debug, PartialEq)]
+struct Meters;
+
+/// Marker fields carry no data but must not stop the derive.
+#[derive(Arbitrary, Debug)]
+struct Tagged {
+    value: u8,
+    unit: Meters,
+    handle: std::marker::PhantomData<Handle>,
+    pinned: std::marker::PhantomPinned,
+    parsed: Result<u8, std::convert::Infallible>,
+}
+
+impl Node {
+    fn len(&self) -> usize {
+        1 + self.next.as_ref().map_or(0, |next| next.len())
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+#[arbitrary(validate = Self::is_ordered)]
+struct Span {
+    start: u8,
+    end: u8,
+}
+
+impl Span {
+    fn is_ordered(&self) -> bool {
+        self.start <= self.end
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+#[arbitrary(validate = Self::is_positive)]
+enum Reading {
+    Celsius(i8),
+    Kelvin(u8),
+}
+
+impl Reading {
+    fn is_positive(&self) -> bool {
+        match self {
+            Reading::Celsius(value) => *value > 0,
+            Reading::Kelvin(value) => *value > 0,
+        }
+    }
+}
+
+/// A validated type whose first field shrinks past the second.
+#[derive(Arbitrary, Debug)]
+#[arbitrary(validate = Self::is_ordered)]
+struct Descending {
+    high: u8,
+    low: u8,
+}
+
+impl Descending {
+    fn is_ordered(&self) -> bool {
+        self.high >= self.low
+    }
+}
+
+/// An invariant no value meets, so every draw fails validation.
+#[derive(Arbitrary, Debug)]
+#[arbitrary(validate = |_: &Impossible| false, retries = 3)]
+struct Impossible(u8);
+
+#[test]
+fn test_derive_fills_fixed_fields() {
+    for _ in 0..64 {
+        let account = random::<Account>().take();
+        assert_eq!(account.handle, Handle::default());
+        assert!(account.tags.is_empty());
+        assert_eq!(account.owner, Handle { id: 7 });
+    }
+}
+
+#[test]
+fn test_derive_reaches_every_variant() {
+    let mut seen = BTreeSet::new();
+    for _ in 0..256 {
+        seen.insert(match Shape::random().take() {
+            Shape::Empty => 0,
+            Shape::Circle(_) => 1,
+            Shape::Rect { .. } => 2,
+        });
+    }
+    assert_eq!(seen.len(), 3);
+}
+
+#[test]
+fn test_derive_picks_variants_by_weight() {
+    let mut ok = 0;
+    for _ in 0..900 {
+        match Response::random().take() {
+            Response::Ok => ok += 1,
+            Response::Timeout => {}
+            Response::Unreachable => panic!("weight 0 variant was generated"),
+        }
+    }
+    assert!((700..=900).contains(&ok), "{ok} of 900 were Ok");
+}
+
+#[test]
+fn test_derive_recursive_enums_stop_at_the_recursion_limit() {
+    let mut generator = Generator::build(rand::rng()).with_limit(3);
+    for _ in 0..256 {
+        let expr = Expr::generate(&mut generator).take();
+        assert!(expr.depth() <= 3, "depth {} over the limit", expr.depth());
+        assert_eq!(generator.depth(), 0);
+    }
+}
+
+#[test]
+fn test_derive_recursive_enums_scale_depth_with_size() {
+    let mut generator = Generator::build(rand::rng()).with_size(0);
+    for _ in 0..64 {
+        assert_eq!(Expr::generate(&mut generator).take().depth(), 0);
+    }
+
+    let mut deepest = 0;
+    for _ in 0..256 {
+        deepest = deepest.max(Expr::random().take().depth());
+    }
+    assert!((1..=10).contains(&deepest), "deepest expression {deepest}");
+}
+
+#[proptest(cases = 256)]
+fn test_derive_generates_recursive_arguments(expr: Expr) {
+    let _ = expr.eval();
+}
+
+#[test]
+fn test_derive_recurses_through_collections_of_itself() {
+    let mut generator = Generator::build(rand::rng()).with_limit(3);
+    for _ in 0..256 {
+        let tree = Tree::generate(&mut generator).take();
+        assert!(tree.depth() <= 3, "depth {} over the limit", tree.depth());
+        assert_eq!(generator.depth(), 0);
+    }
+}
+
+#[proptest(cases = 256)]
+fn test_derive_draws_fields_from_strategies(rating: Rating, review: Review) {
+    assert!((1..=10).contains(&rating.stars));
+    assert!((1..=4).contains(&rating.deltas.len()));
+    assert!(rating.deltas.iter().all(|delta| (-5..=5).contains(delta)));
+    let _ = rating.comment;
+    if let Review::Rated(stars) = review {
+        assert!((1..=10).contains(&stars));
+    }
+}
+
+#[should_panic(expected = "stars = 2")]
+#[proptest(cases = 64)]
+fn test_derive_shrinks_strategy_fields(rating: Rating) {
+    assert!(rating.stars == 1, "stars = {}", rating.stars);
+}
+
+#[should_panic(expected = "shape = Circle(0)")]
+#[proptest(cases = 64)]
+fn test_derive_shrinks_enums_towards_earlier_variants(shape: Shape) {
+    assert!(shape == Shape::Empty, "shape = {shape:?}");
+}
+
+#[test]
+fn test_derive_shrinks_enums_to_their_first_variant() {
+    let mut generator = Generator::build(rand::rng());
+    for _ in 0..64 {
+        let mut tree = Shape::generate_tree(&mut generator).take();
+        while tree.simplify() {}
+        assert_eq!(*tree.current(), Shape::Empty);
+    }
+}
+
+#[test]
+fn test_derive_rejects_values_its_strategies_reject() {
+    let mut generator = Generator::build(rand::rng());
+    match Rejected::generate(&mut generator) {
+        Generation::Rejected { value, .. } => assert_eq!(value.value, 0),
+        Generation::Accepted { .. } => panic!("rejection was dropped"),
+    }
+}
+
+#[proptest(cases = 64)]
+fn test_derive_generates_generic_arguments(
+    pair: Pair<Shape>,
+    account: Account,
+) {
+    let Pair(first, second) = pair;
+    assert_eq!(first.max(second), second.max(first));
+    assert_eq!(account.owner.id, 7);
+    let _ = (account.name, account.balance);
+}
+
+#[proptest(cases = 256)]
+fn test_derive_generates_linked_structures(node: Node) {
+    assert!(node.len() >= 1);
+    let _ = (node.value, node.parent.upgrade());
+}
+
+#[proptest(cases = 64)]
+fn test_derive_fills_marker_fields(tagged: Tagged) {
+    assert_eq!(tagged.unit, Meters);
+    let Ok(parsed) = tagged.parsed;
+    let _ = (tagged.value, parsed, tagged.handle, tagged.pinned);
+}
+
+#[proptest(cases = 256)]
+fn test_derive_validates_generated_values(span: Span, reading: Reading) {
+    assert!(span.is_ordered());
+    assert!(reading.is_positive());
+}
+
+#[test]
+fn test_derive_shrinks_only_to_valid_values() {
+    let mut generator = Generator::build(rand::rng());
+    for _ in 0..64 {
+        let mut tree = Descending::generate_tree(&mut generator).take();
+        while tree.simplify() {
+            let value = tree.current();
+            assert!(value.is_ordered(), "{value:?} is not ordered");
+        }
+        assert_eq!(tree.current().low, 0);
+    }
+}
+
+#[test]
+fn test_derive_rejects_values_that_keep_failing_validation() {
+    let mut generator = Generator::build(CountingRng::new(rand::rng()));
+    match Impossible::generate(&mut generator) {
+        Generation::Rejected { reason, value, .. } => {
+            assert_eq!(reason, Some(RejectReason::Filter));
+            let _ = value.0;
+        }
+        Generation::Accepted { .. } => panic!("invalid value was accepted"),
+    }
+    assert_eq!(generator.stats().rejected, 1);
+}
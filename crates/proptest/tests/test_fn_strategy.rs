@@ -0,0 +1,64 @@
+use estoa_proptest::{
+    Arbitrary,
+    proptest,
+    strategy::{
+        Strategy,
+        ValueTree,
+        from_fn,
+        runtime::{Generation, Generator},
+    },
+};
+use rand::Rng;
+
+#[test]
+fn fn_strategy_wraps_closure_output() {
+    let mut strategy = from_fn(|generator| {
+        let value = generator.rng.random_range(10u8..20);
+        generator.accept(value)
+    });
+    let mut generator = Generator::build(rand::rng());
+
+    let mut tree = match strategy.new_tree(&mut generator) {
+        Generation::Accepted { value, .. } => value,
+        Generation::Rejected { .. } => panic!("strategy rejected"),
+    };
+    assert!((10..20).contains(tree.current()));
+    assert!(!tree.simplify());
+}
+
+#[test]
+fn fn_strategy_shares_generator_state() {
+    let mut strategy = from_fn(|generator| {
+        generator.advance_iteration();
+        generator.recurse(|inner| inner.reject(inner.depth()))
+    });
+    let mut generator = Generator::build(rand::rng());
+
+    match strategy.new_tree(&mut generator) {
+        Generation::Rejected {
+            iteration,
+            depth,
+            reason,
+            value,
+        } => {
+            assert_eq!(iteration, 1);
+            assert_eq!(depth, 1);
+            assert_eq!(reason, None);
+            assert_eq!(*value.current(), 1);
+        }
+        Generation::Accepted { .. } => panic!("strategy should reject"),
+    }
+    assert_eq!(generator.iteration(), 1);
+    assert_eq!(generator.depth(), 0);
+}
+
+#[proptest(cases = 32)]
+fn fn_strategy_plugs_into_the_macro(
+    #[strategy(from_fn(|generator| {
+        let value = u32::arbitrary(&mut generator.rng);
+        generator.accept(value % 7)
+    }))]
+    value: u32,
+) {
+    assert!(value < 7);
+}
@@ -0,0 +1,126 @@
+use std::collections::BTreeSet;
+
+use estoa_proptest::strategy::{
+    Strategy,
+    ValueTree,
+    graphs::{Graph, GraphStrategy},
+    runtime::Generator,
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+fn is_connected(graph: &Graph) -> bool {
+    if graph.node_count() == 0 {
+        return true;
+    }
+
+    let mut seen = BTreeSet::from([0]);
+    let mut stack = vec![0];
+    while let Some(node) = stack.pop() {
+        for &(from, to) in graph.edges() {
+            let next = if from == node {
+                to
+            } else if to == node {
+                from
+            } else {
+                continue;
+            };
+            if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    seen.len() == graph.node_count()
+}
+
+fn has_cycle(graph: &Graph) -> bool {
+    let adjacency = graph.adjacency();
+    let mut in_degree = vec![0; graph.node_count()];
+    for &(_, to) in graph.edges() {
+        in_degree[to] += 1;
+    }
+
+    let mut ready: Vec<usize> = (0..graph.node_count())
+        .filter(|&n| in_degree[n] == 0)
+        .collect();
+    let mut visited = 0;
+    while let Some(node) = ready.pop() {
+        visited += 1;
+        for &next in &adjacency[&node] {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.push(next);
+            }
+        }
+    }
+    visited < graph.node_count()
+}
+
+fn assert_class_through_shrinking(
+    mut strategy: GraphStrategy,
+    seed: u64,
+    check: impl Fn(&Graph),
+) {
+    let mut generator = Generator::build(StdRng::seed_from_u64(seed));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        loop {
+            check(tree.current());
+            if !tree.simplify() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn trees_stay_trees_while_shrinking() {
+    assert_class_through_shrinking(
+        GraphStrategy::tree(1usize..=16usize),
+        1,
+        |graph| {
+            assert!(graph.node_count() >= 1);
+            assert_eq!(graph.edges().len(), graph.node_count() - 1);
+            assert!(is_connected(graph));
+        },
+    );
+}
+
+#[test]
+fn dags_stay_acyclic_while_shrinking() {
+    assert_class_through_shrinking(
+        GraphStrategy::dag(0usize..=16usize),
+        2,
+        |graph| {
+            assert!(graph.is_directed());
+            assert!(!has_cycle(graph));
+        },
+    );
+}
+
+#[test]
+fn connected_graphs_respect_their_max_degree_while_shrinking() {
+    assert_class_through_shrinking(
+        GraphStrategy::connected(0usize..=16usize).with_max_degree(3),
+        3,
+        |graph| {
+            assert!(is_connected(graph));
+            for neighbours in graph.adjacency().values() {
+                assert!(neighbours.len() <= 3);
+            }
+        },
+    );
+}
+
+#[test]
+fn connected_graphs_shrink_to_their_minimum_size() {
+    let mut strategy = GraphStrategy::connected(2usize..=16usize);
+    let mut generator = Generator::build(StdRng::seed_from_u64(4));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        while tree.simplify() {}
+        assert_eq!(tree.current().node_count(), 2);
+        assert_eq!(tree.current().edges(), &[(0, 1)]);
+    }
+}
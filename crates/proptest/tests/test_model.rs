@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use estoa_proptest::proptest;
+
+/// Stands in for `loom::model`, running the body once for each of a few
+/// interleavings.
+mod loom {
+    pub fn model<F: Fn() + Sync + Send + 'static>(body: F) {
+        for _ in 0..3 {
+            body();
+        }
+    }
+}
+
+#[proptest(model = loom::model, cases = 16)]
+fn every_execution_gets_fresh_arguments(
+    values: &[u8],
+    #[strategy(estoa_proptest::strategy::AnyU8::default())] byte: &u8,
+    #[fixture(Vec::new())] log: &mut Vec<u8>,
+) {
+    assert!(log.is_empty());
+    log.extend_from_slice(values);
+    log.push(*byte);
+    assert_eq!(log.len(), values.len() + 1);
+}
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[proptest(model = loom::model, cases = 1)]
+#[should_panic(
+    expected = "lost update (in execution 2 of the model, with inputs: value = "
+)]
+fn failures_name_the_execution_and_inputs(value: u8) {
+    let _ = value;
+    if EXECUTIONS.fetch_add(1, Ordering::Relaxed) == 1 {
+        panic!("lost update");
+    }
+}
+
+#[proptest(model = loom::model, examples = [7], cases = 1)]
+fn examples_run_under_the_model(value: u8) {
+    let _ = value;
+}
@@ -0,0 +1,140 @@
+use std::sync::Mutex;
+
+use estoa_proptest::{
+    observer::{CaseEvent, CaseObserver, CaseStatus, RunEvent, ShrinkAttempt},
+    proptest,
+    strategy::{AnyU8, AnyU32, Strategy, VecStrategy},
+};
+
+/// A fixture without a `Debug` impl.
+struct Connection;
+
+struct CheckInputs;
+
+impl CaseObserver for CheckInputs {
+    fn on_case(&self, event: &CaseEvent<'_>) {
+        assert_eq!(event.status, CaseStatus::Passed);
+        let names: Vec<_> =
+            event.inputs.iter().map(|input| input.name).collect();
+        assert_eq!(names, ["value", "name", "connection"]);
+
+        let value = event.inputs[0].value.as_deref().expect("u8 is Debug");
+        assert!(value.parse::<u8>().is_ok());
+        assert!(event.inputs[1].value.as_deref().unwrap().starts_with('"'));
+        assert_eq!(event.inputs[2].value, None);
+    }
+}
+
+fn check_inputs() -> CheckInputs {
+    CheckInputs
+}
+
+#[proptest(cases = 32, observer = check_inputs)]
+fn observers_see_every_argument(
+    value: u8,
+    name: &str,
+    #[fixture(Connection)] connection: Connection,
+) {
+    let _ = (value, name, connection);
+}
+
+struct CheckLocation;
+
+impl CaseObserver for CheckLocation {
+    fn on_case(&self, _event: &CaseEvent<'_>) {}
+
+    fn on_run(&self, event: &RunEvent<'_>) {
+        let test = event.test.expect("#[proptest] names its test");
+        assert_eq!(test.name, "test_observer::runs_name_their_test");
+        assert_eq!(test.file, file!());
+        assert_eq!(test.line, LINE);
+        assert_eq!(event.report.cases_run, 4);
+        assert_eq!(event.failure, None);
+    }
+}
+
+fn check_location() -> CheckLocation {
+    CheckLocation
+}
+
+const LINE: u32 = line!() + 3;
+
+#[proptest(cases = 4, observer = check_location)]
+fn runs_name_their_test(value: u8) {
+    let _ = value;
+}
+
+struct CheckProvenance;
+
+impl CaseObserver for CheckProvenance {
+    fn on_case(&self, event: &CaseEvent<'_>) {
+        let [items, value] = event.inputs else {
+            panic!("expected two inputs, got {:?}", event.inputs);
+        };
+        let provenance = items.provenance.as_ref().expect("vectors track it");
+        assert!(provenance.label.starts_with("items: VecStrategy len="));
+        assert!(
+            provenance
+                .parts
+                .iter()
+                .all(|(_, part)| part.label.starts_with("AnyU8"))
+        );
+        assert_eq!(value.provenance, None);
+    }
+
+    fn on_run(&self, event: &RunEvent<'_>) {
+        let failure = event.failure.expect("the property fails");
+        assert!(failure.contains("\n    items (items: VecStrategy len="));
+        assert!(failure.contains("-> [0] (AnyU8"));
+    }
+}
+
+fn check_provenance() -> CheckProvenance {
+    CheckProvenance
+}
+
+#[proptest(cases = 256, observer = check_provenance)]
+#[should_panic(expected = "found a large byte")]
+fn failures_break_inputs_down_by_strategy(
+    #[strategy(VecStrategy::new(AnyU8::default(), 1usize..=3usize).labeled("items"))]
+    items: Vec<u8>,
+    #[fixture(0)] value: u8,
+) {
+    assert!(items.iter().all(|&item| item < 128), "found a large byte");
+    let _ = value;
+}
+
+#[derive(Default)]
+struct CheckShrinkAttempts {
+    attempts: Mutex<Vec<ShrinkAttempt>>,
+}
+
+impl CaseObserver for CheckShrinkAttempts {
+    fn on_case(&self, _event: &CaseEvent<'_>) {}
+
+    fn on_shrink_attempt(&self, attempt: &ShrinkAttempt) {
+        self.attempts.lock().unwrap().push(*attempt);
+    }
+
+    fn on_run(&self, event: &RunEvent<'_>) {
+        let failure =
+            event.report.failure.as_ref().expect("the property fails");
+        let attempts = self.attempts.lock().unwrap();
+        assert!(!attempts.is_empty());
+        for (index, attempt) in attempts.iter().enumerate() {
+            assert_eq!((attempt.case, attempt.attempt), (failure.case, index));
+        }
+    }
+}
+
+fn check_shrink_attempts() -> CheckShrinkAttempts {
+    CheckShrinkAttempts::default()
+}
+
+#[proptest(cases = 64, observer = check_shrink_attempts)]
+#[should_panic(expected = "value too large")]
+fn observers_see_the_runs_of_the_shrink_phase(
+    #[strategy(AnyU32::new(0..=999))] value: u32,
+) {
+    assert!(value < 100, "value too large");
+}
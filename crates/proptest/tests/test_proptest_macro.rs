@@ -1,12 +1,22 @@
 #![allow(clippy::absurd_extreme_comparisons)]
 
 use std::{
+    collections::{HashMap, HashSet},
     panic::{AssertUnwindSafe, catch_unwind},
-    sync::{Mutex, OnceLock},
+    pin::pin,
+    sync::{
+        Mutex,
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Waker},
 };
 
 use estoa_proptest::{
     Arbitrary,
+    coverage,
+    prop_assert,
+    prop_assert_eq,
     proptest,
     strategy::{
         runtime::{Generation, Generator},
@@ -75,7 +85,7 @@ struct RetryStrategy {
 
 impl Strategy for RetryStrategy {
     type Value = u8;
-    type Tree = StaticTree<u8>;
+    type Tree = ConstantValueTree<u8>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
@@ -83,9 +93,9 @@ impl Strategy for RetryStrategy {
     ) -> Generation<Self::Tree> {
         if !self.rejected {
             self.rejected = true;
-            generator.reject(StaticTree::new(0))
+            generator.reject(ConstantValueTree::new(0))
         } else {
-            generator.accept(StaticTree::new(42))
+            generator.accept(ConstantValueTree::new(42))
         }
     }
 }
@@ -102,7 +112,7 @@ struct NestedVecStrategy;
 
 impl Strategy for NestedVecStrategy {
     type Value = Vec<Vec<u8>>;
-    type Tree = StaticTree<Vec<Vec<u8>>>;
+    type Tree = ConstantValueTree<Vec<Vec<u8>>>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
@@ -117,7 +127,7 @@ impl Strategy for NestedVecStrategy {
                 }
                 values.push(inner);
             }
-            outer.accept(StaticTree::new(values))
+            outer.accept(ConstantValueTree::new(values))
         })
     }
 }
@@ -157,13 +167,13 @@ struct AlwaysReject;
 
 impl Strategy for AlwaysReject {
     type Value = u8;
-    type Tree = StaticTree<u8>;
+    type Tree = ConstantValueTree<u8>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
-        generator.reject(StaticTree::new(0))
+        generator.reject(ConstantValueTree::new(0))
     }
 }
 
@@ -175,6 +185,17 @@ fn test_proptest_respects_rejection_limit_panics(
     unreachable!("strategy should always reject");
 }
 
+#[should_panic(
+    expected = "strategy for `value` (AlwaysReject) rejected value after 3 attempts"
+)]
+#[proptest(rejection_limit = 3)]
+fn test_proptest_rejection_panic_names_the_argument(
+    flag: bool,
+    #[strategy(AlwaysReject)] value: u8,
+) {
+    let _ = (flag, value);
+}
+
 #[test]
 fn test_rejection_limit_panics_after_expected_attempts() {
     let result = catch_unwind(AssertUnwindSafe(|| {
@@ -188,14 +209,14 @@ struct RecursiveOverflow;
 
 impl Strategy for RecursiveOverflow {
     type Value = usize;
-    type Tree = StaticTree<usize>;
+    type Tree = ConstantValueTree<usize>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
         generator: &mut Generator<R>,
     ) -> Generation<Self::Tree> {
         generator.recurse(|outer| {
-            outer.recurse(|inner| inner.accept(StaticTree::new(1usize)))
+            outer.recurse(|inner| inner.accept(ConstantValueTree::new(1usize)))
         })
     }
 }
@@ -215,3 +236,530 @@ fn test_recursion_limit_panics_when_exceeded() {
     }));
     assert!(result.is_err(), "recursion limit did not trigger panic");
 }
+
+#[should_panic(expected = "ESTOA_PROPTEST_SEED")]
+#[proptest(cases = 64, threads = 4)]
+fn test_proptest_threads_report_failing_seed(value: u8) {
+    assert!(value < 128);
+}
+
+#[proptest(time_budget_ms = 50)]
+fn test_proptest_time_budget_bounds_unlimited_runs(_value: u8) {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[proptest(cases = 16)]
+fn test_proptest_accepts_result_bodies(text: String) -> Result<(), String> {
+    if text.chars().count() <= 128 {
+        Ok(())
+    } else {
+        Err(format!("string too long: {}", text.len()))
+    }
+}
+
+#[should_panic(expected = "body returned Err")]
+#[proptest(cases = 4)]
+fn test_proptest_err_fails_the_case(value: u8) -> Result<(), u8> {
+    Err(value)
+}
+
+#[should_panic(expected = "body returned Err: 1")]
+#[proptest(cases = 64)]
+fn test_proptest_err_cases_shrink(
+    #[strategy(AnyU32::new(0..=999))] value: u32,
+) -> Result<(), u32> {
+    if value == 0 { Ok(()) } else { Err(value) }
+}
+
+#[proptest(cases = 32)]
+fn test_proptest_supports_borrowed_str(text: &str) {
+    assert!(text.chars().count() <= 128);
+}
+
+#[proptest(cases = 32)]
+fn test_proptest_supports_borrowed_slices(
+    #[strategy(VecStrategy::new(AnyU8::default(), 1usize..=8usize))]
+    bytes: &[u8],
+    value: &u16,
+) {
+    assert!((1..=8).contains(&bytes.len()));
+    assert!(*value <= u16::MAX);
+}
+
+/// Panics when cloned, so receiving it proves the value was borrowed from or
+/// moved out of its tree.
+#[derive(Debug, Default)]
+struct CloneTrap;
+
+impl Clone for CloneTrap {
+    fn clone(&self) -> Self {
+        panic!("CloneTrap was cloned");
+    }
+}
+
+struct CloneTrapStrategy;
+
+impl Strategy for CloneTrapStrategy {
+    type Value = CloneTrap;
+    type Tree = ConstantValueTree<CloneTrap>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        generator.accept(ConstantValueTree::new(CloneTrap))
+    }
+}
+
+#[proptest(cases = 8)]
+fn test_proptest_borrows_strategy_values_from_their_tree(
+    #[strategy(CloneTrapStrategy)] trap: &CloneTrap,
+) {
+    let _ = trap;
+}
+
+#[proptest(cases = 8)]
+fn test_proptest_moves_strategy_values_out_of_their_tree(
+    #[strategy(CloneTrapStrategy)] trap: CloneTrap,
+    #[strategy(CloneTrapStrategy)] other: &mut CloneTrap,
+) {
+    *other = trap;
+}
+
+#[proptest(cases = 32)]
+fn test_proptest_supports_mutable_borrows(values: &mut Vec<u8>) {
+    values.push(0);
+    assert_eq!(values.last(), Some(&0));
+}
+
+#[proptest(cases = 64)]
+fn test_proptest_generated_functions_obey_map_fusion(
+    values: Vec<u8>,
+    #[strategy(function(AnyU8::default()))] f: GeneratedFn<u8, u8>,
+    #[strategy(function(AnyU8::default()))] g: GeneratedFn<u8, u8>,
+) {
+    let fused: Vec<u8> = values.iter().map(|v| g.call(&f.call(v))).collect();
+    let chained: Vec<u8> = values
+        .iter()
+        .map(|v| f.call(v))
+        .map(|v| g.call(&v))
+        .collect();
+    assert_eq!(fused, chained, "f = {f:?}, g = {g:?}");
+}
+
+fn case_setup() -> Vec<u8> {
+    vec![0]
+}
+
+fn case_teardown(log: Vec<u8>) {
+    assert_eq!(log.len(), 2, "each case must start from a fresh context");
+}
+
+#[proptest(cases = 16, setup = case_setup, teardown = case_teardown)]
+fn test_proptest_runs_hooks_around_every_case(
+    #[context] log: &mut Vec<u8>,
+    value: u8,
+) {
+    assert_eq!(log.as_slice(), &[0]);
+    log.push(value);
+}
+
+fn once_setup() -> usize {
+    0
+}
+
+fn once_teardown(cases: usize) {
+    assert_eq!(cases, 32, "the context must be shared by every case");
+}
+
+#[proptest(
+    cases = 32,
+    threads = 4,
+    setup_once = once_setup,
+    teardown_once = once_teardown
+)]
+fn test_proptest_shares_once_context_across_cases(
+    #[context] cases: &mut usize,
+    _value: bool,
+) {
+    *cases += 1;
+}
+
+struct FakeClock {
+    now: u64,
+}
+
+impl FakeClock {
+    fn advance(&mut self, by: u8) -> u64 {
+        self.now += u64::from(by);
+        self.now
+    }
+}
+
+#[proptest(cases = 64)]
+fn test_proptest_fixtures_are_built_per_case(
+    #[fixture(FakeClock { now: 1_000 })] clock: &mut FakeClock,
+    step: u8,
+) {
+    assert_eq!(clock.advance(step), 1_000 + u64::from(step));
+}
+
+#[proptest(cases = 4)]
+fn test_proptest_fixture_only_arguments(
+    #[fixture(vec![1u8, 2, 3])] values: Vec<u8>,
+) {
+    assert_eq!(values.len(), 3);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Fast,
+    Safe,
+}
+
+static MATRIX_RUNS: Mutex<Vec<(Mode, u8)>> = Mutex::new(Vec::new());
+
+#[proptest(cases = 8)]
+fn values_matrix_property(
+    #[values(Mode::Fast, Mode::Safe)] mode: Mode,
+    #[values(1, 2, 3)] width: u8,
+    value: u8,
+) {
+    let _ = value;
+    let mut runs = MATRIX_RUNS.lock().unwrap();
+    if !runs.contains(&(mode, width)) {
+        runs.push((mode, width));
+    }
+}
+
+#[test]
+fn test_proptest_values_cover_the_cartesian_product() {
+    MATRIX_RUNS.lock().unwrap().clear();
+    values_matrix_property();
+    let runs = MATRIX_RUNS.lock().unwrap();
+    assert_eq!(runs.len(), 6);
+}
+
+#[proptest(cases = 8)]
+#[should_panic(expected = "with mode = Safe")]
+fn test_proptest_values_name_the_failing_combination(
+    #[values(Mode::Fast, Mode::Safe)] mode: Mode,
+) {
+    assert_eq!(mode, Mode::Fast);
+}
+
+struct Codec;
+
+#[proptest]
+impl Codec {
+    fn encode(value: u16) -> [u8; 2] {
+        value.to_le_bytes()
+    }
+
+    fn decode(bytes: [u8; 2]) -> u16 {
+        u16::from_le_bytes(bytes)
+    }
+
+    #[proptest(cases = 64)]
+    fn test_proptest_associated_functions(value: u16) {
+        assert_eq!(Self::decode(Self::encode(value)), value);
+    }
+}
+
+#[proptest(cases = 8, test_attr = cfg_attr(all(), test))]
+fn test_proptest_custom_test_attribute(value: u8) {
+    assert_eq!(value.checked_add(0), Some(value));
+}
+
+static ASYNC_CASES: AtomicUsize = AtomicUsize::new(0);
+
+// Not a test itself: the async test is driven by the one below, standing in
+// for an async harness.
+#[proptest(cases = 8, test_attr = cfg_attr(any(), test), async_test = true)]
+fn async_property(value: u8) {
+    ASYNC_CASES.fetch_add(1, Ordering::Relaxed);
+    assert_eq!(value.checked_add(0), Some(value));
+}
+
+#[test]
+fn test_proptest_async_test_generates_an_async_test() {
+    let mut test = pin!(async_property());
+    let mut context = Context::from_waker(Waker::noop());
+    assert!(test.as_mut().poll(&mut context).is_ready());
+    assert_eq!(ASYNC_CASES.load(Ordering::Relaxed), 8);
+}
+
+#[proptest(cases = 64, swarm = true)]
+fn test_proptest_swarm_mode(values: Vec<Option<u8>>, result: Result<u8, bool>) {
+    assert!(values.iter().flatten().count() <= values.len());
+    assert_eq!(result.is_ok(), !result.is_err());
+}
+
+static DEDUP_BODIES: AtomicUsize = AtomicUsize::new(0);
+
+#[proptest(cases = 256, dedup = true)]
+fn test_proptest_dedup_runs_each_input_once(flag: bool) {
+    let _ = flag;
+    assert!(DEDUP_BODIES.fetch_add(1, Ordering::Relaxed) < 2);
+}
+
+#[proptest(cases = 16, shrink = "ddmin")]
+fn test_proptest_accepts_a_shrink_scheduler(value: u8) {
+    let _ = value;
+}
+
+#[should_panic(expected = "value = 1")]
+#[proptest(cases = 64)]
+fn test_proptest_shrinks_strategy_arguments(
+    #[strategy(AnyU32::new(0..=999))] value: u32,
+) {
+    assert!(value == 0, "value = {value}");
+}
+
+#[should_panic(expected = "value = 1")]
+#[proptest(cases = 64)]
+fn test_proptest_shrinks_arbitrary_arguments(value: u32) {
+    assert!(value == 0, "value = {value}");
+}
+
+#[should_panic(expected = "value = -1")]
+#[proptest(cases = 64)]
+fn test_proptest_shrinks_negative_arbitrary_arguments(value: i64) {
+    assert!(value >= 0, "value = {value}");
+}
+
+#[should_panic(expected = "value = 1")]
+#[proptest(cases = 64, shrink = "ddmin")]
+fn test_proptest_shrinks_with_the_configured_scheduler(
+    flag: bool,
+    #[strategy(AnyU32::new(0..=999))] value: u32,
+) {
+    let _ = flag;
+    assert!(value == 0, "value = {value}");
+}
+
+#[proptest(cases = 512)]
+fn test_proptest_coverage_requirement(value: u8) {
+    coverage::require("high bit set", 25.0);
+    if value >= 128 {
+        coverage::hit("high bit set");
+    }
+}
+
+#[should_panic(expected = "`above u8::MAX` was hit in 0.0% of 64 cases")]
+#[proptest(cases = 64)]
+fn test_proptest_unmet_coverage_fails(value: u8) {
+    coverage::require("above u8::MAX", 1.0);
+    if u16::from(value) > 255 {
+        coverage::hit("above u8::MAX");
+    }
+}
+
+fn rows_setup() -> Vec<[u8; 4]> {
+    Vec::new()
+}
+
+fn rows_teardown(rows: Vec<[u8; 4]>) {
+    assert!(rows.len() < 81, "covering ran the full product");
+    for (a, b) in [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)] {
+        for x in 0..3 {
+            for y in 0..3 {
+                assert!(
+                    rows.iter().any(|row| row[a] == x && row[b] == y),
+                    "pair ({a} = {x}, {b} = {y}) was not covered",
+                );
+            }
+        }
+    }
+}
+
+#[proptest(
+    cases = 1,
+    covering = 2,
+    setup_once = rows_setup,
+    teardown_once = rows_teardown
+)]
+fn test_proptest_covering_runs_every_pair(
+    #[context] rows: &mut Vec<[u8; 4]>,
+    #[values(0, 1, 2)] a: u8,
+    #[values(0, 1, 2)] b: u8,
+    #[values(0, 1, 2)] c: u8,
+    #[values(0, 1, 2)] d: u8,
+) {
+    rows.push([a, b, c, d]);
+}
+
+static EXAMPLE_RUNS: Mutex<Vec<(u8, String)>> = Mutex::new(Vec::new());
+
+#[proptest(cases = 4, examples = [(0, "x"), (255, "")])]
+#[example(7, "seven")]
+fn examples_property(value: u8, text: &str) {
+    EXAMPLE_RUNS.lock().unwrap().push((value, text.to_owned()));
+}
+
+#[test]
+fn test_proptest_examples_run_before_generated_cases() {
+    EXAMPLE_RUNS.lock().unwrap().clear();
+    examples_property();
+    let runs = EXAMPLE_RUNS.lock().unwrap();
+    assert_eq!(runs.len(), 7);
+    assert_eq!(
+        runs[..3],
+        [
+            (0, "x".to_owned()),
+            (255, String::new()),
+            (7, "seven".to_owned()),
+        ],
+    );
+}
+
+#[proptest(cases = 8)]
+#[example(&mut vec![1, 2])]
+#[example((&mut vec![],))]
+fn test_proptest_examples_bind_like_the_parameters(
+    #[fixture(Vec::new())] seen: Vec<u8>,
+    values: &mut Vec<u8>,
+) {
+    assert!(seen.is_empty());
+    values.push(0);
+}
+
+#[proptest(cases = 8, examples = [(3, 4)])]
+#[should_panic(expected = "example 0 failed: sum too large")]
+fn test_proptest_failing_examples_are_named(
+    #[strategy(AnyU8::new(0..=1))] a: u8,
+    #[strategy(AnyU8::new(0..=1))] b: u8,
+) {
+    assert!(a + b <= 2, "sum too large");
+}
+
+static FLAKY_ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+
+#[proptest(cases = 8, retries = 2)]
+fn test_proptest_retries_tolerate_flaky_cases(value: u8) {
+    let _ = value;
+    // Every other attempt fails, so each failure passes on its retry.
+    let attempt = FLAKY_ATTEMPTS.fetch_add(1, Ordering::Relaxed);
+    assert!(attempt % 2 == 1, "flaky");
+}
+
+#[proptest(cases = 8, retries = 2)]
+#[should_panic(expected = "case 0 failed, and all 2 retries")]
+fn test_proptest_retries_keep_real_failures(value: u8) {
+    let _ = value;
+    panic!("always fails");
+}
+
+#[proptest(cases = 64)]
+#[should_panic(expected = "assertion `value < 200` failed: 2")]
+fn test_proptest_reports_failed_assertions(value: u8) {
+    prop_assert!(value < 200, "{}", value / 100);
+    prop_assert_eq!(value.checked_add(0), Some(value));
+}
+
+#[proptest(cases = 8, capture_panics = false)]
+fn test_proptest_capture_panics_can_be_disabled(value: u8) {
+    prop_assert_eq!(u16::from(value) + 1, u16::from(value) + 1, "{value}");
+}
+
+/// Draws bytes until one is above `u8::MAX`, which none ever is.
+struct DifferentStrategy;
+
+impl Strategy for DifferentStrategy {
+    type Value = u8;
+    type Tree = ConstantValueTree<u8>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        loop {
+            let value = generator.rng.random::<u8>();
+            if u16::from(value) > 256 {
+                return generator.accept(ConstantValueTree::new(value));
+            }
+        }
+    }
+}
+
+#[proptest(cases = 8, entropy_limit = 4096)]
+#[should_panic(
+    expected = "strategy `test_proptest_macro::DifferentStrategy` drew past the entropy limit of 4096 bytes"
+)]
+fn test_proptest_entropy_limit_stops_runaway_strategies(
+    #[strategy(DifferentStrategy)] value: u8,
+) {
+    let _ = value;
+}
+
+// Drawn without shrinking, which would take every failure down to 0 and
+// leave a single kind of failure.
+#[proptest(cases = 512, max_failures = 32)]
+#[should_panic(expected = "Other failures:\n  case ")]
+fn test_proptest_max_failures_reports_every_kind_of_failure(
+    #[strategy(from_fn(|generator| {
+        let value = generator.rng.random::<u8>();
+        generator.accept(value)
+    }))]
+    value: u8,
+) {
+    assert!(value < 224, "too large");
+    assert!(!value.is_multiple_of(8), "multiple of 8");
+}
+
+/// A fixture too expensive to build per case or per test.
+struct SharedTable(Vec<u16>);
+
+static SHARED_TABLES_BUILT: AtomicUsize = AtomicUsize::new(0);
+
+fn shared_table() -> &'static SharedTable {
+    estoa_proptest::once(|| {
+        SHARED_TABLES_BUILT.fetch_add(1, Ordering::Relaxed);
+        SharedTable((0..=255).map(|n| n * n).collect())
+    })
+}
+
+#[proptest(cases = 32)]
+fn test_proptest_once_fixtures_are_shared_by_cases(
+    #[fixture(shared_table())] table: &'static SharedTable,
+    value: u8,
+) {
+    assert_eq!(table.0[usize::from(value)], u16::from(value).pow(2));
+    assert_eq!(SHARED_TABLES_BUILT.load(Ordering::Relaxed), 1);
+}
+
+#[proptest(cases = 32)]
+fn test_proptest_once_fixtures_are_shared_by_tests(
+    #[fixture(shared_table())] table: &'static SharedTable,
+) {
+    assert!(std::ptr::eq(table, shared_table()));
+    assert_eq!(SHARED_TABLES_BUILT.load(Ordering::Relaxed), 1);
+}
+
+/// Picks the first entry of a set as its smallest, which only holds in
+/// some iteration orders.
+fn first_is_smallest(set: &HashSet<u8, ChaosState>) -> bool {
+    set.iter().next() == set.iter().min()
+}
+
+#[proptest(cases = 64, chaos_order = true)]
+#[should_panic(expected = "; ordering seed 0x")]
+fn test_proptest_chaos_order_flushes_out_order_dependence(
+    #[strategy(
+        HashSetStrategy::new(AnyU8::default(), 8usize..=8usize)
+            .with_hasher::<ChaosState>()
+    )]
+    set: HashSet<u8, ChaosState>,
+) {
+    assert!(first_is_smallest(&set), "{set:?} does not start smallest");
+}
+
+#[proptest(cases = 64, chaos_order = true)]
+fn test_proptest_chaos_order_keeps_order_independent_properties(
+    map: HashMap<u8, u8, ChaosState>,
+) {
+    let mut sorted: Vec<_> = map.iter().collect();
+    sorted.sort_unstable();
+    assert_eq!(sorted.len(), map.len());
+    assert_eq!(map.hasher(), &ChaosState::default());
+}
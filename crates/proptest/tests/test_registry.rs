@@ -0,0 +1,27 @@
+use estoa_proptest::{
+    Arbitrary,
+    proptest,
+    registry::Registry,
+    strategy::{AnyString, AnyU8, compose},
+};
+
+#[derive(Arbitrary, Clone, Debug)]
+struct Percent(u8);
+
+fn small_defaults() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(|| AnyString::new(0usize..=4usize));
+    registry.register(|| {
+        compose((AnyU8::new(0..=100),), |(value,)| Percent(value))
+    });
+    registry
+}
+
+#[proptest(registry = small_defaults)]
+fn registered_types_use_the_test_registry(name: String, percent: Percent) {
+    assert!(name.chars().count() <= 4);
+    assert!(percent.0 <= 100);
+}
+
+#[proptest(registry = small_defaults)]
+fn other_types_fall_back_to_arbitrary(_flag: bool, _bytes: Vec<u8>) {}
@@ -0,0 +1,45 @@
+//! Sets `ESTOA_PROPTEST_RETRIES` for the whole process, so it runs in a
+//! test binary of its own.
+
+use std::{
+    env,
+    panic::catch_unwind,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use estoa_proptest::{proptest, runner::RETRIES_ENV};
+
+static RETRIED_RUNS: AtomicUsize = AtomicUsize::new(0);
+static UNRETRIED_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Fails the first time it runs and passes from then on.
+fn flaky(runs: &AtomicUsize) {
+    assert!(runs.fetch_add(1, Ordering::Relaxed) > 0, "first run fails");
+}
+
+// Neither is a test itself: the test below runs them once the environment
+// is set.
+#[proptest(cases = 1, test_attr = cfg_attr(any(), test))]
+fn retried(value: u8) {
+    let _ = value;
+    flaky(&RETRIED_RUNS);
+}
+
+#[proptest(cases = 1, retries = 0, test_attr = cfg_attr(any(), test))]
+fn unretried(value: u8) {
+    let _ = value;
+    flaky(&UNRETRIED_RUNS);
+}
+
+#[test]
+fn test_retries_zero_overrides_the_environment() {
+    // SAFETY: this is the only test of the binary, so no other thread reads
+    // the environment.
+    unsafe { env::set_var(RETRIES_ENV, "3") };
+
+    retried();
+    assert_eq!(RETRIED_RUNS.load(Ordering::Relaxed), 2);
+
+    // A retry would pass, so only a run without retries fails.
+    assert!(catch_unwind(unretried).is_err());
+}
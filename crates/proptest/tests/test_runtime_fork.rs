@@ -0,0 +1,60 @@
+use estoa_proptest::strategy::runtime::{CountingRng, Generator};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+#[test]
+fn forks_are_deterministic() {
+    let mut first =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+    let mut second =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+
+    let a: [u64; 4] = first.fork().rng.random();
+    let b: [u64; 4] = second.fork().rng.random();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn draws_from_a_fork_do_not_move_the_parent() {
+    let mut light =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+    let mut heavy =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+
+    let _ = light.fork().rng.random::<u8>();
+    let mut child = heavy.fork();
+    for _ in 0..100 {
+        let _ = child.rng.random::<u64>();
+    }
+
+    assert_eq!(light.rng.random::<u64>(), heavy.rng.random::<u64>());
+}
+
+#[test]
+fn sibling_forks_draw_different_streams() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+    let first: u64 = generator.fork().rng.random();
+    let second: u64 = generator.fork().rng.random();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn forks_keep_the_parent_settings_and_count_on_their_own() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_size(12);
+    let mut child = generator.fork();
+    assert_eq!(child.size(), 12);
+    assert_eq!(child.depth(), generator.depth());
+
+    let _ = child.accept(1u8);
+    let _ = child.rng.random::<u32>();
+    assert_eq!(child.stats().accepted, 1);
+    assert_eq!(child.stats().entropy_bytes, 4);
+    assert_eq!(generator.stats().accepted, 0);
+}
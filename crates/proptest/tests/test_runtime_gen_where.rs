@@ -0,0 +1,78 @@
+use estoa_proptest::strategy::{
+    AnyU8,
+    Strategy,
+    ValueTree,
+    runtime::{
+        ConstantValueTree,
+        CountingRng,
+        Generation,
+        Generator,
+        RejectReason,
+    },
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+/// Two distinct bytes, the second drawn until it differs from the first.
+struct DistinctPair;
+
+impl Strategy for DistinctPair {
+    type Value = (u8, u8);
+    type Tree = ConstantValueTree<(u8, u8)>;
+
+    fn new_tree<R: rand::RngCore + rand::CryptoRng>(
+        &mut self,
+        generator: &mut Generator<R>,
+    ) -> Generation<Self::Tree> {
+        let first = AnyU8::new(0..=3).new_tree(generator).take();
+        let first = *first.current();
+        let second =
+            generator.gen_where(&mut AnyU8::new(0..=3), |b| *b != first, 64);
+        let rejected = matches!(second, Generation::Rejected { .. });
+        let tree = ConstantValueTree::new((first, *second.take().current()));
+        if rejected {
+            generator.reject_with(RejectReason::AttemptsExhausted, tree)
+        } else {
+            generator.accept(tree)
+        }
+    }
+}
+
+#[test]
+fn gen_where_returns_a_value_the_predicate_accepts() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(3)));
+    for _ in 0..100 {
+        let Generation::Accepted { value, .. } =
+            DistinctPair.new_tree(&mut generator)
+        else {
+            panic!("four values leave plenty of room");
+        };
+        let (a, b) = *value.current();
+        assert_ne!(a, b);
+    }
+}
+
+#[test]
+fn gen_where_gives_up_after_its_attempts() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(3)));
+    let generation =
+        generator.gen_where(&mut AnyU8::new(0..=3), |value| *value > 3, 5);
+
+    let Generation::Rejected { reason, value, .. } = generation else {
+        panic!("no value above 3 exists");
+    };
+    assert_eq!(reason, Some(RejectReason::AttemptsExhausted));
+    assert!(*value.current() <= 3);
+    assert_eq!(generator.stats().accepted, 5);
+    assert_eq!(generator.stats().rejected, 1);
+    assert!(generator.stats().entropy_bytes > 0);
+}
+
+#[test]
+fn gen_where_tries_at_least_once() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(3)));
+    let generation = generator.gen_where(&mut AnyU8::new(1..=1), |_| true, 0);
+    assert!(matches!(generation, Generation::Accepted { .. }));
+}
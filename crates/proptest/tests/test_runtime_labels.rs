@@ -0,0 +1,58 @@
+use estoa_proptest::strategy::{
+    AnyU64,
+    Strategy,
+    ValueTree,
+    runtime::{CountingRng, Generator},
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+fn draw(generator: &mut Generator<CountingRng<StdRng>>, label: &str) -> u64 {
+    *generator
+        .draw_labeled(label, &mut AnyU64::default())
+        .take()
+        .current()
+}
+
+#[test]
+fn new_labels_leave_existing_labels_alone() {
+    for seed in 0..16 {
+        let mut before =
+            Generator::build(CountingRng::new(StdRng::seed_from_u64(seed)));
+        let name = draw(&mut before, "name");
+        let age = draw(&mut before, "age");
+
+        let mut after =
+            Generator::build(CountingRng::new(StdRng::seed_from_u64(seed)));
+        let _ = draw(&mut after, "email");
+        assert_eq!(draw(&mut after, "age"), age);
+        assert_eq!(draw(&mut after, "name"), name);
+    }
+}
+
+#[test]
+fn repeated_labels_draw_fresh_values() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(3)));
+    let first = draw(&mut generator, "item");
+    let second = draw(&mut generator, "item");
+    assert_ne!(first, second);
+}
+
+#[test]
+fn labeled_draws_depend_on_the_case() {
+    let mut first =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(5)));
+    let mut second =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(6)));
+    assert_ne!(draw(&mut first, "id"), draw(&mut second, "id"));
+}
+
+#[test]
+fn labeled_draws_count_towards_the_generator() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(7)));
+    let mut strategy = AnyU64::default();
+    let _ = generator.draw_labeled("id", &mut strategy);
+    let _ = strategy.new_tree(&mut generator);
+    assert_eq!(generator.stats().accepted, 2);
+}
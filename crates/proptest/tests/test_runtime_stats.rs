@@ -0,0 +1,91 @@
+use estoa_proptest::strategy::runtime::{CountingRng, Generator};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+#[test]
+fn stats_start_at_zero() {
+    let generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)));
+    let stats = generator.stats();
+    assert_eq!(stats.entropy_bytes, 0);
+    assert_eq!(stats.accepted, 0);
+    assert_eq!(stats.rejected, 0);
+    assert_eq!(stats.max_depth, 0);
+}
+
+#[test]
+fn stats_count_entropy_and_outcomes() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)));
+    let _ = generator.rng.random::<u64>();
+    let mut bytes = [0u8; 5];
+    generator.rng.fill(&mut bytes);
+    let _ = generator.accept(1u8);
+    let _ = generator.accept(2u8);
+    let _ = generator.reject(3u8);
+
+    let stats = generator.stats();
+    assert_eq!(stats.entropy_bytes, 13);
+    assert_eq!(stats.accepted, 2);
+    assert_eq!(stats.rejected, 1);
+}
+
+#[test]
+fn stats_track_the_deepest_recursion() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)));
+    generator.recurse(|outer| {
+        outer.recurse(|inner| {
+            let _ = inner.accept(());
+        })
+    });
+    generator.recurse(|outer| {
+        let _ = outer.accept(());
+    });
+
+    let stats = generator.stats();
+    assert_eq!(stats.max_depth, 2);
+    assert_eq!(stats.accepted, 2);
+    assert_eq!(generator.depth(), 0);
+}
+
+#[test]
+fn stats_include_erased_draws() {
+    let mut generator =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)));
+    generator.erased(|erased| {
+        let _ = erased.rng.random::<u32>();
+        let _ = erased.reject(());
+    });
+
+    let stats = generator.stats();
+    assert_eq!(stats.entropy_bytes, 4);
+    assert_eq!(stats.rejected, 1);
+}
+
+#[test]
+fn reset_generator_behaves_like_a_fresh_one() {
+    let mut reused =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1)))
+            .with_limit(3)
+            .with_size(10);
+    let _ = reused.rng.random::<u64>();
+    let _ = reused.accept(());
+    let _ = reused.reject(());
+    reused.advance_iteration();
+    reused.recurse(|inner| {
+        let _ = inner.accept(());
+    });
+
+    reused.reset(CountingRng::new(StdRng::seed_from_u64(2)), 40, 0);
+    assert_eq!(
+        reused.stats(),
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(1))).stats()
+    );
+    assert_eq!(reused.iteration(), 0);
+    assert_eq!(reused.size(), 40);
+    assert_eq!(reused.recursion_limit(), 3);
+
+    let mut fresh =
+        Generator::build(CountingRng::new(StdRng::seed_from_u64(2)));
+    assert_eq!(reused.rng.random::<u64>(), fresh.rng.random::<u64>());
+}
@@ -14,7 +14,7 @@ struct DifferentStrategy;
 
 impl estoa_proptest::strategy::Strategy for DifferentStrategy {
     type Value = (u8, u8);
-    type Tree = StaticTree<(u8, u8)>;
+    type Tree = ConstantValueTree<(u8, u8)>;
 
     fn new_tree<R: rand::RngCore + rand::CryptoRng>(
         &mut self,
@@ -25,7 +25,8 @@ impl estoa_proptest::strategy::Strategy for DifferentStrategy {
             let second = generator.rng.random::<u8>();
 
             if first != second {
-                return generator.accept(StaticTree::new((first, second)));
+                return generator
+                    .accept(ConstantValueTree::new((first, second)));
             }
         }
     }
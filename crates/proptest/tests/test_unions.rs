@@ -0,0 +1,82 @@
+use estoa_proptest::{
+    proptest,
+    strategy::{
+        AnyI32,
+        AnyU8,
+        Either,
+        Strategy,
+        TupleUnion,
+        ValueTree,
+        runtime::Generator,
+    },
+    strategy_test::check_shrink_invariants,
+};
+use rand::{SeedableRng, rngs::StdRng};
+
+#[test]
+fn tuple_union_draws_from_every_alternative() {
+    let mut strategy = TupleUnion::new((
+        AnyI32::new(0..=9),
+        AnyI32::new(100..=109),
+        AnyI32::new(200..=209),
+    ));
+    let mut generator = Generator::build(StdRng::seed_from_u64(17));
+    let mut seen = [false; 3];
+
+    for _ in 0..128 {
+        let value = *strategy.new_tree(&mut generator).take().current();
+        seen[value as usize / 100] = true;
+    }
+    assert_eq!(seen, [true; 3]);
+}
+
+#[test]
+fn tuple_union_shrinks_to_the_first_alternative() {
+    let mut strategy =
+        TupleUnion::new((AnyI32::new(5..=9), AnyI32::new(100..=109)));
+    let mut generator = Generator::build(StdRng::seed_from_u64(19));
+
+    for _ in 0..32 {
+        let mut tree = strategy.new_tree(&mut generator).take();
+        while tree.simplify() {}
+        assert_eq!(*tree.current(), 5);
+    }
+}
+
+#[test]
+fn tuple_union_keeps_the_contract() {
+    check_shrink_invariants(
+        TupleUnion::new((
+            AnyU8::new(0..=9),
+            AnyU8::new(50..=60),
+            AnyU8::new(200..=255),
+        )),
+        64,
+    );
+}
+
+#[test]
+fn either_picks_a_strategy_without_boxing() {
+    let pick = |wide: bool| {
+        if wide {
+            Either::Left(AnyU8::default())
+        } else {
+            Either::Right(AnyU8::new(0..=3))
+        }
+    };
+    check_shrink_invariants(pick(true), 32);
+
+    let mut narrow = pick(false);
+    let mut generator = Generator::build(StdRng::seed_from_u64(23));
+    for _ in 0..32 {
+        assert!(*narrow.new_tree(&mut generator).take().current() <= 3);
+    }
+}
+
+#[proptest]
+fn tuple_unions_drive_proptest_arguments(
+    #[strategy(TupleUnion::new((AnyU8::new(0..=1), AnyU8::new(8..=9))))]
+    value: u8,
+) {
+    assert!(matches!(value, 0 | 1 | 8 | 9));
+}
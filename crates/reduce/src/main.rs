@@ -0,0 +1,180 @@
+//! Shrink a failure saved by `estoa_proptest::reduce::check` further.
+//!
+//! ```text
+//! estoa-reduce [--budget N] [--scheduler NAME]... [-p PACKAGE] TEST [-- ARGS]
+//! ```
+//!
+//! Runs `cargo test` for the one test in reduction mode, so it shrinks its
+//! saved failure with a larger budget and writes the smaller case back.
+//! `ARGS` go to `cargo test` as they are.
+
+use std::{
+    env,
+    process::{Command, ExitCode},
+};
+
+use estoa_proptest::{
+    reduce::{BUDGET_ENV, REDUCE_ENV, SCHEDULERS_ENV},
+    shrink::ShrinkScheduler,
+};
+
+const USAGE: &str = "usage: estoa-reduce [--budget N] [--scheduler NAME]... \
+                     [-p PACKAGE] TEST [-- CARGO_TEST_ARGS]";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    budget: Option<usize>,
+    schedulers: Vec<ShrinkScheduler>,
+    package: Option<String>,
+    test: String,
+    cargo_args: Vec<String>,
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut test = None;
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next().ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match arg.as_str() {
+            "--budget" => {
+                let budget = value("--budget")?;
+                parsed.budget = Some(budget.parse().map_err(|_| {
+                    format!("--budget must be a number, not `{budget}`")
+                })?);
+            }
+            "--scheduler" => {
+                let scheduler = value("--scheduler")?;
+                parsed
+                    .schedulers
+                    .push(scheduler.parse().map_err(|err| format!("{err}"))?);
+            }
+            "-p" | "--package" => parsed.package = Some(value(&arg)?),
+            "--" => {
+                parsed.cargo_args = args.collect();
+                break;
+            }
+            flag if flag.starts_with('-') => {
+                return Err(format!("unknown option `{flag}`"));
+            }
+            _ if test.is_some() => {
+                return Err(format!("unexpected argument `{arg}`"));
+            }
+            _ => test = Some(arg),
+        }
+    }
+    parsed.test = test.ok_or("missing the name of the test")?;
+    Ok(parsed)
+}
+
+impl Args {
+    fn command(&self) -> Command {
+        let mut command = Command::new(
+            env::var_os("CARGO").unwrap_or_else(|| "cargo".into()),
+        );
+        command.arg("test");
+        if let Some(package) = &self.package {
+            command.args(["--package", package]);
+        }
+        command
+            .args(&self.cargo_args)
+            .args(["--", &self.test, "--exact", "--nocapture"])
+            .env(REDUCE_ENV, "1");
+        if let Some(budget) = self.budget {
+            command.env(BUDGET_ENV, budget.to_string());
+        }
+        if !self.schedulers.is_empty() {
+            let names: Vec<_> = self
+                .schedulers
+                .iter()
+                .map(|scheduler| scheduler.name())
+                .collect();
+            command.env(SCHEDULERS_ENV, names.join(","));
+        }
+        command
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("estoa-reduce: {err}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match args.command().status() {
+        Ok(status) if status.success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("estoa-reduce: cannot run cargo: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Args, String> {
+        parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn options_and_cargo_args_are_parsed() {
+        let args = parse_args(&[
+            "--budget",
+            "500",
+            "--scheduler",
+            "ddmin",
+            "-p",
+            "codec",
+            "round_trips",
+            "--",
+            "--release",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            Args {
+                budget: Some(500),
+                schedulers: vec![ShrinkScheduler::Ddmin],
+                package: Some("codec".into()),
+                test: "round_trips".into(),
+                cargo_args: vec!["--release".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert!(parse_args(&[]).is_err());
+        assert!(parse_args(&["--budget", "lots", "t"]).is_err());
+        assert!(parse_args(&["--scheduler", "fastest", "t"]).is_err());
+        assert!(parse_args(&["--budget"]).is_err());
+        assert!(parse_args(&["--verbose", "t"]).is_err());
+        assert!(parse_args(&["t", "u"]).is_err());
+    }
+
+    #[test]
+    fn the_test_runs_in_reduction_mode() {
+        let command = parse_args(&["--scheduler", "ddmin", "t"])
+            .unwrap()
+            .command();
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, ["test", "--", "t", "--exact", "--nocapture"]);
+        let envs: Vec<_> = command.get_envs().collect();
+        assert!(
+            envs.contains(&(OsStr::new(REDUCE_ENV), Some(OsStr::new("1"))))
+        );
+        assert!(envs.contains(&(
+            OsStr::new(SCHEDULERS_ENV),
+            Some(OsStr::new("ddmin"))
+        )));
+        assert!(!envs.iter().any(|(name, _)| *name == BUDGET_ENV));
+    }
+}